@@ -0,0 +1,169 @@
+// Opens a native window, lays out a sample graph with the GPU physics and draws
+// it with the same pipelines and shaders as the web build:
+//
+//     cargo run --example native_viewer
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use fast_graph_core::{Adjacency, Camera, EdgeData, NodeData, PhysicsSettings, Renderer};
+use winit::application::ApplicationHandler;
+use winit::dpi::PhysicalSize;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+const WIDTH: u32 = 1024;
+const HEIGHT: u32 = 768;
+const CLUSTERS: usize = 8;
+const NODES_PER_CLUSTER: usize = 60;
+
+// Clusters of densely linked nodes, joined in a ring by a few bridge edges
+fn sample_graph() -> (Vec<NodeData>, Vec<[u32; 2]>) {
+    let mut seed = 0x2545_f491_u32;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+
+    let mut nodes = Vec::new();
+    let mut edges = Vec::new();
+    for cluster in 0..CLUSTERS {
+        let hue = cluster as f32 / CLUSTERS as f32;
+        let first = nodes.len() as u32;
+        for i in 0..NODES_PER_CLUSTER {
+            nodes.push(NodeData {
+                x: WIDTH as f32 * (0.2 + 0.6 * random()),
+                y: HEIGHT as f32 * (0.2 + 0.6 * random()),
+                vx: 0.0,
+                vy: 0.0,
+                fx: 0.0,
+                fy: 0.0,
+                r: 0.5 + 0.5 * (hue * std::f32::consts::TAU).cos(),
+                g: 0.5 + 0.5 * ((hue + 1.0 / 3.0) * std::f32::consts::TAU).cos(),
+                b: 0.5 + 0.5 * ((hue + 2.0 / 3.0) * std::f32::consts::TAU).cos(),
+                a: 1.0,
+                size: 4.0,
+                mass: 1.0,
+                flags: 0,
+            });
+            // Each node links to two earlier ones in its cluster
+            for _ in 0..2.min(i) {
+                let other = first + (random() * i as f32) as u32;
+                edges.push([first + i as u32, other]);
+            }
+        }
+        let next = ((cluster + 1) % CLUSTERS * NODES_PER_CLUSTER) as u32;
+        edges.push([first, next]);
+    }
+    (nodes, edges)
+}
+
+struct Viewer {
+    window: Option<Arc<Window>>,
+    renderer: Renderer,
+    nodes: Vec<NodeData>,
+    edge_indices: Vec<[u32; 2]>,
+    edges: Vec<EdgeData>,
+    physics: PhysicsSettings,
+    upload_nodes: bool,
+    start: Instant,
+}
+
+impl Viewer {
+    fn new() -> Self {
+        let (nodes, edge_indices) = sample_graph();
+        let edges = edge_indices
+            .iter()
+            .map(|_| EdgeData { x1: 0.0, y1: 0.0, x2: 0.0, y2: 0.0, r: 0.6, g: 0.6, b: 0.7, a: 0.4, width: 1.0 })
+            .collect();
+        Self {
+            window: None,
+            renderer: Renderer::new(),
+            nodes,
+            edge_indices,
+            edges,
+            physics: PhysicsSettings {
+                gravity_strength: 0.02,
+                gravity_center: [WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0],
+                ..PhysicsSettings::default()
+            },
+            upload_nodes: true,
+            start: Instant::now(),
+        }
+    }
+
+    fn tick(&mut self) {
+        if !self.renderer.has_gpu_physics() || !self.physics.is_active() {
+            return;
+        }
+        self.renderer.read_physics_positions(&mut self.nodes);
+        if let Err(e) = self.renderer.integrate_physics(&self.nodes, &[], &[], self.upload_nodes, &self.physics) {
+            eprintln!("Physics tick failed: {}", e);
+            return;
+        }
+        self.upload_nodes = false;
+        self.physics.decay_alpha();
+    }
+
+    fn refresh_edge_endpoints(&mut self) {
+        for (edge, &[source, target]) in self.edges.iter_mut().zip(&self.edge_indices) {
+            let (source, target) = (&self.nodes[source as usize], &self.nodes[target as usize]);
+            [edge.x1, edge.y1, edge.x2, edge.y2] = [source.x, source.y, target.x, target.y];
+        }
+    }
+}
+
+impl ApplicationHandler for Viewer {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+        let attributes = Window::default_attributes()
+            .with_title("fast-graph native viewer")
+            .with_inner_size(PhysicalSize::new(WIDTH, HEIGHT));
+        let window = Arc::new(event_loop.create_window(attributes).expect("Failed to create window"));
+
+        let size = window.inner_size();
+        pollster::block_on(self.renderer.init_with_target(window.clone(), size.width, size.height))
+            .expect("Failed to initialize the renderer");
+        if self.renderer.has_gpu_physics() {
+            let adjacency = Adjacency::new(self.nodes.len(), &self.edge_indices);
+            self.renderer.upload_adjacency(&adjacency, &[], &[]);
+        } else {
+            eprintln!("No compute shader support, showing the initial positions only");
+        }
+
+        window.request_redraw();
+        self.window = Some(window);
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window_id: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::Resized(size) => self.renderer.resize(size.width, size.height),
+            WindowEvent::RedrawRequested => {
+                self.tick();
+                self.refresh_edge_endpoints();
+                let time = self.start.elapsed().as_secs_f64();
+                if let Err(e) = self.renderer.render(time, &[0.08, 0.09, 0.12, 1.0], &[0.12, 0.14, 0.19, 1.0], &self.nodes, &self.edges, Camera { position: [0.0, 0.0], zoom: 1.0 }) {
+                    eprintln!("Render failed: {}", e);
+                }
+                for error in self.renderer.take_validation_errors() {
+                    eprintln!("Validation error: {}", error);
+                }
+                if let Some(window) = &self.window {
+                    window.request_redraw();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let event_loop = EventLoop::new().expect("Failed to create event loop");
+    event_loop.run_app(&mut Viewer::new()).expect("Event loop failed");
+}
@@ -0,0 +1,99 @@
+// Times the CPU physics on a 10k-node graph, vectorized against scalar:
+//
+//     cargo run --release --no-default-features --example physics_bench
+//
+// This is a plain example rather than a criterion bench, which isn't a
+// dependency. To keep runs comparable anyway, every sample starts from the
+// same graph, the two paths take turns so drift in machine load hits both, and
+// the median and fastest sample are reported.
+//
+// The four-lane passes use SSE2 on x86_64, and WebAssembly SIMD on wasm32,
+// which .cargo/config.toml enables. These timings are native only.
+
+use std::time::{Duration, Instant};
+
+use fast_graph_core::physics::{self, PhysicsInputs};
+use fast_graph_core::{Adjacency, NodeData, PhysicsSettings};
+
+const NODES: usize = 10_000;
+const EDGES_PER_NODE: usize = 3;
+const TICKS: u32 = 40; // per sample
+const SAMPLES: usize = 9;
+
+fn sample_graph() -> (Vec<NodeData>, Vec<[u32; 2]>) {
+    let mut seed = 0x9e37_79b9_u32;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+
+    let side = (NODES as f32).sqrt() * 20.0;
+    let nodes = (0..NODES)
+        .map(|_| NodeData {
+            x: side * random(),
+            y: side * random(),
+            vx: 0.0,
+            vy: 0.0,
+            fx: 0.0,
+            fy: 0.0,
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+            size: 4.0,
+            mass: 1.0,
+            flags: 0,
+        })
+        .collect();
+    // Mostly local links, as in real graphs
+    let edges = (1..NODES)
+        .flat_map(|i| (0..EDGES_PER_NODE.min(i)).map(move |k| [i as u32, (i - 1 - k * 7 % i) as u32]))
+        .collect();
+    (nodes, edges)
+}
+
+type Tick = fn(&mut [NodeData], &PhysicsInputs, &PhysicsSettings);
+
+// One sample: TICKS ticks from the initial graph
+fn sample(nodes: &[NodeData], adjacency: &Adjacency, tick: Tick) -> Duration {
+    let mut nodes = nodes.to_vec();
+    let inputs = PhysicsInputs { adjacency: Some(adjacency), edge_lengths: &[], edge_weights: &[], groups: None, damping: &[], excluded: &[], frozen: &[] };
+    let mut settings = PhysicsSettings { repulsion_radius: 40.0, repulsion_strength: 50.0, alpha_min: 0.0, ..PhysicsSettings::default() };
+
+    let start = Instant::now();
+    for _ in 0..TICKS {
+        tick(&mut nodes, &inputs, &settings);
+        settings.decay_alpha();
+    }
+    start.elapsed()
+}
+
+// Median and fastest sample in ms per tick
+fn report(name: &str, samples: &mut [Duration]) -> f64 {
+    samples.sort_unstable();
+    let per_tick = |duration: Duration| duration.as_secs_f64() * 1000.0 / TICKS as f64;
+    let median = per_tick(samples[samples.len() / 2]);
+    println!("{:<10} {:>8.2} ms/tick median {:>8.2} fastest {:>8.1} ticks/s", name, median, per_tick(samples[0]), 1000.0 / median);
+    median
+}
+
+fn main() {
+    let (nodes, edges) = sample_graph();
+    let adjacency = Adjacency::new(nodes.len(), &edges);
+    println!("{} nodes, {} edges", nodes.len(), edges.len());
+
+    println!("{} samples of {} ticks each", SAMPLES, TICKS);
+
+    // Warm-up, then alternate
+    sample(&nodes, &adjacency, physics::step);
+    let (mut scalar, mut vectorized) = (Vec::new(), Vec::new());
+    for _ in 0..SAMPLES {
+        scalar.push(sample(&nodes, &adjacency, physics::step_scalar));
+        vectorized.push(sample(&nodes, &adjacency, physics::step));
+    }
+    let scalar = report("scalar", &mut scalar);
+    let vectorized = report("vectorized", &mut vectorized);
+    println!("speedup    {:>8.2}x", scalar / vectorized);
+}
@@ -0,0 +1,805 @@
+// Graph algorithms over the indexed edges
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, VecDeque};
+
+use crate::graph::Adjacency;
+use crate::layout::Rng;
+
+// Component id per node, numbered in order of each component's lowest node
+// index, and the number of components. Isolated nodes are components of their own.
+pub fn connected_components(node_count: usize, edges: &[[u32; 2]]) -> (Vec<u32>, usize) {
+    let mut parent: Vec<usize> = (0..node_count).collect();
+    for &[source, target] in edges {
+        let (source, target) = (source as usize, target as usize);
+        if source >= node_count || target >= node_count {
+            continue;
+        }
+        let (a, b) = (find_root(&mut parent, source), find_root(&mut parent, target));
+        if a != b {
+            // The lower root wins, so roots are the lowest index of their component
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut ids = vec![u32::MAX; node_count];
+    let mut count = 0;
+    for node in 0..node_count {
+        let root = find_root(&mut parent, node);
+        if ids[root] == u32::MAX {
+            ids[root] = count as u32;
+            count += 1;
+        }
+        ids[node] = ids[root];
+    }
+    (ids, count)
+}
+
+// Edge ids of a minimum spanning forest: a minimum spanning tree of every
+// connected component, by `costs` per edge id (Kruskal's). Edges with a
+// non-finite cost, self-loops and edges to unknown nodes are never picked. Ids
+// are in the order they were picked, cheapest first.
+pub fn minimum_spanning_forest(node_count: usize, edges: &[[u32; 2]], costs: &[f32]) -> Vec<u32> {
+    let mut order: Vec<usize> = (0..edges.len().min(costs.len())).filter(|&edge| costs[edge].is_finite()).collect();
+    // Stable, so equal costs go in edge id order
+    order.sort_by(|&a, &b| costs[a].total_cmp(&costs[b]));
+
+    let mut parent: Vec<usize> = (0..node_count).collect();
+    let mut forest = Vec::new();
+    for edge in order {
+        let [source, target] = edges[edge];
+        let (source, target) = (source as usize, target as usize);
+        if source >= node_count || target >= node_count {
+            continue;
+        }
+        let (a, b) = (find_root(&mut parent, source), find_root(&mut parent, target));
+        if a != b {
+            parent[a.max(b)] = a.min(b);
+            forest.push(edge as u32);
+        }
+    }
+    forest
+}
+
+// Strongly connected component id per node over the indexed edges as
+// directed links, and the number of components (Tarjan's, iterative so deep
+// graphs don't overflow the stack). Ids are in reverse topological order of
+// the components.
+pub fn strongly_connected_components(node_count: usize, edges: &[[u32; 2]]) -> (Vec<u32>, usize) {
+    let (offsets, targets) = out_links(node_count, edges);
+    let mut index = vec![u32::MAX; node_count];
+    let mut low = vec![0u32; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack = Vec::new();
+    let mut ids = vec![u32::MAX; node_count];
+    let (mut next_index, mut count) = (0u32, 0usize);
+    let mut calls: Vec<(usize, usize)> = Vec::new(); // node and its next out-link
+
+    for root in 0..node_count {
+        if index[root] != u32::MAX {
+            continue;
+        }
+        index[root] = next_index;
+        low[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        calls.push((root, offsets[root]));
+
+        while let Some((node, link)) = calls.last_mut() {
+            let node = *node;
+            if *link < offsets[node + 1] {
+                let target = targets[*link] as usize;
+                *link += 1;
+                if index[target] == u32::MAX {
+                    index[target] = next_index;
+                    low[target] = next_index;
+                    next_index += 1;
+                    stack.push(target);
+                    on_stack[target] = true;
+                    calls.push((target, offsets[target]));
+                } else if on_stack[target] {
+                    low[node] = low[node].min(index[target]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[node]);
+            }
+            if low[node] == index[node] {
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    ids[member] = count as u32;
+                    if member == node {
+                        break;
+                    }
+                }
+                count += 1;
+            }
+        }
+    }
+    (ids, count)
+}
+
+// Search steps directed_cycles takes at most
+pub const CYCLE_SEARCH_BUDGET: usize = 1 << 22;
+
+// Directed cycles over the indexed edges, each as its nodes in order starting
+// from its lowest index, at most `max_length` nodes long. Stops after
+// `max_cycles` cycles or CYCLE_SEARCH_BUDGET search steps, whichever comes
+// first, since dense components have exponentially many. Self-loops are
+// cycles of one node; parallel edges don't repeat a cycle.
+pub fn directed_cycles(node_count: usize, edges: &[[u32; 2]], max_cycles: usize, max_length: usize) -> Vec<Vec<u32>> {
+    if max_cycles == 0 || max_length == 0 {
+        return Vec::new();
+    }
+    let (offsets, targets) = out_links(node_count, edges);
+    let (components, count) = strongly_connected_components(node_count, edges);
+    let mut sizes = vec![0usize; count];
+    for &component in &components {
+        sizes[component as usize] += 1;
+    }
+
+    let mut cycles = Vec::new();
+    let mut budget = CYCLE_SEARCH_BUDGET;
+    let mut on_path = vec![false; node_count];
+    for start in 0..node_count {
+        let component = components[start];
+        let self_loop = targets[offsets[start]..offsets[start + 1]].contains(&(start as u32));
+        if sizes[component as usize] < 2 && !self_loop {
+            continue;
+        }
+        // Only through higher nodes of the same component, so each cycle is
+        // found once, from its lowest node
+        let mut path = vec![start as u32];
+        let mut links = vec![offsets[start]];
+        on_path[start] = true;
+        while let (Some(&node), Some(link)) = (path.last(), links.last_mut()) {
+            let node = node as usize;
+            if *link >= offsets[node + 1] || budget == 0 {
+                on_path[node] = false;
+                path.pop();
+                links.pop();
+                continue;
+            }
+            let target = targets[*link] as usize;
+            *link += 1;
+            budget -= 1;
+            if target == start {
+                if path.len() <= max_length {
+                    cycles.push(path.clone());
+                    if cycles.len() >= max_cycles {
+                        return cycles;
+                    }
+                }
+            } else if target > start && components[target] == component && !on_path[target] && path.len() < max_length {
+                on_path[target] = true;
+                path.push(target as u32);
+                links.push(offsets[target]);
+            }
+        }
+        if budget == 0 {
+            break;
+        }
+    }
+    cycles
+}
+
+// Outgoing links per node in CSR form: node `i` links to
+// `targets[offsets[i]..offsets[i + 1]]`, sorted and without repeats
+fn out_links(node_count: usize, edges: &[[u32; 2]]) -> (Vec<usize>, Vec<u32>) {
+    let mut links: Vec<[u32; 2]> = edges
+        .iter()
+        .copied()
+        .filter(|&[source, target]| (source as usize) < node_count && (target as usize) < node_count)
+        .collect();
+    links.sort_unstable();
+    links.dedup();
+    let mut offsets = vec![0usize; node_count + 1];
+    for &[source, _] in &links {
+        offsets[source as usize + 1] += 1;
+    }
+    for i in 1..offsets.len() {
+        offsets[i] += offsets[i - 1];
+    }
+    (offsets, links.into_iter().map(|[_, target]| target).collect())
+}
+
+// Union-find root of `node`
+fn find_root(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        // Path halving keeps the trees shallow
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+// Node sequence from `source` to `target` with the fewest edges, or the least
+// total cost when `costs` (per edge id, missing entries cost 1) is given. Empty
+// when the target is unreachable; just the source for a path to itself.
+pub fn shortest_path(adjacency: &Adjacency, source: usize, target: usize, costs: Option<&[f32]>) -> Vec<u32> {
+    let node_count = adjacency.node_count();
+    if source >= node_count || target >= node_count {
+        return Vec::new();
+    }
+
+    let mut previous = vec![u32::MAX; node_count];
+    let mut distance = vec![f32::INFINITY; node_count];
+    distance[source] = 0.0;
+
+    match costs {
+        None => {
+            let mut queue = VecDeque::from([source]);
+            while let Some(node) = queue.pop_front() {
+                if node == target {
+                    break;
+                }
+                for &neighbor in adjacency.neighbors(node) {
+                    let neighbor = neighbor as usize;
+                    if distance[neighbor].is_infinite() {
+                        distance[neighbor] = distance[node] + 1.0;
+                        previous[neighbor] = node as u32;
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+        Some(costs) => {
+            let mut heap = BinaryHeap::from([Candidate(0.0, source)]);
+            while let Some(Candidate(cost, node)) = heap.pop() {
+                if node == target {
+                    break;
+                }
+                if cost > distance[node] {
+                    continue; // stale entry
+                }
+                for (&neighbor, &edge_id) in adjacency.neighbors(node).iter().zip(adjacency.incident_edges(node)) {
+                    let neighbor = neighbor as usize;
+                    let next = cost + costs.get(edge_id as usize).copied().unwrap_or(1.0);
+                    if next < distance[neighbor] {
+                        distance[neighbor] = next;
+                        previous[neighbor] = node as u32;
+                        heap.push(Candidate(next, neighbor));
+                    }
+                }
+            }
+        }
+    }
+
+    if distance[target].is_infinite() {
+        return Vec::new();
+    }
+    let mut path = vec![target as u32];
+    while let Some(&node) = path.last().filter(|&&node| node as usize != source) {
+        path.push(previous[node as usize]);
+    }
+    path.reverse();
+    path
+}
+
+// Min-heap entry for Dijkstra: (distance, node), ordered by smallest distance
+struct Candidate(f32, usize);
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.total_cmp(&self.0).then(other.1.cmp(&self.1))
+    }
+}
+
+// Local-moving sweeps per Louvain level before giving up on convergence
+const LOUVAIN_MAX_SWEEPS: usize = 32;
+
+// Louvain modularity optimization (Blondel et al., 2008). Edges weigh 1, or
+// `weights[edge]` when given (missing entries weigh 1). Higher `resolution`
+// favors smaller communities. Nodes are visited in an order shuffled with
+// `seed`, so results are reproducible. Returns community ids numbered in order of
+// each community's lowest node index.
+pub fn louvain(node_count: usize, edges: &[[u32; 2]], weights: Option<&[f32]>, resolution: f32, seed: u64) -> Vec<u32> {
+    // Symmetric weighted adjacency lists; a self-loop row entry holds A_ii, which
+    // counts the loop twice like every other edge of the node
+    let mut graph: Vec<Vec<(usize, f64)>> = vec![Vec::new(); node_count];
+    for (edge_id, &[source, target]) in edges.iter().enumerate() {
+        let (source, target) = (source as usize, target as usize);
+        if source >= node_count || target >= node_count {
+            continue;
+        }
+        let weight = weights.and_then(|weights| weights.get(edge_id)).copied().unwrap_or(1.0).max(0.0) as f64;
+        if source == target {
+            graph[source].push((source, 2.0 * weight));
+        } else {
+            graph[source].push((target, weight));
+            graph[target].push((source, weight));
+        }
+    }
+
+    let mut rng = Rng::new(seed);
+    let mut membership: Vec<usize> = (0..node_count).collect();
+    loop {
+        let (communities, moved) = louvain_local_moves(&graph, resolution as f64, &mut rng);
+        if !moved {
+            break;
+        }
+
+        let (dense, community_count) = renumber(&communities);
+        for community in membership.iter_mut() {
+            *community = dense[*community];
+        }
+        graph = aggregate(&graph, &dense, community_count);
+    }
+
+    renumber(&membership).0.into_iter().map(|id| id as u32).collect()
+}
+
+// One level of local moving; returns each node's community (a node index) and
+// whether any node changed community
+fn louvain_local_moves(graph: &[Vec<(usize, f64)>], resolution: f64, rng: &mut Rng) -> (Vec<usize>, bool) {
+    let node_count = graph.len();
+    let degrees: Vec<f64> = graph.iter().map(|row| row.iter().map(|&(_, weight)| weight).sum()).collect();
+    let total_weight: f64 = degrees.iter().sum();
+    let mut community: Vec<usize> = (0..node_count).collect();
+    if total_weight <= 0.0 {
+        return (community, false);
+    }
+
+    let mut community_degree = degrees.clone();
+    let mut order: Vec<usize> = (0..node_count).collect();
+    for i in (1..node_count).rev() {
+        order.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+
+    // Weight from the current node to each neighboring community
+    let mut link_weight = vec![0.0f64; node_count];
+    let mut linked: Vec<usize> = Vec::new();
+
+    let mut moved = false;
+    for _ in 0..LOUVAIN_MAX_SWEEPS {
+        let mut moves = 0;
+        for &node in &order {
+            let current = community[node];
+            for &(neighbor, weight) in &graph[node] {
+                if neighbor == node || weight <= 0.0 {
+                    continue;
+                }
+                let target = community[neighbor];
+                if link_weight[target] == 0.0 {
+                    linked.push(target);
+                }
+                link_weight[target] += weight;
+            }
+
+            // Gain of joining community c, up to a constant: k_i,in(c) - γ·Σtot(c)·k_i / 2m
+            community_degree[current] -= degrees[node];
+            let gain = |c: usize, link: f64| link - resolution * community_degree[c] * degrees[node] / total_weight;
+            let mut best = current;
+            let mut best_gain = gain(current, link_weight[current]);
+            for &candidate in &linked {
+                let candidate_gain = gain(candidate, link_weight[candidate]);
+                if candidate_gain > best_gain + 1e-12 {
+                    best = candidate;
+                    best_gain = candidate_gain;
+                }
+            }
+            community_degree[best] += degrees[node];
+
+            for &c in &linked {
+                link_weight[c] = 0.0;
+            }
+            linked.clear();
+
+            if best != current {
+                community[node] = best;
+                moves += 1;
+            }
+        }
+
+        if moves == 0 {
+            break;
+        }
+        moved = true;
+    }
+    (community, moved)
+}
+
+// Maps arbitrary labels to 0..count in order of first appearance
+fn renumber(labels: &[usize]) -> (Vec<usize>, usize) {
+    let mut ids = vec![usize::MAX; labels.iter().max().map_or(0, |&max| max + 1)];
+    let mut count = 0;
+    let dense = labels
+        .iter()
+        .map(|&label| {
+            if ids[label] == usize::MAX {
+                ids[label] = count;
+                count += 1;
+            }
+            ids[label]
+        })
+        .collect();
+    (dense, count)
+}
+
+// Collapses each community into one node; internal edges become self-loops
+fn aggregate(graph: &[Vec<(usize, f64)>], community: &[usize], community_count: usize) -> Vec<Vec<(usize, f64)>> {
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); community_count];
+    for (node, &c) in community.iter().enumerate() {
+        members[c].push(node);
+    }
+
+    let mut weight_to = vec![0.0f64; community_count];
+    let mut touched: Vec<usize> = Vec::new();
+    members
+        .iter()
+        .map(|nodes| {
+            for &node in nodes {
+                for &(neighbor, weight) in graph[node].iter().filter(|&&(_, weight)| weight > 0.0) {
+                    let c = community[neighbor];
+                    if weight_to[c] == 0.0 {
+                        touched.push(c);
+                    }
+                    weight_to[c] += weight;
+                }
+            }
+            touched.sort_unstable();
+            let row = touched.iter().map(|&c| (c, weight_to[c])).collect();
+            for &c in &touched {
+                weight_to[c] = 0.0;
+            }
+            touched.clear();
+            row
+        })
+        .collect()
+}
+
+// PageRank over the indexed edges as directed links (source to target), by
+// power iteration. Rank of nodes without outgoing links is spread over all
+// nodes. The result sums to 1. A NaN `damping` means the usual 0.85.
+pub fn pagerank(node_count: usize, edges: &[[u32; 2]], damping: f32, iterations: u32) -> Vec<f32> {
+    if node_count == 0 {
+        return Vec::new();
+    }
+    let damping = if damping.is_nan() { 0.85 } else { damping.clamp(0.0, 1.0) as f64 };
+    let n = node_count as f64;
+
+    let links: Vec<(usize, usize)> = edges
+        .iter()
+        .map(|&[source, target]| (source as usize, target as usize))
+        .filter(|&(source, target)| source < node_count && target < node_count)
+        .collect();
+    let mut out_degree = vec![0usize; node_count];
+    for &(source, _) in &links {
+        out_degree[source] += 1;
+    }
+
+    let mut rank = vec![1.0 / n; node_count];
+    let mut next = vec![0.0f64; node_count];
+    for _ in 0..iterations {
+        let dangling: f64 = (0..node_count).filter(|&node| out_degree[node] == 0).map(|node| rank[node]).sum();
+        next.fill((1.0 - damping + damping * dangling) / n);
+        for &(source, target) in &links {
+            next[target] += damping * rank[source] / out_degree[source] as f64;
+        }
+        std::mem::swap(&mut rank, &mut next);
+    }
+    rank.into_iter().map(|value| value as f32).collect()
+}
+
+// Core number per node (the largest k such that the node is in the k-core),
+// by Batagelj–Zaveršnik bucket peeling in O(nodes + edges)
+pub fn core_numbers(adjacency: &Adjacency) -> Vec<u32> {
+    let node_count = adjacency.node_count();
+    let mut degree: Vec<usize> = (0..node_count).map(|node| adjacency.degree(node)).collect();
+    let max_degree = degree.iter().copied().max().unwrap_or(0);
+
+    // Nodes sorted by degree, with the start of each degree's bucket
+    let mut bucket_start = vec![0usize; max_degree + 2];
+    for &d in &degree {
+        bucket_start[d + 1] += 1;
+    }
+    for d in 1..bucket_start.len() {
+        bucket_start[d] += bucket_start[d - 1];
+    }
+    let mut sorted = vec![0usize; node_count];
+    let mut position = vec![0usize; node_count];
+    let mut fill = bucket_start.clone();
+    for node in 0..node_count {
+        position[node] = fill[degree[node]];
+        sorted[position[node]] = node;
+        fill[degree[node]] += 1;
+    }
+
+    for i in 0..node_count {
+        let node = sorted[i];
+        for &neighbor in adjacency.neighbors(node) {
+            let neighbor = neighbor as usize;
+            if degree[neighbor] > degree[node] {
+                // Move the neighbor to the front of its bucket, then shrink the bucket
+                let d = degree[neighbor];
+                let front = bucket_start[d].max(i + 1);
+                let swapped = sorted[front];
+                if swapped != neighbor {
+                    sorted.swap(front, position[neighbor]);
+                    position[swapped] = position[neighbor];
+                    position[neighbor] = front;
+                }
+                bucket_start[d] = front + 1;
+                degree[neighbor] -= 1;
+            }
+        }
+    }
+    degree.into_iter().map(|core| core as u32).collect()
+}
+
+// Nodes within `max_hops` edges of `root`, root first and then in BFS order.
+// With `outgoing_only`, edges are only followed from source to target.
+pub fn k_hop_neighborhood(adjacency: &Adjacency, edges: &[[u32; 2]], root: usize, max_hops: u32, outgoing_only: bool) -> Vec<u32> {
+    let node_count = adjacency.node_count();
+    if root >= node_count {
+        return Vec::new();
+    }
+
+    let mut hops = vec![u32::MAX; node_count];
+    hops[root] = 0;
+    let mut visited = vec![root as u32];
+    let mut head = 0;
+    while let Some(&node) = visited.get(head) {
+        head += 1;
+        let node = node as usize;
+        if hops[node] >= max_hops {
+            continue;
+        }
+        for (&neighbor, &edge_id) in adjacency.neighbors(node).iter().zip(adjacency.incident_edges(node)) {
+            if outgoing_only && edges.get(edge_id as usize).is_some_and(|&[source, _]| source as usize != node) {
+                continue;
+            }
+            if hops[neighbor as usize] == u32::MAX {
+                hops[neighbor as usize] = hops[node] + 1;
+                visited.push(neighbor);
+            }
+        }
+    }
+    visited
+}
+
+// Brandes' betweenness centrality over the indexed edges, unweighted and
+// undirected, run a batch of sources at a time so callers can yield between
+// batches. With a sample of sources the scores are scaled up to estimate the
+// exact ones. Scores are unnormalized: the number of shortest paths between
+// other node pairs through each node, split evenly among equally short ones.
+pub struct Betweenness {
+    sources: Vec<u32>,
+    next: usize, // sources done
+    scores: Vec<f64>,
+    // Per-source scratch, kept for its capacity
+    distance: Vec<u32>,
+    paths: Vec<f64>,
+    dependency: Vec<f64>,
+    order: Vec<u32>,
+}
+
+impl Betweenness {
+    // Every node is a source when `sample_size` is 0 or at least the node
+    // count; otherwise a `seed`ed random sample of that many
+    pub fn new(node_count: usize, sample_size: usize, seed: u64) -> Self {
+        let mut sources: Vec<u32> = (0..node_count as u32).collect();
+        if sample_size > 0 && sample_size < node_count {
+            // Partial Fisher-Yates: the first `sample_size` entries are the sample
+            let mut rng = Rng::new(seed);
+            for i in 0..sample_size {
+                let j = i + (rng.next_u64() % (node_count - i) as u64) as usize;
+                sources.swap(i, j);
+            }
+            sources.truncate(sample_size);
+        }
+        Self {
+            sources,
+            next: 0,
+            scores: vec![0.0; node_count],
+            distance: vec![u32::MAX; node_count],
+            paths: vec![0.0; node_count],
+            dependency: vec![0.0; node_count],
+            order: Vec::with_capacity(node_count),
+        }
+    }
+
+    // Share of the sources done, 0 to 1
+    pub fn progress(&self) -> f32 {
+        if self.sources.is_empty() { 1.0 } else { self.next as f32 / self.sources.len() as f32 }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.sources.len()
+    }
+
+    // Runs up to `count` more sources
+    pub fn step(&mut self, adjacency: &Adjacency, count: usize) {
+        let end = (self.next + count).min(self.sources.len());
+        for i in self.next..end {
+            self.accumulate(adjacency, self.sources[i] as usize);
+        }
+        self.next = end;
+    }
+
+    // Scores per node from the sources done so far
+    pub fn scores(&self) -> Vec<f32> {
+        // Each pair is reached from both of its ends when every node is a source
+        let scale = 0.5 * self.scores.len() as f64 / self.sources.len().max(1) as f64;
+        self.scores.iter().map(|&score| (score * scale) as f32).collect()
+    }
+
+    fn accumulate(&mut self, adjacency: &Adjacency, source: usize) {
+        if source >= adjacency.node_count() {
+            return;
+        }
+        for &node in &self.order {
+            let node = node as usize;
+            self.distance[node] = u32::MAX;
+            self.paths[node] = 0.0;
+            self.dependency[node] = 0.0;
+        }
+        self.order.clear();
+
+        // Breadth-first from the source, counting shortest paths to each node
+        self.distance[source] = 0;
+        self.paths[source] = 1.0;
+        self.order.push(source as u32);
+        let mut head = 0;
+        while let Some(&node) = self.order.get(head) {
+            head += 1;
+            let node = node as usize;
+            for &neighbor in adjacency.neighbors(node) {
+                let neighbor = neighbor as usize;
+                if self.distance[neighbor] == u32::MAX {
+                    self.distance[neighbor] = self.distance[node] + 1;
+                    self.order.push(neighbor as u32);
+                }
+                if self.distance[neighbor] == self.distance[node] + 1 {
+                    self.paths[neighbor] += self.paths[node];
+                }
+            }
+        }
+
+        // Back from the farthest nodes, passing each node's dependency to its
+        // predecessors in proportion to the paths through them
+        for &node in self.order.iter().rev() {
+            let node = node as usize;
+            for &neighbor in adjacency.neighbors(node) {
+                let neighbor = neighbor as usize;
+                if self.distance[neighbor] != u32::MAX && self.distance[neighbor] + 1 == self.distance[node] {
+                    self.dependency[neighbor] += self.paths[neighbor] / self.paths[node] * (1.0 + self.dependency[node]);
+                }
+            }
+            if node != source {
+                self.scores[node] += self.dependency[node];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn louvain_recovers_planted_cliques() {
+        // Four 6-cliques in a ring, each joined to the next by one bridge edge
+        let (cliques, size) = (4u32, 6u32);
+        let mut edges = Vec::new();
+        for clique in 0..cliques {
+            let first = clique * size;
+            for a in first..first + size {
+                for b in a + 1..first + size {
+                    edges.push([a, b]);
+                }
+            }
+            edges.push([first, (first + size + 1) % (cliques * size)]);
+        }
+        let node_count = (cliques * size) as usize;
+        let planted: Vec<u32> = (0..node_count as u32).map(|node| node / size).collect();
+        for seed in [1, 2, 3] {
+            let communities = louvain(node_count, &edges, None, 1.0, seed);
+            assert_eq!(communities, planted, "seed {seed}");
+            assert_eq!(communities, louvain(node_count, &edges, None, 1.0, seed));
+        }
+    }
+
+    #[test]
+    fn louvain_is_reproducible_for_a_seed() {
+        // Sparse random graph, where the visiting order matters
+        let mut rng = Rng::new(11);
+        let edges: Vec<[u32; 2]> = (0..400).map(|_| [(rng.next_u64() % 200) as u32, (rng.next_u64() % 200) as u32]).collect();
+        for seed in [1, 2, 3] {
+            assert_eq!(louvain(200, &edges, None, 1.0, seed), louvain(200, &edges, None, 1.0, seed));
+        }
+    }
+
+    fn betweenness(node_count: usize, edges: &[[u32; 2]]) -> Vec<f32> {
+        let adjacency = Adjacency::new(node_count, edges);
+        let mut betweenness = Betweenness::new(node_count, 0, 0);
+        while !betweenness.is_done() {
+            betweenness.step(&adjacency, 2);
+        }
+        betweenness.scores()
+    }
+
+    // Two triangles, 0-1-2 and 3-4-5, joined by the bridge 2-3
+    const BARBELL: [[u32; 2]; 7] = [[0, 1], [1, 2], [0, 2], [2, 3], [3, 4], [4, 5], [3, 5]];
+
+    #[test]
+    fn betweenness_counts_the_shortest_paths_through_each_node() {
+        // The star center is on the path between each of the 6 leaf pairs
+        assert_eq!(betweenness(5, &[[0, 1], [0, 2], [0, 3], [0, 4]]), [6.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(betweenness(5, &[[0, 1], [1, 2], [2, 3], [3, 4]]), [0.0, 3.0, 4.0, 3.0, 0.0]);
+        assert_eq!(betweenness(6, &BARBELL), [0.0, 0.0, 6.0, 6.0, 0.0, 0.0]);
+        // Two equally short paths share the pair between them
+        assert_eq!(betweenness(4, &[[0, 1], [1, 2], [2, 3], [3, 0]]), [0.5; 4]);
+    }
+
+    #[test]
+    fn sampled_betweenness_is_scaled_to_the_node_count() {
+        let adjacency = Adjacency::new(6, &BARBELL);
+        let mut betweenness = Betweenness::new(6, 3, 5);
+        assert_eq!(betweenness.progress(), 0.0);
+        betweenness.step(&adjacency, 10);
+        assert!(betweenness.is_done());
+        let scores = betweenness.scores();
+        assert!(scores.iter().all(|score| score.is_finite() && *score >= 0.0));
+        assert_eq!(scores[0..2], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn core_numbers_peel_the_graph() {
+        let cores = |node_count, edges: &[[u32; 2]]| core_numbers(&Adjacency::new(node_count, edges));
+        assert_eq!(cores(5, &[[0, 1], [0, 2], [0, 3], [0, 4]]), [1; 5]);
+        assert_eq!(cores(5, &[[0, 1], [1, 2], [2, 3], [3, 4]]), [1; 5]);
+        assert_eq!(cores(6, &BARBELL), [2; 6]);
+        // A 4-clique with a two-node tail, and an isolated node
+        let tail = [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3], [3, 4], [4, 5]];
+        assert_eq!(cores(7, &tail), [3, 3, 3, 3, 1, 1, 0]);
+    }
+
+    // Both directions of each edge, as for an undirected graph
+    fn both_ways(edges: &[[u32; 2]]) -> Vec<[u32; 2]> {
+        edges.iter().flat_map(|&[a, b]| [[a, b], [b, a]]).collect()
+    }
+
+    #[test]
+    fn pagerank_ranks_central_nodes_higher() {
+        let star = pagerank(5, &both_ways(&[[0, 1], [0, 2], [0, 3], [0, 4]]), 0.85, 50);
+        assert!(star[1..].iter().all(|&leaf| leaf < star[0] && (leaf - star[1]).abs() < 1e-6));
+
+        let path = pagerank(5, &both_ways(&[[0, 1], [1, 2], [2, 3], [3, 4]]), 0.85, 50);
+        assert!(path[0] < path[2] && path[4] < path[2]);
+        assert!((path[0] - path[4]).abs() < 1e-6 && (path[1] - path[3]).abs() < 1e-6);
+
+        let complete: Vec<[u32; 2]> = (0..4).flat_map(|a| (0..4).filter(move |&b| b != a).map(move |b| [a, b])).collect();
+        assert!(pagerank(4, &complete, 0.85, 50).iter().all(|&rank| (rank - 0.25).abs() < 1e-6));
+    }
+
+    #[test]
+    fn pagerank_sums_to_one_without_nans() {
+        // Node 3 has no outgoing links; an out-of-range edge is ignored
+        let edges = [[0, 1], [1, 2], [2, 0], [2, 3], [0, 9]];
+        for damping in [0.85, 0.0, 1.0, -1.0, 2.0, f32::NAN] {
+            let ranks = pagerank(4, &edges, damping, 30);
+            assert!(ranks.iter().all(|rank| rank.is_finite()), "{damping}: {ranks:?}");
+            assert!((ranks.iter().sum::<f32>() - 1.0).abs() < 1e-4, "{damping}: {ranks:?}");
+        }
+        assert!(pagerank(0, &[], 0.85, 30).is_empty());
+    }
+}
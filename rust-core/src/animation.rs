@@ -0,0 +1,240 @@
+// Timed transitions of node positions, opacity and the camera, advanced by the
+// render loop
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn, // cubic
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn parse(easing: &str) -> Option<Self> {
+        match easing {
+            "linear" => Some(Self::Linear),
+            "ease-in" => Some(Self::EaseIn),
+            "ease-out" => Some(Self::EaseOut),
+            "ease-in-out" => Some(Self::EaseInOut),
+            _ => None,
+        }
+    }
+
+    // Maps linear progress in [0, 1] to eased progress in [0, 1]
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) * 0.5
+                }
+            }
+        }
+    }
+}
+
+// A node's color and size: r, g, b, a, size
+pub type Appearance = [f32; 5];
+
+pub struct PositionAnimation {
+    from: Vec<[f32; 2]>,
+    to: Vec<[f32; 2]>,
+    looks: Option<(Vec<Appearance>, Vec<Appearance>)>, // from and to, if restyling too
+    final_node_count: usize, // nodes beyond it are removed at the end
+    duration_ms: f32,
+    easing: Easing,
+    start_time: Option<f64>, // render time (seconds) of the first frame
+}
+
+impl PositionAnimation {
+    pub fn new(from: Vec<[f32; 2]>, to: Vec<[f32; 2]>, duration_ms: f32, easing: Easing) -> Self {
+        Self {
+            final_node_count: to.len(),
+            from,
+            to,
+            looks: None,
+            duration_ms: duration_ms.max(0.0),
+            easing,
+            start_time: None,
+        }
+    }
+
+    // Also blends colors and sizes, and drops the nodes from `final_node_count`
+    // on once finished (their targets should fade them out)
+    pub fn with_appearance(mut self, from: Vec<Appearance>, to: Vec<Appearance>, final_node_count: usize) -> Self {
+        self.looks = Some((from, to));
+        self.final_node_count = final_node_count.min(self.to.len());
+        self
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.to.len()
+    }
+
+    pub fn targets(&self) -> &[[f32; 2]] {
+        &self.to
+    }
+
+    pub fn target_appearance(&self) -> Option<&[Appearance]> {
+        self.looks.as_ref().map(|(_, to)| to.as_slice())
+    }
+
+    pub fn final_node_count(&self) -> usize {
+        self.final_node_count
+    }
+
+    // Positions (and appearances, if restyling) at render `time`, and whether
+    // the animation has finished. The clock starts at the first frame, so setup
+    // time doesn't eat into it.
+    pub fn frame(&mut self, time: f64) -> (Vec<[f32; 2]>, Option<Vec<Appearance>>, bool) {
+        let start = *self.start_time.get_or_insert(time);
+        let elapsed_ms = ((time - start) * 1000.0) as f32;
+        let t = if self.duration_ms > 0.0 { elapsed_ms / self.duration_ms } else { 1.0 };
+        let eased = self.easing.apply(t);
+
+        let positions = self
+            .from
+            .iter()
+            .zip(&self.to)
+            .map(|(from, to)| [from[0] + (to[0] - from[0]) * eased, from[1] + (to[1] - from[1]) * eased])
+            .collect();
+        let looks = self.looks.as_ref().map(|(from, to)| {
+            from.iter()
+                .zip(to)
+                .map(|(from, to)| std::array::from_fn(|i| from[i] + (to[i] - from[i]) * eased))
+                .collect()
+        });
+        (positions, looks, t >= 1.0)
+    }
+}
+
+// A timed change of the global opacity, on the same clock as PositionAnimation
+pub struct OpacityFade {
+    from: f32,
+    to: f32,
+    duration_ms: f32,
+    start_time: Option<f64>,
+}
+
+impl OpacityFade {
+    pub fn new(from: f32, to: f32, duration_ms: f32) -> Self {
+        Self {
+            from,
+            to,
+            duration_ms: duration_ms.max(0.0),
+            start_time: None,
+        }
+    }
+
+    // Opacity at render `time`, and whether the fade has finished
+    pub fn frame(&mut self, time: f64) -> (f32, bool) {
+        let start = *self.start_time.get_or_insert(time);
+        let elapsed_ms = ((time - start) * 1000.0) as f32;
+        let t = if self.duration_ms > 0.0 { elapsed_ms / self.duration_ms } else { 1.0 };
+        let eased = Easing::EaseInOut.apply(t);
+        (self.from + (self.to - self.from) * eased, t >= 1.0)
+    }
+}
+
+// A camera fly-through of a list of nodes: travels to each one in turn, then
+// dwells on it. A leg's destination is looked up when the leg starts, so the
+// nodes may move (e.g. while simulating) during the tour. Works on the view
+// center rather than the camera corner, so zooming keeps the node centered.
+pub struct CameraTour {
+    stops: Vec<u32>,
+    dwell_ms: f32,
+    travel_ms: f32,
+    zoom: f32,
+    next: usize, // the stop the current leg heads for
+    leg: Option<TourLeg>,
+}
+
+struct TourLeg {
+    from: [f32; 2],
+    from_zoom: f32,
+    to: [f32; 2],
+    start_time: f64,
+}
+
+impl CameraTour {
+    pub fn new(stops: Vec<u32>, dwell_ms: f32, travel_ms: f32, zoom: f32) -> Self {
+        Self {
+            stops,
+            dwell_ms: dwell_ms.max(0.0),
+            travel_ms: travel_ms.max(0.0),
+            zoom,
+            next: 0,
+            leg: None,
+        }
+    }
+
+    // View center and zoom at render `time`, starting from the given ones, or
+    // None once the last dwell is over. `position_of` is None for stops that
+    // no longer exist, which are skipped.
+    pub fn frame(&mut self, time: f64, center: [f32; 2], zoom: f32, position_of: impl Fn(u32) -> Option<[f32; 2]>) -> Option<([f32; 2], f32)> {
+        let (mut center, mut zoom) = (center, zoom);
+        loop {
+            if self.leg.is_none() {
+                let &stop = self.stops.get(self.next)?;
+                match position_of(stop) {
+                    Some(to) => self.leg = Some(TourLeg { from: center, from_zoom: zoom, to, start_time: time }),
+                    None => {
+                        self.next += 1;
+                        continue;
+                    }
+                }
+            }
+
+            let leg = self.leg.as_ref()?;
+            let elapsed_ms = ((time - leg.start_time) * 1000.0) as f32;
+            if elapsed_ms < self.travel_ms {
+                let t = Easing::EaseInOut.apply(elapsed_ms / self.travel_ms);
+                let position = [leg.from[0] + (leg.to[0] - leg.from[0]) * t, leg.from[1] + (leg.to[1] - leg.from[1]) * t];
+                // Geometric, so zooming in and out feel equally fast
+                return Some((position, leg.from_zoom * (self.zoom / leg.from_zoom).powf(t)));
+            }
+            if elapsed_ms < self.travel_ms + self.dwell_ms {
+                return Some((leg.to, self.zoom));
+            }
+
+            (center, zoom) = (leg.to, self.zoom);
+            self.next += 1;
+            self.leg = None;
+        }
+    }
+}
+
+// A single flight to one node that ends with it centered at `zoom`. Unlike a
+// tour leg, the node's position is read every frame, so a node the
+// simulation is moving stays the destination. Also works on the view center.
+pub struct CameraFocus {
+    node: u32,
+    duration_ms: f32,
+    zoom: f32,
+    from: Option<([f32; 2], f32, f64)>, // view center, zoom and render time at the first frame
+}
+
+impl CameraFocus {
+    pub fn new(node: u32, duration_ms: f32, zoom: f32) -> Self {
+        Self { node, duration_ms: duration_ms.max(0.0), zoom, from: None }
+    }
+
+    // View center and zoom at render `time`, starting from the given ones, and
+    // whether the flight is over. None once the node no longer exists.
+    pub fn frame(&mut self, time: f64, center: [f32; 2], zoom: f32, position_of: impl Fn(u32) -> Option<[f32; 2]>) -> Option<([f32; 2], f32, bool)> {
+        let to = position_of(self.node)?;
+        let &mut (from, from_zoom, start_time) = self.from.get_or_insert((center, zoom, time));
+        let elapsed_ms = ((time - start_time) * 1000.0) as f32;
+        if elapsed_ms >= self.duration_ms {
+            return Some((to, self.zoom, true));
+        }
+        let t = Easing::EaseInOut.apply(elapsed_ms / self.duration_ms);
+        let position = [from[0] + (to[0] - from[0]) * t, from[1] + (to[1] - from[1]) * t];
+        Some((position, from_zoom * (self.zoom / from_zoom).powf(t), false))
+    }
+}
@@ -0,0 +1,6224 @@
+// The wasm-bindgen API: FastGraphRenderer and the free functions exported to JS
+
+use wasm_bindgen::prelude::*;
+#[cfg(target_arch = "wasm32")]
+use web_sys::HtmlCanvasElement;
+
+use super::*;
+use crate::animation::{Appearance, CameraFocus, CameraTour, Easing, OpacityFade, PositionAnimation};
+use crate::bundling::EdgeBundler;
+use crate::clustering::{self, Clusters};
+use crate::color::parse_css_color;
+use crate::events::{self, CameraChange, CanvasListeners, EventKind, MinimapListeners, PointerInput, ResizeWatcher, TouchGesture};
+use crate::forceatlas2::ForceAtlas2;
+use crate::groups::NodeGroups;
+use crate::history::History;
+use crate::labels;
+use crate::layers::Layers;
+use crate::logging::{self, LogLevel};
+use crate::layout::{Alignment, LayerDirection, SeedStrategy};
+use crate::morton;
+use crate::palettes::{self, Colormap};
+use crate::physics::{self, ActiveSet, PhysicsInputs};
+use crate::picking;
+use crate::quadtree::QuadTree;
+use crate::renderer::{self, Camera, Capabilities, ArrowStyle, EdgeFilter, GradientStyle, IdleMotion, NodeStyle, PulseState, Renderer, MAX_EDGES, MAX_NODES, MAX_OVERLAY_EDGES, MAX_STYLE_CLASSES, MAX_SURFACE_SIZE};
+use crate::scale::{self, ValueScale};
+use crate::search::{LabelIndex, SearchMode};
+use crate::theme::{self, Theme};
+use crate::timeline::TimeIndex;
+use crate::view_state::ViewState;
+
+// Masses are clamped to this so force / mass stays finite
+const MIN_NODE_MASS: f32 = 0.01;
+// Bits of `flags` below the style class
+const NODE_FLAG_BITS: u32 = (1 << NODE_CLASS_SHIFT) - 1;
+const DEFAULT_PHYSICS_RATE: f32 = 60.0;
+const MAX_PHYSICS_STEPS_PER_FRAME: u32 = 4;
+const PRELAYOUT_CHUNK_TICKS: u32 = 25;
+// A local reheat (set_local_reheat) keeps simulating a node while it or a
+// neighbor moves more than this many rest lengths per tick
+const LOCAL_REHEAT_SPEED: f32 = 0.02;
+// Nodes plus edges compute_betweenness visits between yields, and the seed of
+// its source sample
+const BETWEENNESS_BATCH_WORK: usize = 1 << 21;
+const BETWEENNESS_SEED: u64 = 0x6265_7477;
+// Point moves bundle_edges makes between yields, and how far (in mean edge
+// lengths) an endpoint may move before the bundles it was computed for are dropped
+const BUNDLING_BATCH_WORK: usize = 1 << 21;
+const BUNDLE_TOLERANCE: f32 = 0.05;
+// Width factor of the edges highlight_cycles emphasizes
+const CYCLE_EDGE_WIDTH_SCALE: f32 = 2.0;
+// set_auto_clustering draws clusters below this zoom, crossfading over
+// CLUSTER_FADE_MS; the widest bundle is CLUSTER_EDGE_MAX_PX across, and
+// cluster colors come from CLUSTER_COLORMAP by member count
+const CLUSTER_ZOOM_THRESHOLD: f32 = 0.5;
+const CLUSTER_FADE_MS: f32 = 250.0;
+const CLUSTER_EDGE_MAX_PX: f32 = 8.0;
+const CLUSTER_COLORMAP: &str = "viridis";
+const DEFAULT_TICK_EVENT_INTERVAL: u32 = 10;
+// Share of the view focus_node fills with a node's neighborhood, and the
+// flight time of double-click focusing
+const FOCUS_FILL: f32 = 0.6;
+const FOCUS_DURATION_MS: f32 = 500.0;
+const PRELAYOUT_MAX_READBACK_WAITS: u32 = 100;
+const DEFAULT_FREEZE_MAX_ITERATIONS: u32 = 3000;
+
+// Categorical palette used when no palette is given
+const DEFAULT_CATEGORICAL_PALETTE: &str = "tableau10";
+// Color for values that can't be mapped, e.g. NaN in color_by_values
+const DEFAULT_MISSING_COLOR: [f32; 4] = [0.6, 0.6, 0.6, 1.0];
+const DEFAULT_UNDO_LIMIT: usize = 50;
+const BENCHMARK_SEED: u32 = 0x9e37_79b9;
+const DEFAULT_PULSE_GLOW: f32 = 0.5;
+const DEFAULT_SQUIRCLE_CORNER: f32 = 0.5;
+// How far outside a node or edge a click still hits it, in screen pixels
+const PICK_SLOP_PX: f32 = 3.0;
+// Flags only the CPU side of drawing applies; the simulation's copy of the
+// nodes may not have them
+const NODE_DRAW_ONLY_FLAGS: u32 = NODE_FLAG_BELOW_CORE | NODE_FLAG_OUTSIDE_FOCUS | NODE_FLAG_OUTSIDE_TIME | NODE_FLAG_LAYER_HIDDEN;
+const DEFAULT_MINIMAP_INTERVAL: u32 = 10;
+const DEFAULT_MINIMAP_POINT_SIZE: f32 = 2.0;
+// Fraction of the view that fitted bounds fill along the tighter axis
+const FIT_FILL: f32 = 0.9;
+// Preview edges are sized in screen pixels, preview nodes in world units
+const DEFAULT_PREVIEW_EDGE_WIDTH: f32 = 2.0;
+const DEFAULT_PREVIEW_DASH: f32 = 6.0;
+const DEFAULT_PREVIEW_NODE_SIZE: f32 = 10.0;
+const DEFAULT_PREVIEW_ALPHA: f32 = 0.6;
+
+// A node being dragged; it is pinned for the duration of the drag
+struct NodeDrag {
+    index: usize,
+    position: [f32; 2],
+    was_pinned: bool,
+}
+
+// Node and edge colors from before a highlight, restored by clear_highlight
+struct ColorBackup {
+    nodes: Vec<[f32; 4]>,
+    edges: Vec<[f32; 4]>,
+    edge_widths: Vec<f32>, // empty unless the highlight changed widths
+}
+
+// Node positions a worker writes into shared memory, see bind_shared_positions
+struct SharedPositions {
+    view: js_sys::Float32Array, // x, y per node
+    scratch: Vec<f32>,          // the view copied out once per frame
+}
+
+// Everything run_benchmark replaces, put back when it finishes
+struct BenchmarkBackup {
+    graph: UndoSnapshot,
+    camera: ([f32; 2], f32),
+    groups: Option<NodeGroups>,
+    node_damping: Vec<f32>,
+    color_backup: Option<ColorBackup>,
+    physics: PhysicsSettings,
+    layout_algorithm: LayoutAlgorithm,
+    simulation_paused: bool,
+    simulation_running: bool,
+    iteration: u32,
+    interpolate_positions: bool,
+    physics_accumulator: f32,
+    simulation_event_callback: Option<js_sys::Function>,
+    shared_positions: Option<SharedPositions>,
+}
+
+// Graph state saved by push_undo_snapshot. The edges rarely change between
+// snapshots, so they are shared with the neighboring snapshot when equal.
+struct UndoSnapshot {
+    nodes: Vec<NodeData>,
+    edges: std::sync::Arc<EdgeSnapshot>,
+    camera: Option<([f32; 2], f32)>, // with set_undo_camera(true)
+}
+
+struct EdgeSnapshot {
+    edges: Vec<EdgeData>,
+    edge_indices: Vec<[u32; 2]>,
+    edge_lengths: Vec<f32>,
+    edge_weights: Vec<f32>,
+}
+
+impl EdgeSnapshot {
+    // Whether the snapshot holds these edges, compared in place so a match costs
+    // no copy. Endpoints of indexed edges follow the nodes, so only their style
+    // counts.
+    fn holds(&self, edges: &[EdgeData], edge_indices: &[[u32; 2]], edge_lengths: &[f32], edge_weights: &[f32]) -> bool {
+        let style = |edge: &EdgeData| [edge.r, edge.g, edge.b, edge.a, edge.width];
+        let endpoints = |edge: &EdgeData| [edge.x1, edge.y1, edge.x2, edge.y2];
+        self.edge_indices == edge_indices
+            && self.edge_lengths == edge_lengths
+            && self.edge_weights == edge_weights
+            && self.edges.len() == edges.len()
+            && self.edges.iter().zip(edges).enumerate().all(|(i, (a, b))| {
+                style(a) == style(b) && (i < self.edge_indices.len() || endpoints(a) == endpoints(b))
+            })
+    }
+}
+
+// Extents of the visible nodes, cached for get_graph_bounds and get_graph_centroid
+#[derive(Clone, Copy)]
+struct GraphExtent {
+    bounds: [f32; 4], // min x, min y, max x, max y, including radii
+    centroid: [f32; 2],
+    weighted_centroid: [f32; 2], // by mass
+    count: usize,
+}
+
+impl GraphExtent {
+    // None when no visible node has a finite position
+    fn of<'a>(nodes: impl IntoIterator<Item = &'a NodeData>) -> Option<Self> {
+        let mut bounds = [f32::INFINITY, f32::INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY];
+        let (mut sum, mut weighted_sum, mut count, mut mass) = ([0.0f64; 2], [0.0f64; 2], 0usize, 0.0f64);
+        for node in nodes.into_iter().filter(|node| node.flags & NODE_HIDDEN_FLAGS == 0 && node.x.is_finite() && node.y.is_finite()) {
+            bounds = [bounds[0].min(node.x - node.size), bounds[1].min(node.y - node.size), bounds[2].max(node.x + node.size), bounds[3].max(node.y + node.size)];
+            // Summed in f64 so 100k nodes far from the origin don't lose precision
+            sum = [sum[0] + node.x as f64, sum[1] + node.y as f64];
+            weighted_sum = [weighted_sum[0] + (node.x * node.mass) as f64, weighted_sum[1] + (node.y * node.mass) as f64];
+            count += 1;
+            mass += node.mass as f64;
+        }
+        if count == 0 {
+            return None;
+        }
+        let centroid = sum.map(|total| (total / count as f64) as f32);
+        Some(Self {
+            bounds,
+            centroid,
+            weighted_centroid: if mass > 0.0 { weighted_sum.map(|total| (total / mass) as f32) } else { centroid },
+            count,
+        })
+    }
+}
+
+// The edge endpoints bundle_edges bundled, to tell when they've moved
+struct BundledEnds {
+    ends: Vec<[[f32; 2]; 2]>, // by edge index
+    tolerance: f32,           // world units
+}
+
+// The clusters set_auto_clustering draws, and what they were built from
+struct ClusterCache {
+    bucket: i32,
+    pixel_radius: f32,
+    fingerprint: u64, // of the shown nodes' positions and the shown edges
+    clusters: Clusters,
+}
+
+// The render transform from world to CSS pixels, for get_screen_positions
+struct ScreenTransform {
+    projection: Projection, // the canvas size is its resolution
+    css_scale: [f32; 2],    // CSS pixels per canvas pixel
+}
+
+impl ScreenTransform {
+    // x, y in CSS pixels and 1 if a disc of `radius` there is at least partly
+    // on the canvas, else 0
+    fn apply(&self, position: [f32; 2], radius: f32) -> [f32; 3] {
+        let pixel = self.projection.world_to_pixel(position);
+        let reach = radius * self.projection.camera.zoom;
+        let on_canvas = (0..2).all(|axis| pixel[axis] + reach >= 0.0 && pixel[axis] - reach <= self.projection.resolution[axis]);
+        [pixel[0] * self.css_scale[0], pixel[1] * self.css_scale[1], if on_canvas { 1.0 } else { 0.0 }]
+    }
+
+    // The world box from `min` to `max` as x, y, width, height in CSS pixels,
+    // and whether any of it is on the canvas
+    fn rect(&self, min: [f32; 2], max: [f32; 2]) -> ([f32; 4], bool) {
+        let [x0, y0, _] = self.apply(min, 0.0);
+        let [x1, y1, _] = self.apply(max, 0.0);
+        let view = [0, 1].map(|axis| self.projection.resolution[axis] * self.css_scale[axis]);
+        let on_canvas = x1 >= 0.0 && x0 <= view[0] && y1 >= 0.0 && y0 <= view[1];
+        ([x0, y0, x1 - x0, y1 - y0], on_canvas)
+    }
+
+    // The world point under `css` pixels from the canvas's top-left corner
+    fn to_world(&self, css: [f32; 2]) -> [f32; 2] {
+        let Camera { position, zoom } = self.projection.camera;
+        [0, 1].map(|axis| position[axis] + css[axis] / (self.css_scale[axis] * zoom))
+    }
+
+    // The same mapping as a CSS matrix(a, b, c, d, e, f); y points down in both
+    // spaces and the camera doesn't rotate, so b and c are 0
+    fn css_matrix(&self) -> [f32; 6] {
+        let Camera { position, zoom } = self.projection.camera;
+        let [sx, sy] = self.css_scale.map(|scale| scale * zoom);
+        [sx, 0.0, 0.0, sy, -position[0] * sx, -position[1] * sy]
+    }
+}
+
+// The edge set_preview_edge draws from a node to a point, redrawn every frame
+// so it follows the node
+struct PreviewEdge {
+    source: usize,
+    target: [f32; 2],
+    color: [f32; 4],
+    width: f32, // screen pixels
+    dash: f32,  // screen pixels of each dash and gap; 0 is solid
+}
+
+// Node and edge indices sorted into layer order, cached for the counts they
+// were built for
+struct LayerOrder {
+    nodes: Vec<u32>,
+    edges: Vec<u32>,
+}
+
+// A minimap attached with attach_minimap. It redraws every `interval` frames,
+// and on the next frame once the graph, the main view or its canvas changes.
+struct Minimap {
+    canvas: web_sys::HtmlCanvasElement,
+    listeners: Option<MinimapListeners>, // with navigation on
+    interval: u32,
+    point_size: f32,
+    viewport_color: [f32; 4],
+    frames_since_draw: u32,
+    stale: bool,                                     // the graph changed since the last draw
+    drawn_view: Option<([f32; 2], f32, (u32, u32))>, // main camera and surface size of the last draw
+    camera: Option<([f32; 2], f32)>,                 // the minimap's own camera in the last draw
+}
+
+// The camera that shows `bounds` (min x, min y, max x, max y) whole and
+// centered on a surface of `size` pixels
+fn fit_camera(bounds: [f32; 4], size: (u32, u32)) -> ([f32; 2], f32) {
+    let size = [size.0 as f32, size.1 as f32];
+    let span = [(bounds[2] - bounds[0]).max(1.0), (bounds[3] - bounds[1]).max(1.0)];
+    let zoom = (size[0] / span[0]).min(size[1] / span[1]) * FIT_FILL;
+    let center = [(bounds[0] + bounds[2]) * 0.5, (bounds[1] + bounds[3]) * 0.5];
+    ([center[0] - size[0] * 0.5 / zoom, center[1] - size[1] * 0.5 / zoom], zoom)
+}
+
+#[wasm_bindgen]
+pub struct FastGraphRenderer {
+    renderer: Renderer,
+    color1: [f32; 4],
+    color2: [f32; 4],
+    nodes: Vec<NodeData>,
+    edges: Vec<EdgeData>,
+    edge_indices: Vec<[u32; 2]>, // source/target per edge, empty for positional edges
+    edge_lengths: Vec<f32>,      // per-edge rest lengths, <= 0 uses the global one
+    edge_weights: Vec<f32>,      // per-edge spring strength multipliers, missing means 1
+    edge_arrows: Vec<(u32, f32)>, // set_edge_arrows overrides as EdgeFilter arrow_style and arrow_size, missing means none
+    adjacency: Option<Adjacency>,
+    topology_dirty: bool,
+    groups: Option<NodeGroups>,
+    groups_dirty: bool,
+    node_damping: Vec<f32>, // see set_node_damping, empty when unset
+    damping_dirty: bool,
+    physics: PhysicsSettings,
+    layout_algorithm: LayoutAlgorithm,
+    forceatlas2: ForceAtlas2,
+    kinetic_energy: f32,
+    drag: Option<NodeDrag>,
+    local_reheat: Option<u32>, // the hops around perturbed nodes a reheat simulates, while on
+    active_set: Option<ActiveSet>, // during a local reheat
+    physics_tick_ms: f64, // CPU time of the last tick, including dispatching a GPU one
+    color_backup: Option<ColorBackup>,
+    missing_color: [f32; 4],
+    theme: Theme,
+    theme_name: String,
+    custom_themes: std::collections::HashMap<String, Theme>,
+    camera_position: [f32; 2],
+    camera_zoom: f32,
+    is_initialized: bool,
+    is_rendering: bool,
+    physics_dirty: bool,
+    physics_rate: f32,        // fixed ticks per second for step_simulation
+    physics_accumulator: f32, // real time not yet simulated, in seconds
+    interpolate_positions: bool,
+    previous_positions: Vec<[f32; 2]>, // the two latest simulated states, for interpolation
+    latest_positions: Vec<[f32; 2]>,
+    simulation_paused: bool,
+    simulation_running: bool, // a "start" event was sent without a matching "end"
+    iteration: u32,           // ticks since the simulation last started
+    simulation_event_callback: Option<js_sys::Function>,
+    tick_event_interval: u32,
+    animation: Option<PositionAnimation>,
+    global_opacity: f32,
+    opacity_fade: Option<OpacityFade>,
+    last_error: Option<String>,
+    render_error_callback: Option<js_sys::Function>,
+    warning_callback: Option<js_sys::Function>,
+    truncated: [bool; 2], // whether nodes and edges are over MAX_NODES and MAX_EDGES, see check_capacity
+    history: History<UndoSnapshot>,
+    undo_camera: bool,
+    shared_positions: Option<SharedPositions>,
+    graph_loading: bool, // between begin_graph_load and end_graph_load
+    pulses: Vec<PulseState>, // by node index, empty when none is running
+    pulses_dirty: bool,
+    pulses_filtered: bool, // the GPU copy is in visible_frame order
+    node_phases: Vec<f32>, // idle motion phases by node index, empty when all are 0
+    phases_dirty: bool,    // the phases go up with the pulses
+    user_flags: Vec<u32>, // set_node_flags, one per node or empty
+    visibility_filter: [u32; 2], // required and forbidden user flags
+    user_flags_dirty: bool,
+    user_flags_filtered: bool, // the GPU copy is in visible_frame order
+    preview_edge: Option<PreviewEdge>,
+    preview_node: Option<NodeData>,
+    layers: Layers,
+    node_layers: Vec<u32>, // empty puts every node in layer 0
+    edge_layers: Vec<u32>, // ignored unless there's one per edge
+    layer_order: Option<LayerOrder>,
+    spatial_sorting: bool, // see set_spatial_sorting
+    pulse_glow: f32,
+    edge_times: Option<TimeIndex>,
+    node_times: Option<TimeIndex>,
+    time_window: Option<[f32; 2]>, // as given to set_time_window, without the margin
+    time_fade_margin: f32,
+    core_filter: u32, // k of the last filter_by_core
+    extent: Option<Option<GraphExtent>>, // None until computed after the nodes last changed
+    camera_tour: Option<CameraTour>,
+    camera_focus: Option<CameraFocus>, // see focus_node
+    camera_tour_callback: Option<js_sys::Function>,
+    canvas: Option<web_sys::HtmlCanvasElement>,
+    canvas_listeners: Option<CanvasListeners>, // attached while a mouse event has a callback or touch gestures are on
+    event_callbacks: std::collections::HashMap<EventKind, js_sys::Function>,
+    hovered_node: Option<u32>,
+    hovered_edge: Option<u32>,
+    edge_hit_px: f32, // see set_edge_hit_width
+    split_seam_edges: bool, // see set_split_seam_edges
+    position_waiters: Vec<js_sys::Function>, // read_positions promises waiting on the readback in flight
+    touch_gestures: bool,
+    gravity_to_center_of_mass: bool, // see set_gravity_to_center_of_mass
+    double_click_focus: bool,
+    auto_clustering: Option<f32>, // the pixel radius, while on
+    cluster_cache: Option<ClusterCache>,
+    cluster_blend: f32, // 0 draws the nodes, 1 the clusters, between crossfades
+    cluster_fade: Option<(OpacityFade, f32)>, // and the blend it heads for
+    clusters_drawn: bool, // the last frame drew clusters, with the GPU visibility filter off
+    bundled_ends: Option<BundledEnds>, // while the renderer has edge bundles
+    decimation: Option<f32>, // the target spacing in CSS pixels, while on
+    decimating: bool,        // this frame thins the nodes out, see update_decimation
+    decimated_nodes: u32,    // node instances the last frame skipped
+    touch_gesture: TouchGesture,
+    tap_clicked: bool, // a tap was reported, so the browser's click after it isn't
+    observe_resize: bool,
+    resize_watcher: Option<ResizeWatcher>,
+    observed_size: Option<[f32; 2]>, // the canvas's CSS size, while observing
+    pixel_ratio: f32,                // canvas pixels per CSS pixel, 0 follows devicePixelRatio
+    reported_viewport: ([f32; 2], f32), // camera as of the last viewportChange
+    minimap: Option<Minimap>,
+    node_labels: Vec<String>,                     // by node index, for find_nodes
+    edge_labels: Vec<String>,                     // by edge index, see get_edge_label_positions
+    label_index: std::cell::OnceCell<LabelIndex>, // built by the first search after the labels change
+}
+
+impl Default for FastGraphRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[wasm_bindgen]
+impl FastGraphRenderer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        console_error_panic_hook::set_once();
+        log!("Initializing FastGraph renderer...");
+
+        Self {
+            renderer: Renderer::new(),
+            color1: [1.0, 0.0, 0.0, 1.0], // Default red
+            color2: [0.0, 0.0, 1.0, 1.0], // Default blue
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            edge_indices: Vec::new(),
+            edge_lengths: Vec::new(),
+            edge_weights: Vec::new(),
+            edge_arrows: Vec::new(),
+            adjacency: None,
+            topology_dirty: true,
+            groups: None,
+            groups_dirty: false,
+            node_damping: Vec::new(),
+            damping_dirty: false,
+            physics: PhysicsSettings::default(),
+            layout_algorithm: LayoutAlgorithm::SpringElectric,
+            forceatlas2: ForceAtlas2::default(),
+            kinetic_energy: 0.0,
+            drag: None,
+            local_reheat: None,
+            active_set: None,
+            physics_tick_ms: 0.0,
+            color_backup: None,
+            missing_color: DEFAULT_MISSING_COLOR,
+            theme: Theme::default(),
+            theme_name: "default".to_string(),
+            custom_themes: std::collections::HashMap::new(),
+            camera_position: [0.0, 0.0],
+            camera_zoom: 1.0,
+            is_initialized: false,
+            is_rendering: false,
+            physics_dirty: true,
+            physics_rate: DEFAULT_PHYSICS_RATE,
+            physics_accumulator: 0.0,
+            interpolate_positions: false,
+            previous_positions: Vec::new(),
+            latest_positions: Vec::new(),
+            simulation_paused: false,
+            simulation_running: false,
+            iteration: 0,
+            simulation_event_callback: None,
+            tick_event_interval: DEFAULT_TICK_EVENT_INTERVAL,
+            animation: None,
+            global_opacity: 1.0,
+            opacity_fade: None,
+            last_error: None,
+            render_error_callback: None,
+            warning_callback: None,
+            truncated: [false; 2],
+            history: History::new(DEFAULT_UNDO_LIMIT),
+            undo_camera: false,
+            shared_positions: None,
+            graph_loading: false,
+            pulses: Vec::new(),
+            pulses_dirty: false,
+            pulses_filtered: false,
+            node_phases: Vec::new(),
+            phases_dirty: false,
+            user_flags: Vec::new(),
+            visibility_filter: [0, 0],
+            user_flags_dirty: false,
+            user_flags_filtered: false,
+            preview_edge: None,
+            preview_node: None,
+            layers: Layers::default(),
+            node_layers: Vec::new(),
+            edge_layers: Vec::new(),
+            layer_order: None,
+            spatial_sorting: false,
+            pulse_glow: DEFAULT_PULSE_GLOW,
+            edge_times: None,
+            node_times: None,
+            time_window: None,
+            time_fade_margin: 0.0,
+            core_filter: 0,
+            extent: None,
+            camera_tour: None,
+            camera_focus: None,
+            camera_tour_callback: None,
+            canvas: None,
+            canvas_listeners: None,
+            event_callbacks: std::collections::HashMap::new(),
+            hovered_node: None,
+            hovered_edge: None,
+            edge_hit_px: 0.0,
+            split_seam_edges: true,
+            position_waiters: Vec::new(),
+            touch_gestures: false,
+            gravity_to_center_of_mass: false,
+            double_click_focus: false,
+            auto_clustering: None,
+            cluster_cache: None,
+            cluster_blend: 0.0,
+            cluster_fade: None,
+            clusters_drawn: false,
+            bundled_ends: None,
+            decimation: None,
+            decimating: false,
+            decimated_nodes: 0,
+            touch_gesture: TouchGesture::default(),
+            tap_clicked: false,
+            observe_resize: false,
+            resize_watcher: None,
+            observed_size: None,
+            pixel_ratio: 0.0,
+            reported_viewport: ([0.0, 0.0], 1.0),
+            minimap: None,
+            node_labels: Vec::new(),
+            edge_labels: Vec::new(),
+            label_index: std::cell::OnceCell::new(),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen]
+    pub async fn init(&mut self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+        if self.is_initialized {
+            log!("Renderer already initialized");
+            return Ok(());
+        }
+
+        log!("Initializing WebGPU renderer for canvas");
+
+        // Simple retry logic for WebGPU initialization
+        let mut attempts = 0;
+        let max_attempts = 2;
+
+        while attempts < max_attempts {
+            match self.renderer.init(canvas).await {
+                Ok(_) => {
+                    self.is_initialized = true;
+                    self.canvas = Some(canvas.clone());
+                    self.update_canvas_listeners();
+                    self.update_resize_watcher();
+                    log!("WebGPU renderer initialized successfully");
+                    return Ok(());
+                }
+                Err(e) => {
+                    attempts += 1;
+                    log!("WebGPU initialization attempt {} failed: {}", attempts, e);
+
+                    if attempts >= max_attempts {
+                        return Err(JsValue::from_str(&format!(
+                            "Failed to initialize WebGPU: {}",
+                            e
+                        )));
+                    }
+                }
+            }
+        }
+
+        Err(JsValue::from_str("Failed to initialize WebGPU"))
+    }
+
+    /// Creates a renderer for computing layouts without a canvas, e.g. in a worker.
+    /// Call `init_headless` instead of `init`; `render` does nothing.
+    #[wasm_bindgen]
+    pub fn new_headless() -> FastGraphRenderer {
+        Self::new()
+    }
+
+    /// Sets up the physics without a surface: GPU compute when a WebGPU adapter is
+    /// available, the CPU simulation otherwise. `integrate_physics`,
+    /// `step_simulation` and `get_nodes` then work as usual.
+    #[wasm_bindgen]
+    pub async fn init_headless(&mut self) -> Result<(), JsValue> {
+        if self.is_initialized {
+            log!("Renderer already initialized");
+            return Ok(());
+        }
+
+        if let Err(e) = self.renderer.init_headless().await {
+            log!("Headless GPU initialization failed, using CPU physics: {}", e);
+            self.renderer = Renderer::new();
+        }
+        log!("Headless renderer initialized ({} physics)", if self.renderer.has_gpu_physics() { "GPU" } else { "CPU" });
+
+        self.is_initialized = true;
+        Ok(())
+    }
+
+    /// True when the simulation runs in compute shaders, false for the CPU fallback.
+    #[wasm_bindgen]
+    pub fn has_gpu_physics(&self) -> bool {
+        self.renderer.has_gpu_physics()
+    }
+
+    /// What init negotiated, shaped like `probe_capabilities` plus `gpu_physics`
+    /// and `storage_rendering` (simulated nodes are drawn straight from the GPU
+    /// buffer, without a readback); null before init.
+    #[wasm_bindgen]
+    pub fn get_capabilities(&self) -> JsValue {
+        match self.renderer.capabilities() {
+            Some(capabilities) => capabilities_object(capabilities, true),
+            None => JsValue::NULL,
+        }
+    }
+
+    /// What `init` ended up with, for bug reports: `{adapter_name, vendor, device,
+    /// device_type, backend, driver, driver_info, max_storage_buffers_per_shader_stage,
+    /// max_storage_buffer_binding_size, max_buffer_size, max_texture_dimension_2d,
+    /// gpu_physics, surface_format}`. Limits are the negotiated device limits;
+    /// `surface_format` is null for headless renderers. Null before `init`.
+    #[wasm_bindgen]
+    pub fn get_device_info(&self) -> JsValue {
+        let Some(info) = self.renderer.device_info() else {
+            return JsValue::NULL;
+        };
+        let object = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&object, &JsValue::from_str(key), &value);
+        };
+        set("adapter_name", JsValue::from_str(&info.adapter_name));
+        set("vendor", JsValue::from(info.vendor));
+        set("device", JsValue::from(info.device));
+        set("device_type", JsValue::from_str(info.device_type));
+        set("backend", JsValue::from_str(info.backend));
+        set("driver", JsValue::from_str(&info.driver));
+        set("driver_info", JsValue::from_str(&info.driver_info));
+        set("max_storage_buffers_per_shader_stage", JsValue::from(info.max_storage_buffers_per_shader_stage));
+        set("max_storage_buffer_binding_size", JsValue::from(info.max_storage_buffer_binding_size));
+        set("max_buffer_size", JsValue::from(info.max_buffer_size as f64));
+        set("max_texture_dimension_2d", JsValue::from(info.max_texture_dimension_2d));
+        set("gpu_physics", JsValue::from(info.gpu_physics));
+        set("surface_format", info.surface_format.as_deref().map_or(JsValue::NULL, JsValue::from_str));
+        object.into()
+    }
+
+    #[wasm_bindgen]
+    pub fn render(&mut self, time: f64) {
+        if !self.is_initialized {
+            return;
+        }
+
+        if self.is_rendering {
+            return; // Skip frame if already rendering
+        }
+
+        self.apply_observed_size();
+        // Removing nodes or edges anywhere can end a truncation
+        self.check_capacity();
+        self.update_layer_order();
+        self.is_rendering = true;
+        self.sync_physics_positions();
+        self.advance_animation(time);
+        self.advance_opacity_fade(time);
+        self.advance_pulses(time);
+        self.read_shared_positions();
+        self.follow_minimap();
+        self.advance_camera_tour(time);
+        self.advance_camera_focus(time);
+        self.advance_clustering(time);
+        self.check_edge_bundles();
+        self.update_decimation();
+
+        // The physics buffer holds every node, hidden ones included
+        let from_physics = self.can_render_from_physics();
+        self.renderer.set_render_from_physics(from_physics);
+        let interpolated = if from_physics { None } else { self.interpolated_frame() };
+        let (nodes, edges) = match &interpolated {
+            Some((nodes, edges)) => (nodes, edges),
+            None => (&self.nodes, &self.edges),
+        };
+        let visible = if from_physics { None } else { self.visible_frame(nodes, edges) };
+        let (nodes, edges) = match &visible {
+            Some((nodes, edges)) => (nodes, edges),
+            None => (nodes, edges),
+        };
+        let clustered = self.cluster_frame(nodes, edges, visible.is_some());
+        let (nodes, edges) = match &clustered {
+            Some((nodes, edges)) => (nodes, edges),
+            None => (nodes, edges),
+        };
+        let decimated = if clustered.is_none() { self.decimated_frame(nodes, visible.is_some()) } else { None };
+        self.decimated_nodes = decimated.as_ref().map_or(0, |(kept, _)| nodes.len() - kept.len()) as u32;
+        let nodes = decimated.as_ref().map_or(nodes, |(nodes, _)| nodes);
+        let node_order = decimated.as_ref().map(|(_, indices)| &indices[..]);
+        // The bundles follow the edges in index order
+        self.renderer.set_draw_edge_bundles(self.bundled_ends.is_some() && visible.is_none() && clustered.is_none());
+        // Cluster frames have instances of their own, drawn without pulses,
+        // user flags or the GPU visibility filter (cluster_frame applies it)
+        if clustered.is_some() != self.clusters_drawn {
+            self.clusters_drawn = clustered.is_some();
+            let [required, forbidden] = if self.clusters_drawn { [0, 0] } else { self.visibility_filter };
+            self.renderer.set_visibility_filter(required, forbidden);
+            if self.clusters_drawn {
+                self.renderer.clear_pulses();
+                self.renderer.clear_filter_flags();
+                // Both go up again once the clusters are gone
+                self.pulses_filtered = true;
+                self.user_flags_filtered = true;
+            }
+        }
+        // Filtered and decimated frames shift the node order, so their pulses go up every frame
+        let filtered = visible.is_some() || node_order.is_some();
+        let animated = !self.clusters_drawn && (!self.pulses.is_empty() || !self.node_phases.is_empty());
+        if animated && (self.pulses_dirty || self.phases_dirty || filtered || self.pulses_filtered) {
+            let state = |i: usize| PulseState {
+                phase: self.node_phases.get(i).copied().unwrap_or(0.0),
+                ..self.pulses.get(i).copied().unwrap_or_default()
+            };
+            let pulses: Vec<PulseState> = if let Some(order) = node_order {
+                order.iter().copied().map(state).collect()
+            } else if visible.is_some() {
+                self.frame_nodes().map(state).collect()
+            } else {
+                (0..self.pulses.len().max(self.node_phases.len())).map(state).collect()
+            };
+            self.renderer.upload_pulses(&pulses);
+            self.pulses_dirty = false;
+            self.phases_dirty = false;
+            self.pulses_filtered = filtered;
+        }
+        // The user flags follow the instances in the same way. Arrowheads share
+        // their buffer and go up every frame, as the target node sizes may change.
+        if self.clusters_drawn {
+            // Nothing goes up until the clusters are gone
+        } else if self.user_flags.is_empty() && !self.draws_arrows() {
+            if self.user_flags_dirty {
+                self.renderer.clear_filter_flags();
+            }
+        } else if self.user_flags_dirty || filtered || self.user_flags_filtered || self.draws_arrows() {
+            let (node_flags, edge_flags) = self.frame_user_flags(visible.is_some(), node_order);
+            self.renderer.upload_filter_flags(&node_flags, &edge_flags);
+            self.user_flags_filtered = filtered;
+        }
+        self.user_flags_dirty = false;
+        if self.preview_edge.is_some() || self.preview_node.is_some() {
+            let (edges, nodes) = self.preview_instances(interpolated.as_ref().map_or(&self.nodes, |(nodes, _)| nodes));
+            self.renderer.set_overlay(&edges, &nodes);
+        } else {
+            self.renderer.set_overlay(&[], &[]);
+        }
+
+        let result = self.renderer.render(
+            time,
+            &self.color1,
+            &self.color2,
+            nodes,
+            edges,
+            Camera { position: self.camera_position, zoom: self.camera_zoom },
+        );
+        // Validation errors of earlier frames show up once their error scope resolves
+        let mut errors = self.renderer.take_validation_errors();
+        if let Err(e) = result {
+            errors.push(e);
+        }
+        for error in errors {
+            self.report_render_error(error);
+        }
+        self.draw_minimap();
+        self.dispatch_events();
+
+        self.is_rendering = false;
+    }
+
+    /// Reads node positions from shared memory on every frame, so a layout
+    /// running in a worker can write them without posting messages. `buffer`
+    /// holds `count` (x, y) float pairs starting `offset` bytes in, for nodes
+    /// 0 to count - 1; nodes beyond it keep their own positions. Pause the
+    /// renderer's own simulation while bound, or the two will fight.
+    ///
+    /// Only the positions come from shared memory: node count, colors and edges
+    /// still go through `set_nodes` and friends. A value torn by a concurrent
+    /// write is at worst a position from the previous frame; non-finite values
+    /// are skipped.
+    ///
+    /// SharedArrayBuffer requires a cross-origin isolated page, i.e. served with
+    /// `Cross-Origin-Opener-Policy: same-origin` and
+    /// `Cross-Origin-Embedder-Policy: require-corp` (or `credentialless`).
+    #[wasm_bindgen]
+    pub fn bind_shared_positions(&mut self, buffer: js_sys::SharedArrayBuffer, offset: u32, count: u32) -> Result<(), JsValue> {
+        if !offset.is_multiple_of(4) {
+            return Err(JsValue::from_str(&format!("Offset {} is not a multiple of 4", offset)));
+        }
+        let bytes = offset as u64 + count as u64 * 8;
+        if bytes > buffer.byte_length() as u64 {
+            return Err(JsValue::from_str(&format!(
+                "{} positions at offset {} need {} bytes, the buffer has {}",
+                count,
+                offset,
+                bytes,
+                buffer.byte_length()
+            )));
+        }
+
+        let view = js_sys::Float32Array::new_with_byte_offset_and_length(&buffer, offset, count * 2);
+        self.shared_positions = Some(SharedPositions { view, scratch: vec![0.0; count as usize * 2] });
+        Ok(())
+    }
+
+    /// Stops reading positions from shared memory; nodes keep the last ones read.
+    #[wasm_bindgen]
+    pub fn unbind_shared_positions(&mut self) {
+        self.shared_positions = None;
+    }
+
+    #[wasm_bindgen]
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if !self.is_initialized || self.is_rendering {
+            return;
+        }
+
+        self.renderer.resize(width, height);
+    }
+
+    /// Keeps the canvas's backing size in step with its CSS size (times the
+    /// pixel ratio) through a ResizeObserver, so `resize` needn't be called. A
+    /// new size is applied at the start of the next `render`, at most once per
+    /// frame. Sides are capped at 2048 canvas pixels, keeping the aspect ratio.
+    #[wasm_bindgen]
+    pub fn observe_resize(&mut self, enabled: bool) {
+        self.observe_resize = enabled;
+        self.update_resize_watcher();
+    }
+
+    /// Draws an overview of the whole graph into a second, smaller canvas: nodes
+    /// as dots, edges as hairlines and an outline of the main view, sharing the
+    /// main canvas's GPU device. `options` may set `interval` (frames between
+    /// redraws, default 10; graph and view changes redraw on the next frame),
+    /// `point_size` (dot diameter in canvas pixels, default 2), `viewport_color`
+    /// (a CSS color, default the theme highlight) and `navigate` (default true:
+    /// pressing or dragging on the minimap centers the main view there).
+    /// Replaces any minimap attached before; resize its canvas by setting its
+    /// width and height.
+    #[wasm_bindgen]
+    pub async fn attach_minimap(&mut self, canvas: &web_sys::HtmlCanvasElement, options: JsValue) -> Result<(), JsValue> {
+        if !self.is_initialized {
+            return Err(JsValue::from_str("Renderer is not initialized"));
+        }
+        if !(options.is_undefined() || options.is_null() || options.is_object()) {
+            return Err(JsValue::from_str("Minimap options must be an object"));
+        }
+        let field = |key: &str| js_sys::Reflect::get(&options, &JsValue::from_str(key)).ok().filter(|value| !value.is_undefined() && !value.is_null());
+        let interval = match field("interval") {
+            None => DEFAULT_MINIMAP_INTERVAL,
+            Some(value) => value
+                .as_f64()
+                .filter(|interval| interval.fract() == 0.0 && *interval >= 1.0 && *interval <= u32::MAX as f64)
+                .ok_or_else(|| JsValue::from_str("Minimap interval must be a positive whole number of frames"))? as u32,
+        };
+        let point_size = match field("point_size") {
+            None => DEFAULT_MINIMAP_POINT_SIZE,
+            Some(value) => value
+                .as_f64()
+                .filter(|size| size.is_finite() && *size > 0.0)
+                .ok_or_else(|| JsValue::from_str("Minimap point_size must be a positive number"))? as f32,
+        };
+        let viewport_color = match field("viewport_color") {
+            None => self.theme.highlight,
+            Some(value) => parse_color_arg(&value.as_string().ok_or_else(|| JsValue::from_str("Minimap viewport_color must be a string"))?)?,
+        };
+        let navigate = match field("navigate") {
+            None => true,
+            Some(value) => value.as_bool().ok_or_else(|| JsValue::from_str("Minimap navigate must be a boolean"))?,
+        };
+
+        self.detach_minimap();
+        self.renderer
+            .attach_minimap_canvas(canvas)
+            .map_err(|e| JsValue::from_str(&format!("Failed to attach the minimap: {}", e)))?;
+        let listeners = if navigate {
+            Some(MinimapListeners::attach(canvas).inspect_err(|_| self.renderer.detach_minimap())?)
+        } else {
+            None
+        };
+        self.minimap = Some(Minimap {
+            canvas: canvas.clone(),
+            listeners,
+            interval,
+            point_size,
+            viewport_color,
+            frames_since_draw: 0,
+            stale: true,
+            drawn_view: None,
+            camera: None,
+        });
+        Ok(())
+    }
+
+    /// Stops drawing the minimap and releases its canvas.
+    #[wasm_bindgen]
+    pub fn detach_minimap(&mut self) {
+        self.minimap = None;
+        self.renderer.detach_minimap();
+    }
+
+    /// Canvas pixels per CSS pixel for `observe_resize`; 0 (the default) follows
+    /// `window.devicePixelRatio`, including when the page moves between screens.
+    #[wasm_bindgen]
+    pub fn set_pixel_ratio(&mut self, ratio: f32) -> Result<(), JsValue> {
+        if !(ratio.is_finite() && ratio >= 0.0) {
+            return Err(JsValue::from_str(&format!("Pixel ratio must be a non-negative number, got {}", ratio)));
+        }
+        self.pixel_ratio = ratio;
+        Ok(())
+    }
+
+    // The JS API takes both colors as plain numbers, r, g, b, a each
+    #[allow(clippy::too_many_arguments)]
+    #[wasm_bindgen]
+    pub fn set_colors(
+        &mut self,
+        color1_r: f32,
+        color1_g: f32,
+        color1_b: f32,
+        color1_a: f32,
+        color2_r: f32,
+        color2_g: f32,
+        color2_b: f32,
+        color2_a: f32,
+    ) {
+        self.color1 = [color1_r, color1_g, color1_b, color1_a];
+        self.color2 = [color2_r, color2_g, color2_b, color2_a];
+    }
+
+    #[wasm_bindgen]
+    pub fn set_color1(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.color1 = [r, g, b, a];
+    }
+
+    #[wasm_bindgen]
+    pub fn set_color2(&mut self, r: f32, g: f32, b: f32, a: f32) {
+        self.color2 = [r, g, b, a];
+    }
+
+    /// Sets the first gradient color from a hex or named CSS color. Returns false,
+    /// keeping the current color, if it can't be parsed.
+    #[wasm_bindgen]
+    pub fn set_color1_hex(&mut self, hex: &str) -> bool {
+        parse_css_color(hex).map(|color| self.color1 = color).is_some()
+    }
+
+    /// Like `set_color1_hex`, for the second gradient color.
+    #[wasm_bindgen]
+    pub fn set_color2_hex(&mut self, hex: &str) -> bool {
+        parse_css_color(hex).map(|color| self.color2 = color).is_some()
+    }
+
+    /// Animates the background gradient at `speed` times its normal pace (1), or
+    /// holds it where it is while `enabled` is false. Animated by default.
+    #[wasm_bindgen]
+    pub fn set_gradient_animation(&mut self, enabled: bool, speed: f32) -> Result<(), JsValue> {
+        if !speed.is_finite() || speed < 0.0 {
+            return Err(JsValue::from_str(&format!("Gradient speed must be a non-negative number, got {}", speed)));
+        }
+        self.renderer.set_gradient_speed(if enabled { speed } else { 0.0 });
+        Ok(())
+    }
+
+    /// Shapes the background gradient between the two `set_colors` colors:
+    /// "linear" with `[angle]` (radians, 0 runs left to right and pi / 2 top to
+    /// bottom; the default is the diagonal pi / 4), or "radial" with
+    /// `[center_x, center_y, radius]`, the center as fractions of the canvas
+    /// width and height and the radius of the shorter side (default 0.5).
+    #[wasm_bindgen]
+    pub fn set_gradient_style(&mut self, style: &str, angle_or_center: &[f32]) -> Result<(), JsValue> {
+        if angle_or_center.iter().any(|value| !value.is_finite()) {
+            return Err(JsValue::from_str("Gradient parameters must be finite numbers"));
+        }
+        let style = match (style, angle_or_center) {
+            ("linear", &[angle]) => GradientStyle::Linear { angle },
+            ("radial", &[x, y]) => GradientStyle::Radial { center: [x, y], radius: 0.5 },
+            ("radial", &[x, y, radius]) if radius > 0.0 => GradientStyle::Radial { center: [x, y], radius },
+            ("radial", &[_, _, radius]) => {
+                return Err(JsValue::from_str(&format!("Gradient radius must be positive, got {}", radius)));
+            }
+            ("linear", values) => {
+                return Err(JsValue::from_str(&format!("Linear gradients take [angle], got {} values", values.len())));
+            }
+            ("radial", values) => {
+                return Err(JsValue::from_str(&format!("Radial gradients take [center_x, center_y, radius?], got {} values", values.len())));
+            }
+            (style, _) => return Err(JsValue::from_str(&format!("Unknown gradient style: {}", style))),
+        };
+        self.renderer.set_gradient_style(style);
+        Ok(())
+    }
+
+    /// Switches the background, gradient, highlight, dim and grid colors to the
+    /// built-in "default", "dark" or "light" theme, or one registered with
+    /// `apply_theme_custom`. Node and edge colors are left alone.
+    #[wasm_bindgen]
+    pub fn apply_theme(&mut self, theme: &str) -> Result<(), JsValue> {
+        let resolved = self
+            .custom_themes
+            .get(theme)
+            .cloned()
+            .or_else(|| theme::builtin(theme))
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown theme: {}", theme)))?;
+        self.color1 = resolved.gradient_start;
+        self.color2 = resolved.gradient_end;
+        self.renderer.set_clear_color(resolved.background);
+        self.theme = resolved;
+        self.theme_name = theme.to_string();
+        Ok(())
+    }
+
+    /// Registers a theme from JSON and applies it. The object needs a `name`;
+    /// `base` picks the theme whose values fill in the missing keys ("default"
+    /// if absent). Colors are CSS strings, `dim_alpha` a number in 0-1, e.g.
+    /// `{"name": "solarized", "base": "dark", "background": "#002b36"}`.
+    #[wasm_bindgen]
+    pub fn apply_theme_custom(&mut self, json: &str) -> Result<(), JsValue> {
+        let value = js_sys::JSON::parse(json).map_err(|_| JsValue::from_str("Theme is not valid JSON"))?;
+        if !value.is_object() {
+            return Err(JsValue::from_str("Theme must be a JSON object"));
+        }
+        let field = |key: &str| js_sys::Reflect::get(&value, &JsValue::from_str(key)).ok().filter(|value| !value.is_undefined() && !value.is_null());
+        let string = |key: &str| -> Result<Option<String>, JsValue> {
+            match field(key) {
+                None => Ok(None),
+                Some(value) => value.as_string().map(Some).ok_or_else(|| JsValue::from_str(&format!("Theme {} must be a string", key))),
+            }
+        };
+
+        let name = string("name")?.ok_or_else(|| JsValue::from_str("Theme needs a name"))?;
+        if theme::builtin(&name).is_some() {
+            return Err(JsValue::from_str(&format!("Theme {} is built in and can't be replaced", name)));
+        }
+        let base = string("base")?.unwrap_or_else(|| "default".to_string());
+        let mut custom = self
+            .custom_themes
+            .get(&base)
+            .cloned()
+            .or_else(|| theme::builtin(&base))
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown base theme: {}", base)))?;
+
+        for (key, target) in [
+            ("background", &mut custom.background),
+            ("gradient_start", &mut custom.gradient_start),
+            ("gradient_end", &mut custom.gradient_end),
+            ("highlight", &mut custom.highlight),
+            ("grid", &mut custom.grid),
+        ] {
+            if let Some(color) = string(key)? {
+                *target = parse_color_arg(&color)?;
+            }
+        }
+        if let Some(dim_alpha) = field("dim_alpha") {
+            custom.dim_alpha = dim_alpha
+                .as_f64()
+                .filter(|alpha| (0.0..=1.0).contains(alpha))
+                .ok_or_else(|| JsValue::from_str("Theme dim_alpha must be a number in 0-1"))? as f32;
+        }
+
+        self.custom_themes.insert(name.clone(), custom);
+        self.apply_theme(&name)
+    }
+
+    /// Name of the theme last applied.
+    #[wasm_bindgen]
+    pub fn get_theme_name(&self) -> String {
+        self.theme_name.clone()
+    }
+
+    /// A current theme color as [r, g, b, a]: "background", "gradient_start",
+    /// "gradient_end", "highlight" or "grid". The gradient colors reflect later
+    /// `set_color1_hex`/`set_color2_hex` calls.
+    #[wasm_bindgen]
+    pub fn get_theme_color(&self, key: &str) -> Result<Vec<f32>, JsValue> {
+        let color = match key {
+            "background" => self.theme.background,
+            "gradient_start" => self.color1,
+            "gradient_end" => self.color2,
+            "highlight" => self.theme.highlight,
+            "grid" => self.theme.grid,
+            _ => return Err(JsValue::from_str(&format!("Unknown theme color: {}", key))),
+        };
+        Ok(color.to_vec())
+    }
+
+    /// Alpha multiplier the current theme applies to dimmed nodes and edges.
+    #[wasm_bindgen]
+    pub fn get_theme_dim_alpha(&self) -> f32 {
+        self.theme.dim_alpha
+    }
+
+    /// Replaces all nodes. Indices that still exist keep their mass, pinned state
+    /// and style class, and while the node count stays the same, their place in
+    /// the time window and layer visibility (timestamps and layers are per index
+    /// and dropped otherwise). Hidden and no-physics flags and any isolation are
+    /// cleared; an active core filter is applied to the new nodes.
+    #[wasm_bindgen]
+    pub fn set_nodes(&mut self, node_data: &[f32]) {
+        let kept_flags = NODE_FLAG_PINNED | NODE_FLAG_OUTSIDE_TIME | NODE_FLAG_LAYER_HIDDEN | !NODE_FLAG_BITS;
+        let previous: Vec<(f32, u32)> = self.nodes.iter().map(|node| (node.mass, node.flags & kept_flags)).collect();
+        let previous_count = self.nodes.len();
+        self.nodes.clear();
+
+        // Each node has 7 floats: x, y, r, g, b, a, size
+        let stride = 7;
+        let node_count = node_data.len() / stride;
+
+        for i in 0..node_count {
+            let base = i * stride;
+            if base + stride <= node_data.len() {
+                self.nodes.push(NodeData {
+                    x: node_data[base],
+                    y: node_data[base + 1],
+                    vx: 0.0,
+                    vy: 0.0,
+                    fx: 0.0,
+                    fy: 0.0,
+                    r: node_data[base + 2],
+                    g: node_data[base + 3],
+                    b: node_data[base + 4],
+                    a: node_data[base + 5],
+                    size: node_data[base + 6],
+                    mass: previous.get(i).map_or(1.0, |&(mass, _)| mass),
+                    flags: previous.get(i).map_or(0, |&(_, flags)| flags),
+                });
+            }
+        }
+
+        // Positions changed on the CPU side, so the GPU simulation must start from them
+        self.mark_physics_dirty();
+        // New data supersedes any running transition and highlight
+        self.animation = None;
+        self.color_backup = None;
+        if self.nodes.len() != previous_count {
+            self.node_count_changed();
+        }
+        if self.core_filter > 0 {
+            self.filter_by_core(self.core_filter);
+        }
+        self.refresh_edge_endpoints();
+        self.apply_drag_position();
+        self.positions_replaced();
+
+        log_debug!("Updated nodes: {} nodes", self.nodes.len());
+        self.check_capacity();
+    }
+
+    /// Like `set_nodes`, but nodes glide to their new positions, colors and sizes
+    /// over `duration_ms` (ease-in-out) instead of jumping. Nodes are matched by
+    /// index: new indices fade in at their position, and nodes missing from the
+    /// new data fade out and are removed at the end. Picking and dragging see the
+    /// in-between state. The simulation waits for the transition, like
+    /// `animate_positions`; a duration <= 0 is a plain `set_nodes`.
+    #[wasm_bindgen]
+    pub fn set_nodes_animated(&mut self, node_data: &[f32], duration_ms: f32) {
+        if duration_ms.is_nan() || duration_ms <= 0.0 {
+            self.set_nodes(node_data);
+            return;
+        }
+        // A transition still underway ends its fades before the new one starts
+        if let Some(animation) = self.animation.take() {
+            self.finish_appearance(&animation);
+        }
+        self.color_backup = None;
+
+        // x, y, r, g, b, a, size per node, as in set_nodes
+        let targets: Vec<([f32; 2], Appearance)> = node_data
+            .chunks_exact(7)
+            .map(|node| ([node[0], node[1]], [node[2], node[3], node[4], node[5], node[6]]))
+            .collect();
+        let previous_count = self.nodes.len();
+
+        // New nodes appear fully transparent where they'll stay
+        for &([x, y], [r, g, b, _, size]) in &targets[targets.len().min(previous_count)..] {
+            self.nodes.push(NodeData { x, y, vx: 0.0, vy: 0.0, fx: 0.0, fy: 0.0, r, g, b, a: 0.0, size, mass: 1.0, flags: 0 });
+        }
+        if self.nodes.len() != previous_count {
+            self.node_count_changed();
+        }
+
+        let from_looks = self.nodes.iter().map(|node| [node.r, node.g, node.b, node.a, node.size]).collect();
+        let (positions, to_looks) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| match targets.get(i) {
+                Some(&target) => target,
+                // Nodes being removed fade out in place
+                None => ([node.x, node.y], [node.r, node.g, node.b, 0.0, node.size]),
+            })
+            .unzip();
+        self.start_animation(positions, duration_ms, Easing::EaseInOut);
+        self.animation = self.animation.take().map(|animation| animation.with_appearance(from_looks, to_looks, targets.len()));
+
+        log_debug!("Animating to {} nodes over {} ms", targets.len(), duration_ms);
+        self.check_capacity();
+    }
+
+    /// Returns the current nodes in the `set_nodes` layout (x, y, r, g, b, a, size),
+    /// including positions produced by the GPU simulation.
+    #[wasm_bindgen]
+    pub fn get_nodes(&mut self) -> Vec<f32> {
+        self.sync_physics_positions();
+
+        let mut node_data = Vec::with_capacity(self.nodes.len() * 7);
+        for node in &self.nodes {
+            node_data.extend_from_slice(&[node.x, node.y, node.r, node.g, node.b, node.a, node.size]);
+        }
+        node_data
+    }
+
+    /// [min_x, min_y, max_x, max_y] around the visible nodes, radii included, in
+    /// world coordinates, including positions produced by the GPU simulation; an
+    /// empty array when no node is visible.
+    #[wasm_bindgen]
+    pub fn get_graph_bounds(&mut self) -> Vec<f32> {
+        self.graph_extent().map_or_else(Vec::new, |extent| extent.bounds.to_vec())
+    }
+
+    /// [x, y] mean position of the visible nodes, weighted by mass with
+    /// `weighted`; an empty array when no node is visible.
+    #[wasm_bindgen]
+    pub fn get_graph_centroid(&mut self, weighted: bool) -> Vec<f32> {
+        self.graph_extent()
+            .map_or_else(Vec::new, |extent| if weighted { extent.weighted_centroid } else { extent.centroid }.to_vec())
+    }
+
+    /// The mean position of the visible nodes, weighted by `weights` (one per
+    /// node, e.g. degrees or masses) when given, as [x, y]; a cached
+    /// `get_graph_centroid(false)` without. Negative or non-finite weights count
+    /// as 0, and a total weight of 0 gives the plain mean. Empty when no node
+    /// is visible.
+    #[wasm_bindgen]
+    pub fn get_center_of_mass(&mut self, weights: Option<Vec<f32>>) -> Result<Vec<f32>, JsValue> {
+        if let Some(weights) = weights.as_ref().filter(|weights| weights.len() != self.nodes.len()) {
+            return Err(JsValue::from_str(&format!("Expected {} node weights, got {}", self.nodes.len(), weights.len())));
+        }
+        let Some(extent) = self.graph_extent() else {
+            return Ok(Vec::new());
+        };
+        let Some(weights) = weights else {
+            return Ok(extent.centroid.to_vec());
+        };
+        // Summed in f64 like GraphExtent
+        let (mut sum, mut total) = ([0.0f64; 2], 0.0f64);
+        for (index, node) in self.nodes.iter().enumerate() {
+            let weight = weights[index];
+            if weight > 0.0 && weight.is_finite() && node.x.is_finite() && node.y.is_finite() && self.node_shown(index) {
+                sum = [sum[0] + (node.x * weight) as f64, sum[1] + (node.y * weight) as f64];
+                total += weight as f64;
+            }
+        }
+        if total > 0.0 {
+            Ok(sum.map(|sum| (sum / total) as f32).to_vec())
+        } else {
+            Ok(extent.centroid.to_vec())
+        }
+    }
+
+    /// Where the nodes at `indices` were drawn, as x, y, visible per node: x and
+    /// y in CSS pixels from the canvas's top-left corner, for placing HTML over
+    /// the graph, and visible 1 if the node is drawn at least partly on the
+    /// canvas, 0 if it is hidden or offscreen. Unknown indices give NaN, NaN, 0.
+    #[wasm_bindgen]
+    pub fn get_screen_positions(&self, indices: &[u32]) -> Vec<f32> {
+        let mut positions = vec![0.0; indices.len() * 3];
+        let transform = self.screen_transform();
+        let t = self.interpolation_factor();
+        for (&index, out) in indices.iter().zip(positions.chunks_exact_mut(3)) {
+            out.copy_from_slice(&self.screen_position(index as usize, t, &transform));
+        }
+        positions
+    }
+
+    /// The box node `index` is drawn in, glow included, as x, y, width, height
+    /// in CSS pixels from the canvas's top-left corner, e.g. to anchor a
+    /// tooltip beside it. Empty for unknown or hidden nodes, and for nodes off
+    /// the canvas unless `include_offscreen`. Pulses and idle motion aren't
+    /// counted.
+    #[wasm_bindgen]
+    pub fn get_node_screen_rect(&self, index: u32, include_offscreen: bool) -> Vec<f32> {
+        let index = index as usize;
+        if !self.node_shown(index) {
+            return Vec::new();
+        }
+        let radius = self.drawn_radius(index);
+        let [x, y] = self.drawn_position(index, self.interpolation_factor());
+        let (rect, on_canvas) = self.screen_transform().rect([x - radius, y - radius], [x + radius, y + radius]);
+        if on_canvas || include_offscreen { rect.to_vec() } else { Vec::new() }
+    }
+
+    /// The bounding box of edge `index` as drawn, grown by half its width on
+    /// each side, as x, y, width, height in CSS pixels. Empty for unknown or
+    /// hidden edges, and for edges off the canvas unless `include_offscreen`.
+    #[wasm_bindgen]
+    pub fn get_edge_screen_rect(&self, index: u32, include_offscreen: bool) -> Vec<f32> {
+        let index = index as usize;
+        let Some(edge) = self.edges.get(index).filter(|_| self.edge_shown(index)) else {
+            return Vec::new();
+        };
+        let t = self.interpolation_factor();
+        let (start, end) = match self.edge_indices.get(index) {
+            Some(&[source, target]) if (source as usize) < self.nodes.len() && (target as usize) < self.nodes.len() => {
+                (self.drawn_position(source as usize, t), self.drawn_position(target as usize, t))
+            }
+            _ => ([edge.x1, edge.y1], [edge.x2, edge.y2]),
+        };
+        let half_width = edge.width * 0.5;
+        let min = [start[0].min(end[0]) - half_width, start[1].min(end[1]) - half_width];
+        let max = [start[0].max(end[0]) + half_width, start[1].max(end[1]) + half_width];
+        let (rect, on_canvas) = self.screen_transform().rect(min, max);
+        if on_canvas || include_offscreen { rect.to_vec() } else { Vec::new() }
+    }
+
+    /// The world point (x, y) under CSS pixel (x, y) from the canvas's top-left
+    /// corner, e.g. a mouse event's offsetX / offsetY, for the camera as of the
+    /// call. World units are canvas pixels at zoom 1, y pointing down.
+    #[wasm_bindgen]
+    pub fn screen_to_world(&self, x: f32, y: f32) -> Vec<f32> {
+        self.screen_transform().to_world([x, y]).to_vec()
+    }
+
+    /// Where world point (x, y) is drawn, in CSS pixels from the canvas's
+    /// top-left corner; the inverse of `screen_to_world`.
+    #[wasm_bindgen]
+    pub fn world_to_screen(&self, x: f32, y: f32) -> Vec<f32> {
+        self.screen_transform().apply([x, y], 0.0)[..2].to_vec()
+    }
+
+    /// The world-to-screen transform as a CSS `matrix(a, b, c, d, e, f)`, for
+    /// moving a whole layer of HTML with the graph at once. The container must
+    /// cover the canvas exactly (e.g. `position: absolute; inset: 0` in the
+    /// canvas's positioned parent) with `transform-origin: 0 0`; an element
+    /// inside it at `left: x px; top: y px` then stays on world point (x, y).
+    /// Reflects the camera as of the call, so set it after moving the camera.
+    #[wasm_bindgen]
+    pub fn get_css_transform(&self) -> String {
+        let [a, b, c, d, e, f] = self.screen_transform().css_matrix();
+        format!("matrix({}, {}, {}, {}, {}, {})", a, b, c, d, e, f)
+    }
+
+    /// The six numbers of `get_css_transform`.
+    #[wasm_bindgen]
+    pub fn get_css_transform_values(&self) -> Vec<f32> {
+        self.screen_transform().css_matrix().to_vec()
+    }
+
+    /// `get_screen_positions` for every node, written into `out` (e.g. a
+    /// Float32Array kept across frames), which needs room for 3 floats per node.
+    #[wasm_bindgen]
+    pub fn get_all_screen_positions_into(&self, out: &mut [f32]) -> Result<(), JsValue> {
+        if out.len() < self.nodes.len() * 3 {
+            return Err(JsValue::from_str(&format!("Expected room for {} floats, got {}", self.nodes.len() * 3, out.len())));
+        }
+        let transform = self.screen_transform();
+        let t = self.interpolation_factor();
+        for (index, out) in out.chunks_exact_mut(3).take(self.nodes.len()).enumerate() {
+            out.copy_from_slice(&self.screen_position(index, t, &transform));
+        }
+        Ok(())
+    }
+
+    /// Places every node with a seeded strategy: "random" (uniform in a disc),
+    /// "circle", "grid" or "phyllotaxis" (sunflower spiral, a good force-layout
+    /// start). The same seed always produces the same layout.
+    #[wasm_bindgen]
+    pub fn seed_positions(&mut self, strategy: &str, seed: u64) -> Result<(), JsValue> {
+        let indices: Vec<usize> = (0..self.nodes.len()).collect();
+        self.seed_node_positions(&indices, strategy, seed)
+    }
+
+    /// Like `seed_positions`, but only places nodes whose position is NaN or infinite.
+    #[wasm_bindgen]
+    pub fn seed_unset_positions(&mut self, strategy: &str, seed: u64) -> Result<(), JsValue> {
+        let indices: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| !(self.nodes[i].x.is_finite() && self.nodes[i].y.is_finite()))
+            .collect();
+        self.seed_node_positions(&indices, strategy, seed)
+    }
+
+    /// Moves the nodes to `target_positions` (x, y per node) over `duration_ms`,
+    /// eased with "linear", "ease-in", "ease-out" or "ease-in-out". The simulation
+    /// holds off meanwhile and continues from the targets, at rest. Starting another
+    /// animation retargets from the current state; `set_nodes` cancels it.
+    #[wasm_bindgen]
+    pub fn animate_positions(&mut self, target_positions: &[f32], duration_ms: f32, easing: &str) -> Result<(), JsValue> {
+        if target_positions.len() != self.nodes.len() * 2 {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} target coordinates, got {}",
+                self.nodes.len() * 2,
+                target_positions.len()
+            )));
+        }
+        let easing = Easing::parse(easing).ok_or_else(|| JsValue::from_str(&format!("Unknown easing: {}", easing)))?;
+
+        let targets = target_positions.chunks_exact(2).map(|xy| [xy[0], xy[1]]).collect();
+        self.start_animation(targets, duration_ms, easing);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /// Stops the running animation, leaving the nodes where they are.
+    #[wasm_bindgen]
+    pub fn cancel_animation(&mut self) {
+        if let Some(animation) = self.animation.take() {
+            // Fading nodes don't stay half-transparent, but positions stay put
+            self.finish_appearance(&animation);
+            // The simulation resumes from the intermediate positions
+            self.mark_physics_dirty();
+        }
+    }
+
+    /// Draws attention to nodes with `repeat` pulses (at least one) of
+    /// `duration_ms` each: the rendered size swells to `scale` times and back,
+    /// with a halo (see `set_pulse_glow`). The pulse runs on the GPU from the
+    /// render time and ends by itself. Pulsing a node again restarts it with the
+    /// new settings; other nodes keep their pulses.
+    #[wasm_bindgen]
+    pub fn pulse_nodes(&mut self, indices: &[u32], duration_ms: f32, scale: f32, repeat: u32) -> Result<(), JsValue> {
+        self.check_node_indices(indices)?;
+        if !(duration_ms.is_finite() && duration_ms > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid pulse duration: {}", duration_ms)));
+        }
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid pulse scale: {}", scale)));
+        }
+
+        self.pulses.resize(self.nodes.len(), PulseState::default());
+        let period = duration_ms / 1000.0;
+        for &index in indices {
+            // Timed from the next frame, once the render time is known
+            self.pulses[index as usize] = PulseState {
+                start: f32::NAN,
+                period,
+                end: period * repeat.max(1) as f32,
+                scale,
+                glow: self.pulse_glow,
+                phase: 0.0, // node_phases are merged in on upload
+            };
+        }
+        self.pulses_dirty = true;
+        Ok(())
+    }
+
+    /// Stops every pulse at once.
+    #[wasm_bindgen]
+    pub fn cancel_pulses(&mut self) {
+        self.pulses.clear();
+        self.pulses_dirty = false;
+        self.pulses_filtered = false;
+        self.renderer.clear_pulses();
+        // Clearing took the phases with it
+        self.phases_dirty = !self.node_phases.is_empty();
+    }
+
+    /// Sets the width of the halo at a pulse's peak, as a fraction of the node
+    /// radius (default 0.5); 0 pulses the size only. Applies to later pulses.
+    #[wasm_bindgen]
+    pub fn set_pulse_glow(&mut self, glow: f32) {
+        self.pulse_glow = if glow.is_finite() { glow.max(0.0) } else { DEFAULT_PULSE_GLOW };
+    }
+
+    /// Sets each node's idle motion phase in radians (see `set_idle_motion`), so
+    /// nodes move out of step; all 0 by default. Node count changes keep the
+    /// phases of the remaining nodes; new ones get 0.
+    #[wasm_bindgen]
+    pub fn set_node_phases(&mut self, phases: &[f32]) -> Result<(), JsValue> {
+        if phases.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} node phases, got {}", self.nodes.len(), phases.len())));
+        }
+        self.node_phases = phases.iter().map(|&phase| if phase.is_finite() { phase } else { 0.0 }).collect();
+        self.phases_dirty = true;
+        Ok(())
+    }
+
+    /// Gives the graph an ambient look: every node goes through a sine wave
+    /// `frequency` times a second, offset by its phase (`set_node_phases`).
+    /// `mode` is "bob" (moves up and down by `amplitude` canvas pixels),
+    /// "shimmer" (brightness goes up and down by `amplitude`, e.g. 0.2 for 20%)
+    /// or "none", the default. Only the drawing moves: edges, picking, physics
+    /// and exported positions keep the true positions, so keep bobs small.
+    /// An amplitude of 0 turns the effect off.
+    #[wasm_bindgen]
+    pub fn set_idle_motion(&mut self, amplitude: f32, frequency: f32, mode: &str) -> Result<(), JsValue> {
+        let motion = match mode {
+            "none" => IdleMotion::None,
+            "bob" => IdleMotion::Bob,
+            "shimmer" => IdleMotion::Shimmer,
+            _ => return Err(JsValue::from_str(&format!("Unknown idle motion: {}", mode))),
+        };
+        if !amplitude.is_finite() || !frequency.is_finite() || frequency < 0.0 {
+            return Err(JsValue::from_str(&format!("Invalid idle motion amplitude {} or frequency {}", amplitude, frequency)));
+        }
+        self.renderer.set_idle_motion(motion, amplitude, frequency);
+        Ok(())
+    }
+
+    /// Uploads nodes and edges in a compact format: 8-bit color channels and
+    /// half-precision node sizes, 16 bytes per node and 24 per edge instead
+    /// of 52 and 36. Positions and edge widths keep full precision.
+    #[wasm_bindgen]
+    pub fn set_compact_instances(&mut self, enabled: bool) {
+        self.renderer.set_compact_instances(enabled);
+    }
+
+    /// For very large edge counts: while the camera and edges hold still, draws
+    /// the edges a batch per frame into an offscreen texture and reuses it, with
+    /// the nodes drawn on top every frame. The batch adapts so frames stay under
+    /// `budget_ms` (pick slightly above the display's frame time, e.g. 20 at
+    /// 60 Hz); while the view moves, only every nth edge is drawn. Has no effect
+    /// while drawing from the GPU simulation.
+    #[wasm_bindgen]
+    pub fn set_progressive_rendering(&mut self, enabled: bool, budget_ms: f32) -> Result<(), JsValue> {
+        self.renderer.set_progressive_rendering(enabled, budget_ms).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Bytes of node and edge instance data uploaded by the last frame.
+    #[wasm_bindgen]
+    pub fn get_instance_bytes(&self) -> usize {
+        self.renderer.instance_bytes()
+    }
+
+    /// `{node_count, edge_count, instance_bytes, truncated_nodes,
+    /// truncated_edges, decimated_nodes, simulated_nodes, physics_tick_ms}`,
+    /// where the truncated counts are the nodes and edges past the limits that
+    /// aren't drawn, decimated_nodes the nodes the last frame skipped (see
+    /// `set_decimation`), simulated_nodes the nodes in a local reheat (see
+    /// `set_local_reheat`) or else all of them, and physics_tick_ms the time
+    /// the last tick took on the CPU, dispatch only for GPU physics.
+    #[wasm_bindgen]
+    pub fn get_render_stats(&self) -> JsValue {
+        let stats = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&stats, &JsValue::from_str(key), &value);
+        };
+        set("node_count", JsValue::from(self.nodes.len() as u32));
+        set("edge_count", JsValue::from(self.edges.len() as u32));
+        set("instance_bytes", JsValue::from(self.renderer.instance_bytes() as u32));
+        set("truncated_nodes", JsValue::from(self.nodes.len().saturating_sub(MAX_NODES) as u32));
+        set("truncated_edges", JsValue::from(self.edges.len().saturating_sub(MAX_EDGES) as u32));
+        set("decimated_nodes", JsValue::from(self.decimated_nodes));
+        set("simulated_nodes", JsValue::from(self.active_set.as_ref().map_or(self.nodes.len(), ActiveSet::len) as u32));
+        set("physics_tick_ms", JsValue::from(self.physics_tick_ms));
+        stats.into()
+    }
+
+    /// Multiplies the alpha of every node and edge by `alpha` (clamped to 0-1),
+    /// cancelling a running `fade_to`. The background is not affected.
+    #[wasm_bindgen]
+    pub fn set_global_opacity(&mut self, alpha: f32) {
+        self.opacity_fade = None;
+        self.global_opacity = if alpha.is_nan() { 1.0 } else { alpha.clamp(0.0, 1.0) };
+        self.renderer.set_global_opacity(self.global_opacity);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_global_opacity(&self) -> f32 {
+        self.global_opacity
+    }
+
+    /// Animates the global opacity to `alpha` over `duration_ms`, starting at
+    /// the next rendered frame.
+    #[wasm_bindgen]
+    pub fn fade_to(&mut self, alpha: f32, duration_ms: f32) {
+        let target = if alpha.is_nan() { 1.0 } else { alpha.clamp(0.0, 1.0) };
+        self.opacity_fade = Some(OpacityFade::new(self.global_opacity, target, duration_ms));
+    }
+
+    #[wasm_bindgen]
+    pub fn is_fading(&self) -> bool {
+        self.opacity_fade.is_some()
+    }
+
+    /// Places the nodes evenly on a circle around the origin, in `order` (node
+    /// indices; nodes not listed follow in index order) or in index order.
+    ///
+    /// Like every `apply_*_layout`, moves the nodes at once, or over `animate_ms`
+    /// milliseconds when given (see `animate_positions`).
+    #[wasm_bindgen]
+    pub fn apply_circular_layout(&mut self, radius: f32, order: Option<Vec<u32>>, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        self.apply_arc_layout(radius, 0.0, 2.0 * std::f32::consts::PI, order, animate_ms)
+    }
+
+    /// Like `apply_circular_layout`, but only over the arc from `start_angle` to
+    /// `end_angle` (radians, counterclockwise from the +x axis).
+    #[wasm_bindgen]
+    pub fn apply_arc_layout(&mut self, radius: f32, start_angle: f32, end_angle: f32, order: Option<Vec<u32>>, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        let order = self.layout_order(order)?;
+        let positions = layout::circular_positions(order.len(), radius, start_angle, end_angle);
+        self.place_or_animate(order.into_iter().zip(positions), animate_ms);
+        Ok(())
+    }
+
+    /// Places the nodes row-major on a grid centered on the origin. `columns` of 0
+    /// picks a square grid; with `skip_hidden`, hidden nodes keep their position
+    /// and leave no gaps.
+    #[wasm_bindgen]
+    pub fn apply_grid_layout(&mut self, columns: u32, cell_width: f32, cell_height: f32, skip_hidden: bool, animate_ms: Option<f32>) {
+        let indices: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| !(skip_hidden && self.nodes[i].flags & NODE_HIDDEN_FLAGS != 0))
+            .collect();
+        let positions = layout::grid_positions(indices.len(), columns as usize, cell_width, cell_height);
+        self.place_or_animate(indices.into_iter().zip(positions), animate_ms);
+    }
+
+    /// Hierarchical layout for directed graphs using the indexed edges: nodes are
+    /// put in layers so edges point away from the sources, with barycenter ordering
+    /// to reduce crossings. `direction` is "top-down", "bottom-up", "left-right" or
+    /// "right-left". Cycles are broken by reversing edges; returns how many were.
+    #[wasm_bindgen]
+    pub fn apply_layered_layout(&mut self, direction: &str, layer_gap: f32, node_gap: f32, animate_ms: Option<f32>) -> Result<u32, JsValue> {
+        let direction = LayerDirection::parse(direction)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown layer direction: {}", direction)))?;
+
+        let (positions, reversed) = layout::layered_positions(self.nodes.len(), &self.edge_indices, layer_gap, node_gap);
+        let positions = positions.into_iter().map(|position| direction.orient(position));
+        self.place_or_animate(positions.enumerate(), animate_ms);
+        Ok(reversed as u32)
+    }
+
+    /// Radial tree layout around `root_index` using the indexed edges: each BFS
+    /// depth gets a ring `ring_spacing` further out, and subtrees get angular
+    /// sectors in proportion to their size. With `place_unreachable`, nodes not
+    /// connected to the root go on an outer ring; otherwise they stay where they are.
+    #[wasm_bindgen]
+    pub fn apply_radial_layout(&mut self, root_index: u32, ring_spacing: f32, place_unreachable: bool, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        self.check_node_indices(&[root_index])?;
+        self.sync_physics_positions();
+
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        let placed = layout::radial_layout(adjacency, root_index as usize, ring_spacing, place_unreachable);
+        self.place_or_animate(placed, animate_ms);
+        Ok(())
+    }
+
+    /// Places the nodes on concentric rings by `values` (one per node, e.g. degree
+    /// or PageRank): the value range is split into `levels` rings with the highest
+    /// values innermost. Rings are `ring_spacing` apart, or further when crowded.
+    #[wasm_bindgen]
+    pub fn apply_concentric_layout(&mut self, values: &[f32], levels: u32, ring_spacing: f32, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        if values.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} values, got {}", self.nodes.len(), values.len())));
+        }
+        if let Some(index) = values.iter().position(|value| !value.is_finite()) {
+            return Err(JsValue::from_str(&format!("Value for node {} is not finite", index)));
+        }
+
+        let radii: Vec<f32> = self.nodes.iter().map(|node| node.size).collect();
+        let positions = layout::concentric_positions(values, &radii, levels as usize, ring_spacing);
+        self.place_or_animate(positions.into_iter().enumerate(), animate_ms);
+        Ok(())
+    }
+
+    /// Two-column layout for two-mode graphs: `partition` puts each node in column
+    /// 0 (left) or 1 (right). With `reduce_crossings`, the columns are reordered to
+    /// reduce crossings among the indexed edges between them.
+    #[wasm_bindgen]
+    pub fn apply_bipartite_layout(&mut self, partition: &[u32], column_gap: f32, node_gap: f32, reduce_crossings: bool, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        if partition.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} partition values, got {}", self.nodes.len(), partition.len())));
+        }
+        if let Some(index) = partition.iter().position(|&side| side > 1) {
+            return Err(JsValue::from_str(&format!("Partition of node {} must be 0 or 1, got {}", index, partition[index])));
+        }
+
+        let positions = layout::bipartite_positions(partition, &self.edge_indices, column_gap, node_gap, reduce_crossings);
+        self.place_or_animate(positions.into_iter().enumerate(), animate_ms);
+        Ok(())
+    }
+
+    /// Rounds the positions of the `indices` nodes to the nearest point of a grid
+    /// `spacing` pixels apart, with a point at the origin.
+    ///
+    /// Like `align_nodes` and `distribute_nodes`, moves the nodes at rest at
+    /// once, or over `animate_ms` milliseconds when given.
+    #[wasm_bindgen]
+    pub fn snap_nodes_to_grid(&mut self, indices: &[u32], spacing: f32, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        if !(spacing.is_finite() && spacing > 0.0) {
+            return Err(JsValue::from_str(&format!("Grid spacing must be a positive number, got {}", spacing)));
+        }
+        let selection = self.node_selection(indices)?;
+        let positions: Vec<_> = selection
+            .into_iter()
+            .map(|index| (index, layout::snapped_position([self.nodes[index].x, self.nodes[index].y], spacing)))
+            .collect();
+        self.place_or_animate(positions, animate_ms);
+        Ok(())
+    }
+
+    /// Lines the `indices` nodes up on a side of their bounding box ("left",
+    /// "right", "top" or "bottom", flush with the node edges) or on its middle
+    /// ("center_x" for a shared x, "center_y" for a shared y).
+    #[wasm_bindgen]
+    pub fn align_nodes(&mut self, indices: &[u32], mode: &str, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        let alignment = Alignment::parse(mode).ok_or_else(|| JsValue::from_str(&format!("Unknown alignment: {}", mode)))?;
+        let selection = self.node_selection(indices)?;
+        if selection.len() < 2 {
+            return Ok(());
+        }
+        let nodes: Vec<_> = selection.iter().map(|&index| ([self.nodes[index].x, self.nodes[index].y], self.nodes[index].size)).collect();
+        let positions = layout::aligned_positions(&nodes, alignment);
+        self.place_or_animate(selection.into_iter().zip(positions), animate_ms);
+        Ok(())
+    }
+
+    /// Spaces the `indices` nodes evenly along `axis` ("x" or "y") between the
+    /// two outermost, keeping their order; needs at least three nodes to move any.
+    #[wasm_bindgen]
+    pub fn distribute_nodes(&mut self, indices: &[u32], axis: &str, animate_ms: Option<f32>) -> Result<(), JsValue> {
+        let axis = match axis {
+            "x" => 0,
+            "y" => 1,
+            _ => return Err(JsValue::from_str(&format!("Unknown axis: {}", axis))),
+        };
+        let selection = self.node_selection(indices)?;
+        if selection.len() < 3 {
+            return Ok(());
+        }
+        let positions: Vec<_> = selection.iter().map(|&index| [self.nodes[index].x, self.nodes[index].y]).collect();
+        let positions = layout::distributed_positions(&positions, axis);
+        self.place_or_animate(selection.into_iter().zip(positions), animate_ms);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_edges(&mut self, edge_data: &[f32]) {
+        self.edges.clear();
+        self.color_backup = None;
+
+        // Each edge has 9 floats: x1, y1, x2, y2, r, g, b, a, width
+        let stride = 9;
+        let edge_count = edge_data.len() / stride;
+
+        for i in 0..edge_count {
+            let base = i * stride;
+            if base + stride <= edge_data.len() {
+                self.edges.push(EdgeData {
+                    x1: edge_data[base],
+                    y1: edge_data[base + 1],
+                    x2: edge_data[base + 2],
+                    y2: edge_data[base + 3],
+                    r: edge_data[base + 4],
+                    g: edge_data[base + 5],
+                    b: edge_data[base + 6],
+                    a: edge_data[base + 7],
+                    width: edge_data[base + 8],
+                });
+            }
+        }
+
+        // Positional edges carry no topology for the physics springs
+        self.edge_indices.clear();
+        self.edge_lengths.clear();
+        self.edge_weights.clear();
+        self.clear_edge_arrows();
+        self.invalidate_topology();
+
+        log_debug!("Updated edges: {} edges", self.edges.len());
+        self.check_capacity();
+    }
+
+    /// Sets edges that reference nodes by index. Each edge has 7 floats:
+    /// source, target, r, g, b, a, width. Endpoints follow the node positions
+    /// (including simulated ones) and the edges act as springs in the physics.
+    #[wasm_bindgen]
+    pub fn set_indexed_edges(&mut self, edge_data: &[f32]) {
+        self.edges.clear();
+        self.color_backup = None;
+        self.edge_indices.clear();
+        self.edge_lengths.clear();
+        self.edge_weights.clear();
+        self.clear_edge_arrows();
+
+        let stride = 7;
+        let edge_count = edge_data.len() / stride;
+
+        for i in 0..edge_count {
+            let base = i * stride;
+            let source = edge_data[base] as u32;
+            let target = edge_data[base + 1] as u32;
+            if source as usize >= self.nodes.len() || target as usize >= self.nodes.len() {
+                continue;
+            }
+
+            self.edge_indices.push([source, target]);
+            self.edges.push(EdgeData {
+                x1: 0.0,
+                y1: 0.0,
+                x2: 0.0,
+                y2: 0.0,
+                r: edge_data[base + 2],
+                g: edge_data[base + 3],
+                b: edge_data[base + 4],
+                a: edge_data[base + 5],
+                width: edge_data[base + 6],
+            });
+        }
+
+        self.invalidate_topology();
+        self.refresh_edge_endpoints();
+
+        log_debug!("Updated indexed edges: {} edges", self.edges.len());
+        self.check_capacity();
+    }
+
+    /// Starts loading a graph in chunks, for graphs too large to pass in one
+    /// `set_nodes` / `set_indexed_edges` call without blocking the page. Clears
+    /// the current graph and reserves room for the expected counts, then takes
+    /// `append_node_chunk` and `append_edge_chunk` calls until `end_graph_load`.
+    /// Frames rendered meanwhile show what has arrived so far; the simulation
+    /// waits for the end.
+    #[wasm_bindgen]
+    pub fn begin_graph_load(&mut self, expected_nodes: u32, expected_edges: u32) {
+        self.set_nodes(&[]);
+        self.set_indexed_edges(&[]);
+        self.nodes.reserve(expected_nodes as usize);
+        self.edges.reserve(expected_edges as usize);
+        self.edge_indices.reserve(expected_edges as usize);
+        self.graph_loading = true;
+    }
+
+    /// Appends nodes in the `set_nodes` layout (x, y, r, g, b, a, size). They go
+    /// straight to the simulation's GPU buffer, so no second full copy is built.
+    #[wasm_bindgen]
+    pub fn append_node_chunk(&mut self, data: &[f32]) -> Result<(), JsValue> {
+        self.check_graph_chunk(data, "node")?;
+
+        let first = self.nodes.len();
+        self.nodes.extend(data.chunks_exact(7).map(|node| NodeData {
+            x: node[0],
+            y: node[1],
+            vx: 0.0,
+            vy: 0.0,
+            fx: 0.0,
+            fy: 0.0,
+            r: node[2],
+            g: node[3],
+            b: node[4],
+            a: node[5],
+            size: node[6],
+            mass: 1.0,
+            flags: 0,
+        }));
+        self.renderer.write_nodes(first, &self.nodes[first..]);
+        self.renderer.invalidate_physics_readback();
+        self.invalidate_extent();
+        Ok(())
+    }
+
+    /// Appends edges in the `set_indexed_edges` layout (source, target, r, g, b,
+    /// a, width). Both endpoints must have been appended already; a chunk with
+    /// an unknown endpoint is rejected as a whole.
+    #[wasm_bindgen]
+    pub fn append_edge_chunk(&mut self, data: &[f32]) -> Result<(), JsValue> {
+        self.check_graph_chunk(data, "edge")?;
+        let indices: Vec<u32> = data.chunks_exact(7).flat_map(|edge| [edge[0] as u32, edge[1] as u32]).collect();
+        self.check_node_indices(&indices)?;
+
+        for edge in data.chunks_exact(7) {
+            let (source, target) = (&self.nodes[edge[0] as usize], &self.nodes[edge[1] as usize]);
+            self.edge_indices.push([edge[0] as u32, edge[1] as u32]);
+            self.edges.push(EdgeData {
+                x1: source.x,
+                y1: source.y,
+                x2: target.x,
+                y2: target.y,
+                r: edge[2],
+                g: edge[3],
+                b: edge[4],
+                a: edge[5],
+                width: edge[6],
+            });
+        }
+        Ok(())
+    }
+
+    /// Finishes a chunked load: builds the adjacency once and lets the
+    /// simulation start. Does nothing without a load in progress.
+    #[wasm_bindgen]
+    pub fn end_graph_load(&mut self) {
+        if !std::mem::replace(&mut self.graph_loading, false) {
+            return;
+        }
+
+        self.invalidate_topology();
+        if let Some(groups) = &self.groups {
+            self.groups = Some(groups.resized(self.nodes.len()));
+            self.groups_dirty = true;
+        }
+        self.resize_node_damping();
+        self.resize_node_phases();
+        // The chunks are in the physics buffer already
+        if self.renderer.has_gpu_physics() {
+            self.physics_dirty = false;
+        }
+
+        log_debug!("Loaded graph: {} nodes, {} edges", self.nodes.len(), self.edges.len());
+        self.check_capacity();
+    }
+
+    /// Abandons a chunked load, leaving an empty graph. Does nothing without a
+    /// load in progress.
+    #[wasm_bindgen]
+    pub fn cancel_graph_load(&mut self) {
+        if std::mem::replace(&mut self.graph_loading, false) {
+            self.set_nodes(&[]);
+            self.set_indexed_edges(&[]);
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn is_graph_loading(&self) -> bool {
+        self.graph_loading
+    }
+
+    /// Copies the nodes at `node_indices` (duplicates count once) and the
+    /// indexed edges between them as `{nodes, edges, classes, indices, labels}`:
+    /// `nodes` in the `set_nodes` layout at their current positions, `edges` in
+    /// the `set_indexed_edges` layout numbered within the copy (in ascending
+    /// order of the original indices, which `indices` lists), the nodes' style
+    /// classes, and their labels if any are set. This renderer is left as is.
+    #[wasm_bindgen]
+    pub fn extract_subgraph(&mut self, node_indices: &[u32]) -> Result<JsValue, JsValue> {
+        let selection = self.node_selection(node_indices)?;
+        let mut remap = vec![u32::MAX; self.nodes.len()];
+        for (new, &old) in selection.iter().enumerate() {
+            remap[old] = new as u32;
+        }
+
+        let nodes: Vec<f32> = selection
+            .iter()
+            .flat_map(|&index| {
+                let node = &self.nodes[index];
+                [node.x, node.y, node.r, node.g, node.b, node.a, node.size]
+            })
+            .collect();
+        let classes: Vec<u32> = selection.iter().map(|&index| self.nodes[index].flags >> NODE_CLASS_SHIFT).collect();
+        let mut edges = Vec::new();
+        for (edge, &[source, target]) in self.edges.iter().zip(&self.edge_indices) {
+            let (source, target) = (remap[source as usize], remap[target as usize]);
+            if source != u32::MAX && target != u32::MAX {
+                edges.extend_from_slice(&[source as f32, target as f32, edge.r, edge.g, edge.b, edge.a, edge.width]);
+            }
+        }
+        let indices: Vec<u32> = selection.iter().map(|&index| index as u32).collect();
+
+        let subgraph = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| js_sys::Reflect::set(&subgraph, &JsValue::from_str(key), &value);
+        set("nodes", js_sys::Float32Array::from(&nodes[..]).into())?;
+        set("edges", js_sys::Float32Array::from(&edges[..]).into())?;
+        set("classes", js_sys::Uint32Array::from(&classes[..]).into())?;
+        set("indices", js_sys::Uint32Array::from(&indices[..]).into())?;
+        if !self.node_labels.is_empty() {
+            let labels: js_sys::Array = selection
+                .iter()
+                .map(|&index| JsValue::from_str(self.node_labels.get(index).map_or("", String::as_str)))
+                .collect();
+            set("labels", labels.into())?;
+        }
+        Ok(subgraph.into())
+    }
+
+    /// Loads an `extract_subgraph` result, e.g. from another renderer. With
+    /// `replace` it becomes the whole graph; otherwise its nodes and edges are
+    /// appended after the existing ones, whose state stays. `classes` and
+    /// `labels` may be left out. Nothing changes if the data is invalid.
+    #[wasm_bindgen]
+    pub fn load_subgraph(&mut self, data: JsValue, replace: bool) -> Result<(), JsValue> {
+        let field = |key: &str| js_sys::Reflect::get(&data, &JsValue::from_str(key)).ok().filter(|value| !value.is_undefined() && !value.is_null());
+        let floats = |key: &str| -> Result<Vec<f32>, JsValue> {
+            let values = field(key).map_or_else(Vec::new, |value| js_sys::Float32Array::new(&value).to_vec());
+            if !values.len().is_multiple_of(7) {
+                return Err(JsValue::from_str(&format!("Subgraph {} must hold whole entries of 7 floats, got {} floats", key, values.len())));
+            }
+            Ok(values)
+        };
+        let (nodes, edges) = (floats("nodes")?, floats("edges")?);
+        let node_count = nodes.len() / 7;
+        if let Some(edge) = edges.chunks_exact(7).find(|edge| !(edge[0] >= 0.0 && edge[1] >= 0.0 && (edge[0] as usize) < node_count && (edge[1] as usize) < node_count)) {
+            return Err(JsValue::from_str(&format!("Subgraph edge {}-{} is not between its {} nodes", edge[0], edge[1], node_count)));
+        }
+        let classes = field("classes").map(|value| js_sys::Uint32Array::new(&value).to_vec());
+        if let Some(classes) = &classes {
+            if classes.len() != node_count {
+                return Err(JsValue::from_str(&format!("Expected {} subgraph classes, got {}", node_count, classes.len())));
+            }
+            if let Some(&class_id) = classes.iter().find(|&&id| id as usize >= MAX_STYLE_CLASSES) {
+                return Err(JsValue::from_str(&format!("Style class {} out of range 0-{}", class_id, MAX_STYLE_CLASSES - 1)));
+            }
+        }
+        let labels = field("labels").map(|value| js_sys::Array::from(&value).iter().map(|label| label.as_string().unwrap_or_default()).collect::<Vec<_>>());
+        if !replace && self.edge_indices.len() != self.edges.len() {
+            return Err(JsValue::from_str("Can't append a subgraph to positional edges"));
+        }
+
+        self.sync_physics_positions();
+        let first = if replace { 0 } else { self.nodes.len() };
+        if replace {
+            self.set_nodes(&[]);
+            self.set_indexed_edges(&[]);
+            self.node_labels.clear();
+        }
+        self.nodes.extend(nodes.chunks_exact(7).enumerate().map(|(i, node)| NodeData {
+            x: node[0],
+            y: node[1],
+            vx: 0.0,
+            vy: 0.0,
+            fx: 0.0,
+            fy: 0.0,
+            r: node[2],
+            g: node[3],
+            b: node[4],
+            a: node[5],
+            size: node[6],
+            mass: 1.0,
+            flags: classes.as_ref().map_or(0, |classes| classes[i] << NODE_CLASS_SHIFT),
+        }));
+        for edge in edges.chunks_exact(7) {
+            self.edge_indices.push([first as u32 + edge[0] as u32, first as u32 + edge[1] as u32]);
+            self.edges.push(EdgeData {
+                x1: 0.0,
+                y1: 0.0,
+                x2: 0.0,
+                y2: 0.0,
+                r: edge[2],
+                g: edge[3],
+                b: edge[4],
+                a: edge[5],
+                width: edge[6],
+            });
+        }
+        if let Some(labels) = labels {
+            self.node_labels.resize(first, String::new());
+            self.node_labels.extend(labels.into_iter().take(node_count));
+            self.label_index = std::cell::OnceCell::new();
+        }
+
+        self.mark_physics_dirty();
+        self.animation = None;
+        self.color_backup = None;
+        self.node_count_changed();
+        self.refresh_edge_endpoints();
+        log_debug!("Loaded subgraph: {} nodes, {} edges", node_count, edges.len() / 7);
+        self.check_capacity();
+        Ok(())
+    }
+
+    /// Saves the nodes (positions, colors, sizes, flags) and edges so `undo` can
+    /// return to them. Nothing is saved automatically: call this before each
+    /// edit the user should be able to undo. Clears the redo states.
+    #[wasm_bindgen]
+    pub fn push_undo_snapshot(&mut self) {
+        let snapshot = self.undo_snapshot();
+        self.history.push(snapshot);
+    }
+
+    /// Returns to the latest snapshot, keeping the current state for `redo`.
+    /// Returns false if there is nothing to undo.
+    #[wasm_bindgen]
+    pub fn undo(&mut self) -> bool {
+        if !self.history.can_undo() {
+            return false;
+        }
+        let current = self.undo_snapshot();
+        let previous = self.history.undo(current);
+        previous.map(|snapshot| self.restore_snapshot(snapshot)).is_some()
+    }
+
+    /// Reapplies the latest undone state. Returns false if there is none.
+    #[wasm_bindgen]
+    pub fn redo(&mut self) -> bool {
+        if !self.history.can_redo() {
+            return false;
+        }
+        let current = self.undo_snapshot();
+        let next = self.history.redo(current);
+        next.map(|snapshot| self.restore_snapshot(snapshot)).is_some()
+    }
+
+    #[wasm_bindgen]
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    #[wasm_bindgen]
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// How many snapshots are kept (50 by default); the oldest are dropped
+    /// first. 0 disables undo.
+    #[wasm_bindgen]
+    pub fn set_undo_limit(&mut self, limit: u32) {
+        self.history.set_limit(limit as usize);
+    }
+
+    /// Whether snapshots also save and restore the camera (off by default).
+    #[wasm_bindgen]
+    pub fn set_undo_camera(&mut self, enabled: bool) {
+        self.undo_camera = enabled;
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_undo_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Sets a spring rest length per indexed edge; values <= 0 fall back to the
+    /// `rest_length` passed to `integrate_physics`.
+    #[wasm_bindgen]
+    pub fn set_edge_lengths(&mut self, lengths: &[f32]) -> Result<(), JsValue> {
+        if lengths.len() != self.edge_indices.len() {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} edge lengths, got {}",
+                self.edge_indices.len(),
+                lengths.len()
+            )));
+        }
+
+        self.edge_lengths = lengths.to_vec();
+        self.topology_dirty = true;
+        Ok(())
+    }
+
+    /// Sets a weight per indexed edge that multiplies the global spring constant,
+    /// so strong ties pull their endpoints closer. Negative weights are treated as 0.
+    #[wasm_bindgen]
+    pub fn set_edge_weights(&mut self, weights: &[f32]) -> Result<(), JsValue> {
+        if weights.len() != self.edge_indices.len() {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} edge weights, got {}",
+                self.edge_indices.len(),
+                weights.len()
+            )));
+        }
+
+        self.edge_weights = weights.iter().map(|&weight| if weight.is_finite() { weight.max(0.0) } else { 1.0 }).collect();
+        self.topology_dirty = true;
+        Ok(())
+    }
+
+    /// Draws an arrowhead at the target end of every edge: `style` is "triangle",
+    /// "open" (a chevron), "circle" or "none", the default. `size_px` is the
+    /// head's length in canvas pixels whatever the zoom, but at most half the
+    /// visible edge so short edges keep some line. The line stops at the back
+    /// of filled heads; on indexed edges the tip sits just outside the target
+    /// node. `set_edge_arrows` overrides this per edge.
+    #[wasm_bindgen]
+    pub fn set_arrow_style(&mut self, size_px: f32, style: &str) -> Result<(), JsValue> {
+        let style = ArrowStyle::parse(style).ok_or_else(|| JsValue::from_str(&format!("Unknown arrow style: {}", style)))?;
+        if !(size_px.is_finite() && size_px >= 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid arrow size: {}", size_px)));
+        }
+        self.renderer.set_arrow_style(style, size_px);
+        // Turning arrows off clears their part of the GPU copy
+        self.user_flags_dirty = true;
+        Ok(())
+    }
+
+    /// Snaps nodes drawn under 8 canvas pixels across to the pixel grid: whole
+    /// pixel diameters, centered on pixels, so dense graphs of tiny nodes show
+    /// crisp, identical dots instead of blurry ones. With the canvas sized by
+    /// the pixel ratio (as `observe_resize` does) these are device pixels.
+    /// Larger nodes keep moving smoothly, and only the drawing changes:
+    /// picking and exports use the true positions. Off by default.
+    #[wasm_bindgen]
+    pub fn set_pixel_snap(&mut self, enabled: bool) {
+        self.renderer.set_pixel_snap(enabled);
+    }
+
+    /// Tunes edge anti-aliasing: outlines fade over `smoothing_px` screen pixels
+    /// (default 1; 0 gives hard, aliased edges), and edges drawn thinner than
+    /// `min_width_px` canvas pixels (default 1) are widened to it with their alpha
+    /// cut by the same factor, so hairlines fade out when zooming out rather than
+    /// breaking up. A minimum of 0 draws every edge at its true width.
+    #[wasm_bindgen]
+    pub fn set_edge_antialias(&mut self, smoothing_px: f32, min_width_px: f32) -> Result<(), JsValue> {
+        if !(smoothing_px.is_finite() && smoothing_px >= 0.0 && min_width_px.is_finite() && min_width_px >= 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid edge smoothing {} or minimum width {}", smoothing_px, min_width_px)));
+        }
+        self.renderer.set_edge_antialias(smoothing_px, min_width_px);
+        Ok(())
+    }
+
+    /// Draws an image behind the edges and nodes, stretched over `world_rect`
+    /// ([min x, min y, max x, max y] in world units) so it pans and zooms with
+    /// the graph. `rgba` holds `width` by `height` pixels, 4 bytes each, rows
+    /// top first. The image fades with the global opacity, and the clear color
+    /// shows around it. Setting another image of the same size reuses its
+    /// texture rather than allocating a new one.
+    #[wasm_bindgen]
+    pub fn set_background_image(&mut self, rgba: &[u8], width: u32, height: u32, world_rect: &[f32]) -> Result<(), JsValue> {
+        let &[min_x, min_y, max_x, max_y] = world_rect else {
+            return Err(JsValue::from_str(&format!("Background rect needs 4 values, got {}", world_rect.len())));
+        };
+        if !world_rect.iter().all(|value| value.is_finite()) || max_x <= min_x || max_y <= min_y {
+            return Err(JsValue::from_str(&format!("Invalid background rect {:?}", world_rect)));
+        }
+        self.renderer
+            .set_background_image(rgba, width, height, [min_x, min_y, max_x, max_y])
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// Removes the image set by `set_background_image`.
+    #[wasm_bindgen]
+    pub fn clear_background_image(&mut self) {
+        self.renderer.clear_background_image();
+    }
+
+    /// Gives the edges at `indices` their own arrowhead: `style` as in
+    /// `set_arrow_style` ("none" leaves them without one even while others
+    /// have arrows), or "default" for the global style; `size_px` of 0 takes
+    /// the global size. The overrides last until the edges are replaced.
+    #[wasm_bindgen]
+    pub fn set_edge_arrows(&mut self, indices: &[u32], style: &str, size_px: f32) -> Result<(), JsValue> {
+        if let Some(index) = indices.iter().find(|&&index| index as usize >= self.edges.len()) {
+            return Err(JsValue::from_str(&format!("Edge index {} out of range ({} edges)", index, self.edges.len())));
+        }
+        let style = match style {
+            "default" => 0,
+            _ => ArrowStyle::parse(style).ok_or_else(|| JsValue::from_str(&format!("Unknown arrow style: {}", style)))?.code() + 1,
+        };
+        if !(size_px.is_finite() && size_px >= 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid arrow size: {}", size_px)));
+        }
+
+        self.edge_arrows.resize(self.edges.len(), (0, 0.0));
+        for &index in indices {
+            self.edge_arrows[index as usize] = (style, size_px);
+        }
+        Ok(())
+    }
+
+    /// Drops every `set_edge_arrows` override.
+    #[wasm_bindgen]
+    pub fn clear_edge_arrows(&mut self) {
+        if !self.edge_arrows.is_empty() {
+            self.edge_arrows.clear();
+            // The GPU copy keeps them until the next upload
+            self.user_flags_dirty = true;
+        }
+    }
+
+    /// Maps the edge weights linearly onto rendered widths between `min_width`
+    /// (lightest edge) and `max_width` (heaviest edge).
+    #[wasm_bindgen]
+    pub fn set_edge_widths_from_weights(&mut self, min_width: f32, max_width: f32) {
+        let (lightest, heaviest) = self
+            .edge_weights
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), &weight| (lo.min(weight), hi.max(weight)));
+        let range = heaviest - lightest;
+
+        for (edge, &weight) in self.edges.iter_mut().zip(&self.edge_weights) {
+            let t = if range > 0.0 { (weight - lightest) / range } else { 1.0 };
+            edge.width = min_width + (max_width - min_width) * t;
+        }
+    }
+
+    // Moving the camera by hand ends a running camera tour or focus_node
+    #[wasm_bindgen]
+    pub fn set_camera_position(&mut self, x: f32, y: f32) {
+        self.camera_tour = None;
+        self.camera_focus = None;
+        self.camera_position = [x, y];
+    }
+
+    /// Canvas pixels per world unit, the same on both axes whatever the canvas's
+    /// aspect ratio, so a resize shows more or less of the world instead of
+    /// stretching it. The camera position is the world point at the top left.
+    #[wasm_bindgen]
+    pub fn set_camera_zoom(&mut self, zoom: f32) {
+        self.camera_tour = None;
+        self.camera_focus = None;
+        // NaN gets the minimum
+        self.camera_zoom = if zoom.is_nan() { 0.1 } else { zoom.clamp(0.1, 10.0) };
+    }
+
+    /// Scales the zoom by `factor` while keeping the world point under canvas
+    /// pixel (screen_x, screen_y) in place, e.g. for wheel or pinch zooming.
+    #[wasm_bindgen]
+    pub fn zoom_at(&mut self, screen_x: f32, screen_y: f32, factor: f32) {
+        let world = [
+            self.camera_position[0] + screen_x / self.camera_zoom,
+            self.camera_position[1] + screen_y / self.camera_zoom,
+        ];
+        self.set_camera_zoom(self.camera_zoom * factor);
+        self.camera_position = [world[0] - screen_x / self.camera_zoom, world[1] - screen_y / self.camera_zoom];
+    }
+
+    /// Pans with one finger and pinch-zooms with two on touch screens. Taps
+    /// count as clicks for the `on` callbacks, even with a little movement.
+    /// Mouse and pen input are left to the app. Off by default.
+    #[wasm_bindgen]
+    pub fn set_touch_gestures(&mut self, enabled: bool) {
+        self.touch_gestures = enabled;
+        self.touch_gesture.clear();
+        self.update_canvas_listeners();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_camera_position_x(&self) -> f32 {
+        self.camera_position[0]
+    }
+
+    #[wasm_bindgen]
+    pub fn get_camera_position_y(&self) -> f32 {
+        self.camera_position[1]
+    }
+
+    #[wasm_bindgen]
+    pub fn get_camera_zoom(&self) -> f32 {
+        self.camera_zoom
+    }
+
+    #[wasm_bindgen]
+    pub fn reset_camera(&mut self) {
+        self.camera_tour = None;
+        self.camera_focus = None;
+        self.camera_position = [0.0, 0.0];
+        self.camera_zoom = 1.0;
+    }
+
+    /// Flies the camera to each of `node_indices` in turn over `travel_ms`,
+    /// zooming to `zoom`, and stays on each for `dwell_ms`. Each node's
+    /// position is read when the camera sets off towards it, so the tour
+    /// follows a running simulation. Any other camera change stops the tour.
+    #[wasm_bindgen]
+    pub fn start_camera_tour(&mut self, node_indices: &[u32], dwell_ms: f32, travel_ms: f32, zoom: f32) -> Result<(), JsValue> {
+        self.check_node_indices(node_indices)?;
+        if !(dwell_ms.is_finite() && travel_ms.is_finite() && dwell_ms >= 0.0 && travel_ms >= 0.0) {
+            return Err(JsValue::from_str(&format!("Tour durations must be non-negative, got dwell {} and travel {}", dwell_ms, travel_ms)));
+        }
+        if !(zoom.is_finite() && zoom > 0.0) {
+            return Err(JsValue::from_str(&format!("Tour zoom must be positive, got {}", zoom)));
+        }
+        self.camera_focus = None;
+        self.camera_tour = Some(CameraTour::new(node_indices.to_vec(), dwell_ms, travel_ms, zoom.clamp(0.1, 10.0)));
+        Ok(())
+    }
+
+    /// Stops the camera tour where it is, without calling the end callback.
+    #[wasm_bindgen]
+    pub fn stop_camera_tour(&mut self) {
+        self.camera_tour = None;
+    }
+
+    #[wasm_bindgen]
+    pub fn is_camera_tour_running(&self) -> bool {
+        self.camera_tour.is_some()
+    }
+
+    /// Calls `callback` with no arguments when a camera tour finishes its last
+    /// dwell. Stopped or interrupted tours don't call it.
+    #[wasm_bindgen]
+    pub fn set_on_camera_tour_end(&mut self, callback: js_sys::Function) {
+        self.camera_tour_callback = Some(callback);
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_on_camera_tour_end(&mut self) {
+        self.camera_tour_callback = None;
+    }
+
+    /// Flies the camera over `duration_ms` (ease-in-out) until the node at
+    /// `index` is centered at `target_zoom`. A zoom <= 0 picks one that makes
+    /// the node and its neighbors fill about 60% of the view. The node's
+    /// position is followed during the flight, so a node the simulation is
+    /// moving still ends up centered. Any other camera change or a camera
+    /// tour stops it.
+    #[wasm_bindgen]
+    pub fn focus_node(&mut self, index: u32, target_zoom: f32, duration_ms: f32) -> Result<(), JsValue> {
+        self.check_node_indices(&[index])?;
+        if !(target_zoom.is_finite() && duration_ms.is_finite()) {
+            return Err(JsValue::from_str(&format!("Invalid focus zoom {} or duration {}", target_zoom, duration_ms)));
+        }
+        let zoom = if target_zoom > 0.0 { target_zoom } else { self.neighborhood_zoom(index as usize) };
+        self.camera_tour = None;
+        self.camera_focus = Some(CameraFocus::new(index, duration_ms, zoom.clamp(0.1, 10.0)));
+        Ok(())
+    }
+
+    /// Double-clicking a node focuses it as `focus_node` does, at the zoom
+    /// fitting its neighborhood, over half a second. Off by default.
+    #[wasm_bindgen]
+    pub fn set_double_click_focus(&mut self, enabled: bool) {
+        self.double_click_focus = enabled;
+        self.update_canvas_listeners();
+    }
+
+    #[wasm_bindgen]
+    pub fn get_max_nodes(&self) -> u32 {
+        MAX_NODES as u32
+    }
+
+    #[wasm_bindgen]
+    pub fn get_max_edges(&self) -> u32 {
+        MAX_EDGES as u32
+    }
+
+    #[wasm_bindgen]
+    pub fn get_current_node_count(&self) -> u32 {
+        self.nodes.len() as u32
+    }
+
+    /// Number of nodes drawn, i.e. not hidden or filtered out.
+    #[wasm_bindgen]
+    pub fn get_visible_node_count(&self) -> u32 {
+        (0..self.nodes.len()).filter(|&i| self.node_shown(i)).count() as u32
+    }
+
+    /// Gives every node a 32-bit mask of flags of your own, one per node, for
+    /// `set_visibility_filter`; an empty array removes them. Independent of
+    /// the renderer's own node flags.
+    #[wasm_bindgen]
+    pub fn set_node_flags(&mut self, flags: &[u32]) -> Result<(), JsValue> {
+        if !flags.is_empty() && flags.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} node flags, got {}", self.nodes.len(), flags.len())));
+        }
+        self.user_flags = flags.to_vec();
+        self.user_flags_dirty = true;
+        self.invalidate_extent();
+        Ok(())
+    }
+
+    /// Draws only the nodes whose `set_node_flags` mask has every bit of
+    /// `required_mask` and none of `forbidden_mask`, and the indexed edges
+    /// between them; positional edges are always drawn. The filter runs on the
+    /// GPU, so changing it costs nothing per node. Picking, visible counts and
+    /// bounds follow it. `set_visibility_filter(0, 0)` shows everything.
+    #[wasm_bindgen]
+    pub fn set_visibility_filter(&mut self, required_mask: u32, forbidden_mask: u32) {
+        self.visibility_filter = [required_mask, forbidden_mask];
+        // Cluster frames apply it themselves
+        if !self.clusters_drawn {
+            self.renderer.set_visibility_filter(required_mask, forbidden_mask);
+        }
+        self.invalidate_extent();
+    }
+
+    /// Draws an edge from node `source_index` to (`world_x`, `world_y`) over
+    /// everything else, following the node as it moves, e.g. while the user
+    /// picks the target of a new edge. The edge data is left alone. `style` can
+    /// set `color` (CSS string, default the theme's highlight at 60% alpha),
+    /// `width` and `dash` (screen pixels, default 2 and 6; a dash of 0 is
+    /// solid). A negative index clears the preview.
+    #[wasm_bindgen]
+    pub fn set_preview_edge(&mut self, source_index: i32, world_x: f32, world_y: f32, style: JsValue) -> Result<(), JsValue> {
+        let Ok(source) = usize::try_from(source_index) else {
+            self.clear_preview_edge();
+            return Ok(());
+        };
+        if source >= self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Node index {} out of range (0-{})", source, self.nodes.len() as i64 - 1)));
+        }
+        if !(world_x.is_finite() && world_y.is_finite()) {
+            return Err(JsValue::from_str("Preview edge target must be finite"));
+        }
+        let style = PreviewStyle::parse(&style, &["color", "width", "dash"])?;
+        self.preview_edge = Some(PreviewEdge {
+            source,
+            target: [world_x, world_y],
+            color: style.color.unwrap_or(self.preview_color()),
+            width: style.number("width").unwrap_or(DEFAULT_PREVIEW_EDGE_WIDTH),
+            dash: style.number("dash").unwrap_or(DEFAULT_PREVIEW_DASH),
+        });
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_preview_edge(&mut self) {
+        self.preview_edge = None;
+    }
+
+    /// Draws a node at (`world_x`, `world_y`) over everything else without
+    /// adding it, e.g. where a click would add one. `style` can set `color`
+    /// (like `set_preview_edge`) and `size` (world units, default 10).
+    #[wasm_bindgen]
+    pub fn set_preview_node(&mut self, world_x: f32, world_y: f32, style: JsValue) -> Result<(), JsValue> {
+        if !(world_x.is_finite() && world_y.is_finite()) {
+            return Err(JsValue::from_str("Preview node position must be finite"));
+        }
+        let style = PreviewStyle::parse(&style, &["color", "size"])?;
+        let [r, g, b, a] = style.color.unwrap_or(self.preview_color());
+        self.preview_node = Some(NodeData {
+            x: world_x,
+            y: world_y,
+            vx: 0.0,
+            vy: 0.0,
+            fx: 0.0,
+            fy: 0.0,
+            r,
+            g,
+            b,
+            a,
+            size: style.number("size").unwrap_or(DEFAULT_PREVIEW_NODE_SIZE),
+            mass: 1.0,
+            flags: 0,
+        });
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_preview_node(&mut self) {
+        self.preview_node = None;
+    }
+
+    /// Adds a layer on top of the others and returns its id. Layer 0 always
+    /// exists and holds every node and edge not put elsewhere with
+    /// `set_node_layers` or `set_edge_layers`. Layers share the camera; higher
+    /// layers' nodes are drawn over lower layers' nodes, and their edges over
+    /// lower layers' edges, with every edge still under every node.
+    #[wasm_bindgen]
+    pub fn create_layer(&mut self) -> u32 {
+        self.layer_order = None;
+        self.layers.create()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_layer_count(&self) -> u32 {
+        self.layers.len() as u32
+    }
+
+    /// Puts each node in a layer, one id per node, or every node back in layer
+    /// 0 if empty. Reset when nodes are added or removed.
+    #[wasm_bindgen]
+    pub fn set_node_layers(&mut self, layers: &[u32]) -> Result<(), JsValue> {
+        if !layers.is_empty() && layers.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} node layers, got {}", self.nodes.len(), layers.len())));
+        }
+        self.check_layers(layers)?;
+        self.node_layers = layers.to_vec();
+        self.layers_changed();
+        Ok(())
+    }
+
+    /// Puts each edge in a layer, one id per edge, or every edge back in layer
+    /// 0 if empty. Ignored once the edge count changes.
+    #[wasm_bindgen]
+    pub fn set_edge_layers(&mut self, layers: &[u32]) -> Result<(), JsValue> {
+        if !layers.is_empty() && layers.len() != self.edges.len() {
+            return Err(JsValue::from_str(&format!("Expected {} edge layers, got {}", self.edges.len(), layers.len())));
+        }
+        self.check_layers(layers)?;
+        self.edge_layers = layers.to_vec();
+        self.layers_changed();
+        Ok(())
+    }
+
+    /// The layer of node `index`, or undefined if there's no such node.
+    #[wasm_bindgen]
+    pub fn get_node_layer(&self, index: u32) -> Option<u32> {
+        ((index as usize) < self.nodes.len()).then(|| self.node_layer(index as usize))
+    }
+
+    /// The layer of edge `index`, or undefined if there's no such edge.
+    #[wasm_bindgen]
+    pub fn get_edge_layer(&self, index: u32) -> Option<u32> {
+        ((index as usize) < self.edges.len()).then(|| self.edge_layer(index as usize))
+    }
+
+    /// Shows or hides a layer's nodes and edges, and the edges touching its
+    /// nodes. Hidden items can't be picked.
+    #[wasm_bindgen]
+    pub fn set_layer_visible(&mut self, layer: u32, visible: bool) -> Result<(), JsValue> {
+        self.check_layers(&[layer])?;
+        if let Some(entry) = self.layers.get_mut(layer) {
+            entry.visible = visible;
+        }
+        self.layers_changed();
+        Ok(())
+    }
+
+    /// Multiplies the alpha of a layer's nodes and edges by `opacity` (clamped
+    /// to 0-1), on top of their colors and the global opacity.
+    #[wasm_bindgen]
+    pub fn set_layer_opacity(&mut self, layer: u32, opacity: f32) -> Result<(), JsValue> {
+        self.check_layers(&[layer])?;
+        if opacity.is_nan() {
+            return Err(JsValue::from_str("Layer opacity must be a number"));
+        }
+        if let Some(entry) = self.layers.get_mut(layer) {
+            entry.opacity = opacity.clamp(0.0, 1.0);
+        }
+        Ok(())
+    }
+
+    /// Sets the drawing order of the layers, bottom first; `order` must list
+    /// every layer once. New layers go on top.
+    #[wasm_bindgen]
+    pub fn set_layer_order(&mut self, order: &[u32]) -> Result<(), JsValue> {
+        self.layers.set_order(order).map_err(|e| JsValue::from_str(&e))?;
+        self.layer_order = None;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_layer_order(&self) -> Vec<u32> {
+        self.layers.order().to_vec()
+    }
+
+    /// With `enabled`, draws the nodes of each layer in Morton order over
+    /// their positions, so neighboring instances cover nearby pixels, which
+    /// can spare the GPU's framebuffer cache under heavy overdraw. The order is
+    /// worked out when the nodes are set or placed and when the simulation
+    /// settles, not per frame, and node indices everywhere keep referring to
+    /// the original order. Off by default: the instances are then gathered on
+    /// the CPU every frame rather than drawn from the GPU simulation, so
+    /// compare frame times before turning it on.
+    #[wasm_bindgen]
+    pub fn set_spatial_sorting(&mut self, enabled: bool) {
+        self.spatial_sorting = enabled;
+        self.layer_order = None;
+    }
+
+    /// Number of edges drawn: indexed edges need both endpoints visible.
+    #[wasm_bindgen]
+    pub fn get_visible_edge_count(&self) -> u32 {
+        (0..self.edges.len()).filter(|&i| self.edge_shown(i)).count() as u32
+    }
+
+    #[wasm_bindgen]
+    pub fn get_current_edge_count(&self) -> u32 {
+        self.edges.len() as u32
+    }
+
+    /// Runs one simulation tick with the given parameters, which are kept for
+    /// later `step_simulation` calls.
+    #[wasm_bindgen]
+    pub fn integrate_physics(
+        &mut self,
+        delta_time: f32,
+        damping_factor: f32,
+        spring_constant: f32,
+        rest_length: f32,
+        repulsion_strength: f32,
+        repulsion_radius: f32,
+    ) -> Result<(), JsValue> {
+        self.set_physics_parameters(delta_time, damping_factor, spring_constant, rest_length, repulsion_strength, repulsion_radius);
+        self.interpolate_positions = false;
+        self.tick_physics().map(|_| ())
+    }
+
+    /// Runs up to `iterations` simulation ticks before the first frame so the graph
+    /// appears already laid out. Work is split into chunks that yield to the event
+    /// loop, and `on_progress` (if given) is called with the completed fraction
+    /// after each one. Resolves once the final positions have been read back.
+    ///
+    /// Intended sequence: `set_nodes` / `set_indexed_edges`, `seed_positions`,
+    /// `await prelayout(n)`, then fit the camera and start the render loop. No
+    /// other method may be called on the renderer until the promise resolves.
+    #[wasm_bindgen]
+    pub async fn prelayout(&mut self, iterations: u32, on_progress: Option<js_sys::Function>) -> Result<(), JsValue> {
+        if !self.is_initialized {
+            return Err(JsValue::from_str("Renderer not initialized"));
+        }
+
+        let mut completed = 0;
+        while completed < iterations && self.is_simulation_active() {
+            let chunk_end = (completed + PRELAYOUT_CHUNK_TICKS).min(iterations);
+            while completed < chunk_end && self.tick_physics()? {
+                completed += 1;
+            }
+
+            if let Some(on_progress) = &on_progress {
+                // Errors thrown by the callback must not abort the layout
+                let _ = on_progress.call1(&JsValue::NULL, &JsValue::from(completed as f32 / iterations as f32));
+            }
+            yield_to_event_loop().await?;
+        }
+
+        self.finish_readback().await?;
+
+        if let Some(on_progress) = &on_progress {
+            let _ = on_progress.call1(&JsValue::NULL, &JsValue::from(1.0));
+        }
+        Ok(())
+    }
+
+    /// Runs the simulation until it cools down (alpha below `alpha_min`) or
+    /// for `max_iterations` ticks (default 3000), then pauses it, reads the
+    /// final positions back so `get_nodes` and the exports see them, and zeroes
+    /// the velocities. Resolves with `{iterations, kinetic_energy, converged}`,
+    /// the energy being from just before the velocities were zeroed. `options`
+    /// can also set `on_progress`, called like `prelayout`'s with the larger of
+    /// the share of `max_iterations` run and how far alpha has cooled. When the
+    /// simulation isn't running it freezes the current state at once.
+    /// `resume_simulation` (and `reheat`) thaw the layout. No other method may
+    /// be called on the renderer until the promise resolves.
+    #[wasm_bindgen]
+    pub async fn freeze_layout(&mut self, options: JsValue) -> Result<JsValue, JsValue> {
+        if !self.is_initialized {
+            return Err(JsValue::from_str("Renderer not initialized"));
+        }
+        if self.animation.is_some() || self.graph_loading {
+            return Err(JsValue::from_str("Can't freeze the layout during a transition or graph load"));
+        }
+        if !(options.is_undefined() || options.is_null() || options.is_object()) {
+            return Err(JsValue::from_str("Freeze options must be an object"));
+        }
+        let field = |key: &str| js_sys::Reflect::get(&options, &JsValue::from_str(key)).ok().filter(|value| !value.is_undefined() && !value.is_null());
+        let max_iterations = match field("max_iterations") {
+            None => DEFAULT_FREEZE_MAX_ITERATIONS,
+            Some(value) => value
+                .as_f64()
+                .filter(|count| count.fract() == 0.0 && *count >= 0.0 && *count <= u32::MAX as f64)
+                .ok_or_else(|| JsValue::from_str("Freeze max_iterations must be a whole number"))? as u32,
+        };
+        let on_progress = match field("on_progress") {
+            None => None,
+            Some(value) => Some(value.dyn_into::<js_sys::Function>().map_err(|_| JsValue::from_str("Freeze on_progress must be a function"))?),
+        };
+
+        // Alpha cools geometrically, so its log gives a steady share
+        let start_alpha = self.physics.alpha;
+        let cooled = |alpha: f32, alpha_min: f32| {
+            let share = (start_alpha.ln() - alpha.ln()) / (start_alpha.ln() - alpha_min.ln());
+            if share.is_finite() { share.clamp(0.0, 1.0) } else { 0.0 }
+        };
+        let mut iterations = 0;
+        while iterations < max_iterations && self.is_simulation_active() {
+            let chunk_end = (iterations + PRELAYOUT_CHUNK_TICKS).min(max_iterations);
+            while iterations < chunk_end && self.tick_physics()? {
+                iterations += 1;
+            }
+            if let Some(on_progress) = &on_progress {
+                let progress = (iterations as f32 / max_iterations as f32).max(cooled(self.physics.alpha, self.physics.alpha_min));
+                let _ = on_progress.call1(&JsValue::NULL, &JsValue::from(progress));
+            }
+            yield_to_event_loop().await?;
+        }
+        let converged = !self.physics.is_active();
+
+        self.pause_simulation();
+        self.finish_readback().await?;
+        let kinetic_energy: f32 = self.nodes.iter().map(|node| 0.5 * node.mass * (node.vx * node.vx + node.vy * node.vy)).sum();
+        for node in &mut self.nodes {
+            node.vx = 0.0;
+            node.vy = 0.0;
+            node.fx = 0.0;
+            node.fy = 0.0;
+        }
+        // The GPU copy gets the resting nodes before the next tick
+        self.mark_physics_dirty();
+        self.kinetic_energy = 0.0;
+
+        if let Some(on_progress) = &on_progress {
+            let _ = on_progress.call1(&JsValue::NULL, &JsValue::from(1.0));
+        }
+        let summary = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&summary, &JsValue::from_str(key), &value);
+        };
+        set("iterations", JsValue::from(iterations));
+        set("kinetic_energy", JsValue::from(kinetic_energy));
+        set("converged", JsValue::from(converged));
+        Ok(summary.into())
+    }
+
+    /// Renders `frames` frames of a random graph (the same for a given size on
+    /// every device) with the default spring-electric simulation running, for
+    /// comparing devices and spotting regressions. Resolves with `{node_count,
+    /// edge_count, frames, gpu_physics, avg_frame_ms, p95_frame_ms,
+    /// avg_physics_ms, upload_bytes_per_frame, fps}`. Frame and physics times are
+    /// CPU time; GPU work that overlaps with it isn't counted. Frames are spaced
+    /// by a zero timeout rather than the display, so `fps` is not capped by the
+    /// refresh rate, though browsers clamp nested timeouts to a few milliseconds.
+    ///
+    /// The graph, camera and simulation settings are restored afterwards, also
+    /// when the benchmark fails; a running transition or drag is dropped. No
+    /// other method may be called on the renderer until the promise resolves.
+    #[wasm_bindgen]
+    pub async fn run_benchmark(&mut self, node_count: u32, edge_count: u32, frames: u32) -> Result<JsValue, JsValue> {
+        if !self.is_initialized {
+            return Err(JsValue::from_str("Renderer not initialized"));
+        }
+        if node_count == 0 || frames == 0 {
+            return Err(JsValue::from_str("Benchmark needs at least one node and one frame"));
+        }
+
+        let backup = self.begin_benchmark(node_count as usize, edge_count as usize);
+        let report = self.benchmark_frames(frames).await;
+        self.end_benchmark(backup);
+        report
+    }
+
+    /// Sets the simulation parameters used by `step_simulation`; `delta_time` is
+    /// the simulation time advanced per tick.
+    #[wasm_bindgen]
+    pub fn set_physics_parameters(
+        &mut self,
+        delta_time: f32,
+        damping_factor: f32,
+        spring_constant: f32,
+        rest_length: f32,
+        repulsion_strength: f32,
+        repulsion_radius: f32,
+    ) {
+        self.physics.delta_time = delta_time;
+        self.physics.damping_factor = damping_factor;
+        self.physics.spring_constant = spring_constant;
+        self.physics.rest_length = rest_length;
+        self.physics.repulsion_strength = repulsion_strength;
+        self.physics.repulsion_radius = repulsion_radius;
+    }
+
+    /// Sets how many simulation ticks `step_simulation` runs per second of real
+    /// time, independent of the display refresh rate.
+    #[wasm_bindgen]
+    pub fn set_physics_rate(&mut self, hz: f32) -> Result<(), JsValue> {
+        if !(hz.is_finite() && hz > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid physics rate: {}", hz)));
+        }
+        self.physics_rate = hz;
+        Ok(())
+    }
+
+    /// Advances the simulation by `elapsed_seconds` of real time using fixed-rate
+    /// ticks. Time left over is carried to the next call and used to interpolate
+    /// rendered positions between the last two simulated states; at most
+    /// MAX_PHYSICS_STEPS_PER_FRAME ticks run per call, so a long pause (e.g. a
+    /// backgrounded tab) doesn't trigger a burst of catch-up work. Returns the
+    /// number of ticks run.
+    #[wasm_bindgen]
+    pub fn step_simulation(&mut self, elapsed_seconds: f32) -> Result<u32, JsValue> {
+        let step = 1.0 / self.physics_rate;
+        if elapsed_seconds.is_finite() {
+            self.physics_accumulator += elapsed_seconds.max(0.0);
+        }
+
+        let mut steps = 0;
+        while self.physics_accumulator >= step && steps < MAX_PHYSICS_STEPS_PER_FRAME {
+            if !self.tick_physics()? {
+                break;
+            }
+            self.physics_accumulator -= step;
+            steps += 1;
+        }
+        // Drop the backlog that didn't fit into this frame
+        self.physics_accumulator = self.physics_accumulator.min(step);
+        self.interpolate_positions = true;
+
+        Ok(steps)
+    }
+
+    /// Sets the simulation temperature directly; forces are scaled by alpha.
+    #[wasm_bindgen]
+    pub fn set_alpha(&mut self, alpha: f32) {
+        self.physics.alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_alpha(&self) -> f32 {
+        self.physics.alpha
+    }
+
+    /// Sets how quickly alpha approaches its target each tick, in [0, 1].
+    #[wasm_bindgen]
+    pub fn set_alpha_decay(&mut self, decay: f32) {
+        self.physics.alpha_decay = decay.clamp(0.0, 1.0);
+    }
+
+    /// Sets the alpha below which the simulation pauses itself.
+    #[wasm_bindgen]
+    pub fn set_alpha_min(&mut self, alpha_min: f32) {
+        self.physics.alpha_min = alpha_min.clamp(0.0, 1.0);
+    }
+
+    /// Sets the alpha the simulation cools towards; a target above `alpha_min`
+    /// keeps the simulation running indefinitely (useful while dragging).
+    #[wasm_bindgen]
+    pub fn set_alpha_target(&mut self, alpha_target: f32) {
+        self.physics.alpha_target = alpha_target.clamp(0.0, 1.0);
+    }
+
+    /// Restarts a cooled-down simulation at full strength, e.g. after a drag.
+    /// Simulates every node, ending a local reheat.
+    #[wasm_bindgen]
+    pub fn reheat(&mut self) {
+        self.active_set = None;
+        self.physics.alpha = 1.0;
+    }
+
+    /// With `enabled`, a drag or `reheat_nodes` on a settled layout simulates
+    /// only the nodes within `hops` edges of those perturbed, the rest holding
+    /// still while still repelling them. The region grows to the neighbors of
+    /// nodes moving faster than a small threshold and shrinks back as they
+    /// settle, until the simulation cools down. Applies to the spring-electric
+    /// layout; see `simulated_nodes` and `physics_tick_ms` in
+    /// `get_render_stats`.
+    #[wasm_bindgen]
+    pub fn set_local_reheat(&mut self, enabled: bool, hops: u32) {
+        self.local_reheat = enabled.then_some(hops);
+        if !enabled {
+            self.active_set = None;
+        }
+    }
+
+    /// Reheats after a small change to the given nodes, e.g. to their edges or
+    /// masses: locally around them with `set_local_reheat`, else like `reheat`.
+    #[wasm_bindgen]
+    pub fn reheat_nodes(&mut self, indices: &[u32]) -> Result<(), JsValue> {
+        self.check_node_indices(indices)?;
+        self.reheat_around(indices);
+        Ok(())
+    }
+
+    /// Returns false once alpha has decayed below `alpha_min` (or the simulation is
+    /// paused) and ticks are skipped.
+    #[wasm_bindgen]
+    pub fn is_simulation_active(&self) -> bool {
+        self.physics.is_active() && !self.simulation_paused
+    }
+
+    /// Stops running ticks until `resume_simulation`; sends an "end" event if the
+    /// simulation was running.
+    #[wasm_bindgen]
+    pub fn pause_simulation(&mut self) {
+        self.simulation_paused = true;
+        self.end_simulation();
+    }
+
+    #[wasm_bindgen]
+    pub fn resume_simulation(&mut self) {
+        self.simulation_paused = false;
+    }
+
+    /// Registers a callback receiving `{ type, alpha, iteration }` objects, where
+    /// type is "start" (the first tick after the layout was cold or paused),
+    /// "tick" (every `set_tick_event_interval` ticks) or "end" (alpha fell below
+    /// `alpha_min`, or `pause_simulation` was called). Exceptions thrown by the
+    /// callback are ignored.
+    #[wasm_bindgen]
+    pub fn set_on_simulation_event(&mut self, callback: js_sys::Function) {
+        self.simulation_event_callback = Some(callback);
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_on_simulation_event(&mut self) {
+        self.simulation_event_callback = None;
+    }
+
+    /// Calls `callback` with a message whenever a frame fails: the surface
+    /// texture can't be acquired, or the GPU reports a validation error.
+    #[wasm_bindgen]
+    pub fn set_on_render_error(&mut self, callback: js_sys::Function) {
+        self.render_error_callback = Some(callback);
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_on_render_error(&mut self) {
+        self.render_error_callback = None;
+    }
+
+    /// Calls `callback` with a message and a details object for each warning.
+    /// Going over the node or edge limit warns once when it starts and once
+    /// when it ends, with `{kind: "nodes" | "edges", count, limit, truncated}`;
+    /// cycle searches over positional edges warn with `{kind: "cycles"}`.
+    /// Warnings are also logged at "warn" level.
+    #[wasm_bindgen]
+    pub fn set_on_warning(&mut self, callback: js_sys::Function) {
+        self.warning_callback = Some(callback);
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_on_warning(&mut self) {
+        self.warning_callback = None;
+    }
+
+    /// Calls `callback` on "nodeClick", "nodeHover", "edgeClick", "edgeHover",
+    /// "backgroundClick" or "viewportChange", replacing any earlier callback for
+    /// the event. Mouse events get `{index, layer, worldX, worldY, screenX,
+    /// screenY, originalEvent}`, with screen coordinates in CSS pixels from the canvas
+    /// corner; "nodeHover" fires when the hovered node changes, with a null
+    /// index once the pointer leaves every node, and "edgeHover" likewise for
+    /// edges, which nodes cover, hit as `pick_edge` does. "viewportChange" gets `{x, y,
+    /// zoom}` after the camera moved. Events are picked up by `render` and the
+    /// callbacks run just after it returns, so they may use the renderer.
+    #[wasm_bindgen]
+    pub fn on(&mut self, event: &str, callback: js_sys::Function) -> Result<(), JsValue> {
+        let kind = EventKind::parse(event).ok_or_else(|| JsValue::from_str(&format!("Unknown event: {}", event)))?;
+        if kind == EventKind::ViewportChange && !self.event_callbacks.contains_key(&kind) {
+            // Changes from before the callback was registered don't count
+            self.reported_viewport = (self.camera_position, self.camera_zoom);
+        }
+        self.event_callbacks.insert(kind, callback);
+        self.update_canvas_listeners();
+        Ok(())
+    }
+
+    /// Removes the callback registered with `on` for `event`.
+    #[wasm_bindgen]
+    pub fn off(&mut self, event: &str) -> Result<(), JsValue> {
+        let kind = EventKind::parse(event).ok_or_else(|| JsValue::from_str(&format!("Unknown event: {}", event)))?;
+        self.event_callbacks.remove(&kind);
+        self.update_canvas_listeners();
+        Ok(())
+    }
+
+    /// The topmost visible node under world point (x, y), if any; nodes are
+    /// hit a few screen pixels beyond their edge.
+    #[wasm_bindgen]
+    pub fn pick_node(&self, x: f32, y: f32) -> Option<u32> {
+        let drawn = self.layered_nodes().filter(|&i| self.node_shown(i)).map(|i| (i, &self.nodes[i]));
+        picking::node_at(drawn, [x, y], PICK_SLOP_PX / self.camera_zoom).map(|index| index as u32)
+    }
+
+    /// The drawn edge nearest to world point (x, y) within a few screen pixels
+    /// of its stroke, or within the `set_edge_hit_width` distance of its center
+    /// line if that's further, if any. Of equally near edges, the one drawn on
+    /// top wins.
+    #[wasm_bindgen]
+    pub fn pick_edge(&self, x: f32, y: f32) -> Option<u32> {
+        let drawn: Vec<(usize, EdgeData)> = self
+            .layered_edges()
+            .filter(|&i| self.edge_shown(i))
+            .flat_map(|i| (0..self.edge_instances(i)).map(move |segment| (i, self.seam_segment(i, &self.edges[i], segment))))
+            .collect();
+        let drawn = drawn.iter().map(|(i, edge)| (*i, edge));
+        picking::edge_at(drawn, [x, y], PICK_SLOP_PX / self.camera_zoom, self.edge_hit_px / self.camera_zoom).map(|index| index as u32)
+    }
+
+    /// The members of the cluster drawn at world point (x, y), by node index,
+    /// while `set_auto_clustering` draws clusters; a lone node in its cell is a
+    /// cluster of one. Empty when nothing is hit or no cluster is drawn.
+    #[wasm_bindgen]
+    pub fn pick_cluster(&self, x: f32, y: f32) -> Vec<u32> {
+        let Some(cache) = self.cluster_cache.as_ref().filter(|_| self.clusters_drawn) else {
+            return Vec::new();
+        };
+        let clusters = &cache.clusters.clusters;
+        let max_size = self.cluster_max_size(cache.pixel_radius);
+        let discs: Vec<NodeData> = clusters.iter().map(|cluster| self.cluster_node(&cache.clusters, cluster, max_size)).collect();
+        picking::node_at(discs.iter().enumerate(), [x, y], PICK_SLOP_PX / self.camera_zoom)
+            .map_or_else(Vec::new, |index| clusters[index].members.clone())
+    }
+
+    /// Below a zoom of 0.5, draws the shown nodes binned into square screen
+    /// cells twice `pixel_radius` CSS pixels across, one node per cell: a node
+    /// alone in its cell as itself, others as a disc up to `pixel_radius` in
+    /// radius, sized and colored (viridis) by member count. Edges between cells are bundled into one per
+    /// pair of cells, as wide as their count; edges within a cell aren't drawn,
+    /// nor are edges without endpoint indices. Crossing the zoom threshold
+    /// crossfades between the two views.
+    ///
+    /// Cells are sized for steps of the zoom a factor of √2 apart, so clusters
+    /// change when a step is crossed or the nodes move, not while panning or
+    /// zooming within a step. Use `pick_cluster` for the members under a point.
+    /// Pulses, idle motion and arrowheads aren't drawn while clustered.
+    #[wasm_bindgen]
+    pub fn set_auto_clustering(&mut self, enabled: bool, pixel_radius: f32) -> Result<(), JsValue> {
+        if enabled && !(pixel_radius.is_finite() && pixel_radius > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid cluster radius: {}", pixel_radius)));
+        }
+        // Disabling fades out the clusters last built
+        if enabled {
+            self.cluster_cache = None;
+        }
+        self.auto_clustering = enabled.then_some(pixel_radius);
+        Ok(())
+    }
+
+    /// Zoomed out so far that the shown nodes average less than `target_px` CSS
+    /// pixels apart on screen, draws only one node per square cell of that
+    /// size, the largest (of equals, the one drawn on top), and skips the rest
+    /// for the frame. Cells are aligned to the screen, so the image holds
+    /// still while the camera does. Above the spacing nothing is skipped.
+    /// Edges are drawn as usual. See `decimated_nodes` in `get_render_stats`.
+    #[wasm_bindgen]
+    pub fn set_decimation(&mut self, enabled: bool, target_px: f32) -> Result<(), JsValue> {
+        if enabled && !(target_px.is_finite() && target_px > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid decimation spacing: {}", target_px)));
+        }
+        self.decimation = enabled.then_some(target_px);
+        Ok(())
+    }
+
+    /// Draws the edges bundled (force-directed edge bundling): edges running
+    /// alike in direction, length and place curve toward each other into
+    /// bundles, which untangles dense drawings. `iterations` is the length of
+    /// the first of four refinement cycles (50 is typical), and
+    /// `compatibility_threshold` (between 0 and 1, e.g. 0.6) how alike two
+    /// edges must be to attract. The work yields to the event loop after each
+    /// iteration, and the promise resolves once the bundles are drawn. No other
+    /// method may be called on the renderer until then.
+    ///
+    /// Meant for static graphs: the bundles are dropped, and the edges drawn
+    /// straight again, once an endpoint moves by more than 5% of the mean edge
+    /// length or edges are added or removed. They're drawn while no edge is
+    /// hidden, faded or split at a seam, without auto clustering or compact
+    /// instances. Every edge needs endpoint indices, and the device vertex
+    /// shaders that read storage buffers (not WebGL).
+    #[wasm_bindgen]
+    pub async fn bundle_edges(&mut self, iterations: u32, compatibility_threshold: f32) -> Result<(), JsValue> {
+        if !(compatibility_threshold > 0.0 && compatibility_threshold < 1.0) {
+            return Err(JsValue::from_str(&format!("Compatibility threshold must be between 0 and 1, got {}", compatibility_threshold)));
+        }
+        if !self.renderer.has_edge_bundling() {
+            return Err(JsValue::from_str("Edge bundling needs vertex shaders that read storage buffers"));
+        }
+        if self.edges.is_empty() || self.edges.len() != self.edge_indices.len() {
+            return Err(JsValue::from_str("Edge bundling needs endpoint indices for every edge"));
+        }
+        self.sync_physics_positions();
+        let ends = self.edge_ends();
+        let mut bundler = EdgeBundler::new(&ends, iterations, compatibility_threshold);
+        while !bundler.is_done() {
+            bundler.step(BUNDLING_BATCH_WORK);
+            if !bundler.is_done() {
+                yield_to_event_loop().await?;
+            }
+        }
+        self.renderer.set_edge_bundles(&bundler.points(), bundler.segments() as u32).map_err(|e| JsValue::from_str(&e))?;
+        let lengths: f64 = ends.iter().map(|[a, b]| (b[0] - a[0]).hypot(b[1] - a[1])).filter(|length| length.is_finite()).map(f64::from).sum();
+        let tolerance = BUNDLE_TOLERANCE * (lengths / ends.len() as f64) as f32;
+        self.bundled_ends = Some(BundledEnds { ends, tolerance });
+        Ok(())
+    }
+
+    /// Draws the edges straight again after `bundle_edges`.
+    #[wasm_bindgen]
+    pub fn clear_bundling(&mut self) {
+        self.bundled_ends = None;
+        self.renderer.clear_edge_bundles();
+    }
+
+    /// Makes edges hit up to `min_pixels` screen pixels from their center line
+    /// however thin they're drawn, for `pick_edge`, "edgeClick" and "edgeHover".
+    /// 0 (the default) leaves the hit area at the drawn stroke.
+    #[wasm_bindgen]
+    pub fn set_edge_hit_width(&mut self, min_pixels: f32) -> Result<(), JsValue> {
+        if !(min_pixels.is_finite() && min_pixels >= 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid edge hit width: {}", min_pixels)));
+        }
+        self.edge_hit_px = min_pixels;
+        Ok(())
+    }
+
+    /// Sets how much is written to the console: "off", "error", "warn", "info"
+    /// (the default) or "debug". Applies to every renderer on the page.
+    #[wasm_bindgen]
+    pub fn set_log_level(&mut self, level: &str) -> Result<(), JsValue> {
+        let level = LogLevel::parse(level).ok_or_else(|| JsValue::from_str(&format!("Unknown log level: {}", level)))?;
+        logging::set_level(level);
+        Ok(())
+    }
+
+    /// The most recent render error, if any frame failed.
+    #[wasm_bindgen]
+    pub fn get_last_error(&self) -> Option<String> {
+        self.last_error.clone()
+    }
+
+    /// Sends a "tick" event every `interval` ticks; 0 disables tick events.
+    #[wasm_bindgen]
+    pub fn set_tick_event_interval(&mut self, interval: u32) {
+        self.tick_event_interval = interval;
+    }
+
+    /// Releases the GPU resources, graph data and JS callbacks. The renderer can
+    /// be initialized again with `init` afterwards.
+    #[wasm_bindgen]
+    pub fn destroy(&mut self) {
+        self.simulation_event_callback = None;
+        self.render_error_callback = None;
+        self.warning_callback = None;
+        self.camera_tour_callback = None;
+        self.camera_tour = None;
+        self.camera_focus = None;
+        self.event_callbacks.clear();
+        self.canvas_listeners = None;
+        self.canvas = None;
+        self.hovered_node = None;
+        self.hovered_edge = None;
+        self.touch_gestures = false;
+        self.double_click_focus = false;
+        self.touch_gesture.clear();
+        self.observe_resize = false;
+        self.resize_watcher = None;
+        self.minimap = None;
+        self.preview_edge = None;
+        self.preview_node = None;
+        self.layers = Layers::default();
+        self.node_layers.clear();
+        self.edge_layers.clear();
+        self.layer_order = None;
+        self.observed_size = None;
+        self.drag = None;
+        self.animation = None;
+        self.nodes.clear();
+        self.edges.clear();
+        self.edge_indices.clear();
+        self.edge_lengths.clear();
+        self.edge_weights.clear();
+        self.clear_edge_arrows();
+        self.groups = None;
+        self.node_damping.clear();
+        self.invalidate_topology();
+        self.previous_positions.clear();
+        self.latest_positions.clear();
+        self.simulation_running = false;
+        self.renderer = Renderer::new();
+        self.is_initialized = false;
+        self.physics_dirty = true;
+    }
+
+    /// Sets one mass per node; forces are divided by mass, so heavy nodes move
+    /// sluggishly. Values are clamped to a small positive minimum.
+    #[wasm_bindgen]
+    pub fn set_node_masses(&mut self, masses: &[f32]) -> Result<(), JsValue> {
+        if masses.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} node masses, got {}",
+                self.nodes.len(),
+                masses.len()
+            )));
+        }
+
+        for (node, &mass) in self.nodes.iter_mut().zip(masses) {
+            node.mass = if mass.is_finite() { mass.max(MIN_NODE_MASS) } else { 1.0 };
+        }
+        self.mark_physics_dirty();
+        Ok(())
+    }
+
+    /// Sets each node's mass to `1 + scale * degree` using the indexed edges,
+    /// so hubs stay put while leaves swing freely.
+    #[wasm_bindgen]
+    pub fn set_masses_from_degree(&mut self, scale: f32) {
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            node.mass = (1.0 + scale * adjacency.degree(i) as f32).max(MIN_NODE_MASS);
+        }
+        self.mark_physics_dirty();
+    }
+
+    /// Sets one damping factor per node, the share of its velocity a node keeps
+    /// each tick, in place of the global one from `set_physics_parameters`: low
+    /// values make sluggish nodes, values near 1 lively ones. Values are
+    /// clamped to at most 1; zero, negative or NaN ones use the global factor.
+    /// Pinned nodes don't move, so their damping has no effect. Node count
+    /// changes keep the factors of the remaining nodes; new ones get the global one.
+    #[wasm_bindgen]
+    pub fn set_node_damping(&mut self, values: &[f32]) -> Result<(), JsValue> {
+        if values.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} node damping values, got {}",
+                self.nodes.len(),
+                values.len()
+            )));
+        }
+
+        self.node_damping = values.iter().map(|&value| if value > 0.0 { value.min(1.0) } else { 0.0 }).collect();
+        self.damping_dirty = true;
+        Ok(())
+    }
+
+    /// Each node's damping factor, 0 where the global one applies.
+    #[wasm_bindgen]
+    pub fn get_node_damping(&self) -> Vec<f32> {
+        if self.node_damping.is_empty() {
+            vec![0.0; self.nodes.len()]
+        } else {
+            self.node_damping.clone()
+        }
+    }
+
+    /// Goes back to the global damping factor for every node.
+    #[wasm_bindgen]
+    pub fn clear_node_damping(&mut self) {
+        self.node_damping.clear();
+        self.damping_dirty = true;
+    }
+
+    /// Colors the nodes by one value per node through a colormap ("viridis",
+    /// "plasma", "inferno", "coolwarm", or reversed with "_r"), `min` and `max`
+    /// mapping to its ends. NaN values get the missing color.
+    #[wasm_bindgen]
+    pub fn color_by_values(&mut self, values: &[f32], colormap: &str, min: f32, max: f32) -> Result<(), JsValue> {
+        if values.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} values, got {}", self.nodes.len(), values.len())));
+        }
+        let colormap = Colormap::parse(colormap).ok_or_else(|| JsValue::from_str(&format!("Unknown colormap: {}", colormap)))?;
+
+        let range = max - min;
+        for (node, &value) in self.nodes.iter_mut().zip(values) {
+            let color = if value.is_nan() {
+                self.missing_color
+            } else {
+                colormap.sample(if range != 0.0 { (value - min) / range } else { 0.5 })
+            };
+            [node.r, node.g, node.b, node.a] = color;
+        }
+        Ok(())
+    }
+
+    /// Sets the color `color_by_values` uses for NaN values.
+    #[wasm_bindgen]
+    pub fn set_missing_color(&mut self, color: &str) -> Result<(), JsValue> {
+        self.missing_color = parse_color_arg(color)?;
+        Ok(())
+    }
+
+    /// Sizes the nodes by degree, from `min_size` (lowest degree) to `max_size`
+    /// (highest), with "linear", "sqrt" or "log" scaling.
+    #[wasm_bindgen]
+    pub fn size_by_degree(&mut self, min_size: f32, max_size: f32, scale: &str) -> Result<(), JsValue> {
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        let degrees: Vec<f32> = (0..self.nodes.len()).map(|i| adjacency.degree(i) as f32).collect();
+        self.size_by_values(&degrees, min_size, max_size, scale)
+    }
+
+    /// Sizes the nodes by one value per node (e.g. from `compute_pagerank`), like
+    /// `size_by_degree`. NaN values get `min_size`; if all values are equal, every
+    /// node gets the middle size.
+    #[wasm_bindgen]
+    pub fn size_by_values(&mut self, values: &[f32], min_size: f32, max_size: f32, scale: &str) -> Result<(), JsValue> {
+        if values.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} values, got {}", self.nodes.len(), values.len())));
+        }
+        let scale = ValueScale::parse(scale).ok_or_else(|| JsValue::from_str(&format!("Unknown scale: {}", scale)))?;
+
+        for (node, size) in self.nodes.iter_mut().zip(scale::scale_to_range(values, scale, min_size, max_size)) {
+            node.size = size;
+        }
+        self.invalidate_extent();
+        Ok(())
+    }
+
+    /// PageRank per node, treating indexed edges as links from source to target.
+    /// `damping` is usually 0.85; 20 to 50 iterations are plenty for display.
+    #[wasm_bindgen]
+    pub fn compute_pagerank(&self, damping: f32, iterations: u32) -> Vec<f32> {
+        algorithms::pagerank(self.nodes.len(), &self.edge_indices, damping, iterations)
+    }
+
+    /// Assigns each node to a group (community, cluster); negative ids leave a
+    /// node ungrouped. Used by the group attraction force.
+    #[wasm_bindgen]
+    pub fn set_node_groups(&mut self, groups: &[i32]) -> Result<(), JsValue> {
+        if groups.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!(
+                "Expected {} node groups, got {}",
+                self.nodes.len(),
+                groups.len()
+            )));
+        }
+
+        self.groups = Some(NodeGroups::new(groups));
+        self.groups_dirty = true;
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_node_groups(&self) -> Vec<i32> {
+        self.groups.as_ref().map_or_else(|| vec![-1; self.nodes.len()], |groups| groups.ids().to_vec())
+    }
+
+    /// Pulls the members of each group towards the group's centroid so
+    /// communities form separate clusters. A strength of 0 disables it.
+    #[wasm_bindgen]
+    pub fn set_group_attraction(&mut self, strength: f32) {
+        self.physics.group_attraction = strength.max(0.0);
+    }
+
+    /// Connected component id per node over the indexed edges, numbered in order
+    /// of each component's lowest node index. Isolated nodes are their own component.
+    #[wasm_bindgen]
+    pub fn compute_components(&self) -> Vec<u32> {
+        algorithms::connected_components(self.nodes.len(), &self.edge_indices).0
+    }
+
+    /// Colors each connected component from `palette` (CSS colors) or a built-in
+    /// categorical palette, cycling when there are more components than colors.
+    /// Returns the number of components.
+    #[wasm_bindgen]
+    pub fn color_by_components(&mut self, palette: Option<Vec<String>>) -> Result<u32, JsValue> {
+        let (ids, count) = algorithms::connected_components(self.nodes.len(), &self.edge_indices);
+        self.color_nodes_by_class(&ids, palette)?;
+        Ok(count as u32)
+    }
+
+    /// Community id per node from Louvain modularity optimization over the indexed
+    /// edges, weighted by the edge weights when `weighted`. `resolution` 1 is
+    /// standard modularity; higher values give smaller communities. The same
+    /// `seed` always gives the same result.
+    #[wasm_bindgen]
+    pub fn detect_communities(&self, resolution: f32, weighted: bool, seed: u64) -> Vec<u32> {
+        let weights = weighted.then_some(self.edge_weights.as_slice());
+        algorithms::louvain(self.nodes.len(), &self.edge_indices, weights, resolution, seed)
+    }
+
+    /// Detects communities like `detect_communities`, makes them the node groups
+    /// (see `set_group_attraction`) and colors them from `palette` (CSS colors)
+    /// or the built-in palette. Returns the number of communities.
+    #[wasm_bindgen]
+    pub fn group_by_communities(&mut self, resolution: f32, weighted: bool, seed: u64, palette: Option<Vec<String>>) -> Result<u32, JsValue> {
+        let communities = self.detect_communities(resolution, weighted, seed);
+        self.color_nodes_by_class(&communities, palette)?;
+
+        let ids: Vec<i32> = communities.iter().map(|&id| id as i32).collect();
+        self.groups = Some(NodeGroups::new(&ids));
+        self.groups_dirty = true;
+        Ok(communities.iter().max().map_or(0, |&max| max + 1))
+    }
+
+    /// Node indices along a shortest path from `source` to `target` over the
+    /// indexed edges, both included: fewest edges, or least total edge weight
+    /// (weights as costs, 1 when unset) when `weighted`. Empty if there is no path.
+    #[wasm_bindgen]
+    pub fn shortest_path(&mut self, source: u32, target: u32, weighted: bool) -> Vec<u32> {
+        let costs = weighted.then_some(self.edge_weights.as_slice());
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        algorithms::shortest_path(adjacency, source as usize, target as usize, costs)
+    }
+
+    /// Colors the nodes of `path` and the indexed edges between consecutive ones
+    /// with `color` (a CSS color, the theme's highlight color if omitted), and with
+    /// `dim_others` fades everything else by the theme's dim alpha. Replaces any
+    /// previous highlight; `clear_highlight` restores the original colors.
+    #[wasm_bindgen]
+    pub fn highlight_path(&mut self, path: &[u32], color: Option<String>, dim_others: bool) -> Result<(), JsValue> {
+        self.check_node_indices(path)?;
+        let color = match color {
+            Some(color) => parse_color_arg(&color)?,
+            None => self.theme.highlight,
+        };
+
+        let mut on_path = vec![false; self.nodes.len()];
+        for &index in path {
+            on_path[index as usize] = true;
+        }
+        let steps: std::collections::HashSet<(u32, u32)> = path.windows(2).map(|step| (step[0].min(step[1]), step[0].max(step[1]))).collect();
+        self.apply_highlight(
+            &on_path,
+            |endpoints| endpoints.is_some_and(|[source, target]| steps.contains(&(source.min(target), source.max(target)))),
+            color,
+            dim_others,
+        );
+        Ok(())
+    }
+
+    /// Names the nodes for `find_nodes` and `focus_search`, by node index; nodes
+    /// past the end of `labels` have none. Labels stay when nodes change.
+    #[wasm_bindgen]
+    pub fn set_node_labels(&mut self, labels: Vec<String>) {
+        self.node_labels = labels;
+        self.label_index = std::cell::OnceCell::new();
+    }
+
+    /// Names the edges by edge index for `get_edge_label_positions`; edges
+    /// past the end of `labels` or with an empty one have none. Labels stay
+    /// when edges change.
+    #[wasm_bindgen]
+    pub fn set_edge_labels(&mut self, labels: Vec<String>) {
+        self.edge_labels = labels;
+    }
+
+    /// Where to draw the edge labels at `font_px` CSS pixels, as index, x, y,
+    /// angle per label: the edge's midpoint in CSS pixels from the canvas's
+    /// top-left corner and the edge's direction in radians (clockwise), turned
+    /// to keep the text upright. The renderer draws no text, so the app draws
+    /// each label and its background pill there, e.g. with a CSS rotate().
+    /// Labels of hidden or offscreen edges, and of edges drawn shorter than
+    /// the label (estimated at 0.6 em a character plus padding), are left out.
+    #[wasm_bindgen]
+    pub fn get_edge_label_positions(&self, font_px: f32) -> Result<Vec<f32>, JsValue> {
+        if !(font_px.is_finite() && font_px > 0.0) {
+            return Err(JsValue::from_str(&format!("Invalid label font size {}", font_px)));
+        }
+        let transform = self.screen_transform();
+        let t = self.interpolation_factor();
+        let mut positions = Vec::new();
+        for (index, (label, &[source, target])) in self.edge_labels.iter().zip(&self.edge_indices).enumerate() {
+            let (source, target) = (source as usize, target as usize);
+            if label.is_empty() || !self.edge_shown(index) || !self.node_shown(source) || !self.node_shown(target) {
+                continue;
+            }
+            let (start, end) = (self.drawn_position(source, t), self.drawn_position(target, t));
+            let [_, _, on_canvas] = transform.apply([(start[0] + end[0]) * 0.5, (start[1] + end[1]) * 0.5], 0.0);
+            if on_canvas == 0.0 {
+                continue;
+            }
+            let [x1, y1, _] = transform.apply(start, 0.0);
+            let [x2, y2, _] = transform.apply(end, 0.0);
+            if let Some(([x, y], angle)) = labels::edge_label_placement([x1, y1], [x2, y2], labels::label_width(label, font_px)) {
+                positions.extend_from_slice(&[index as f32, x, y, angle]);
+            }
+        }
+        Ok(positions)
+    }
+
+    /// The label of node `index`, if it has one.
+    #[wasm_bindgen]
+    pub fn get_node_label(&self, index: u32) -> Option<String> {
+        self.node_labels.get(index as usize).cloned()
+    }
+
+    /// Nodes whose label matches `query` ignoring case, in ascending order:
+    /// "exact", "prefix" or "substring" matches. An empty query matches nothing.
+    /// The first search after `set_node_labels` builds the index; later ones
+    /// are fast enough to run on every keystroke.
+    #[wasm_bindgen]
+    pub fn find_nodes(&self, query: &str, mode: &str) -> Result<Vec<u32>, JsValue> {
+        let mode = SearchMode::parse(mode).ok_or_else(|| JsValue::from_str(&format!("Unknown search mode: {}", mode)))?;
+        let index = self.label_index.get_or_init(|| LabelIndex::new(&self.node_labels));
+        let mut found = index.find(query, mode);
+        found.retain(|&node| (node as usize) < self.nodes.len());
+        Ok(found)
+    }
+
+    /// Highlights the nodes whose label contains `query` (`find_nodes` in
+    /// "substring" mode) in the theme's highlight color, dims the rest like
+    /// `highlight_path`, and fits the camera to the visible matches. Returns the
+    /// number of matches; with none the highlight is cleared and the camera
+    /// left alone.
+    #[wasm_bindgen]
+    pub fn focus_search(&mut self, query: &str) -> u32 {
+        let found = self.find_nodes(query, "substring").unwrap_or_default();
+        if found.is_empty() {
+            self.clear_highlight();
+            return 0;
+        }
+
+        let mut matched = vec![false; self.nodes.len()];
+        for &index in &found {
+            matched[index as usize] = true;
+        }
+        self.apply_highlight(&matched, |_| false, self.theme.highlight, true);
+
+        self.sync_physics_positions();
+        let shown = found.iter().map(|&index| index as usize).filter(|&index| self.node_shown(index)).map(|index| &self.nodes[index]);
+        if let (Some(extent), Some(size)) = (GraphExtent::of(shown), self.renderer.surface_size()) {
+            let bounds = extent.bounds;
+            self.set_camera_zoom(fit_camera(bounds, size).1);
+            self.camera_position = [
+                (bounds[0] + bounds[2]) * 0.5 - size.0 as f32 * 0.5 / self.camera_zoom,
+                (bounds[1] + bounds[3]) * 0.5 - size.1 as f32 * 0.5 / self.camera_zoom,
+            ];
+        }
+        found.len() as u32
+    }
+
+    /// Restores the colors, and edge widths, from before `highlight_path` or
+    /// `highlight_edges`.
+    #[wasm_bindgen]
+    pub fn clear_highlight(&mut self) {
+        let Some(backup) = self.color_backup.take() else {
+            return;
+        };
+        for (node, color) in self.nodes.iter_mut().zip(backup.nodes) {
+            [node.r, node.g, node.b, node.a] = color;
+        }
+        for (edge, color) in self.edges.iter_mut().zip(backup.edges) {
+            [edge.r, edge.g, edge.b, edge.a] = color;
+        }
+        for (edge, width) in self.edges.iter_mut().zip(backup.edge_widths) {
+            edge.width = width;
+        }
+    }
+
+    /// Colors the edges at `edge_indices` with `color` (a CSS color) and scales
+    /// their width by `width_scale`; with `dim_others` fades the other edges,
+    /// and the nodes they don't touch, by the theme's dim alpha. Replaces any
+    /// previous highlight; `clear_edge_highlight` restores the original styles.
+    #[wasm_bindgen]
+    pub fn highlight_edges(&mut self, edge_indices: &[u32], color: &str, width_scale: f32, dim_others: bool) -> Result<(), JsValue> {
+        if let Some(&index) = edge_indices.iter().find(|&&index| index as usize >= self.edges.len()) {
+            return Err(JsValue::from_str(&format!("Edge index {} out of range ({} edges)", index, self.edges.len())));
+        }
+        let color = parse_color_arg(color)?;
+        if !(width_scale.is_finite() && width_scale > 0.0) {
+            return Err(JsValue::from_str(&format!("Edge width scale must be positive, got {}", width_scale)));
+        }
+
+        let mut selected = vec![false; self.edges.len()];
+        for &index in edge_indices {
+            selected[index as usize] = true;
+        }
+        let mut touched = vec![false; self.nodes.len()];
+        for (&[source, target], _) in self.edge_indices.iter().zip(&selected).filter(|(_, &selected)| selected) {
+            for node in [source, target] {
+                if let Some(touched) = touched.get_mut(node as usize) {
+                    *touched = true;
+                }
+            }
+        }
+
+        self.backup_colors();
+        if let Some(backup) = &mut self.color_backup {
+            backup.edge_widths = self.edges.iter().map(|edge| edge.width).collect();
+        }
+        let dim_alpha = self.theme.dim_alpha;
+        for (edge, &selected) in self.edges.iter_mut().zip(&selected) {
+            if selected {
+                [edge.r, edge.g, edge.b, edge.a] = color;
+                edge.width *= width_scale;
+            } else if dim_others {
+                edge.a *= dim_alpha;
+            }
+        }
+        if dim_others {
+            for (node, _) in self.nodes.iter_mut().zip(&touched).filter(|(_, &touched)| !touched) {
+                node.a *= dim_alpha;
+            }
+        }
+        Ok(())
+    }
+
+    /// Undoes `highlight_edges`; the same as `clear_highlight`.
+    #[wasm_bindgen]
+    pub fn clear_edge_highlight(&mut self) {
+        self.clear_highlight();
+    }
+
+    /// Directed cycles over the indexed edges (source to target), e.g.
+    /// circular dependencies, as an array of node index arrays, each starting
+    /// from its lowest node. Returns at most `max_cycles` cycles of at most
+    /// `max_length` nodes; dense graphs may stop sooner, as the search is
+    /// bounded too. Positional edges have no direction, so with those it warns
+    /// (see `set_on_warning`) and returns an empty array.
+    #[wasm_bindgen]
+    pub fn find_cycles(&self, max_cycles: u32, max_length: u32) -> JsValue {
+        if !self.has_directed_edges() {
+            return js_sys::Array::new().into();
+        }
+        let cycles = algorithms::directed_cycles(self.nodes.len(), &self.edge_indices, max_cycles as usize, max_length as usize);
+        cycles
+            .iter()
+            .map(|cycle| JsValue::from(js_sys::Uint32Array::from(&cycle[..])))
+            .collect::<js_sys::Array>()
+            .into()
+    }
+
+    /// Highlights, like `highlight_edges` with `color`, every indexed edge
+    /// within a strongly connected component of more than one node, i.e. on
+    /// some directed cycle, and dims the rest. Returns the number of edges
+    /// highlighted; 0 (and a warning) with positional edges.
+    #[wasm_bindgen]
+    pub fn highlight_cycles(&mut self, color: &str) -> Result<u32, JsValue> {
+        if !self.has_directed_edges() {
+            return Ok(0);
+        }
+        let (components, count) = algorithms::strongly_connected_components(self.nodes.len(), &self.edge_indices);
+        let mut sizes = vec![0usize; count];
+        for &component in &components {
+            sizes[component as usize] += 1;
+        }
+        let cyclic: Vec<u32> = (0..self.edge_indices.len() as u32)
+            .filter(|&edge| {
+                let [source, target] = self.edge_indices[edge as usize].map(|node| components[node as usize]);
+                source == target && sizes[source as usize] > 1
+            })
+            .collect();
+        self.highlight_edges(&cyclic, color, CYCLE_EDGE_WIDTH_SCALE, true)?;
+        Ok(cyclic.len() as u32)
+    }
+
+    /// Edge ids of a minimum spanning forest over the indexed edges: a
+    /// minimum spanning tree per connected component, cheapest edge first.
+    /// Costs are `weights` (one per edge; NaN leaves an edge out) or, by
+    /// default, the edges' current lengths. Pass the result to
+    /// `highlight_edges` to show the graph's backbone.
+    #[wasm_bindgen]
+    pub fn compute_mst(&mut self, weights: Option<Vec<f32>>) -> Result<Vec<u32>, JsValue> {
+        let costs = match weights {
+            Some(weights) if weights.len() != self.edge_indices.len() => {
+                return Err(JsValue::from_str(&format!("Expected {} edge weights, got {}", self.edge_indices.len(), weights.len())));
+            }
+            Some(weights) => weights,
+            None => {
+                self.sync_physics_positions();
+                let nodes = &self.nodes;
+                self.edge_indices
+                    .iter()
+                    .map(|&[source, target]| match (nodes.get(source as usize), nodes.get(target as usize)) {
+                        (Some(a), Some(b)) => (a.x - b.x).hypot(a.y - b.y),
+                        _ => f32::NAN,
+                    })
+                    .collect()
+            }
+        };
+        Ok(algorithms::minimum_spanning_forest(self.nodes.len(), &self.edge_indices, &costs))
+    }
+
+    /// Betweenness centrality per node over the indexed edges (Brandes'
+    /// algorithm, unweighted, undirected): how many shortest paths between
+    /// other nodes pass through each node, unnormalized, for `size_by_values`
+    /// or `color_by_values`. With `sample_size` below the node count (and not
+    /// 0) that many sources, picked the same way every time, give an estimate
+    /// in proportionally less time. The work yields to the event loop between
+    /// batches of sources, and `on_progress` (if given) is called with the
+    /// completed fraction after each. Resolves with a Float32Array. No other
+    /// method may be called on the renderer until the promise resolves.
+    #[wasm_bindgen]
+    pub async fn compute_betweenness(&mut self, sample_size: u32, on_progress: Option<js_sys::Function>) -> Result<JsValue, JsValue> {
+        let node_count = self.nodes.len();
+        let adjacency = &*self.adjacency.get_or_insert_with(|| Adjacency::new(node_count, &self.edge_indices));
+        let mut betweenness = algorithms::Betweenness::new(node_count, sample_size as usize, BETWEENNESS_SEED);
+        // Each source visits every node and edge once or twice
+        let batch = (BETWEENNESS_BATCH_WORK / (node_count + self.edge_indices.len()).max(1)).max(1);
+        while !betweenness.is_done() {
+            betweenness.step(adjacency, batch);
+            if let Some(on_progress) = &on_progress {
+                // Errors thrown by the callback must not abort the computation
+                let _ = on_progress.call1(&JsValue::NULL, &JsValue::from(betweenness.progress()));
+            }
+            if !betweenness.is_done() {
+                yield_to_event_loop().await?;
+            }
+        }
+        Ok(js_sys::Float32Array::from(&betweenness.scores()[..]).into())
+    }
+
+    /// Core number per node over the indexed edges: the largest k for which the
+    /// node belongs to the k-core (where every node has at least k neighbors).
+    #[wasm_bindgen]
+    pub fn compute_core_numbers(&mut self) -> Vec<u32> {
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        algorithms::core_numbers(adjacency)
+    }
+
+    /// Hides the nodes outside the k-core, and their edges, without removing
+    /// them; `filter_by_core(0)` shows everything again. Independent of
+    /// `set_nodes_hidden`.
+    #[wasm_bindgen]
+    pub fn filter_by_core(&mut self, k: u32) {
+        self.core_filter = k;
+        self.invalidate_extent();
+        let cores = if k > 0 { self.compute_core_numbers() } else { Vec::new() };
+        // Visibility is applied on the CPU when drawing, so the simulation's copy
+        // of the flags doesn't need these bits
+        for (i, node) in self.nodes.iter_mut().enumerate() {
+            if cores.get(i).is_some_and(|&core| core < k) {
+                node.flags |= NODE_FLAG_BELOW_CORE;
+            } else {
+                node.flags &= !NODE_FLAG_BELOW_CORE;
+            }
+        }
+    }
+
+    /// Nodes at most `k` edges away from `root`, starting with the root itself.
+    /// Indexed edges are followed both ways, or only from source to target with
+    /// `outgoing_only`.
+    #[wasm_bindgen]
+    pub fn get_k_hop_neighborhood(&mut self, root: u32, k: u32, outgoing_only: bool) -> Result<Vec<u32>, JsValue> {
+        self.check_node_indices(&[root])?;
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        Ok(algorithms::k_hop_neighborhood(adjacency, &self.edge_indices, root as usize, k, outgoing_only))
+    }
+
+    /// Hides every node not in `indices`, and the edges touching them, until
+    /// `clear_isolation`. Independent of `set_nodes_hidden` and `filter_by_core`.
+    #[wasm_bindgen]
+    pub fn isolate_nodes(&mut self, indices: &[u32]) -> Result<(), JsValue> {
+        self.check_node_indices(indices)?;
+        let mut focused = vec![false; self.nodes.len()];
+        for &index in indices {
+            focused[index as usize] = true;
+        }
+        self.invalidate_extent();
+        // Like filter_by_core, only the drawing side looks at this bit
+        for (node, focused) in self.nodes.iter_mut().zip(focused) {
+            if focused {
+                node.flags &= !NODE_FLAG_OUTSIDE_FOCUS;
+            } else {
+                node.flags |= NODE_FLAG_OUTSIDE_FOCUS;
+            }
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_isolation(&mut self) {
+        self.invalidate_extent();
+        for node in &mut self.nodes {
+            node.flags &= !NODE_FLAG_OUTSIDE_FOCUS;
+        }
+    }
+
+    /// The camera, theme and filters (`filter_by_core`, `isolate_nodes`,
+    /// `set_time_window`) as a URL-safe string, for links that reopen the same
+    /// view of the same graph. The graph itself isn't included.
+    #[wasm_bindgen]
+    pub fn get_view_state(&self) -> String {
+        let isolating = self.nodes.iter().any(|node| node.flags & NODE_FLAG_OUTSIDE_FOCUS != 0);
+        let focus = isolating.then(|| (0..self.nodes.len() as u32).filter(|&i| self.nodes[i as usize].flags & NODE_FLAG_OUTSIDE_FOCUS == 0).collect());
+        ViewState {
+            camera: Some((self.camera_position, self.camera_zoom)),
+            core_filter: self.core_filter,
+            focus,
+            time_window: self.time_window,
+            time_fade_margin: self.time_fade_margin,
+            theme: Some(self.theme_name.clone()),
+        }
+        .encode()
+    }
+
+    /// Restores a view saved with `get_view_state`. Parts this version doesn't
+    /// know are ignored. A corrupted string, or one that doesn't fit the current
+    /// graph or themes, is an error and changes nothing.
+    #[wasm_bindgen]
+    pub fn set_view_state(&mut self, state: &str) -> Result<(), JsValue> {
+        let state = ViewState::decode(state).map_err(|e| JsValue::from_str(&e))?;
+
+        // Everything is checked before anything is applied
+        if let Some(([x, y], zoom)) = state.camera {
+            if !(x.is_finite() && y.is_finite() && zoom.is_finite() && zoom > 0.0) {
+                return Err(JsValue::from_str("View state has an invalid camera"));
+            }
+        }
+        if let Some(theme) = &state.theme {
+            if !self.custom_themes.contains_key(theme) && theme::builtin(theme).is_none() {
+                return Err(JsValue::from_str(&format!("Unknown theme: {}", theme)));
+            }
+        }
+        if let Some(focus) = &state.focus {
+            self.check_node_indices(focus)?;
+        }
+        if let Some([start, end]) = state.time_window {
+            if !(start.is_finite() && end.is_finite()) || start > end {
+                return Err(JsValue::from_str(&format!("Invalid time window [{}, {}]", start, end)));
+            }
+        }
+        if !(state.time_fade_margin.is_finite() && state.time_fade_margin >= 0.0) {
+            return Err(JsValue::from_str(&format!("Fade margin must be a non-negative number, got {}", state.time_fade_margin)));
+        }
+
+        if let Some(([x, y], zoom)) = state.camera {
+            self.set_camera_position(x, y);
+            self.set_camera_zoom(zoom);
+        }
+        if let Some(theme) = &state.theme {
+            self.apply_theme(theme)?;
+        }
+        self.filter_by_core(state.core_filter);
+        match &state.focus {
+            Some(focus) => self.isolate_nodes(focus)?,
+            None => self.clear_isolation(),
+        }
+        self.time_fade_margin = state.time_fade_margin;
+        self.time_window = state.time_window;
+        self.apply_time_window();
+        Ok(())
+    }
+
+    /// One timestamp per edge, for `set_time_window`; NaN keeps an edge always
+    /// shown and an empty array removes the timestamps.
+    #[wasm_bindgen]
+    pub fn set_edge_timestamps(&mut self, times: &[f32]) -> Result<(), JsValue> {
+        if !times.is_empty() && times.len() != self.edges.len() {
+            return Err(JsValue::from_str(&format!("Expected {} edge timestamps, got {}", self.edges.len(), times.len())));
+        }
+        self.edge_times = (!times.is_empty()).then(|| TimeIndex::new(times));
+        self.apply_time_window();
+        Ok(())
+    }
+
+    /// One timestamp per node, for `set_time_window`; NaN keeps a node always
+    /// shown and an empty array removes the timestamps.
+    #[wasm_bindgen]
+    pub fn set_node_timestamps(&mut self, times: &[f32]) -> Result<(), JsValue> {
+        if !times.is_empty() && times.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} node timestamps, got {}", self.nodes.len(), times.len())));
+        }
+        for node in &mut self.nodes {
+            node.flags &= !NODE_FLAG_OUTSIDE_TIME;
+        }
+        self.node_times = (!times.is_empty()).then(|| TimeIndex::new(times));
+        self.apply_time_window();
+        Ok(())
+    }
+
+    /// Shows only the edges and nodes with timestamps in [start, end], plus
+    /// those within the fade margin on either side, which fade out towards its
+    /// far end. Cheap to call on every scrub: only the items crossing the
+    /// window's ends are touched. Independent of `set_nodes_hidden`.
+    #[wasm_bindgen]
+    pub fn set_time_window(&mut self, start: f32, end: f32) -> Result<(), JsValue> {
+        if !(start.is_finite() && end.is_finite()) || start > end {
+            return Err(JsValue::from_str(&format!("Invalid time window [{}, {}]", start, end)));
+        }
+        self.time_window = Some([start, end]);
+        self.apply_time_window();
+        Ok(())
+    }
+
+    /// How far outside the time window items stay shown while fading out, in
+    /// timestamp units; 0 (the default) cuts them off at the window's ends.
+    #[wasm_bindgen]
+    pub fn set_time_fade_margin(&mut self, margin: f32) -> Result<(), JsValue> {
+        if !(margin.is_finite() && margin >= 0.0) {
+            return Err(JsValue::from_str(&format!("Fade margin must be a non-negative number, got {}", margin)));
+        }
+        self.time_fade_margin = margin;
+        self.apply_time_window();
+        Ok(())
+    }
+
+    /// Shows every edge and node again; the timestamps are kept.
+    #[wasm_bindgen]
+    pub fn clear_time_window(&mut self) {
+        self.time_window = None;
+        self.apply_time_window();
+    }
+
+    /// Defines the appearance of style class `class_id` (1-63) from an object
+    /// with any of `color`, `size`, `border_color`, `border_width`, `shape`
+    /// ("circle", "square", "diamond", "triangle", "squircle"), `corner` (the
+    /// squircle's roundness from 0, a square, to 1, a circle; default 0.5) and
+    /// `glow`. Fields left out fall back to each node's own attributes.
+    #[wasm_bindgen]
+    pub fn define_style(&mut self, class_id: u32, style: JsValue) -> Result<(), JsValue> {
+        if class_id == 0 || class_id as usize >= MAX_STYLE_CLASSES {
+            return Err(JsValue::from_str(&format!("Style class {} out of range 1-{}", class_id, MAX_STYLE_CLASSES - 1)));
+        }
+        let style = parse_node_style(&style)?;
+        self.renderer.set_node_style(class_id as usize, style);
+        Ok(())
+    }
+
+    /// Assigns a style class to every node; class 0 draws the node from its own
+    /// color and size.
+    #[wasm_bindgen]
+    pub fn set_node_classes(&mut self, class_ids: &[u32]) -> Result<(), JsValue> {
+        if class_ids.len() != self.nodes.len() {
+            return Err(JsValue::from_str(&format!("Expected {} class ids, got {}", self.nodes.len(), class_ids.len())));
+        }
+        if let Some(&class_id) = class_ids.iter().find(|&&id| id as usize >= MAX_STYLE_CLASSES) {
+            return Err(JsValue::from_str(&format!("Style class {} out of range 0-{}", class_id, MAX_STYLE_CLASSES - 1)));
+        }
+        for (node, &class_id) in self.nodes.iter_mut().zip(class_ids) {
+            node.flags = (node.flags & NODE_FLAG_BITS) | (class_id << NODE_CLASS_SHIFT);
+        }
+        Ok(())
+    }
+
+    /// Pins a node so the simulation no longer moves it.
+    #[wasm_bindgen]
+    pub fn pin_node(&mut self, index: u32) -> Result<(), JsValue> {
+        self.set_pinned(&[index], true)
+    }
+
+    #[wasm_bindgen]
+    pub fn unpin_node(&mut self, index: u32) -> Result<(), JsValue> {
+        self.set_pinned(&[index], false)
+    }
+
+    #[wasm_bindgen]
+    pub fn set_pinned(&mut self, indices: &[u32], pinned: bool) -> Result<(), JsValue> {
+        self.check_node_indices(indices)?;
+
+        for &index in indices {
+            let node = &mut self.nodes[index as usize];
+            node.vx = 0.0;
+            node.vy = 0.0;
+        }
+        self.set_node_flag(indices, NODE_FLAG_PINNED, pinned);
+        Ok(())
+    }
+
+    /// Takes nodes out of the simulation, or puts them back: left out, they
+    /// neither move nor push or pull on others, and keep their velocity for when
+    /// they return. Hidden nodes (and those the visibility filter hides) are
+    /// left out the same way; pinned ones still exert forces.
+    #[wasm_bindgen]
+    pub fn set_physics_enabled_for(&mut self, indices: &[u32], enabled: bool) -> Result<(), JsValue> {
+        self.check_node_indices(indices)?;
+        self.set_node_flag(indices, NODE_FLAG_NO_PHYSICS, !enabled);
+        Ok(())
+    }
+
+    /// Whether `set_physics_enabled_for` left node `index` in the simulation.
+    #[wasm_bindgen]
+    pub fn is_physics_enabled_for(&self, index: u32) -> bool {
+        self.nodes
+            .get(index as usize)
+            .is_some_and(|node| node.flags & NODE_FLAG_NO_PHYSICS == 0)
+    }
+
+    /// Hides or shows nodes without removing them; edges are hidden along with
+    /// either endpoint. Hidden nodes are also left out of the simulation.
+    #[wasm_bindgen]
+    pub fn set_nodes_hidden(&mut self, indices: &[u32], hidden: bool) -> Result<(), JsValue> {
+        self.check_node_indices(indices)?;
+        self.set_node_flag(indices, NODE_FLAG_HIDDEN, hidden);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn is_node_hidden(&self, index: u32) -> bool {
+        self.nodes
+            .get(index as usize)
+            .is_some_and(|node| node.flags & NODE_FLAG_HIDDEN != 0)
+    }
+
+    #[wasm_bindgen]
+    pub fn is_node_pinned(&self, index: u32) -> bool {
+        self.nodes
+            .get(index as usize)
+            .is_some_and(|node| node.flags & NODE_FLAG_PINNED != 0)
+    }
+
+    /// Starts dragging a node: it is pinned until `end_node_drag` and the
+    /// simulation is kept warm so neighbors follow it.
+    #[wasm_bindgen]
+    pub fn begin_node_drag(&mut self, index: u32) -> Result<(), JsValue> {
+        self.check_node_indices(&[index])?;
+        self.end_node_drag();
+
+        let node = &self.nodes[index as usize];
+        self.drag = Some(NodeDrag {
+            index: index as usize,
+            position: [node.x, node.y],
+            was_pinned: node.flags & NODE_FLAG_PINNED != 0,
+        });
+        self.set_pinned(&[index], true)?;
+        self.reheat_around(&[index]);
+        self.physics.alpha_target = 0.3;
+        Ok(())
+    }
+
+    /// Moves the dragged node to (x, y); ignored when no drag is active.
+    #[wasm_bindgen]
+    pub fn drag_node_to(&mut self, x: f32, y: f32) {
+        if let Some(drag) = &mut self.drag {
+            drag.position = [x, y];
+            self.apply_drag_position();
+        }
+    }
+
+    /// Releases the dragged node, restoring its previous pinned state.
+    #[wasm_bindgen]
+    pub fn end_node_drag(&mut self) {
+        if let Some(drag) = self.drag.take() {
+            if drag.index < self.nodes.len() && !drag.was_pinned {
+                let _ = self.set_pinned(&[drag.index as u32], false);
+            }
+            self.physics.alpha_target = 0.0;
+        }
+    }
+
+    /// Resolves with a Float32Array of every node's (x, y) once the CPU copy of
+    /// the nodes has caught up with the GPU simulation, so `get_nodes`, picking
+    /// and the exports agree with it too. Waits for the readback in flight, or
+    /// starts one if recent ticks haven't been read back; calls made meanwhile
+    /// share it. The promise settles on the next frame or tick after the copy
+    /// completes. Without GPU physics it resolves at once.
+    #[wasm_bindgen]
+    pub fn read_positions(&mut self) -> js_sys::Promise {
+        let mut resolve = None;
+        let promise = js_sys::Promise::new(&mut |resolve_fn, _reject| resolve = Some(resolve_fn));
+        let Some(resolve) = resolve else { return promise };
+
+        self.sync_physics_positions();
+        if self.is_initialized && self.renderer.request_physics_readback() {
+            self.position_waiters.push(resolve);
+        } else {
+            let _ = resolve.call1(&JsValue::NULL, &self.position_pairs());
+        }
+        promise
+    }
+
+    /// Total kinetic energy (sum of 1/2 m v^2) as of the latest position readback.
+    /// Approaches zero as the layout stabilizes.
+    #[wasm_bindgen]
+    pub fn get_kinetic_energy(&self) -> f32 {
+        self.kinetic_energy
+    }
+
+    /// Selects the repulsion algorithm: "grid" (neighboring cells only, fast) or
+    /// "barnes-hut" (long-range repulsion approximated with a quadtree).
+    #[wasm_bindgen]
+    pub fn set_repulsion_method(&mut self, method: &str) -> Result<(), JsValue> {
+        let method = RepulsionMethod::parse(method)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown repulsion method: {}", method)))?;
+        self.physics.repulsion_method = method;
+        Ok(())
+    }
+
+    /// Selects the force model: "spring-electric" (the default) or "forceatlas2".
+    /// ForceAtlas2 runs on the CPU and shares pinning, dragging and cooling with
+    /// the default model; see `set_forceatlas2_settings` for its parameters.
+    #[wasm_bindgen]
+    pub fn set_layout_algorithm(&mut self, algorithm: &str) -> Result<(), JsValue> {
+        let algorithm = LayoutAlgorithm::parse(algorithm)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown layout algorithm: {}", algorithm)))?;
+        if algorithm != self.layout_algorithm {
+            // Continue from the latest simulated positions, and have the GPU pick
+            // up the CPU-side ones when switching back
+            self.sync_physics_positions();
+            self.mark_physics_dirty();
+            self.forceatlas2.reset();
+            self.layout_algorithm = algorithm;
+        }
+        Ok(())
+    }
+
+    /// ForceAtlas2 parameters: `scaling` (repulsion), `gravity` (towards the origin,
+    /// proportional to the distance with `strong_gravity`), LinLog attraction,
+    /// overlap prevention using node sizes, and `edge_weight_influence` (0 ignores
+    /// edge weights, 1 uses them as-is).
+    #[wasm_bindgen]
+    pub fn set_forceatlas2_settings(&mut self, scaling: f32, gravity: f32, strong_gravity: bool, linlog: bool, prevent_overlap: bool, edge_weight_influence: f32) {
+        self.forceatlas2.settings = forceatlas2::ForceAtlas2Settings {
+            scaling: scaling.max(0.0),
+            gravity: gravity.max(0.0),
+            strong_gravity,
+            linlog,
+            prevent_overlap,
+            edge_weight_influence: edge_weight_influence.max(0.0),
+        };
+    }
+
+    /// Sets the Barnes-Hut accuracy: lower values are more exact and slower,
+    /// 0 degenerates to all pairs. Typical values are 0.5 to 1.2.
+    #[wasm_bindgen]
+    pub fn set_repulsion_theta(&mut self, theta: f32) {
+        self.physics.theta = theta.max(0.0);
+    }
+
+    /// Pulls every node towards (center_x, center_y) so layouts stay on screen and
+    /// disconnected components don't drift away. A strength of 0 disables gravity.
+    #[wasm_bindgen]
+    pub fn set_gravity(&mut self, strength: f32, center_x: f32, center_y: f32) {
+        self.physics.gravity_strength = strength.max(0.0);
+        self.physics.gravity_center = [center_x, center_y];
+    }
+
+    /// Moves the gravity center to the visible nodes' mass-weighted center of
+    /// mass before every tick, ignoring the center given to `set_gravity`, so a
+    /// lopsided graph settles around itself instead of drifting towards a fixed
+    /// point. Off by default.
+    #[wasm_bindgen]
+    pub fn set_gravity_to_center_of_mass(&mut self, enabled: bool) {
+        self.gravity_to_center_of_mass = enabled;
+    }
+
+    /// Selects how gravity scales with distance: "linear" (proportional to the
+    /// distance from the center) or "constant" (fixed magnitude).
+    #[wasm_bindgen]
+    pub fn set_gravity_mode(&mut self, mode: &str) -> Result<(), JsValue> {
+        let mode = GravityMode::parse(mode)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown gravity mode: {}", mode)))?;
+        self.physics.gravity_mode = mode;
+        Ok(())
+    }
+
+    /// Keeps the layout inside a rectangle. Modes: "clamp" (hard walls), "bounce"
+    /// (velocity reflected at the walls) or "force" (soft push back inside).
+    /// Pinned and dragged nodes may sit outside; they are constrained once released.
+    #[wasm_bindgen]
+    pub fn set_bounds_constraint(&mut self, min_x: f32, min_y: f32, max_x: f32, max_y: f32, mode: &str) -> Result<(), JsValue> {
+        let bounds_mode = BoundsMode::parse(mode)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown bounds mode: {}", mode)))?;
+        if [min_x, min_y, max_x, max_y].iter().any(|v| !v.is_finite()) || max_x < min_x || max_y < min_y {
+            return Err(JsValue::from_str(&format!(
+                "Invalid bounds: ({}, {}) to ({}, {})", min_x, min_y, max_x, max_y
+            )));
+        }
+
+        self.physics.bounds = Some(BoundsConstraint {
+            min: [min_x, min_y],
+            max: [max_x, max_y],
+            mode: bounds_mode,
+        });
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_bounds_constraint(&mut self) {
+        self.physics.bounds = None;
+    }
+
+    /// Makes the same graph and seed produce the same layout on every run: grid
+    /// cells are filled in node order instead of by atomic insertion, and each
+    /// tick waits for the previous one to be read back. Costs some throughput
+    /// (at most one tick per readback).
+    #[wasm_bindgen]
+    pub fn set_deterministic(&mut self, enabled: bool) {
+        self.physics.deterministic = enabled;
+    }
+
+    /// Fixes the area covered by the physics spatial grid. By default the grid is
+    /// refitted to the node bounding box every tick; nodes outside fixed bounds
+    /// are grouped into the border cells.
+    #[wasm_bindgen]
+    pub fn set_world_bounds(&mut self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Result<(), JsValue> {
+        let bounds = [min_x, min_y, max_x, max_y];
+        if bounds.iter().any(|v| !v.is_finite()) || max_x <= min_x || max_y <= min_y {
+            return Err(JsValue::from_str(&format!(
+                "Invalid world bounds: ({}, {}) to ({}, {})", min_x, min_y, max_x, max_y
+            )));
+        }
+        self.physics.world_bounds = Some(bounds);
+        Ok(())
+    }
+
+    /// Goes back to fitting the spatial grid to the nodes every tick.
+    #[wasm_bindgen]
+    pub fn clear_world_bounds(&mut self) {
+        self.physics.world_bounds = None;
+    }
+
+    /// Makes the world bounds periodic: nodes leaving one side re-enter on the
+    /// opposite one, and repulsion and springs act across the seams between the
+    /// nearest copies of each pair. Only applies while world bounds are set
+    /// (`set_world_bounds`). Edges taking the short way across a seam are split
+    /// into two segments running off either side, see `set_split_seam_edges`.
+    #[wasm_bindgen]
+    pub fn set_world_wrap(&mut self, enabled: bool) {
+        self.physics.world_wrap = enabled;
+    }
+
+    /// Whether edges crossing a seam of the wrapping world are drawn as two
+    /// segments (the default) or not at all; hidden ones can't be picked either.
+    #[wasm_bindgen]
+    pub fn set_split_seam_edges(&mut self, enabled: bool) {
+        self.split_seam_edges = enabled;
+    }
+}
+
+impl FastGraphRenderer {
+    // Waits a bounded number of event loop turns for an in-flight position readback
+    async fn finish_readback(&mut self) -> Result<(), JsValue> {
+        let mut waits = 0;
+        while self.renderer.is_readback_pending() && waits < PRELAYOUT_MAX_READBACK_WAITS {
+            yield_to_event_loop().await?;
+            self.sync_physics_positions();
+            waits += 1;
+        }
+        Ok(())
+    }
+
+    // Runs one simulation tick; returns false if none could run
+    fn tick_physics(&mut self) -> Result<bool, JsValue> {
+        if !self.is_initialized {
+            return Err(JsValue::from_str("Renderer not initialized"));
+        }
+
+        // Pick up the results of the previous tick before dispatching the next one
+        self.sync_physics_positions();
+
+        // Transitions own the positions until they finish
+        if self.animation.is_some() {
+            return Ok(false);
+        }
+
+        // A half-loaded graph has no adjacency yet; end_graph_load builds it
+        if self.graph_loading {
+            return Ok(false);
+        }
+
+        // The layout has cooled down; reheat() or set_alpha() starts it again
+        if !self.is_simulation_active() {
+            return Ok(false);
+        }
+
+        // Deterministic ticks wait for the previous one to be read back, so the
+        // CPU-built inputs (grid, quadtree, centroids, bounds) are never stale
+        if self.physics.deterministic && self.renderer.is_readback_pending() {
+            return Ok(false);
+        }
+
+        if self.gravity_to_center_of_mass {
+            if let Some(extent) = self.graph_extent() {
+                self.physics.gravity_center = extent.weighted_centroid;
+            }
+        }
+
+        let started = now_ms();
+        self.update_active_set();
+        if self.layout_algorithm == LayoutAlgorithm::SpringElectric && self.renderer.has_gpu_physics() {
+            self.dispatch_gpu_tick()?;
+        } else {
+            self.run_cpu_tick();
+        }
+        self.physics_dirty = false;
+        self.physics_tick_ms = now_ms() - started;
+
+        if !self.simulation_running {
+            self.simulation_running = true;
+            self.iteration = 0;
+            self.emit_simulation_event("start");
+        }
+        self.iteration += 1;
+        if self.tick_event_interval > 0 && self.iteration.is_multiple_of(self.tick_event_interval) {
+            self.emit_simulation_event("tick");
+        }
+
+        self.physics.decay_alpha();
+        if !self.physics.is_active() {
+            self.active_set = None;
+            self.end_simulation();
+        }
+
+        Ok(true)
+    }
+
+    // Starts a full run, or with local reheat on a settled layout, or one
+    // already reheated locally, simulates around `indices`
+    fn reheat_around(&mut self, indices: &[u32]) {
+        let hops = self.local_reheat.filter(|_| !self.physics.is_active() || self.active_set.is_some());
+        let Some(hops) = hops else {
+            self.reheat();
+            return;
+        };
+
+        let node_count = self.nodes.len();
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(node_count, &self.edge_indices));
+        let set = match &mut self.active_set {
+            Some(set) if set.node_count() == node_count => set,
+            set => set.insert(ActiveSet::new(node_count)),
+        };
+        for &index in indices {
+            set.seed(&algorithms::k_hop_neighborhood(adjacency, &self.edge_indices, index as usize, hops, false));
+        }
+        self.physics.alpha = 1.0;
+    }
+
+    // Grows and shrinks a local reheat by the speeds of the last tick. A
+    // changed node count ends it, simulating everything from then on.
+    fn update_active_set(&mut self) {
+        let Some(set) = &mut self.active_set else {
+            return;
+        };
+        if set.node_count() != self.nodes.len() {
+            self.active_set = None;
+            return;
+        }
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        let threshold = LOCAL_REHEAT_SPEED * self.physics.rest_length / self.physics.delta_time.max(f32::MIN_POSITIVE);
+        set.update(&self.nodes, adjacency, threshold);
+    }
+
+    fn dispatch_gpu_tick(&mut self) -> Result<(), JsValue> {
+        if self.topology_dirty {
+            let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+            self.renderer.upload_adjacency(adjacency, &self.edge_lengths, &self.edge_weights);
+            self.renderer.upload_edge_indices(&self.edge_indices);
+            self.topology_dirty = false;
+        }
+
+        if let Some(groups) = self.groups.as_ref().filter(|_| self.physics.group_attraction > 0.0) {
+            if self.groups_dirty {
+                self.renderer.upload_node_groups(groups.slots());
+                self.groups_dirty = false;
+            }
+            self.renderer.upload_group_centroids(&groups.centroids(&self.nodes));
+        }
+
+        if self.damping_dirty {
+            self.renderer.upload_node_damping(&self.node_damping);
+            self.damping_dirty = false;
+        }
+
+        let excluded = self.physics_excluded();
+        let frozen = self.active_set.as_ref().map_or(&[][..], |set| set.frozen());
+        if self.physics.repulsion_method == RepulsionMethod::BarnesHut {
+            let nodes = &self.nodes[..self.nodes.len().min(MAX_NODES)];
+            let tree = QuadTree::build_among(nodes, &physics::participants(nodes, &excluded), 4 * MAX_NODES);
+            self.renderer.upload_quadtree(&tree);
+        }
+
+        // Run physics integration on GPU
+        self.renderer
+            .integrate_physics(&self.nodes, &excluded, frozen, self.physics_dirty, &self.physics)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    // Nodes the visibility filter hides, left out of the simulation like hidden
+    // ones; empty while it shows everything
+    fn physics_excluded(&self) -> Vec<bool> {
+        if self.visibility_filter == [0, 0] {
+            return Vec::new();
+        }
+        (0..self.nodes.len()).map(|i| !self.passes_visibility_filter(i)).collect()
+    }
+
+    // Same simulation on the CPU, for devices without usable compute shaders, and
+    // the CPU-only models
+    fn run_cpu_tick(&mut self) {
+        let excluded = self.physics_excluded();
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        if self.layout_algorithm == LayoutAlgorithm::ForceAtlas2 {
+            self.forceatlas2.step(&mut self.nodes, adjacency, &self.edge_weights, self.physics.alpha);
+            self.positions_updated();
+            return;
+        }
+
+        let inputs = PhysicsInputs {
+            adjacency: Some(adjacency),
+            edge_lengths: &self.edge_lengths,
+            edge_weights: &self.edge_weights,
+            groups: self.groups.as_ref(),
+            damping: &self.node_damping,
+            excluded: &excluded,
+            frozen: self.active_set.as_ref().map_or(&[], |set| set.frozen()),
+        };
+        physics::step(&mut self.nodes, &inputs, &self.physics);
+        self.positions_updated();
+    }
+
+    fn end_simulation(&mut self) {
+        if self.simulation_running {
+            self.simulation_running = false;
+            self.positions_replaced();
+            self.emit_simulation_event("end");
+        }
+    }
+
+    fn report_render_error(&mut self, error: String) {
+        log_error!("Render error: {}", error);
+        if let Some(callback) = &self.render_error_callback {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&error));
+        }
+        self.last_error = Some(error);
+    }
+
+    fn preview_color(&self) -> [f32; 4] {
+        let [r, g, b, a] = self.theme.highlight;
+        [r, g, b, a * DEFAULT_PREVIEW_ALPHA]
+    }
+
+    // The preview edge from the rim of its source node, cut into dashes, and
+    // the preview node
+    fn preview_instances(&self, nodes: &[NodeData]) -> (Vec<EdgeData>, Vec<NodeData>) {
+        let mut edges = Vec::new();
+        if let Some(preview) = &self.preview_edge {
+            if let Some(source) = nodes.get(preview.source) {
+                let [r, g, b, a] = preview.color;
+                let (dx, dy) = (preview.target[0] - source.x, preview.target[1] - source.y);
+                let length = (dx * dx + dy * dy).sqrt();
+                let start = source.size.min(length);
+                let width = preview.width / self.camera_zoom;
+                let dash = preview.dash / self.camera_zoom;
+                // Longer dashes where there would be more than the overlay holds
+                let period = if dash > 0.0 { (2.0 * dash).max((length - start) / MAX_OVERLAY_EDGES as f32) } else { f32::INFINITY };
+                let point = |distance: f32| {
+                    let t = if length > 0.0 { distance / length } else { 0.0 };
+                    (source.x + dx * t, source.y + dy * t)
+                };
+                let mut distance = start;
+                while distance < length && edges.len() < MAX_OVERLAY_EDGES {
+                    let (x1, y1) = point(distance);
+                    let (x2, y2) = point((distance + period / 2.0).min(length));
+                    edges.push(EdgeData { x1, y1, x2, y2, r, g, b, a, width });
+                    distance += period;
+                }
+            }
+        }
+        (edges, self.preview_node.into_iter().collect())
+    }
+
+    fn warn(&self, message: String, details: &JsValue) {
+        log_warn!("{}", message);
+        if let Some(callback) = &self.warning_callback {
+            let _ = callback.call2(&JsValue::NULL, &JsValue::from_str(&message), details);
+        }
+    }
+
+    // Warns once when the data starts exceeding what the renderer draws, and
+    // once when it fits again
+    fn check_capacity(&mut self) {
+        let limits = [("nodes", self.nodes.len(), MAX_NODES), ("edges", self.edges.len(), MAX_EDGES)];
+        for (index, (kind, count, limit)) in limits.into_iter().enumerate() {
+            let over = count > limit;
+            if over == self.truncated[index] {
+                continue;
+            }
+            self.truncated[index] = over;
+            let message = if over {
+                format!("{} {} exceeds limit of {}. Only rendering first {} {}.", count, kind, limit, limit, kind)
+            } else {
+                format!("{} {} fits the limit of {} again.", count, kind, limit)
+            };
+            let details = js_sys::Object::new();
+            let set = |key: &str, value: JsValue| {
+                let _ = js_sys::Reflect::set(&details, &JsValue::from_str(key), &value);
+            };
+            set("kind", JsValue::from_str(kind));
+            set("count", JsValue::from(count as u32));
+            set("limit", JsValue::from(limit as u32));
+            set("truncated", JsValue::from(over));
+            self.warn(message, &details);
+        }
+    }
+
+    fn emit_simulation_event(&self, event_type: &str) {
+        let Some(callback) = &self.simulation_event_callback else {
+            return;
+        };
+
+        let event = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&event, &JsValue::from_str("type"), &JsValue::from_str(event_type));
+        let _ = js_sys::Reflect::set(&event, &JsValue::from_str("alpha"), &JsValue::from(self.physics.alpha));
+        let _ = js_sys::Reflect::set(&event, &JsValue::from_str("iteration"), &JsValue::from(self.iteration));
+        if let Err(error) = callback.call1(&JsValue::NULL, &event) {
+            log!("Simulation event callback threw: {:?}", error);
+        }
+    }
+
+    // Apply the latest GPU simulation results to the CPU-side nodes, if any are ready
+    fn sync_physics_positions(&mut self) {
+        if self.is_initialized && self.renderer.read_physics_positions(&mut self.nodes) {
+            self.positions_updated();
+        }
+        // A readback dropped as stale leaves the CPU nodes authoritative, so
+        // waiters are answered either way
+        if !self.position_waiters.is_empty() && !self.renderer.is_readback_pending() {
+            let positions = self.position_pairs();
+            for resolve in std::mem::take(&mut self.position_waiters) {
+                let _ = resolve.call1(&JsValue::NULL, &positions);
+            }
+        }
+    }
+
+    fn position_pairs(&self) -> js_sys::Float32Array {
+        let positions: Vec<f32> = self.nodes.iter().flat_map(|node| [node.x, node.y]).collect();
+        js_sys::Float32Array::from(&positions[..])
+    }
+
+    // Bookkeeping after the simulation produced new positions
+    fn positions_updated(&mut self) {
+        // The simulated state may predate the latest drag movement
+        self.apply_drag_position();
+        self.refresh_edge_endpoints();
+        self.previous_positions = std::mem::take(&mut self.latest_positions);
+        self.latest_positions = self.nodes.iter().map(|node| [node.x, node.y]).collect();
+        self.kinetic_energy = self
+            .nodes
+            .iter()
+            .map(|node| 0.5 * node.mass * (node.vx * node.vx + node.vy * node.vy))
+            .sum();
+    }
+
+    // Move indexed edge endpoints onto the current node positions
+    fn refresh_edge_endpoints(&mut self) {
+        self.invalidate_extent();
+        for (edge, &[source, target]) in self.edges.iter_mut().zip(&self.edge_indices) {
+            if let (Some(source), Some(target)) = (self.nodes.get(source as usize), self.nodes.get(target as usize)) {
+                edge.x1 = source.x;
+                edge.y1 = source.y;
+                edge.x2 = target.x;
+                edge.y2 = target.y;
+            }
+        }
+    }
+
+    // Keep the dragged node under the pointer on both the CPU and GPU side
+    fn apply_drag_position(&mut self) {
+        let Some(drag) = &self.drag else {
+            return;
+        };
+        if let Some(node) = self.nodes.get_mut(drag.index) {
+            node.x = drag.position[0];
+            node.y = drag.position[1];
+            node.vx = 0.0;
+            node.vy = 0.0;
+            self.renderer.write_node_position(drag.index, node);
+            self.refresh_edge_endpoints();
+        }
+    }
+
+    // Re-upload all nodes on the next tick, discarding any in-flight readback
+    fn mark_physics_dirty(&mut self) {
+        self.invalidate_extent();
+        self.physics_dirty = true;
+        self.renderer.invalidate_physics_readback();
+        // Don't interpolate across positions set from outside the simulation
+        self.previous_positions.clear();
+        self.latest_positions.clear();
+    }
+
+    // True when the GPU simulation's buffer holds exactly what the frame would
+    // show, so it can be drawn without waiting for the readback: the simulation
+    // is running there, nothing changed since the last tick, and no other source
+    // (transitions, interpolation, shared memory) owns the positions
+    fn can_render_from_physics(&self) -> bool {
+        self.renderer.has_storage_rendering()
+            && self.simulation_running
+            && self.layout_algorithm == LayoutAlgorithm::SpringElectric
+            && !self.physics_dirty
+            && !self.topology_dirty
+            && !self.graph_loading
+            && !self.interpolate_positions
+            && self.animation.is_none()
+            && self.shared_positions.is_none()
+            && self.edges.len() == self.edge_indices.len()
+            && self.time_window.is_none()
+            && !self.layered()
+            && !self.spatial_sorting
+            && self.physics.wrap().is_none()
+            && self.cluster_blend == 0.0
+            && !self.decimating
+            && !self.nodes.iter().any(|node| node.flags & NODE_DRAW_ONLY_FLAGS != 0)
+    }
+
+    // Moves the timestamp indices to the current window widened by the fade
+    // margin, flagging the nodes that leave or enter it
+    fn apply_time_window(&mut self) {
+        self.invalidate_extent();
+        let [start, end] = match self.time_window {
+            Some([start, end]) => [start - self.time_fade_margin, end + self.time_fade_margin],
+            None => [f32::NEG_INFINITY, f32::INFINITY],
+        };
+        if let Some(times) = &mut self.edge_times {
+            times.set_window(start, end, |_, _| {});
+        }
+        let nodes = &mut self.nodes;
+        if let Some(times) = &mut self.node_times {
+            times.set_window(start, end, |index, inside| {
+                if inside {
+                    nodes[index].flags &= !NODE_FLAG_OUTSIDE_TIME;
+                } else {
+                    nodes[index].flags |= NODE_FLAG_OUTSIDE_TIME;
+                }
+            });
+        }
+    }
+
+    // Opacity of an item with timestamp `time` inside the widened window: 1 in
+    // the window itself, falling to 0 across the margin
+    fn time_fade(&self, time: f32) -> f32 {
+        match self.time_window {
+            Some([start, end]) if self.time_fade_margin > 0.0 && time.is_finite() => {
+                let outside = (start - time).max(time - end).max(0.0);
+                1.0 - outside / self.time_fade_margin
+            }
+            _ => 1.0,
+        }
+    }
+
+    fn check_layers(&self, layers: &[u32]) -> Result<(), JsValue> {
+        match layers.iter().find(|&&layer| layer as usize >= self.layers.len()) {
+            Some(layer) => Err(JsValue::from_str(&format!("Layer {} does not exist ({} layers)", layer, self.layers.len()))),
+            None => Ok(()),
+        }
+    }
+
+    fn node_layer(&self, index: usize) -> u32 {
+        self.node_layers.get(index).copied().unwrap_or(0)
+    }
+
+    fn edge_layer(&self, index: usize) -> u32 {
+        if self.edge_layers.len() == self.edges.len() {
+            self.edge_layers.get(index).copied().unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    // Whether layers change the frame: items are faded, or spread over layers
+    // that need sorting
+    fn layered(&self) -> bool {
+        let assigned = !self.node_layers.is_empty() || (!self.edge_layers.is_empty() && self.edge_layers.len() == self.edges.len());
+        self.layers.is_faded() || (self.layers.len() > 1 && assigned)
+    }
+
+    // After layer membership, visibility or order changed
+    fn layers_changed(&mut self) {
+        self.layer_order = None;
+        self.invalidate_extent();
+        // Like the other draw-only flags, the simulation never sees this one
+        for i in 0..self.nodes.len() {
+            let hidden = !self.layers.get(self.node_layer(i)).visible;
+            let node = &mut self.nodes[i];
+            if hidden {
+                node.flags |= NODE_FLAG_LAYER_HIDDEN;
+            } else {
+                node.flags &= !NODE_FLAG_LAYER_HIDDEN;
+            }
+        }
+    }
+
+    // Sorts the nodes and edges into layer order when layers are in use, and
+    // the nodes of each layer into Morton order with spatial sorting, when the
+    // counts changed since (or the positions, see positions_replaced)
+    fn update_layer_order(&mut self) {
+        let layered = self.layered() && self.layers.len() > 1;
+        if !layered && !self.spatial_sorting {
+            self.layer_order = None;
+            return;
+        }
+        let current = self.layer_order.as_ref().is_some_and(|order| order.nodes.len() == self.nodes.len() && order.edges.len() == self.edges.len());
+        if current {
+            return;
+        }
+        let (mut nodes, edges) = match layered {
+            true => (self.layers.draw_order(|i| self.node_layer(i), self.nodes.len()), self.layers.draw_order(|i| self.edge_layer(i), self.edges.len())),
+            false => ((0..self.nodes.len() as u32).collect(), (0..self.edges.len() as u32).collect()),
+        };
+        if self.spatial_sorting {
+            let positions: Vec<[f32; 2]> = self.nodes.iter().map(|node| [node.x, node.y]).collect();
+            let codes = morton::codes(&positions);
+            for run in nodes.chunk_by_mut(|&a, &b| self.node_layer(a as usize) == self.node_layer(b as usize)) {
+                run.sort_by_key(|&i| codes[i as usize]);
+            }
+        }
+        self.layer_order = Some(LayerOrder { nodes, edges });
+    }
+
+    // With spatial sorting, re-sorts the nodes on the next frame after their
+    // positions changed wholesale
+    fn positions_replaced(&mut self) {
+        if self.spatial_sorting {
+            self.layer_order = None;
+        }
+    }
+
+    // Every node index in drawing order, bottom first
+    fn layered_nodes(&self) -> Box<dyn DoubleEndedIterator<Item = usize> + '_> {
+        match &self.layer_order {
+            Some(order) if order.nodes.len() == self.nodes.len() => Box::new(order.nodes.iter().map(|&i| i as usize)),
+            _ => Box::new(0..self.nodes.len()),
+        }
+    }
+
+    fn layered_edges(&self) -> Box<dyn DoubleEndedIterator<Item = usize> + '_> {
+        match &self.layer_order {
+            Some(order) if order.edges.len() == self.edges.len() => Box::new(order.edges.iter().map(|&i| i as usize)),
+            _ => Box::new(0..self.edges.len()),
+        }
+    }
+
+    // The node instances of visible_frame, as node indices
+    fn frame_nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        self.layered_nodes().filter(|&i| self.nodes[i].flags & NODE_HIDDEN_FLAGS == 0)
+    }
+
+    // Edges split at a seam are listed twice, once per segment
+    fn frame_edges(&self) -> impl Iterator<Item = usize> + '_ {
+        self.layered_edges().flat_map(|i| std::iter::repeat_n(i, self.edge_instances(i)))
+    }
+
+    // The instances edge `index` is drawn as: none, one, or two when split at a seam
+    fn edge_instances(&self, index: usize) -> usize {
+        match self.edge_drawn(index) {
+            false => 0,
+            true if self.crosses_seam(index) => 2,
+            true => 1,
+        }
+    }
+
+    // Whether indexed edge `index` takes the short way across a seam of the
+    // wrapping world
+    fn crosses_seam(&self, index: usize) -> bool {
+        let Some(wrap) = self.physics.wrap() else {
+            return false;
+        };
+        self.edge_indices.get(index).is_some() && self.edges.get(index).is_some_and(|edge| wrap.crosses_seam([edge.x1, edge.y1], [edge.x2, edge.y2]))
+    }
+
+    // Segment `segment` of `edge`, edge `index`'s geometry: the edge itself, or
+    // for one crossing a seam, the part from its source (0) or its target (1) to
+    // the nearest copy of the other end
+    fn seam_segment(&self, index: usize, edge: &EdgeData, segment: usize) -> EdgeData {
+        let Some(wrap) = self.physics.wrap().filter(|_| self.crosses_seam(index)) else {
+            return *edge;
+        };
+        let (source, target) = ([edge.x1, edge.y1], [edge.x2, edge.y2]);
+        let mut edge = *edge;
+        if segment == 0 {
+            [edge.x2, edge.y2] = wrap.nearest(source, target);
+        } else {
+            [edge.x1, edge.y1] = wrap.nearest(target, source);
+        }
+        edge
+    }
+
+    // Whether node `index` is drawn: not hidden, and passing the visibility filter
+    fn node_shown(&self, index: usize) -> bool {
+        self.nodes.get(index).is_some_and(|node| node.flags & NODE_HIDDEN_FLAGS == 0) && self.passes_visibility_filter(index)
+    }
+
+    // Whether edge `index` is drawn, after the visibility filter
+    fn edge_shown(&self, index: usize) -> bool {
+        self.edge_drawn(index)
+            && self
+                .edge_indices
+                .get(index)
+                .is_none_or(|&[source, target]| self.passes_visibility_filter(source as usize) && self.passes_visibility_filter(target as usize))
+    }
+
+    // Nodes without user flags have none set
+    fn passes_visibility_filter(&self, index: usize) -> bool {
+        let flags = self.user_flags.get(index).copied().unwrap_or(0);
+        let [required, forbidden] = self.visibility_filter;
+        flags & required == required && flags & forbidden == 0
+    }
+
+    // The user flags of the nodes and edges drawn this frame, in instance order:
+    // visible_frame's when `filtered`, else every node and edge, with the
+    // nodes of `node_order` instead when decimated
+    fn frame_user_flags(&self, filtered: bool, node_order: Option<&[usize]>) -> (Vec<u32>, Vec<EdgeFilter>) {
+        let flags = |index: usize| self.user_flags.get(index).copied().unwrap_or(0);
+        let (nodes, edges): (Box<dyn Iterator<Item = usize>>, Box<dyn Iterator<Item = usize>>) = if filtered {
+            (Box::new(self.frame_nodes()), Box::new(self.frame_edges()))
+        } else {
+            (Box::new(0..self.nodes.len()), Box::new(0..self.edges.len()))
+        };
+        let nodes = match node_order {
+            Some(order) => order.iter().copied().map(flags).collect(),
+            None => nodes.map(flags).collect(),
+        };
+        let edges = edges
+            .map(|i| {
+                let (arrow_style, arrow_size) = self.edge_arrows.get(i).copied().unwrap_or_default();
+                let arrow = EdgeFilter { arrow_style, arrow_size, ..EdgeFilter::default() };
+                match self.edge_indices.get(i) {
+                    Some(&[source, target]) => EdgeFilter {
+                        source_flags: flags(source as usize),
+                        target_flags: flags(target as usize),
+                        has_endpoints: 1,
+                        target_radius: self.nodes.get(target as usize).map_or(0.0, |node| node.size),
+                        ..arrow
+                    },
+                    None => arrow,
+                }
+            })
+            .collect();
+        (nodes, edges)
+    }
+
+    fn draws_arrows(&self) -> bool {
+        self.renderer.arrow_style() != ArrowStyle::None || !self.edge_arrows.is_empty()
+    }
+
+    // Whether edge `index` is in the frame visible_frame builds
+    fn edge_drawn(&self, index: usize) -> bool {
+        let is_visible = |node: u32| self.nodes.get(node as usize).is_none_or(|node| node.flags & NODE_HIDDEN_FLAGS == 0);
+        let endpoints_visible = self.edge_indices.get(index).is_none_or(|&[source, target]| is_visible(source) && is_visible(target));
+        let in_time = self
+            .edge_times
+            .as_ref()
+            .filter(|times| self.time_window.is_some() && times.len() == self.edges.len())
+            .is_none_or(|times| times.is_inside(index));
+        let on_seam = !self.split_seam_edges && self.crosses_seam(index);
+        endpoints_visible && in_time && !on_seam && self.layers.get(self.edge_layer(index)).visible
+    }
+
+    fn update_resize_watcher(&mut self) {
+        if !self.observe_resize {
+            self.resize_watcher = None;
+            self.observed_size = None;
+        } else if let (None, Some(canvas)) = (&self.resize_watcher, &self.canvas) {
+            match ResizeWatcher::observe(canvas) {
+                Ok(watcher) => self.resize_watcher = Some(watcher),
+                Err(error) => log!("Failed to observe canvas resizes: {:?}", error),
+            }
+        }
+    }
+
+    // Sizes the canvas to the observed CSS size; also catches pixel ratio
+    // changes, which don't resize the element
+    fn apply_observed_size(&mut self) {
+        let Some(watcher) = &self.resize_watcher else {
+            return;
+        };
+        if let Some(size) = watcher.take_size() {
+            self.observed_size = Some(size);
+        }
+        let (Some([css_width, css_height]), Some(canvas)) = (self.observed_size, &self.canvas) else {
+            return;
+        };
+
+        let ratio = if self.pixel_ratio > 0.0 {
+            self.pixel_ratio
+        } else {
+            web_sys::window().map_or(1.0, |window| window.device_pixel_ratio() as f32)
+        };
+        let (width, height) = (css_width * ratio, css_height * ratio);
+        let fit = (MAX_SURFACE_SIZE as f32 / width.max(height)).min(1.0);
+        let (width, height) = (((width * fit).round() as u32).max(1), ((height * fit).round() as u32).max(1));
+        if (width, height) != (canvas.width(), canvas.height()) {
+            canvas.set_width(width);
+            canvas.set_height(height);
+            self.resize(width, height);
+        }
+    }
+
+    // Centers the main view where the minimap was last pressed or dragged to
+    fn follow_minimap(&mut self) {
+        let Some(minimap) = &self.minimap else {
+            return;
+        };
+        let (Some(point), Some(([x, y], zoom))) = (minimap.listeners.as_ref().and_then(|listeners| listeners.take_target()), minimap.camera) else {
+            return;
+        };
+        let scale = events::pixel_scale(&minimap.canvas);
+        let world = [x + point[0] * scale[0] / zoom, y + point[1] * scale[1] / zoom];
+        let (width, height) = self.renderer.surface_size().unwrap_or((0, 0));
+        self.camera_tour = None;
+        self.camera_focus = None;
+        self.camera_position = [
+            world[0] - width as f32 * 0.5 / self.camera_zoom,
+            world[1] - height as f32 * 0.5 / self.camera_zoom,
+        ];
+    }
+
+    // Redraws the minimap when it is due, from the instances of the frame just drawn
+    fn draw_minimap(&mut self) {
+        let Some(minimap) = &mut self.minimap else {
+            return;
+        };
+        let size = (minimap.canvas.width(), minimap.canvas.height());
+        let resized = self.renderer.minimap_size() != Some(size);
+        if resized {
+            self.renderer.resize_minimap(size.0, size.1);
+        }
+        let main_size = self.renderer.surface_size().unwrap_or((0, 0));
+        let view = (self.camera_position, self.camera_zoom, main_size);
+        minimap.frames_since_draw += 1;
+        if !(resized || minimap.stale || minimap.drawn_view != Some(view) || minimap.frames_since_draw >= minimap.interval) {
+            return;
+        }
+
+        let [x, y] = self.camera_position;
+        let viewport = [x, y, x + main_size.0 as f32 / self.camera_zoom, y + main_size.1 as f32 / self.camera_zoom];
+        let bounds = self.graph_extent().map_or(viewport, |extent| extent.bounds);
+        let Some(minimap) = &mut self.minimap else {
+            return;
+        };
+        let camera = fit_camera(bounds, self.renderer.minimap_size().unwrap_or(size));
+        minimap.frames_since_draw = 0;
+        minimap.stale = false;
+        minimap.drawn_view = Some(view);
+        minimap.camera = Some(camera);
+        let (point_size, viewport_color) = (minimap.point_size, minimap.viewport_color);
+        if let Err(error) = self.renderer.render_minimap(Camera { position: camera.0, zoom: camera.1 }, point_size, viewport, viewport_color) {
+            self.report_render_error(error);
+        }
+    }
+
+    // Attaches the canvas listeners while a mouse event has a callback, touch
+    // gestures are on or double-clicks focus nodes
+    fn update_canvas_listeners(&mut self) {
+        let mouse = self.event_callbacks.keys().any(|kind| kind.is_pointer());
+        let touch = self.touch_gestures;
+        let double_click = self.double_click_focus;
+        if !mouse {
+            self.hovered_node = None;
+            self.hovered_edge = None;
+        }
+        let current = self
+            .canvas_listeners
+            .as_ref()
+            .map(|listeners| (listeners.handles_mouse(), listeners.handles_touch(), listeners.handles_double_click()));
+        if current == Some((mouse, touch, double_click)) {
+            return;
+        }
+        // Dropped first, so the canvas style it changed is back before reattaching
+        self.canvas_listeners = None;
+        let Some(canvas) = self.canvas.as_ref().filter(|_| mouse || touch || double_click) else {
+            return;
+        };
+        match CanvasListeners::attach(canvas, mouse, touch, double_click) {
+            Ok(listeners) => self.canvas_listeners = Some(listeners),
+            Err(error) => log!("Failed to attach canvas listeners: {:?}", error),
+        }
+    }
+
+    // Picks the mouse input queued since the last frame and schedules the
+    // callbacks; they run once render returns, outside this borrow
+    fn dispatch_events(&mut self) {
+        let viewport = (self.camera_position, self.camera_zoom);
+        if viewport != self.reported_viewport {
+            self.reported_viewport = viewport;
+            if let Some(callback) = self.event_callbacks.get(&EventKind::ViewportChange) {
+                let payload = js_sys::Object::new();
+                let set = |key: &str, value: f32| {
+                    let _ = js_sys::Reflect::set(&payload, &JsValue::from_str(key), &JsValue::from(value));
+                };
+                set("x", viewport.0[0]);
+                set("y", viewport.0[1]);
+                set("zoom", viewport.1);
+                schedule_callback(callback, payload.into());
+            }
+        }
+
+        let Some(listeners) = &self.canvas_listeners else {
+            return;
+        };
+        let (inputs, scale) = (listeners.take_inputs(), listeners.pixel_scale());
+        for input in inputs {
+            match input {
+                PointerInput::Move(event) => {
+                    let world = self.event_world_position(&event, scale);
+                    let index = self.pick_node(world[0], world[1]);
+                    if index != self.hovered_node {
+                        self.hovered_node = index;
+                        self.emit_pointer_event(EventKind::NodeHover, index, world, &event);
+                    }
+                    // Only scans the edges when someone listens
+                    if self.event_callbacks.contains_key(&EventKind::EdgeHover) {
+                        let edge = if index.is_none() { self.pick_edge(world[0], world[1]) } else { None };
+                        if edge != self.hovered_edge {
+                            self.hovered_edge = edge;
+                            self.emit_pointer_event(EventKind::EdgeHover, edge, world, &event);
+                        }
+                    }
+                }
+                PointerInput::Leave(event) => {
+                    let world = self.event_world_position(&event, scale);
+                    if self.hovered_node.take().is_some() {
+                        self.emit_pointer_event(EventKind::NodeHover, None, world, &event);
+                    }
+                    if self.hovered_edge.take().is_some() {
+                        self.emit_pointer_event(EventKind::EdgeHover, None, world, &event);
+                    }
+                }
+                PointerInput::Click(event) => {
+                    if !std::mem::take(&mut self.tap_clicked) {
+                        self.report_click(&event, scale);
+                    }
+                }
+                PointerInput::DoubleClick(event) => {
+                    let world = self.event_world_position(&event, scale);
+                    if let Some(index) = self.pick_node(world[0], world[1]) {
+                        let _ = self.focus_node(index, 0.0, FOCUS_DURATION_MS);
+                    }
+                }
+                PointerInput::TouchDown(event) => {
+                    self.tap_clicked = false;
+                    self.touch_gesture.down(event.pointer_id(), [event.offset_x() as f32, event.offset_y() as f32]);
+                }
+                PointerInput::TouchMove(event) => {
+                    if let Some(change) = self.touch_gesture.moved(event.pointer_id(), [event.offset_x() as f32, event.offset_y() as f32]) {
+                        self.follow_touch(change, scale);
+                    }
+                }
+                PointerInput::TouchUp(event) => {
+                    if self.touch_gesture.up(event.pointer_id()) {
+                        self.report_click(&event, scale);
+                        self.tap_clicked = true;
+                    }
+                }
+                PointerInput::TouchCancel(event) => self.touch_gesture.cancel(event.pointer_id()),
+            }
+        }
+    }
+
+    fn report_click(&self, event: &web_sys::MouseEvent, scale: [f32; 2]) {
+        let world = self.event_world_position(event, scale);
+        if let Some(index) = self.pick_node(world[0], world[1]) {
+            self.emit_pointer_event(EventKind::NodeClick, Some(index), world, event);
+        } else if let Some(index) = self.pick_edge(world[0], world[1]) {
+            self.emit_pointer_event(EventKind::EdgeClick, Some(index), world, event);
+        } else {
+            self.emit_pointer_event(EventKind::BackgroundClick, None, world, event);
+        }
+    }
+
+    // Moves the camera with the fingers; `change` is in CSS pixels
+    fn follow_touch(&mut self, change: CameraChange, scale: [f32; 2]) {
+        self.camera_tour = None;
+        self.camera_focus = None;
+        self.camera_position[0] -= change.pan[0] * scale[0] / self.camera_zoom;
+        self.camera_position[1] -= change.pan[1] * scale[1] / self.camera_zoom;
+        self.zoom_at(change.anchor[0] * scale[0], change.anchor[1] * scale[1], change.zoom);
+    }
+
+    // Mouse offsets are CSS pixels; the camera works in canvas pixels
+    fn event_world_position(&self, event: &web_sys::MouseEvent, scale: [f32; 2]) -> [f32; 2] {
+        [
+            self.camera_position[0] + event.offset_x() as f32 * scale[0] / self.camera_zoom,
+            self.camera_position[1] + event.offset_y() as f32 * scale[1] / self.camera_zoom,
+        ]
+    }
+
+    fn emit_pointer_event(&self, kind: EventKind, index: Option<u32>, world: [f32; 2], event: &web_sys::MouseEvent) {
+        let Some(callback) = self.event_callbacks.get(&kind) else {
+            return;
+        };
+        let payload = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&payload, &JsValue::from_str(key), &value);
+        };
+        set("index", index.map_or(JsValue::NULL, JsValue::from));
+        let layer = index.map(|index| match kind {
+            EventKind::EdgeClick | EventKind::EdgeHover => self.edge_layer(index as usize),
+            _ => self.node_layer(index as usize),
+        });
+        set("layer", layer.map_or(JsValue::NULL, JsValue::from));
+        set("worldX", JsValue::from(world[0]));
+        set("worldY", JsValue::from(world[1]));
+        set("screenX", JsValue::from(event.offset_x()));
+        set("screenY", JsValue::from(event.offset_y()));
+        set("originalEvent", event.into());
+        schedule_callback(callback, payload.into());
+    }
+
+    // Nodes and edges without the hidden ones, faded at the ends of the time
+    // window and by their layer's opacity, in layer order, with edges crossing
+    // a seam split; or None if nothing is hidden, faded, layered or split
+    fn visible_frame(&self, nodes: &[NodeData], edges: &[EdgeData]) -> Option<(Vec<NodeData>, Vec<EdgeData>)> {
+        let timed = self.time_window.is_some();
+        let layered = self.layered();
+        let seams = (0..self.edges.len()).any(|i| self.crosses_seam(i));
+        if !timed && !layered && !self.spatial_sorting && !seams && !nodes.iter().any(|node| node.flags & NODE_HIDDEN_FLAGS != 0) {
+            return None;
+        }
+        let node_times = self.node_times.as_ref().filter(|times| timed && times.len() == nodes.len());
+        let edge_times = self.edge_times.as_ref().filter(|times| timed && times.len() == edges.len());
+
+        let visible_nodes = self
+            .frame_nodes()
+            .map(|i| {
+                let mut alpha = node_times.map_or(1.0, |times| self.time_fade(times.time(i)));
+                if layered {
+                    alpha *= self.layers.get(self.node_layer(i)).opacity;
+                }
+                NodeData { a: nodes[i].a * alpha, ..nodes[i] }
+            })
+            .collect();
+        // frame_edges already dropped the edges outside the window
+        let mut previous = None;
+        let visible_edges = self
+            .frame_edges()
+            .map(|i| {
+                let mut alpha = edge_times.map_or(1.0, |times| self.time_fade(times.time(i)));
+                if layered {
+                    alpha *= self.layers.get(self.edge_layer(i)).opacity;
+                }
+                let segment = if previous.replace(i) == Some(i) { 1 } else { 0 };
+                EdgeData { a: edges[i].a * alpha, ..self.seam_segment(i, &edges[i], segment) }
+            })
+            .collect();
+        Some((visible_nodes, visible_edges))
+    }
+
+    // Nodes and edges blended between the two latest simulated states by the
+    // fraction of a tick left in the accumulator, for smooth fixed-rate motion
+    fn interpolated_frame(&self) -> Option<(Vec<NodeData>, Vec<EdgeData>)> {
+        let t = self.interpolation_factor()?;
+        let mut nodes = self.nodes.clone();
+        for (i, node) in nodes.iter_mut().enumerate() {
+            [node.x, node.y] = self.drawn_position(i, Some(t));
+        }
+
+        let mut edges = self.edges.clone();
+        for (edge, &[source, target]) in edges.iter_mut().zip(&self.edge_indices) {
+            if let (Some(source), Some(target)) = (nodes.get(source as usize), nodes.get(target as usize)) {
+                edge.x1 = source.x;
+                edge.y1 = source.y;
+                edge.x2 = target.x;
+                edge.y2 = target.y;
+            }
+        }
+
+        Some((nodes, edges))
+    }
+
+    // Computed at most once between node changes
+    fn graph_extent(&mut self) -> Option<GraphExtent> {
+        self.sync_physics_positions();
+        if self.extent.is_none() {
+            let shown = (0..self.nodes.len()).filter(|&i| self.node_shown(i)).map(|i| &self.nodes[i]);
+            self.extent = Some(GraphExtent::of(shown));
+        }
+        self.extent.flatten()
+    }
+
+    // Drops the cached extent; called wherever node positions, sizes or
+    // visibility change
+    fn invalidate_extent(&mut self) {
+        self.extent = None;
+    }
+
+    fn screen_transform(&self) -> ScreenTransform {
+        let (width, height) = self.renderer.surface_size().unwrap_or((0, 0));
+        let scale = |css: Option<i32>, pixels: u32| match css {
+            Some(css) if pixels > 0 => css as f32 / pixels as f32,
+            _ => 1.0,
+        };
+        ScreenTransform {
+            projection: Projection::new(Camera { position: self.camera_position, zoom: self.camera_zoom }, (width, height)),
+            css_scale: [
+                scale(self.canvas.as_ref().map(|canvas| canvas.client_width()), width),
+                scale(self.canvas.as_ref().map(|canvas| canvas.client_height()), height),
+            ],
+        }
+    }
+
+    fn screen_position(&self, index: usize, t: Option<f32>, transform: &ScreenTransform) -> [f32; 3] {
+        match self.nodes.get(index) {
+            Some(node) if !self.node_shown(index) => {
+                let [x, y, _] = transform.apply(self.drawn_position(index, t), node.size);
+                [x, y, 0.0]
+            }
+            Some(node) => transform.apply(self.drawn_position(index, t), node.size),
+            None => [f32::NAN, f32::NAN, 0.0],
+        }
+    }
+
+    // World radius of node `index` as drawn, after its style class, with the
+    // glow around it
+    fn drawn_radius(&self, index: usize) -> f32 {
+        let node = &self.nodes[index];
+        let class_id = (node.flags >> NODE_CLASS_SHIFT) as usize;
+        match self.renderer.node_style(class_id).filter(|_| class_id > 0) {
+            Some(style) => {
+                let size = if style.fields & renderer::STYLE_FIELD_SIZE != 0 { style.size } else { node.size };
+                let glow = if style.fields & renderer::STYLE_FIELD_GLOW != 0 { style.glow.max(0.0) } else { 0.0 };
+                size * (1.0 + glow)
+            }
+            None => node.size,
+        }
+    }
+
+    // How far between the two latest simulated states frames are drawn, or None
+    // when they are drawn as simulated
+    fn interpolation_factor(&self) -> Option<f32> {
+        let ready = self.previous_positions.len() == self.nodes.len() && self.latest_positions.len() == self.nodes.len();
+        (self.interpolate_positions && ready).then(|| (self.physics_accumulator * self.physics_rate).clamp(0.0, 1.0))
+    }
+
+    // Where node `index` is drawn, given the interpolation_factor
+    fn drawn_position(&self, index: usize, t: Option<f32>) -> [f32; 2] {
+        let node = &self.nodes[index];
+        let Some(t) = t else {
+            return [node.x, node.y];
+        };
+        let (previous, latest) = (self.previous_positions[index], self.latest_positions[index]);
+        // Nodes moved since the readback (e.g. while dragged) are shown as they are
+        if node.x == latest[0] && node.y == latest[1] {
+            [previous[0] + (latest[0] - previous[0]) * t, previous[1] + (latest[1] - previous[1]) * t]
+        } else {
+            [node.x, node.y]
+        }
+    }
+
+    // Indices must have been checked with check_node_indices
+    fn set_node_flag(&mut self, indices: &[u32], flag: u32, enabled: bool) {
+        self.invalidate_extent();
+        for &index in indices {
+            let node = &mut self.nodes[index as usize];
+            if enabled {
+                node.flags |= flag;
+            } else {
+                node.flags &= !flag;
+            }
+            let flags = node.flags;
+            self.renderer.write_node_flags(index as usize, flags);
+        }
+    }
+
+    fn check_graph_chunk(&self, data: &[f32], kind: &str) -> Result<(), JsValue> {
+        if !self.graph_loading {
+            return Err(JsValue::from_str("No graph load in progress; call begin_graph_load first"));
+        }
+        if !data.len().is_multiple_of(7) {
+            return Err(JsValue::from_str(&format!("A {} chunk must hold whole {}s of 7 floats, got {} floats", kind, kind, data.len())));
+        }
+        Ok(())
+    }
+
+    fn check_node_indices(&self, indices: &[u32]) -> Result<(), JsValue> {
+        match indices.iter().find(|&&index| index as usize >= self.nodes.len()) {
+            Some(index) => Err(JsValue::from_str(&format!(
+                "Node index {} out of range ({} nodes)",
+                index,
+                self.nodes.len()
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // Colors the marked nodes, and the edges `edge_highlighted` picks by their
+    // indexed endpoints, with `color`; with `dim_others` fades the rest by the
+    // theme's dim alpha. Replaces any previous highlight.
+    fn apply_highlight(&mut self, highlighted_nodes: &[bool], edge_highlighted: impl Fn(Option<[u32; 2]>) -> bool, color: [f32; 4], dim_others: bool) {
+        let dim_alpha = self.theme.dim_alpha;
+        self.backup_colors();
+
+        for (node, &highlighted) in self.nodes.iter_mut().zip(highlighted_nodes) {
+            if highlighted {
+                [node.r, node.g, node.b, node.a] = color;
+            } else if dim_others {
+                node.a *= dim_alpha;
+            }
+        }
+        for (i, edge) in self.edges.iter_mut().enumerate() {
+            if edge_highlighted(self.edge_indices.get(i).copied()) {
+                [edge.r, edge.g, edge.b, edge.a] = color;
+            } else if dim_others {
+                edge.a *= dim_alpha;
+            }
+        }
+    }
+
+    // Whether the edges are indexed, so they have a direction; warns if not
+    fn has_directed_edges(&self) -> bool {
+        if self.edge_indices.len() == self.edges.len() {
+            return true;
+        }
+        let details = js_sys::Object::new();
+        let _ = js_sys::Reflect::set(&details, &JsValue::from_str("kind"), &JsValue::from_str("cycles"));
+        self.warn("Cycle search needs indexed edges; positional edges have no direction".to_string(), &details);
+        false
+    }
+
+    // Undoes any highlight and keeps the colors it starts from
+    fn backup_colors(&mut self) {
+        self.clear_highlight();
+        self.color_backup = Some(ColorBackup {
+            nodes: self.nodes.iter().map(|node| [node.r, node.g, node.b, node.a]).collect(),
+            edges: self.edges.iter().map(|edge| [edge.r, edge.g, edge.b, edge.a]).collect(),
+            edge_widths: Vec::new(),
+        });
+    }
+
+    // Distinct node indices in ascending order, at their latest simulated positions
+    fn node_selection(&mut self, indices: &[u32]) -> Result<Vec<usize>, JsValue> {
+        self.check_node_indices(indices)?;
+        self.sync_physics_positions();
+        let mut selection: Vec<usize> = indices.iter().map(|&index| index as usize).collect();
+        selection.sort_unstable();
+        selection.dedup();
+        Ok(selection)
+    }
+
+    fn seed_node_positions(&mut self, indices: &[usize], strategy: &str, seed: u64) -> Result<(), JsValue> {
+        let strategy = SeedStrategy::parse(strategy)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown seeding strategy: {}", strategy)))?;
+        self.sync_physics_positions();
+
+        let positions = layout::seed_positions(strategy, indices.len(), seed, self.physics.rest_length.max(1.0));
+        self.place_nodes(indices.iter().copied().zip(positions));
+        Ok(())
+    }
+
+    // Node indices in layout order: the given ones first, then the rest by index
+    fn layout_order(&self, order: Option<Vec<u32>>) -> Result<Vec<usize>, JsValue> {
+        layout::layout_order(self.nodes.len(), &order.unwrap_or_default()).map_err(|e| JsValue::from_str(&e))
+    }
+
+    // Move nodes to new positions at rest, so the simulation starts calmly from them
+    fn place_nodes(&mut self, positions: impl IntoIterator<Item = (usize, [f32; 2])>) {
+        for (index, [x, y]) in positions {
+            if let Some(node) = self.nodes.get_mut(index) {
+                node.x = x;
+                node.y = y;
+                node.vx = 0.0;
+                node.vy = 0.0;
+                node.fx = 0.0;
+                node.fy = 0.0;
+            }
+        }
+
+        self.mark_physics_dirty();
+        self.apply_drag_position();
+        self.refresh_edge_endpoints();
+        self.positions_replaced();
+    }
+
+    // Colors node i with palette entry ids[i] (modulo the palette size)
+    fn color_nodes_by_class(&mut self, ids: &[u32], palette: Option<Vec<String>>) -> Result<(), JsValue> {
+        let palette = match palette {
+            Some(palette) if !palette.is_empty() => palette
+                .iter()
+                .map(|color| parse_color_arg(color))
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => palettes::categorical(DEFAULT_CATEGORICAL_PALETTE).unwrap_or_default().iter().map(|&rgb| color::rgb24(rgb)).collect(),
+        };
+
+        for (node, &id) in self.nodes.iter_mut().zip(ids) {
+            let [r, g, b, a] = palette[id as usize % palette.len()];
+            node.r = r;
+            node.g = g;
+            node.b = b;
+            node.a = a;
+        }
+        Ok(())
+    }
+
+    // Layout placement, immediate or as an eased transition over `animate_ms`
+    fn place_or_animate(&mut self, positions: impl IntoIterator<Item = (usize, [f32; 2])>, animate_ms: Option<f32>) {
+        let Some(duration_ms) = animate_ms.filter(|&ms| ms > 0.0) else {
+            self.place_nodes(positions);
+            return;
+        };
+
+        let mut targets: Vec<[f32; 2]> = self.nodes.iter().map(|node| [node.x, node.y]).collect();
+        for (index, position) in positions {
+            if let Some(target) = targets.get_mut(index) {
+                *target = position;
+            }
+        }
+        self.start_animation(targets, duration_ms, Easing::EaseInOut);
+    }
+
+    fn start_animation(&mut self, mut targets: Vec<[f32; 2]>, duration_ms: f32, easing: Easing) {
+        // A transition that restyles nodes jumps to its end look, possibly
+        // removing nodes, before positions are retargeted
+        if let Some(animation) = self.animation.take() {
+            self.finish_appearance(&animation);
+            targets.truncate(self.nodes.len());
+        }
+
+        // From the latest simulated (or mid-animation) positions; readbacks still in
+        // flight would overwrite the animated ones, so drop them
+        self.sync_physics_positions();
+        self.mark_physics_dirty();
+
+        let from = self.nodes.iter().map(|node| [node.x, node.y]).collect();
+        self.animation = Some(PositionAnimation::new(from, targets, duration_ms, easing));
+    }
+
+    // Moves the nodes to the animation's state at render `time`, settling them on
+    // the targets at the end
+    fn advance_animation(&mut self, time: f64) {
+        let Some(animation) = &mut self.animation else {
+            return;
+        };
+        if animation.node_count() != self.nodes.len() {
+            self.animation = None;
+            return;
+        }
+
+        let (positions, looks, finished) = animation.frame(time);
+        if finished {
+            let Some(animation) = self.animation.take() else {
+                return;
+            };
+            self.place_nodes(animation.targets().iter().copied().enumerate());
+            self.finish_appearance(&animation);
+            return;
+        }
+
+        for (node, [x, y]) in self.nodes.iter_mut().zip(positions) {
+            node.x = x;
+            node.y = y;
+        }
+        for (node, [r, g, b, a, size]) in self.nodes.iter_mut().zip(looks.into_iter().flatten()) {
+            [node.r, node.g, node.b, node.a, node.size] = [r, g, b, a, size];
+        }
+        self.apply_drag_position();
+        self.refresh_edge_endpoints();
+    }
+
+    // Gives the nodes the animation's final colors and sizes and removes the
+    // ones it fades out; positions are left alone
+    fn finish_appearance(&mut self, animation: &PositionAnimation) {
+        let Some(looks) = animation.target_appearance() else {
+            return;
+        };
+        for (node, &[r, g, b, a, size]) in self.nodes.iter_mut().zip(looks) {
+            [node.r, node.g, node.b, node.a, node.size] = [r, g, b, a, size];
+        }
+        if animation.final_node_count() < self.nodes.len() {
+            self.nodes.truncate(animation.final_node_count());
+            self.node_count_changed();
+            self.refresh_edge_endpoints();
+        }
+        self.mark_physics_dirty();
+    }
+
+    // Times the pulses started since the last frame, and forgets them all once
+    // the last one has ended; the GPU copy has expired by then too
+    fn advance_pulses(&mut self, time: f64) {
+        if self.pulses.is_empty() {
+            return;
+        }
+        let now = time as f32;
+        for pulse in &mut self.pulses {
+            if pulse.start.is_nan() {
+                pulse.start = now;
+                pulse.end += now;
+                self.pulses_dirty = true;
+            }
+        }
+        if self.pulses.iter().all(|pulse| pulse.end <= now) {
+            self.pulses.clear();
+            self.pulses_dirty = false;
+        }
+    }
+
+    fn advance_opacity_fade(&mut self, time: f64) {
+        let Some(fade) = &mut self.opacity_fade else {
+            return;
+        };
+        let (opacity, finished) = fade.frame(time);
+        if finished {
+            self.opacity_fade = None;
+        }
+        self.global_opacity = opacity;
+        self.renderer.set_global_opacity(opacity);
+    }
+
+    fn advance_camera_tour(&mut self, time: f64) {
+        let Some(tour) = &mut self.camera_tour else {
+            return;
+        };
+        // The tour moves the view center; the camera position is the top-left
+        let (width, height) = self.renderer.surface_size().unwrap_or((0, 0));
+        let half_view = [width as f32 * 0.5 / self.camera_zoom, height as f32 * 0.5 / self.camera_zoom];
+        let center = [self.camera_position[0] + half_view[0], self.camera_position[1] + half_view[1]];
+        let nodes = &self.nodes;
+        let position_of = |index: u32| nodes.get(index as usize).map(|node| [node.x, node.y]);
+
+        match tour.frame(time, center, self.camera_zoom, position_of) {
+            Some((center, zoom)) => {
+                self.camera_zoom = zoom;
+                self.camera_position = [center[0] - width as f32 * 0.5 / zoom, center[1] - height as f32 * 0.5 / zoom];
+            }
+            None => {
+                self.camera_tour = None;
+                if let Some(callback) = &self.camera_tour_callback {
+                    if let Err(error) = callback.call0(&JsValue::NULL) {
+                        log!("Camera tour callback threw: {:?}", error);
+                    }
+                }
+            }
+        }
+    }
+
+    fn advance_camera_focus(&mut self, time: f64) {
+        let Some(focus) = &mut self.camera_focus else {
+            return;
+        };
+        let (width, height) = self.renderer.surface_size().unwrap_or((0, 0));
+        let half_view = [width as f32 * 0.5 / self.camera_zoom, height as f32 * 0.5 / self.camera_zoom];
+        let center = [self.camera_position[0] + half_view[0], self.camera_position[1] + half_view[1]];
+        let nodes = &self.nodes;
+        let position_of = |index: u32| nodes.get(index as usize).map(|node| [node.x, node.y]);
+
+        let Some((center, zoom, finished)) = focus.frame(time, center, self.camera_zoom, position_of) else {
+            self.camera_focus = None;
+            return;
+        };
+        if finished {
+            self.camera_focus = None;
+        }
+        self.camera_zoom = zoom;
+        self.camera_position = [center[0] - width as f32 * 0.5 / zoom, center[1] - height as f32 * 0.5 / zoom];
+    }
+
+    // Decimates while the shown nodes, spread evenly over their bounds, would be
+    // closer together on screen than the target spacing
+    fn update_decimation(&mut self) {
+        let Some(target_px) = self.decimation else {
+            self.decimating = false;
+            return;
+        };
+        let css_scale = self.screen_transform().css_scale[0];
+        self.decimating = self.graph_extent().is_some_and(|extent| {
+            let [min_x, min_y, max_x, max_y] = extent.bounds;
+            let area = (max_x - min_x) * (max_y - min_y) * self.camera_zoom * self.camera_zoom;
+            (area / extent.count as f32).sqrt() * css_scale < target_px
+        });
+    }
+
+    // The node instances of the frame (listed as visible_frame does when
+    // `filtered`) thinned to one per decimation cell, and their node indices;
+    // None while not decimating. Nodes the visibility filter hides can't stand
+    // for a cell, so they're left out.
+    fn decimated_frame(&self, nodes: &[NodeData], filtered: bool) -> Option<(Vec<NodeData>, Vec<usize>)> {
+        let target_px = self.decimation.filter(|_| self.decimating)?;
+        let order: Vec<usize> = if filtered { self.frame_nodes().collect() } else { (0..nodes.len()).collect() };
+        let shown: Vec<usize> = (0..nodes.len().min(order.len())).filter(|&k| self.passes_visibility_filter(order[k])).collect();
+        let cell = target_px / self.screen_transform().css_scale[0].max(f32::MIN_POSITIVE);
+        let [camera_x, camera_y] = self.camera_position;
+        let screen = shown.iter().map(|&k| {
+            let node = &nodes[k];
+            ([(node.x - camera_x) * self.camera_zoom, (node.y - camera_y) * self.camera_zoom], self.drawn_radius(order[k]))
+        });
+        let kept: Vec<usize> = clustering::representatives(screen, cell).into_iter().map(|position| shown[position]).collect();
+        Some((kept.iter().map(|&k| nodes[k]).collect(), kept.iter().map(|&k| order[k]).collect()))
+    }
+
+    // Source and target position of every indexed edge, NaN where a node is missing
+    fn edge_ends(&self) -> Vec<[[f32; 2]; 2]> {
+        let position = |index: u32| self.nodes.get(index as usize).map_or([f32::NAN; 2], |node| [node.x, node.y]);
+        self.edge_indices.iter().map(|&[source, target]| [position(source), position(target)]).collect()
+    }
+
+    // Drops the edge bundles once the edges change or their endpoints move
+    // beyond the tolerance
+    fn check_edge_bundles(&mut self) {
+        let Some(bundled) = &self.bundled_ends else {
+            return;
+        };
+        let moved = |from: [f32; 2], to: [f32; 2]| (to[0] - from[0]).hypot(to[1] - from[1]).total_cmp(&bundled.tolerance).is_gt();
+        let stale = bundled.ends.len() != self.edge_indices.len()
+            || self.edges.len() != self.edge_indices.len()
+            || self.edge_ends().iter().zip(&bundled.ends).any(|(now, then)| moved(then[0], now[0]) || moved(then[1], now[1]));
+        if stale {
+            self.clear_bundling();
+        }
+    }
+
+    // Moves cluster_blend toward 1 while auto clustering is on below the zoom
+    // threshold and toward 0 otherwise, then rebuilds the clusters if they're
+    // drawn and out of date
+    fn advance_clustering(&mut self, time: f64) {
+        let target = if self.auto_clustering.is_some() && self.camera_zoom < CLUSTER_ZOOM_THRESHOLD { 1.0 } else { 0.0 };
+        let heading = self.cluster_fade.as_ref().map_or(self.cluster_blend, |(_, to)| *to);
+        if heading != target {
+            self.cluster_fade = Some((OpacityFade::new(self.cluster_blend, target, CLUSTER_FADE_MS), target));
+        }
+        if let Some((fade, _)) = &mut self.cluster_fade {
+            let (blend, finished) = fade.frame(time);
+            self.cluster_blend = blend;
+            if finished {
+                self.cluster_fade = None;
+            }
+        }
+        let Some(pixel_radius) = self.auto_clustering.filter(|_| self.cluster_blend > 0.0) else {
+            return;
+        };
+
+        let bucket = clustering::zoom_bucket(self.camera_zoom);
+        let fingerprint = self.cluster_fingerprint();
+        if self.cluster_cache.as_ref().is_some_and(|cache| cache.bucket == bucket && cache.fingerprint == fingerprint) {
+            return;
+        }
+        // Converted from CSS to canvas pixels and then to world units
+        let css_scale = self.screen_transform().css_scale[0];
+        let cell = 2.0 * pixel_radius / css_scale.max(f32::MIN_POSITIVE) / clustering::bucket_zoom(bucket);
+        let nodes = (0..self.nodes.len()).filter(|&i| self.node_shown(i)).map(|i| (i, [self.nodes[i].x, self.nodes[i].y]));
+        let edges = (0..self.edge_indices.len()).filter(|&i| self.edge_shown(i)).map(|i| (i, self.edge_indices[i]));
+        let clusters = Clusters::build(nodes, self.nodes.len(), edges, cell);
+        self.cluster_cache = Some(ClusterCache { bucket, pixel_radius, fingerprint, clusters });
+    }
+
+    // Changes whenever a shown node moves, a node is shown or hidden, or the
+    // shown edges change
+    fn cluster_fingerprint(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        for (i, node) in self.nodes.iter().enumerate().filter(|&(i, _)| self.node_shown(i)) {
+            (i, node.x.to_bits(), node.y.to_bits()).hash(&mut hasher);
+        }
+        self.nodes.len().hash(&mut hasher);
+        for i in (0..self.edge_indices.len()).filter(|&i| self.edge_shown(i)) {
+            (i, self.edge_indices[i]).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    // World radius of the largest cluster at the current zoom
+    fn cluster_max_size(&self, pixel_radius: f32) -> f32 {
+        pixel_radius / self.screen_transform().css_scale[0].max(f32::MIN_POSITIVE) / self.camera_zoom
+    }
+
+    // The node drawn for `cluster`: a lone member as itself, larger clusters as
+    // a disc up to `max_size` in radius, colored by relative size
+    fn cluster_node(&self, clusters: &Clusters, cluster: &clustering::Cluster, max_size: f32) -> NodeData {
+        let [x, y] = cluster.center;
+        if let [member] = cluster.members[..] {
+            return NodeData { x, y, ..self.nodes[member as usize] };
+        }
+        let [r, g, b, a] = Colormap::parse(CLUSTER_COLORMAP).map_or(DEFAULT_MISSING_COLOR, |colormap| colormap.sample(clusters.relative_size(cluster)));
+        let share = cluster.members.len() as f32 / clusters.largest as f32;
+        NodeData {
+            x,
+            y,
+            vx: 0.0,
+            vy: 0.0,
+            fx: 0.0,
+            fy: 0.0,
+            r,
+            g,
+            b,
+            a,
+            size: max_size * (0.4 + 0.6 * share.sqrt()),
+            mass: 1.0,
+            flags: 0,
+        }
+    }
+
+    // What auto clustering draws this frame, or None if no cluster is drawn:
+    // the frame's own nodes and edges (listed as visible_frame does when
+    // `filtered`) faded out by cluster_blend, and the clusters and their
+    // bundled edges faded in over them
+    fn cluster_frame(&self, nodes: &[NodeData], edges: &[EdgeData], filtered: bool) -> Option<(Vec<NodeData>, Vec<EdgeData>)> {
+        let blend = self.cluster_blend;
+        let cache = self.cluster_cache.as_ref().filter(|_| blend > 0.0)?;
+        let clusters = &cache.clusters;
+        let (mut frame_nodes, mut frame_edges) = (Vec::new(), Vec::new());
+        if blend < 1.0 {
+            let (node_order, edge_order): (Box<dyn Iterator<Item = usize>>, Box<dyn Iterator<Item = usize>>) = if filtered {
+                (Box::new(self.frame_nodes()), Box::new(self.frame_edges()))
+            } else {
+                (Box::new(0..nodes.len()), Box::new(0..edges.len()))
+            };
+            let fade = 1.0 - blend;
+            frame_nodes.extend(node_order.zip(nodes).filter(|&(i, _)| self.passes_visibility_filter(i)).map(|(_, node)| NodeData { a: node.a * fade, ..*node }));
+            frame_edges.extend(edge_order.zip(edges).filter(|&(i, _)| self.edge_shown(i)).map(|(_, edge)| EdgeData { a: edge.a * fade, ..*edge }));
+        }
+
+        // Bundles take the mean color of their edges
+        for bundle in &clusters.bundles {
+            let mut color = [0.0f32; 4];
+            for edge in bundle.edges.iter().filter_map(|&i| self.edges.get(i as usize)) {
+                color = [color[0] + edge.r, color[1] + edge.g, color[2] + edge.b, color[3] + edge.a];
+            }
+            let [r, g, b, a] = color.map(|total| total / bundle.edges.len() as f32);
+            let [start, end] = bundle.clusters.map(|id| clusters.clusters[id as usize].center);
+            let share = bundle.edges.len() as f32 / clusters.largest_bundle as f32;
+            frame_edges.push(EdgeData {
+                x1: start[0],
+                y1: start[1],
+                x2: end[0],
+                y2: end[1],
+                r,
+                g,
+                b,
+                a: a * blend,
+                width: CLUSTER_EDGE_MAX_PX * share / self.camera_zoom,
+            });
+        }
+        let max_size = self.cluster_max_size(cache.pixel_radius);
+        frame_nodes.extend(clusters.clusters.iter().map(|cluster| {
+            let node = self.cluster_node(clusters, cluster, max_size);
+            NodeData { a: node.a * blend, ..node }
+        }));
+        Some((frame_nodes, frame_edges))
+    }
+
+    // Zoom at which the node and its neighbors, centered, span FOCUS_FILL of
+    // the view along whichever axis they fill first
+    fn neighborhood_zoom(&mut self, index: usize) -> f32 {
+        let (width, height) = self.renderer.surface_size().unwrap_or((0, 0));
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edge_indices));
+        let node = &self.nodes[index];
+        let mut reach = [node.size, node.size];
+        for &neighbor in adjacency.neighbors(index) {
+            if let Some(other) = self.nodes.get(neighbor as usize) {
+                reach[0] = reach[0].max((other.x - node.x).abs() + other.size);
+                reach[1] = reach[1].max((other.y - node.y).abs() + other.size);
+            }
+        }
+        FOCUS_FILL * (width as f32 * 0.5 / reach[0].max(1e-3)).min(height as f32 * 0.5 / reach[1].max(1e-3))
+    }
+
+    // Per-node damping follows the node count, new nodes using the global factor
+    fn resize_node_damping(&mut self) {
+        if !self.node_damping.is_empty() {
+            self.node_damping.resize(self.nodes.len(), 0.0);
+            self.damping_dirty = true;
+        }
+    }
+
+    // Idle motion phases too, so stale GPU entries don't reach new nodes
+    fn resize_node_phases(&mut self) {
+        if !self.node_phases.is_empty() {
+            self.node_phases.resize(self.nodes.len(), 0.0);
+            self.phases_dirty = true;
+        }
+    }
+
+    // Drop the cached adjacency after nodes or edges change
+    // Bookkeeping after nodes were added or removed
+    fn node_count_changed(&mut self) {
+        self.invalidate_topology();
+        self.invalidate_extent();
+        if let Some(groups) = &self.groups {
+            self.groups = Some(groups.resized(self.nodes.len()));
+            self.groups_dirty = true;
+        }
+        self.resize_node_damping();
+        self.resize_node_phases();
+        // User flags and node timestamps are per index, so they no longer line up
+        if !self.user_flags.is_empty() {
+            self.user_flags.clear();
+            self.user_flags_dirty = true;
+        }
+        if !self.node_layers.is_empty() {
+            self.node_layers.clear();
+            self.layers_changed();
+        }
+        if self.node_times.take().is_some() {
+            for node in &mut self.nodes {
+                node.flags &= !NODE_FLAG_OUTSIDE_TIME;
+            }
+        }
+    }
+
+    fn invalidate_topology(&mut self) {
+        self.adjacency = None;
+        self.topology_dirty = true;
+        // Edge instances carry their endpoints' user flags
+        self.user_flags_dirty = true;
+        if let Some(minimap) = &mut self.minimap {
+            minimap.stale = true;
+        }
+    }
+
+    fn read_shared_positions(&mut self) {
+        let Some(shared) = &mut self.shared_positions else {
+            return;
+        };
+        // One bulk copy, so the frame sees a single snapshot of the buffer
+        shared.view.copy_to(&mut shared.scratch);
+        for (node, position) in self.nodes.iter_mut().zip(shared.scratch.chunks_exact(2)) {
+            if position[0].is_finite() && position[1].is_finite() {
+                node.x = position[0];
+                node.y = position[1];
+            }
+        }
+
+        self.mark_physics_dirty();
+        self.refresh_edge_endpoints();
+        self.apply_drag_position();
+    }
+
+    // Swaps in the benchmark graph and a fresh simulation
+    fn begin_benchmark(&mut self, node_count: usize, edge_count: usize) -> BenchmarkBackup {
+        let backup = BenchmarkBackup {
+            graph: self.undo_snapshot(),
+            camera: (self.camera_position, self.camera_zoom),
+            groups: self.groups.take(),
+            node_damping: std::mem::take(&mut self.node_damping),
+            color_backup: self.color_backup.take(),
+            physics: self.physics.clone(),
+            layout_algorithm: self.layout_algorithm,
+            simulation_paused: self.simulation_paused,
+            simulation_running: self.simulation_running,
+            iteration: self.iteration,
+            interpolate_positions: self.interpolate_positions,
+            physics_accumulator: self.physics_accumulator,
+            // Events from the benchmark's simulation would confuse the app
+            simulation_event_callback: self.simulation_event_callback.take(),
+            shared_positions: self.shared_positions.take(),
+        };
+        self.damping_dirty = true;
+
+        let (width, height) = self.renderer.surface_size().unwrap_or((1024, 768));
+        let (nodes, edge_indices) = benchmark_graph(node_count, edge_count, width as f32, height as f32);
+        let edges = edge_indices
+            .iter()
+            .map(|_| EdgeData { x1: 0.0, y1: 0.0, x2: 0.0, y2: 0.0, r: 0.6, g: 0.6, b: 0.7, a: 0.4, width: 1.0 })
+            .collect();
+        self.restore_snapshot(UndoSnapshot {
+            nodes,
+            edges: std::sync::Arc::new(EdgeSnapshot { edges, edge_indices, edge_lengths: Vec::new(), edge_weights: Vec::new() }),
+            camera: Some(([0.0, 0.0], 1.0)),
+        });
+
+        // Never cools down, so every frame runs a tick
+        self.physics = PhysicsSettings {
+            gravity_strength: 0.02,
+            gravity_center: [width as f32 / 2.0, height as f32 / 2.0],
+            alpha_min: 0.0,
+            ..PhysicsSettings::default()
+        };
+        self.layout_algorithm = LayoutAlgorithm::SpringElectric;
+        self.simulation_paused = false;
+        self.simulation_running = false;
+        self.interpolate_positions = false;
+        self.physics_accumulator = 0.0;
+        backup
+    }
+
+    async fn benchmark_frames(&mut self, frames: u32) -> Result<JsValue, JsValue> {
+        let mut frame_ms = Vec::with_capacity(frames as usize);
+        let mut physics_ms = 0.0;
+        let mut upload_bytes = 0;
+        let start = now_ms();
+        for _ in 0..frames {
+            let frame_start = now_ms();
+            self.tick_physics()?;
+            physics_ms += now_ms() - frame_start;
+            // Render times are in seconds
+            self.render(now_ms() / 1000.0);
+            frame_ms.push(now_ms() - frame_start);
+            upload_bytes += self.renderer.instance_bytes();
+            // Lets readbacks resolve and the frame reach the screen
+            yield_to_event_loop().await?;
+        }
+        let elapsed_ms = now_ms() - start;
+
+        let average = |total: f64| total / frames as f64;
+        let mut sorted = frame_ms.clone();
+        sorted.sort_by(f64::total_cmp);
+        let p95 = sorted[((sorted.len() as f64 * 0.95).ceil() as usize).clamp(1, sorted.len()) - 1];
+
+        let report = js_sys::Object::new();
+        let set = |key: &str, value: JsValue| {
+            let _ = js_sys::Reflect::set(&report, &JsValue::from_str(key), &value);
+        };
+        set("node_count", JsValue::from(self.nodes.len() as u32));
+        set("edge_count", JsValue::from(self.edges.len() as u32));
+        set("frames", JsValue::from(frames));
+        set("gpu_physics", JsValue::from(self.renderer.has_gpu_physics()));
+        set("avg_frame_ms", JsValue::from(average(frame_ms.iter().sum())));
+        set("p95_frame_ms", JsValue::from(p95));
+        set("avg_physics_ms", JsValue::from(average(physics_ms)));
+        set("upload_bytes_per_frame", JsValue::from(average(upload_bytes as f64)));
+        set("fps", JsValue::from(frames as f64 * 1000.0 / elapsed_ms.max(1e-3)));
+        Ok(report.into())
+    }
+
+    fn end_benchmark(&mut self, backup: BenchmarkBackup) {
+        self.restore_snapshot(backup.graph);
+        (self.camera_position, self.camera_zoom) = backup.camera;
+        self.groups = backup.groups;
+        self.groups_dirty = self.groups.is_some();
+        self.node_damping = backup.node_damping;
+        self.damping_dirty = true;
+        self.color_backup = backup.color_backup;
+        self.physics = backup.physics;
+        self.layout_algorithm = backup.layout_algorithm;
+        self.simulation_paused = backup.simulation_paused;
+        self.simulation_running = backup.simulation_running;
+        self.iteration = backup.iteration;
+        self.interpolate_positions = backup.interpolate_positions;
+        self.physics_accumulator = backup.physics_accumulator;
+        self.simulation_event_callback = backup.simulation_event_callback;
+        self.shared_positions = backup.shared_positions;
+        self.forceatlas2.reset();
+    }
+
+    fn undo_snapshot(&mut self) -> UndoSnapshot {
+        self.sync_physics_positions();
+        // Shared with a neighbor when unchanged, copied only when not
+        let edges = match self.history.neighbors().find(|snapshot| snapshot.edges.holds(&self.edges, &self.edge_indices, &self.edge_lengths, &self.edge_weights)) {
+            Some(snapshot) => snapshot.edges.clone(),
+            None => std::sync::Arc::new(EdgeSnapshot {
+                edges: self.edges.clone(),
+                edge_indices: self.edge_indices.clone(),
+                edge_lengths: self.edge_lengths.clone(),
+                edge_weights: self.edge_weights.clone(),
+            }),
+        };
+
+        UndoSnapshot {
+            nodes: self.nodes.clone(),
+            edges,
+            camera: self.undo_camera.then_some((self.camera_position, self.camera_zoom)),
+        }
+    }
+
+    fn restore_snapshot(&mut self, snapshot: UndoSnapshot) {
+        let node_count = self.nodes.len();
+        self.nodes = snapshot.nodes;
+        let EdgeSnapshot { edges, edge_indices, edge_lengths, edge_weights } = &*snapshot.edges;
+        if *edge_indices != self.edge_indices || self.nodes.len() != node_count {
+            self.invalidate_topology();
+        }
+        if *edge_lengths != self.edge_lengths || *edge_weights != self.edge_weights {
+            self.topology_dirty = true;
+        }
+        // Arrow overrides are per index, so they no longer line up
+        if edges.len() != self.edges.len() {
+            self.clear_edge_arrows();
+        }
+        self.edges = edges.clone();
+        self.edge_indices = edge_indices.clone();
+        self.edge_lengths = edge_lengths.clone();
+        self.edge_weights = edge_weights.clone();
+        if let Some((position, zoom)) = snapshot.camera {
+            self.camera_tour = None;
+            self.camera_focus = None;
+            self.camera_position = position;
+            self.camera_zoom = zoom;
+        }
+
+        if self.nodes.len() != node_count {
+            if let Some(groups) = &self.groups {
+                self.groups = Some(groups.resized(self.nodes.len()));
+                self.groups_dirty = true;
+            }
+            self.resize_node_damping();
+            self.resize_node_phases();
+        }
+        // The snapshot's flags already say whether the dragged node is pinned
+        self.drag = None;
+        self.animation = None;
+        self.color_backup = None;
+        self.mark_physics_dirty();
+        self.refresh_edge_endpoints();
+    }
+}
+
+/// Checks for a usable GPU without creating a renderer. Resolves with
+/// `{webgpu, backend, compute, max_texture_size, max_buffer_size, adapter_name}`,
+/// where `backend` is "webgpu", "gl" or null when there is no adapter at all.
+#[wasm_bindgen]
+pub fn probe_capabilities() -> js_sys::Promise {
+    wasm_bindgen_futures::future_to_promise(async {
+        let capabilities = renderer::probe_capabilities().await;
+        Ok(capabilities_object(&capabilities, false))
+    })
+}
+
+// Calls `callback` with `payload` from a microtask, i.e. after the current call
+// into the renderer has returned and released it
+fn schedule_callback(callback: &js_sys::Function, payload: JsValue) {
+    let callback = callback.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(error) = callback.call1(&JsValue::NULL, &payload) {
+            log!("Event callback threw: {:?}", error);
+        }
+    });
+}
+
+fn capabilities_object(capabilities: &Capabilities, with_gpu_physics: bool) -> JsValue {
+    let object = js_sys::Object::new();
+    let set = |key: &str, value: JsValue| {
+        let _ = js_sys::Reflect::set(&object, &JsValue::from_str(key), &value);
+    };
+    set("webgpu", JsValue::from(capabilities.webgpu));
+    set("backend", capabilities.backend.map_or(JsValue::NULL, JsValue::from_str));
+    set("compute", JsValue::from(capabilities.compute));
+    set("max_texture_size", JsValue::from(capabilities.max_texture_size));
+    // As a number; buffer limits stay well below 2^53
+    set("max_buffer_size", JsValue::from(capabilities.max_buffer_size as f64));
+    set(
+        "adapter_name",
+        if capabilities.backend.is_some() { JsValue::from_str(&capabilities.adapter_name) } else { JsValue::NULL },
+    );
+    if with_gpu_physics {
+        set("gpu_physics", JsValue::from(capabilities.gpu_physics));
+        set("storage_rendering", JsValue::from(capabilities.storage_rendering));
+    }
+    object.into()
+}
+
+/// `count` colors (r, g, b, a each) from a categorical palette: "tableau10",
+/// "set3" or "pastel", repeating once the palette runs out.
+#[wasm_bindgen]
+pub fn get_palette(name: &str, count: u32) -> Result<Vec<f32>, JsValue> {
+    let palette = palettes::categorical(name).ok_or_else(|| JsValue::from_str(&format!("Unknown palette: {}", name)))?;
+    Ok((0..count as usize).flat_map(|i| color::rgb24(palette[i % palette.len()])).collect())
+}
+
+/// The color (r, g, b, a) at `t` in [0, 1] of a continuous colormap: "viridis",
+/// "plasma", "inferno" or "coolwarm", reversed with an "_r" suffix.
+#[wasm_bindgen]
+pub fn sample_colormap(name: &str, t: f32) -> Result<Vec<f32>, JsValue> {
+    let colormap = Colormap::parse(name).ok_or_else(|| JsValue::from_str(&format!("Unknown colormap: {}", name)))?;
+    Ok(colormap.sample(t).to_vec())
+}
+
+// Color strings passed to methods that report errors
+fn parse_color_arg(color: &str) -> Result<[f32; 4], JsValue> {
+    parse_css_color(color).ok_or_else(|| JsValue::from_str(&format!("Invalid color: {}", color)))
+}
+
+// The style object of set_preview_edge and set_preview_node; undefined or
+// null keeps the defaults
+struct PreviewStyle {
+    color: Option<[f32; 4]>,
+    numbers: Vec<(&'static str, f32)>,
+}
+
+impl PreviewStyle {
+    fn parse(style: &JsValue, keys: &[&'static str]) -> Result<Self, JsValue> {
+        let mut parsed = Self { color: None, numbers: Vec::new() };
+        if style.is_undefined() || style.is_null() {
+            return Ok(parsed);
+        }
+        if !style.is_object() {
+            return Err(JsValue::from_str("Style must be an object"));
+        }
+        for &key in keys {
+            let Some(value) = js_sys::Reflect::get(style, &JsValue::from_str(key)).ok().filter(|value| !value.is_undefined() && !value.is_null()) else {
+                continue;
+            };
+            if key == "color" {
+                let color = value.as_string().ok_or_else(|| JsValue::from_str("Style color must be a string"))?;
+                parsed.color = Some(parse_color_arg(&color)?);
+            } else {
+                let number = value
+                    .as_f64()
+                    .filter(|number| number.is_finite() && *number >= 0.0)
+                    .ok_or_else(|| JsValue::from_str(&format!("Style {} must be a non-negative number", key)))?;
+                parsed.numbers.push((key, number as f32));
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn number(&self, key: &str) -> Option<f32> {
+        self.numbers.iter().find(|(name, _)| *name == key).map(|&(_, number)| number)
+    }
+}
+
+fn parse_node_style(style: &JsValue) -> Result<NodeStyle, JsValue> {
+    if !style.is_object() {
+        return Err(JsValue::from_str("Style must be an object"));
+    }
+    let field = |key: &str| js_sys::Reflect::get(style, &JsValue::from_str(key)).ok().filter(|value| !value.is_undefined());
+    let number = |key: &str| -> Result<Option<f32>, JsValue> {
+        match field(key) {
+            None => Ok(None),
+            Some(value) => match value.as_f64() {
+                Some(number) if number.is_finite() && number >= 0.0 => Ok(Some(number as f32)),
+                _ => Err(JsValue::from_str(&format!("Style {} must be a non-negative number", key))),
+            },
+        }
+    };
+    let string = |key: &str| -> Result<Option<String>, JsValue> {
+        match field(key) {
+            None => Ok(None),
+            Some(value) => value.as_string().map(Some).ok_or_else(|| JsValue::from_str(&format!("Style {} must be a string", key))),
+        }
+    };
+
+    let mut node_style = NodeStyle::default();
+    if let Some(color) = string("color")? {
+        node_style.color = parse_color_arg(&color)?;
+        node_style.fields |= renderer::STYLE_FIELD_COLOR;
+    }
+    if let Some(size) = number("size")? {
+        node_style.size = size;
+        node_style.fields |= renderer::STYLE_FIELD_SIZE;
+    }
+    let border_color = string("border_color")?;
+    let border_width = number("border_width")?;
+    if border_color.is_some() || border_width.is_some() {
+        node_style.border_color = match border_color {
+            Some(color) => parse_color_arg(&color)?,
+            None => [1.0, 1.0, 1.0, 1.0],
+        };
+        node_style.border_width = border_width.unwrap_or(1.0);
+        node_style.fields |= renderer::STYLE_FIELD_BORDER;
+    }
+    if let Some(shape) = string("shape")? {
+        node_style.shape = match shape.as_str() {
+            "circle" => 0,
+            "square" => 1,
+            "diamond" => 2,
+            "triangle" => 3,
+            "squircle" => 4,
+            _ => return Err(JsValue::from_str(&format!("Unknown shape: {}", shape))),
+        };
+        node_style.fields |= renderer::STYLE_FIELD_SHAPE;
+    }
+    node_style.corner = match number("corner")? {
+        Some(corner) if corner <= 1.0 => corner,
+        Some(corner) => return Err(JsValue::from_str(&format!("Style corner must be between 0 and 1, got {}", corner))),
+        None => DEFAULT_SQUIRCLE_CORNER,
+    };
+    if let Some(glow) = number("glow")? {
+        node_style.glow = glow;
+        node_style.fields |= renderer::STYLE_FIELD_GLOW;
+    }
+    Ok(node_style)
+}
+
+// Milliseconds from performance.now(), or Date.now() where there is no performance
+fn now_ms() -> f64 {
+    js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("performance"))
+        .ok()
+        .and_then(|performance| {
+            let now = js_sys::Reflect::get(&performance, &JsValue::from_str("now")).ok()?;
+            now.dyn_into::<js_sys::Function>().ok()?.call0(&performance).ok()?.as_f64()
+        })
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+// A seeded random graph for run_benchmark: nodes scattered over the viewport and
+// edges between random distinct nodes (duplicates allowed)
+fn benchmark_graph(node_count: usize, edge_count: usize, width: f32, height: f32) -> (Vec<NodeData>, Vec<[u32; 2]>) {
+    let mut seed = BENCHMARK_SEED;
+    let mut random = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32
+    };
+
+    let nodes = (0..node_count)
+        .map(|_| NodeData {
+            x: width * random(),
+            y: height * random(),
+            vx: 0.0,
+            vy: 0.0,
+            fx: 0.0,
+            fy: 0.0,
+            r: 0.3 + 0.7 * random(),
+            g: 0.3 + 0.7 * random(),
+            b: 0.3 + 0.7 * random(),
+            a: 1.0,
+            size: 3.0,
+            mass: 1.0,
+            flags: 0,
+        })
+        .collect();
+    let count = node_count as u32;
+    let edges = (0..edge_count)
+        .filter(|_| count > 1)
+        .map(|_| {
+            let source = (random() * count as f32) as u32 % count;
+            // Offset by 1 to count - 1, so an edge never loops back to its source
+            let target = (source + 1 + (random() * (count - 1) as f32) as u32 % (count - 1)) % count;
+            [source, target]
+        })
+        .collect();
+    (nodes, edges)
+}
+
+// Resolves on a timer task (setTimeout 0), letting the browser process GPU
+// callbacks and input in between. Works in windows and workers.
+async fn yield_to_event_loop() -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .and_then(|set_timeout| set_timeout.dyn_into::<js_sys::Function>());
+        match set_timeout {
+            Ok(set_timeout) => {
+                let _ = set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0));
+            }
+            Err(_) => {
+                let _ = resolve.call0(&JsValue::NULL);
+            }
+        }
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.map(|_| ())
+}
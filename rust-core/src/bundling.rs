@@ -0,0 +1,267 @@
+// Force-directed edge bundling (Holten and van Wijk, "Force-Directed Edge
+// Bundling for Graph Visualization", 2009). Every edge becomes a polyline
+// whose inner points are pulled toward the matching points of compatible
+// edges, those running alike in direction, length and place, and kept smooth
+// by springs along the edge. Each cycle doubles the segments and halves the
+// step, so bundles form coarsely first and are then refined.
+
+use std::collections::HashMap;
+
+// Cycles of iterations; the segments per edge double from 2 after each, so
+// the result has 2^CYCLES segments
+pub const CYCLES: u32 = 4;
+// Spring stiffness along the edges, against the pull of compatible edges. The
+// share of the bend a point straightens per iteration is capped below 0.5,
+// where the springs would start to oscillate.
+const SPRING: f32 = 4.0;
+const MAX_SPRING_RATE: f32 = 0.4;
+// Distance the points move per iteration in the first cycle, in mean edge lengths
+const INITIAL_STEP: f32 = 0.04;
+// Compatible edges kept per edge, the most compatible first
+pub const MAX_COMPATIBLE: usize = 24;
+// Grid cells searched in each direction for compatible edges, in mean edge lengths
+const SEARCH_CELLS: i64 = 3;
+
+pub struct EdgeBundler {
+    ends: Vec<[[f32; 2]; 2]>, // source and target, divided by `scale`
+    scale: f32,               // the mean edge length
+    threshold: f32,
+    grid: HashMap<(i64, i64), Vec<u32>>, // edges by the cell of their midpoint
+    compatible: Vec<Vec<(u32, bool)>>,   // per edge, and whether the other edge runs the opposite way
+    points: Vec<[f32; 2]>,               // segments + 1 per edge, divided by `scale`
+    segments: usize,
+    step: f32,
+    cycle: u32,
+    cycle_iterations: u32,
+    iteration: u32, // of the current cycle
+    done: u32,      // iterations done over all cycles
+    total: u32,
+}
+
+impl EdgeBundler {
+    // `ends` are the edges' source and target positions; `iterations` the
+    // iterations of the first cycle, each later one running two thirds of the
+    // one before; `threshold` the compatibility (0 to 1) edges need to attract
+    pub fn new(ends: &[[[f32; 2]; 2]], iterations: u32, threshold: f32) -> Self {
+        let lengths: Vec<f32> = ends.iter().map(|&[a, b]| (b[0] - a[0]).hypot(b[1] - a[1])).filter(|length| length.is_finite()).collect();
+        let mean = lengths.iter().map(|&length| length as f64).sum::<f64>() / lengths.len().max(1) as f64;
+        let scale = if mean > 0.0 { mean as f32 } else { 1.0 };
+        let ends: Vec<[[f32; 2]; 2]> = ends.iter().map(|end| end.map(|point| point.map(|value| value / scale))).collect();
+
+        let mut grid: HashMap<(i64, i64), Vec<u32>> = HashMap::new();
+        for (index, end) in ends.iter().enumerate() {
+            if let Some(mid) = midpoint(end) {
+                grid.entry(cell_of(mid)).or_default().push(index as u32);
+            }
+        }
+        let points = ends.iter().flat_map(|&[a, b]| [a, lerp(a, b, 0.5), b]).collect();
+
+        let mut cycle_iterations = iterations;
+        let mut total = 0;
+        for _ in 0..CYCLES {
+            total += cycle_iterations;
+            cycle_iterations = cycle_iterations * 2 / 3;
+        }
+        Self {
+            compatible: Vec::with_capacity(ends.len()),
+            ends,
+            scale,
+            threshold,
+            grid,
+            points,
+            segments: 2,
+            step: INITIAL_STEP,
+            cycle: 0,
+            cycle_iterations: iterations,
+            iteration: 0,
+            done: 0,
+            total,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.compatible.len() == self.ends.len() && self.done == self.total
+    }
+
+    // Fraction of the work done, finding compatible edges counting as one iteration
+    pub fn progress(&self) -> f32 {
+        let compared = self.compatible.len() as f32 / self.ends.len().max(1) as f32;
+        (compared + self.done as f32) / (1 + self.total) as f32
+    }
+
+    pub fn segments(&self) -> usize {
+        self.segments
+    }
+
+    // The polylines in world units, segments + 1 points per edge, ends included
+    pub fn points(&self) -> Vec<[f32; 2]> {
+        self.points.iter().map(|point| point.map(|value| value * self.scale)).collect()
+    }
+
+    // Does about `work` units of work (edge pairs compared or point moves),
+    // at least one unit, finishing whatever iteration it starts
+    pub fn step(&mut self, work: usize) {
+        let mut spent = 0;
+        while spent < work.max(1) && self.compatible.len() < self.ends.len() {
+            spent += self.find_compatible(self.compatible.len()).max(1);
+        }
+        while spent < work.max(1) && self.done < self.total {
+            spent += self.iterate();
+        }
+    }
+
+    // Finds the edges compatible with `index` among those with midpoints
+    // nearby, returning the number compared
+    fn find_compatible(&mut self, index: usize) -> usize {
+        let end = self.ends[index];
+        let Some(mid) = midpoint(&end) else {
+            self.compatible.push(Vec::new());
+            return 1;
+        };
+        let (x, y) = cell_of(mid);
+        let mut found = Vec::new();
+        let mut compared = 0;
+        for cell_x in x - SEARCH_CELLS..=x + SEARCH_CELLS {
+            for cell_y in y - SEARCH_CELLS..=y + SEARCH_CELLS {
+                for &other in self.grid.get(&(cell_x, cell_y)).into_iter().flatten() {
+                    if other as usize == index {
+                        continue;
+                    }
+                    compared += 1;
+                    let other_end = self.ends[other as usize];
+                    let score = compatibility(&end, &other_end);
+                    if score >= self.threshold {
+                        let [a, b] = [direction(&end), direction(&other_end)];
+                        found.push((other, a[0] * b[0] + a[1] * b[1] < 0.0, score));
+                    }
+                }
+            }
+        }
+        if found.len() > MAX_COMPATIBLE {
+            found.select_nth_unstable_by(MAX_COMPATIBLE, |a, b| b.2.total_cmp(&a.2));
+            found.truncate(MAX_COMPATIBLE);
+        }
+        self.compatible.push(found.into_iter().map(|(other, flipped, _)| (other, flipped)).collect());
+        compared
+    }
+
+    // Moves every inner point once from the previous positions, then starts
+    // the next cycle if this one is over; returns the point moves made
+    fn iterate(&mut self) -> usize {
+        let stride = self.segments + 1;
+        let mut next = self.points.clone();
+        let mut moves = 0;
+        for (edge, compatible) in self.compatible.iter().enumerate() {
+            let [a, b] = self.ends[edge];
+            let length = (b[0] - a[0]).hypot(b[1] - a[1]);
+            if length <= 0.0 || !length.is_finite() {
+                continue;
+            }
+            let spring_rate = (self.step * SPRING * self.segments as f32 / length).min(MAX_SPRING_RATE);
+            let points = &self.points[edge * stride..(edge + 1) * stride];
+            for i in 1..self.segments {
+                let point = points[i];
+                let bend = [points[i - 1][0] + points[i + 1][0] - 2.0 * point[0], points[i - 1][1] + points[i + 1][1] - 2.0 * point[1]];
+                // The mean direction to the matching points, each pull easing
+                // off within a step so points settle instead of overshooting
+                let mut pull = [0.0f32; 2];
+                for &(other, flipped) in compatible {
+                    let j = if flipped { self.segments - i } else { i };
+                    let target = self.points[other as usize * stride + j];
+                    let (dx, dy) = (target[0] - point[0], target[1] - point[1]);
+                    let distance = dx.hypot(dy).max(self.step);
+                    pull = [pull[0] + dx / distance, pull[1] + dy / distance];
+                }
+                let count = compatible.len().max(1) as f32;
+                next[edge * stride + i] = [
+                    point[0] + spring_rate * bend[0] + self.step * pull[0] / count,
+                    point[1] + spring_rate * bend[1] + self.step * pull[1] / count,
+                ];
+                moves += compatible.len() + 1;
+            }
+        }
+        self.points = next;
+        self.iteration += 1;
+        self.done += 1;
+
+        if self.iteration >= self.cycle_iterations && self.cycle + 1 < CYCLES {
+            self.subdivide();
+            self.cycle += 1;
+            self.iteration = 0;
+            self.cycle_iterations = self.cycle_iterations * 2 / 3;
+            self.step *= 0.5;
+            // Cycles left without iterations still subdivide
+            while self.cycle_iterations == 0 && self.cycle + 1 < CYCLES {
+                self.subdivide();
+                self.cycle += 1;
+            }
+        }
+        moves.max(1)
+    }
+
+    // Splits every segment in two at its midpoint
+    fn subdivide(&mut self) {
+        let stride = self.segments + 1;
+        let mut points = Vec::with_capacity(self.ends.len() * (self.segments * 2 + 1));
+        for edge in self.points.chunks_exact(stride) {
+            points.push(edge[0]);
+            for pair in edge.windows(2) {
+                points.push(lerp(pair[0], pair[1], 0.5));
+                points.push(pair[1]);
+            }
+        }
+        self.points = points;
+        self.segments *= 2;
+    }
+}
+
+fn lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+// None for edges with a non-finite end
+fn midpoint(end: &[[f32; 2]; 2]) -> Option<[f32; 2]> {
+    let mid = lerp(end[0], end[1], 0.5);
+    (mid[0].is_finite() && mid[1].is_finite()).then_some(mid)
+}
+
+fn cell_of(point: [f32; 2]) -> (i64, i64) {
+    (point[0].floor() as i64, point[1].floor() as i64)
+}
+
+fn direction(end: &[[f32; 2]; 2]) -> [f32; 2] {
+    [end[1][0] - end[0][0], end[1][1] - end[0][1]]
+}
+
+// The product of the paper's angle, scale, position and visibility
+// compatibilities, 0 for zero-length edges
+fn compatibility(p: &[[f32; 2]; 2], q: &[[f32; 2]; 2]) -> f32 {
+    let (dp, dq) = (direction(p), direction(q));
+    let (length_p, length_q) = (dp[0].hypot(dp[1]), dq[0].hypot(dq[1]));
+    if length_p <= 0.0 || length_q <= 0.0 {
+        return 0.0;
+    }
+    let angle = ((dp[0] * dq[0] + dp[1] * dq[1]) / (length_p * length_q)).abs();
+    let mean = (length_p + length_q) * 0.5;
+    let scale = 2.0 / (mean / length_p.min(length_q) + length_p.max(length_q) / mean);
+    let (mid_p, mid_q) = (lerp(p[0], p[1], 0.5), lerp(q[0], q[1], 0.5));
+    let position = mean / (mean + (mid_p[0] - mid_q[0]).hypot(mid_p[1] - mid_q[1]));
+    angle * scale * position * visibility(p, q).min(visibility(q, p))
+}
+
+// How much of `p`, seen from `q`: 1 when q's projection onto p's line is
+// centered on p's midpoint, falling to 0 as it moves off
+fn visibility(p: &[[f32; 2]; 2], q: &[[f32; 2]; 2]) -> f32 {
+    let project = |point: [f32; 2]| {
+        let d = direction(p);
+        let t = ((point[0] - p[0][0]) * d[0] + (point[1] - p[0][1]) * d[1]) / (d[0] * d[0] + d[1] * d[1]);
+        lerp(p[0], p[1], t)
+    };
+    let (start, end) = (project(q[0]), project(q[1]));
+    let span = (end[0] - start[0]).hypot(end[1] - start[1]);
+    if span <= 0.0 {
+        return 0.0;
+    }
+    let (mid_p, mid_i) = (lerp(p[0], p[1], 0.5), lerp(start, end, 0.5));
+    (1.0 - 2.0 * (mid_p[0] - mid_i[0]).hypot(mid_p[1] - mid_i[1]) / span).max(0.0)
+}
@@ -0,0 +1,122 @@
+// Screen-space clustering for zoomed-out views. Nodes are binned into a grid
+// aligned to the world origin, with cells a fixed number of pixels across at
+// the zoom of their bucket, so the clusters change with the zoom bucket but
+// not while panning.
+
+use std::collections::HashMap;
+
+// Zoom buckets per doubling of the zoom
+pub const BUCKETS_PER_OCTAVE: f32 = 2.0;
+
+// The bucket `zoom` falls in
+pub fn zoom_bucket(zoom: f32) -> i32 {
+    (zoom.max(f32::MIN_POSITIVE).log2() * BUCKETS_PER_OCTAVE).floor() as i32
+}
+
+// The lowest zoom of `bucket`, which cells are sized for
+pub fn bucket_zoom(bucket: i32) -> f32 {
+    (bucket as f32 / BUCKETS_PER_OCTAVE).exp2()
+}
+
+pub struct Cluster {
+    pub center: [f32; 2], // mean of the member positions
+    pub members: Vec<u32>,
+}
+
+// The edges between two clusters, drawn as one
+pub struct Bundle {
+    pub clusters: [u32; 2],
+    pub edges: Vec<u32>,
+}
+
+pub struct Clusters {
+    pub clusters: Vec<Cluster>,
+    pub bundles: Vec<Bundle>,
+    pub largest: usize,        // member count of the largest cluster
+    pub largest_bundle: usize, // edge count of the largest bundle
+}
+
+impl Clusters {
+    // Bins the (node index, position) pairs into square cells `cell` world
+    // units across, then bundles `edges` (by edge index) between clusters.
+    // Edges with an endpoint left out, and edges within one cluster, are
+    // dropped. Clusters and bundles are listed in the order first seen.
+    pub fn build(nodes: impl Iterator<Item = (usize, [f32; 2])>, node_count: usize, edges: impl Iterator<Item = (usize, [u32; 2])>, cell: f32) -> Self {
+        let mut node_cluster = vec![u32::MAX; node_count];
+        let mut cells: HashMap<(i64, i64), u32> = HashMap::new();
+        let mut clusters: Vec<Cluster> = Vec::new();
+        let mut sums: Vec<[f64; 2]> = Vec::new();
+        for (index, [x, y]) in nodes {
+            if index >= node_count || !x.is_finite() || !y.is_finite() {
+                continue;
+            }
+            let key = ((x / cell).floor() as i64, (y / cell).floor() as i64);
+            let id = *cells.entry(key).or_insert_with(|| {
+                clusters.push(Cluster { center: [0.0; 2], members: Vec::new() });
+                sums.push([0.0; 2]);
+                clusters.len() as u32 - 1
+            });
+            clusters[id as usize].members.push(index as u32);
+            sums[id as usize][0] += x as f64;
+            sums[id as usize][1] += y as f64;
+            node_cluster[index] = id;
+        }
+        for (cluster, sum) in clusters.iter_mut().zip(&sums) {
+            let count = cluster.members.len() as f64;
+            cluster.center = [(sum[0] / count) as f32, (sum[1] / count) as f32];
+        }
+
+        let mut pairs: HashMap<[u32; 2], usize> = HashMap::new();
+        let mut bundles: Vec<Bundle> = Vec::new();
+        for (index, [source, target]) in edges {
+            let (Some(&a), Some(&b)) = (node_cluster.get(source as usize), node_cluster.get(target as usize)) else {
+                continue;
+            };
+            if a == u32::MAX || b == u32::MAX || a == b {
+                continue;
+            }
+            let pair = [a.min(b), a.max(b)];
+            let slot = *pairs.entry(pair).or_insert_with(|| {
+                bundles.push(Bundle { clusters: pair, edges: Vec::new() });
+                bundles.len() - 1
+            });
+            bundles[slot].edges.push(index as u32);
+        }
+
+        Self {
+            largest: clusters.iter().map(|cluster| cluster.members.len()).max().unwrap_or(0),
+            largest_bundle: bundles.iter().map(|bundle| bundle.edges.len()).max().unwrap_or(0),
+            clusters,
+            bundles,
+        }
+    }
+
+    // Where [0, 1] the cluster sits between a single node and the largest
+    // cluster, on a log scale so a few huge clusters don't wash out the rest
+    pub fn relative_size(&self, cluster: &Cluster) -> f32 {
+        if self.largest <= 1 {
+            return 0.0;
+        }
+        (cluster.members.len() as f32).ln() / (self.largest as f32).ln()
+    }
+}
+
+// For decimation: of the nodes given as (screen position, size) in draw order,
+// the positions (in that order) of one per square cell `cell` pixels across,
+// the largest in its cell and of equals the last drawn, since it's on top
+pub fn representatives(nodes: impl Iterator<Item = ([f32; 2], f32)>, cell: f32) -> Vec<usize> {
+    let mut best: HashMap<(i64, i64), (usize, f32)> = HashMap::new();
+    for (position, ([x, y], size)) in nodes.enumerate() {
+        if !x.is_finite() || !y.is_finite() {
+            continue;
+        }
+        let key = ((x / cell).floor() as i64, (y / cell).floor() as i64);
+        let entry = best.entry(key).or_insert((position, size));
+        if size >= entry.1 {
+            *entry = (position, size);
+        }
+    }
+    let mut kept: Vec<usize> = best.into_values().map(|(position, _)| position).collect();
+    kept.sort_unstable();
+    kept
+}
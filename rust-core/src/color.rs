@@ -0,0 +1,359 @@
+// Color strings from the public API: hex notations, rgb()/hsl() functional
+// notations and CSS named colors
+
+// CSS named colors, sorted by name for binary search
+const NAMED_COLORS: [(&str, u32); 148] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("rebeccapurple", 0x663399),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+// Parses a CSS color: "#RGB", "#RGBA", "#RRGGBB", "#RRGGBBAA" (the "#" is
+// optional), "rgb()"/"rgba()"/"hsl()"/"hsla()" in the comma or space separated
+// syntax, or a named color like "tomato", "transparent". Case-insensitive,
+// surrounding whitespace is ignored and out-of-range components are clamped.
+pub fn parse_css_color(input: &str) -> Option<[f32; 4]> {
+    let input = input.trim();
+    if let Some(hex) = input.strip_prefix('#') {
+        return parse_hex_color(hex);
+    }
+
+    let name = input.to_ascii_lowercase();
+    if let Some((function, arguments)) = name.strip_suffix(')').and_then(|call| call.split_once('(')) {
+        return parse_functional_color(function.trim_end(), arguments);
+    }
+
+    if name == "transparent" {
+        return Some([0.0, 0.0, 0.0, 0.0]);
+    }
+    match NAMED_COLORS.binary_search_by(|&(candidate, _)| candidate.cmp(name.as_str())) {
+        Ok(index) => Some(rgb24(NAMED_COLORS[index].1)),
+        // Bare hex digits, as accepted before named colors existed
+        Err(_) => parse_hex_color(input),
+    }
+}
+
+// Opaque color from 0xRRGGBB
+pub fn rgb24(rgb: u32) -> [f32; 4] {
+    [(rgb >> 16) as f32 / 255.0, ((rgb >> 8) & 0xff) as f32 / 255.0, (rgb & 0xff) as f32 / 255.0, 1.0]
+}
+
+// Hex digits without the "#": 3 or 4 short digits, or 6 or 8 full ones
+fn parse_hex_color(hex: &str) -> Option<[f32; 4]> {
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok();
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let channels: Vec<u8> = match hex.len() {
+        // Short forms repeat each digit: "f80" is "ff8800"
+        3 | 4 => (0..hex.len()).map(|i| digit(i).map(|value| value * 17)).collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len()).step_by(2).map(pair).collect::<Option<_>>()?,
+        _ => return None,
+    };
+
+    let alpha = channels.get(3).copied().unwrap_or(255);
+    Some([
+        channels[0] as f32 / 255.0,
+        channels[1] as f32 / 255.0,
+        channels[2] as f32 / 255.0,
+        alpha as f32 / 255.0,
+    ])
+}
+
+// The arguments of rgb()/rgba()/hsl()/hsla(), either "a, b, c[, alpha]" or
+// "a b c[ / alpha]". The "a" variants are aliases, as in current CSS.
+fn parse_functional_color(function: &str, arguments: &str) -> Option<[f32; 4]> {
+    let (channels, alpha): (Vec<&str>, Option<&str>) = if arguments.contains(',') {
+        let mut parts: Vec<&str> = arguments.split(',').map(str::trim).collect();
+        let alpha = if parts.len() == 4 { parts.pop() } else { None };
+        (parts, alpha)
+    } else {
+        let (channels, alpha) = match arguments.split_once('/') {
+            Some((channels, alpha)) => (channels, Some(alpha.trim())),
+            None => (arguments, None),
+        };
+        (channels.split_whitespace().collect(), alpha)
+    };
+    if channels.len() != 3 {
+        return None;
+    }
+
+    let alpha = match alpha {
+        Some(alpha) => parse_fraction(alpha, 1.0)?,
+        None => 1.0,
+    };
+
+    let [r, g, b] = match function {
+        "rgb" | "rgba" => [
+            parse_fraction(channels[0], 255.0)?,
+            parse_fraction(channels[1], 255.0)?,
+            parse_fraction(channels[2], 255.0)?,
+        ],
+        "hsl" | "hsla" => hsl_to_rgb(parse_hue(channels[0])?, parse_fraction(channels[1], 100.0)?, parse_fraction(channels[2], 100.0)?),
+        _ => return None,
+    };
+    Some([r, g, b, alpha])
+}
+
+// A number out of `full`, or a percentage, as a fraction clamped to [0, 1]
+fn parse_fraction(value: &str, full: f32) -> Option<f32> {
+    let fraction = match value.strip_suffix('%') {
+        Some(percent) => percent.trim_end().parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()? / full,
+    };
+    fraction.is_finite().then(|| fraction.clamp(0.0, 1.0))
+}
+
+// Hue in turns, from degrees (the default) or a deg/rad/grad/turn value
+fn parse_hue(value: &str) -> Option<f32> {
+    let units = [("deg", 1.0 / 360.0), ("grad", 1.0 / 400.0), ("rad", 1.0 / std::f32::consts::TAU), ("turn", 1.0)];
+    let (number, per_turn) = units
+        .iter()
+        .find_map(|&(unit, per_turn)| value.strip_suffix(unit).map(|number| (number, per_turn)))
+        .unwrap_or((value, 1.0 / 360.0));
+    let turns = number.trim_end().parse::<f32>().ok()? * per_turn;
+    turns.is_finite().then(|| turns.rem_euclid(1.0))
+}
+
+// HSL to RGB as in the CSS Color specification; hue in turns, the rest in [0, 1]
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let channel = |n: f32| {
+        let k = (n + hue * 12.0) % 12.0;
+        let a = saturation * lightness.min(1.0 - lightness);
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    [channel(0.0), channel(8.0), channel(4.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_color(input: &str, expected: [f32; 4]) {
+        let color = parse_css_color(input).unwrap_or_else(|| panic!("{input:?} didn't parse"));
+        assert!(color.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-3), "{input:?}: {color:?}, expected {expected:?}");
+    }
+
+    #[test]
+    fn parses_css_colors() {
+        let half = 128.0 / 255.0;
+        let cases = [
+            ("#f80", [1.0, 0.533, 0.0, 1.0]),
+            ("#f808", [1.0, 0.533, 0.0, 0.533]),
+            ("#FF8000", [1.0, half, 0.0, 1.0]),
+            ("#ff800080", [1.0, half, 0.0, half]),
+            ("ff8000", [1.0, half, 0.0, 1.0]),
+            ("rgb(255, 128, 0)", [1.0, half, 0.0, 1.0]),
+            ("rgba(255, 128, 0, 0.5)", [1.0, half, 0.0, 0.5]),
+            ("rgb(100%, 50%, 0%)", [1.0, 0.5, 0.0, 1.0]),
+            ("rgba(100%,50%,0%,25%)", [1.0, 0.5, 0.0, 0.25]),
+            ("rgb(255 128 0 / 50%)", [1.0, half, 0.0, 0.5]),
+            ("RGB(300, -20, 0)", [1.0, 0.0, 0.0, 1.0]),
+            ("tomato", [1.0, 99.0 / 255.0, 71.0 / 255.0, 1.0]),
+            ("RebeccaPurple", [0.4, 0.2, 0.6, 1.0]),
+            ("aliceblue", [240.0 / 255.0, 248.0 / 255.0, 1.0, 1.0]),
+            ("yellowgreen", [154.0 / 255.0, 205.0 / 255.0, 50.0 / 255.0, 1.0]),
+            ("transparent", [0.0, 0.0, 0.0, 0.0]),
+            ("  #f80\t", [1.0, 0.533, 0.0, 1.0]),
+            ("\n rgb( 255 , 128 , 0 ) ", [1.0, half, 0.0, 1.0]),
+            (" red ", [1.0, 0.0, 0.0, 1.0]),
+        ];
+        for (input, expected) in cases {
+            assert_color(input, expected);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_colors() {
+        let cases = [
+            "", "#", "#ff", "#fffff", "#fffffffff", "#ggg", "# fff", "notacolor", "rgb(1, 2)", "rgb(1, 2, 3, 4, 5)", "rgb(a, b, c)",
+            "rgb(1, 2, 3", "cmyk(1, 2, 3)", "rgb(NaN, 0, 0)",
+        ];
+        for input in cases {
+            assert_eq!(parse_css_color(input), None, "{input:?}");
+        }
+    }
+
+    #[test]
+    fn converts_hsl() {
+        let cases = [
+            ("hsl(210, 80%, 52%)", [0.136, 0.52, 0.904, 1.0]),
+            ("hsla(210 80% 52% / 0.5)", [0.136, 0.52, 0.904, 0.5]),
+            ("hsl(0, 100%, 50%)", [1.0, 0.0, 0.0, 1.0]),
+            ("hsl(120, 100%, 25%)", [0.0, 0.5, 0.0, 1.0]),
+            ("hsl(45, 0%, 40%)", [0.4, 0.4, 0.4, 1.0]),
+            // Hue units
+            ("hsl(0.5turn, 100%, 50%)", [0.0, 1.0, 1.0, 1.0]),
+            ("hsl(200grad, 100%, 50%)", [0.0, 1.0, 1.0, 1.0]),
+            ("hsl(3.14159rad, 100%, 50%)", [0.0, 1.0, 1.0, 1.0]),
+            ("hsl(180deg, 100%, 50%)", [0.0, 1.0, 1.0, 1.0]),
+        ];
+        for (input, expected) in cases {
+            assert_color(input, expected);
+        }
+    }
+
+    #[test]
+    fn hsl_hue_wraps_around() {
+        for hue in ["-150", "570", "-510", "930", "-0.41666turn"] {
+            assert_color(&format!("hsl({hue}, 80%, 52%)"), [0.136, 0.52, 0.904, 1.0]);
+        }
+        assert_color("hsl(360, 100%, 50%)", [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn hsl_clamps_saturation_and_lightness() {
+        assert_color("hsl(0, 150%, 50%)", [1.0, 0.0, 0.0, 1.0]);
+        assert_color("hsl(0, -20%, 50%)", [0.5, 0.5, 0.5, 1.0]);
+        assert_color("hsl(210, 80%, 120%)", [1.0, 1.0, 1.0, 1.0]);
+        assert_color("hsl(210, 80%, -5%)", [0.0, 0.0, 0.0, 1.0]);
+        assert_color("hsla(210, 80%, 52%, 2)", [0.136, 0.52, 0.904, 1.0]);
+    }
+}
@@ -0,0 +1,337 @@
+// Canvas listeners for FastGraphRenderer::on, the touch gestures, double-click
+// focusing, observe_resize and minimap navigation. The listeners only record
+// what happened: the renderer drains them once per frame and picks against
+// that frame's positions, so a burst of mousemoves costs a single pick.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, MouseEvent, PointerEvent, ResizeObserver, ResizeObserverEntry};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    NodeClick,
+    NodeHover,
+    EdgeHover,
+    EdgeClick,
+    BackgroundClick,
+    ViewportChange,
+}
+
+impl EventKind {
+    pub fn parse(event: &str) -> Option<Self> {
+        match event {
+            "nodeClick" => Some(Self::NodeClick),
+            "nodeHover" => Some(Self::NodeHover),
+            "edgeHover" => Some(Self::EdgeHover),
+            "edgeClick" => Some(Self::EdgeClick),
+            "backgroundClick" => Some(Self::BackgroundClick),
+            "viewportChange" => Some(Self::ViewportChange),
+            _ => None,
+        }
+    }
+
+    // Whether the event comes from the mouse, and so needs the canvas listeners
+    pub fn is_pointer(self) -> bool {
+        self != Self::ViewportChange
+    }
+}
+
+type MouseListener = Closure<dyn FnMut(MouseEvent)>;
+type PointerListener = Closure<dyn FnMut(PointerEvent)>;
+
+// A touch that moves less than this (in CSS pixels) before lifting is a tap
+const TAP_SLOP: f32 = 8.0;
+
+pub enum PointerInput {
+    Click(MouseEvent),
+    DoubleClick(MouseEvent),
+    Move(MouseEvent),
+    Leave(MouseEvent),
+    // Touch pointers only; mouse and pen input stays with the events above
+    TouchDown(PointerEvent),
+    TouchMove(PointerEvent),
+    TouchUp(PointerEvent),
+    TouchCancel(PointerEvent),
+}
+
+pub struct CanvasListeners {
+    canvas: HtmlCanvasElement,
+    queue: Rc<RefCell<Vec<PointerInput>>>,
+    closures: Vec<(&'static str, MouseListener)>,
+    touch_action: Option<String>, // the canvas style replaced while handling touches
+}
+
+impl CanvasListeners {
+    pub fn attach(canvas: &HtmlCanvasElement, mouse: bool, touch: bool, double_click: bool) -> Result<Self, JsValue> {
+        let queue = Rc::new(RefCell::new(Vec::new()));
+        let mut listeners = Self { canvas: canvas.clone(), queue, closures: Vec::new(), touch_action: None };
+        // Taps are also reported as clicks, so gestures need the click listener
+        if mouse || touch {
+            listeners.listen("click", |event| Some(PointerInput::Click(event)))?;
+        }
+        if double_click {
+            listeners.listen("dblclick", |event| Some(PointerInput::DoubleClick(event)))?;
+        }
+        if mouse {
+            listeners.listen("mousemove", |event| Some(PointerInput::Move(event)))?;
+            listeners.listen("mouseleave", |event| Some(PointerInput::Leave(event)))?;
+        }
+        if touch {
+            listeners.listen("pointerdown", |event| touch_event(event).map(PointerInput::TouchDown))?;
+            listeners.listen("pointermove", |event| touch_event(event).map(PointerInput::TouchMove))?;
+            listeners.listen("pointerup", |event| touch_event(event).map(PointerInput::TouchUp))?;
+            listeners.listen("pointercancel", |event| touch_event(event).map(PointerInput::TouchCancel))?;
+            // Keeps the browser from scrolling or zooming the page instead
+            let style = canvas.style();
+            listeners.touch_action = Some(style.get_property_value("touch-action")?);
+            style.set_property("touch-action", "none")?;
+        }
+        Ok(listeners)
+    }
+
+    pub fn handles_touch(&self) -> bool {
+        self.touch_action.is_some()
+    }
+
+    pub fn handles_mouse(&self) -> bool {
+        self.closures.iter().any(|&(event, _)| event == "mousemove")
+    }
+
+    pub fn handles_double_click(&self) -> bool {
+        self.closures.iter().any(|&(event, _)| event == "dblclick")
+    }
+
+    // The queued inputs, oldest first
+    pub fn take_inputs(&self) -> Vec<PointerInput> {
+        std::mem::take(&mut *self.queue.borrow_mut())
+    }
+
+    pub fn pixel_scale(&self) -> [f32; 2] {
+        pixel_scale(&self.canvas)
+    }
+
+    fn listen(&mut self, event: &'static str, input: fn(MouseEvent) -> Option<PointerInput>) -> Result<(), JsValue> {
+        let queue = self.queue.clone();
+        let canvas = self.canvas.clone();
+        let closure = MouseListener::new(move |event: MouseEvent| {
+            let Some(input) = input(event) else {
+                return;
+            };
+            // Keeps the moves of a finger that slides off the canvas coming
+            if let PointerInput::TouchDown(event) = &input {
+                let _ = canvas.set_pointer_capture(event.pointer_id());
+            }
+            let mut queue = queue.borrow_mut();
+            // Only the latest position matters until the next frame
+            if let (PointerInput::Move(_), Some(PointerInput::Move(_))) = (&input, queue.last()) {
+                queue.pop();
+            }
+            queue.push(input);
+        });
+        self.canvas.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())?;
+        self.closures.push((event, closure));
+        Ok(())
+    }
+}
+
+impl Drop for CanvasListeners {
+    fn drop(&mut self) {
+        for (event, closure) in &self.closures {
+            let _ = self.canvas.remove_event_listener_with_callback(event, closure.as_ref().unchecked_ref());
+        }
+        if let Some(touch_action) = &self.touch_action {
+            let _ = self.canvas.style().set_property("touch-action", touch_action);
+        }
+    }
+}
+
+// Canvas pixels per CSS pixel; mouse offsets are in CSS pixels
+pub fn pixel_scale(canvas: &HtmlCanvasElement) -> [f32; 2] {
+    let scale = |pixels: u32, css: i32| if css > 0 { pixels as f32 / css as f32 } else { 1.0 };
+    [scale(canvas.width(), canvas.client_width()), scale(canvas.height(), canvas.client_height())]
+}
+
+fn touch_event(event: MouseEvent) -> Option<PointerEvent> {
+    let event: PointerEvent = event.dyn_into().ok()?;
+    (event.pointer_type() == "touch").then_some(event)
+}
+
+// How the camera should follow the fingers: move the view by `pan` and scale
+// the zoom by `zoom` around `anchor`, in CSS pixels from the canvas corner
+pub struct CameraChange {
+    pub pan: [f32; 2],
+    pub zoom: f32,
+    pub anchor: [f32; 2],
+}
+
+// The fingers on the canvas. One finger pans; two or more also pinch-zoom
+// around their midpoint. Each move is compared against the same set of fingers
+// just before it, so fingers landing or lifting mid-gesture never make the
+// camera jump. A finger that stays within TAP_SLOP until it lifts, with no other
+// finger down meanwhile, is a tap and leaves the camera alone.
+#[derive(Default)]
+pub struct TouchGesture {
+    touches: Vec<(i32, [f32; 2])>, // pointer id and position, in touch-down order
+    tap: Option<(i32, [f32; 2])>,  // the finger that may still become a tap, and where it landed
+}
+
+impl TouchGesture {
+    pub fn down(&mut self, id: i32, position: [f32; 2]) {
+        self.touches.retain(|&(touch, _)| touch != id);
+        self.touches.push((id, position));
+        self.tap = (self.touches.len() == 1).then_some((id, position));
+    }
+
+    pub fn moved(&mut self, id: i32, position: [f32; 2]) -> Option<CameraChange> {
+        let slot = self.touches.iter().position(|&(touch, _)| touch == id)?;
+        if let Some((_, start)) = self.tap {
+            if distance(start, position) <= TAP_SLOP {
+                // Still a possible tap; the camera only starts moving past the slop
+                self.touches[slot].1 = position;
+                return None;
+            }
+            self.tap = None;
+        }
+
+        let (before_center, before_spread) = centroid_and_spread(&self.touches);
+        self.touches[slot].1 = position;
+        let (after_center, after_spread) = centroid_and_spread(&self.touches);
+        let zoom = if self.touches.len() > 1 && before_spread > 0.0 { after_spread / before_spread } else { 1.0 };
+        Some(CameraChange {
+            pan: [after_center[0] - before_center[0], after_center[1] - before_center[1]],
+            zoom,
+            anchor: after_center,
+        })
+    }
+
+    // True if the lifted finger ends a tap
+    pub fn up(&mut self, id: i32) -> bool {
+        self.touches.retain(|&(touch, _)| touch != id);
+        self.tap.take_if(|&mut (touch, _)| touch == id).is_some()
+    }
+
+    pub fn cancel(&mut self, id: i32) {
+        self.touches.retain(|&(touch, _)| touch != id);
+        self.tap = None;
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn distance(a: [f32; 2], b: [f32; 2]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+// Mean position of the touches and their mean distance from it
+fn centroid_and_spread(touches: &[(i32, [f32; 2])]) -> ([f32; 2], f32) {
+    let count = touches.len().max(1) as f32;
+    let sum = touches.iter().fold([0.0, 0.0], |sum, &(_, p)| [sum[0] + p[0], sum[1] + p[1]]);
+    let center = [sum[0] / count, sum[1] / count];
+    let spread = touches.iter().map(|&(_, p)| distance(p, center)).sum::<f32>() / count;
+    (center, spread)
+}
+
+// Watches the canvas's CSS size
+pub struct ResizeWatcher {
+    observer: ResizeObserver,
+    size: Rc<Cell<Option<[f32; 2]>>>, // latest size not yet taken, in CSS pixels
+    _callback: Closure<dyn FnMut(js_sys::Array)>,
+}
+
+impl ResizeWatcher {
+    // The observer reports the current size right away
+    pub fn observe(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
+        let size = Rc::new(Cell::new(None));
+        let latest = size.clone();
+        let callback = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+            if let Some(entry) = entries.iter().filter_map(|entry| entry.dyn_into::<ResizeObserverEntry>().ok()).next_back() {
+                let rect = entry.content_rect();
+                latest.set(Some([rect.width() as f32, rect.height() as f32]));
+            }
+        });
+        let observer = ResizeObserver::new(callback.as_ref().unchecked_ref())?;
+        observer.observe(canvas);
+        Ok(Self { observer, size, _callback: callback })
+    }
+
+    // The size reported since the last call, if any
+    pub fn take_size(&self) -> Option<[f32; 2]> {
+        self.size.take()
+    }
+}
+
+impl Drop for ResizeWatcher {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+// Presses and drags on a minimap canvas, with any kind of pointer
+pub struct MinimapListeners {
+    canvas: HtmlCanvasElement,
+    target: Rc<Cell<Option<[f32; 2]>>>, // where the pointer pressed or dragged to, not yet taken
+    closures: Vec<(&'static str, PointerListener)>,
+    touch_action: String,
+}
+
+impl MinimapListeners {
+    pub fn attach(canvas: &HtmlCanvasElement) -> Result<Self, JsValue> {
+        let style = canvas.style();
+        let mut listeners = Self {
+            canvas: canvas.clone(),
+            target: Rc::new(Cell::new(None)),
+            closures: Vec::new(),
+            touch_action: style.get_property_value("touch-action")?,
+        };
+        let pressed = Rc::new(Cell::new(None::<i32>));
+        let (down, moved, up) = (pressed.clone(), pressed.clone(), pressed);
+        listeners.listen("pointerdown", move |canvas, target, event| {
+            if down.get().is_none() {
+                down.set(Some(event.pointer_id()));
+                let _ = canvas.set_pointer_capture(event.pointer_id());
+                target.set(Some([event.offset_x() as f32, event.offset_y() as f32]));
+            }
+        })?;
+        listeners.listen("pointermove", move |_, target, event| {
+            if moved.get() == Some(event.pointer_id()) {
+                target.set(Some([event.offset_x() as f32, event.offset_y() as f32]));
+            }
+        })?;
+        let release = move |_: &HtmlCanvasElement, _: &Cell<Option<[f32; 2]>>, event: PointerEvent| {
+            if up.get() == Some(event.pointer_id()) {
+                up.set(None);
+            }
+        };
+        listeners.listen("pointerup", release.clone())?;
+        listeners.listen("pointercancel", release)?;
+        style.set_property("touch-action", "none")?;
+        Ok(listeners)
+    }
+
+    // The latest press or drag position since the last call, in CSS pixels
+    pub fn take_target(&self) -> Option<[f32; 2]> {
+        self.target.take()
+    }
+
+    fn listen(&mut self, event: &'static str, mut handle: impl FnMut(&HtmlCanvasElement, &Cell<Option<[f32; 2]>>, PointerEvent) + 'static) -> Result<(), JsValue> {
+        let (canvas, target) = (self.canvas.clone(), self.target.clone());
+        let closure = PointerListener::new(move |event: PointerEvent| handle(&canvas, &target, event));
+        self.canvas.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())?;
+        self.closures.push((event, closure));
+        Ok(())
+    }
+}
+
+impl Drop for MinimapListeners {
+    fn drop(&mut self) {
+        for (event, closure) in &self.closures {
+            let _ = self.canvas.remove_event_listener_with_callback(event, closure.as_ref().unchecked_ref());
+        }
+        let _ = self.canvas.style().set_property("touch-action", &self.touch_action);
+    }
+}
@@ -0,0 +1,256 @@
+// ForceAtlas2 (Jacomy et al., 2014) on the CPU, an alternative to the
+// spring-electric model in PHYSICS_SHADER. Repulsion is weighted by degree and
+// approximated with a Barnes-Hut quadtree; step sizes adapt per node from the
+// swinging and traction of its force, and globally like Gephi's implementation.
+
+use crate::graph::Adjacency;
+use crate::quadtree::{QuadTree, QUAD_NO_BODY};
+use crate::{NodeData, NODE_FLAG_PINNED};
+
+// Repulsion between overlapping nodes when prevent_overlap is on
+const OVERLAP_REPULSION_FACTOR: f32 = 100.0;
+// Cap on how fast the global speed may grow per tick
+const MAX_SPEED_RISE: f32 = 0.5;
+// Barnes-Hut opening criterion, as in Gephi
+const THETA: f32 = 1.2;
+
+#[derive(Clone, Debug)]
+pub struct ForceAtlas2Settings {
+    pub scaling: f32,                // repulsion strength
+    pub gravity: f32,                // pull towards the origin
+    pub strong_gravity: bool,        // gravity grows with the distance instead of being constant
+    pub linlog: bool,                // logarithmic attraction, tighter clusters
+    pub prevent_overlap: bool,       // repulsion and attraction use the gap between node radii
+    pub edge_weight_influence: f32,  // 0 ignores edge weights, 1 uses them as-is
+}
+
+impl Default for ForceAtlas2Settings {
+    fn default() -> Self {
+        Self {
+            scaling: 10.0,
+            gravity: 1.0,
+            strong_gravity: false,
+            linlog: false,
+            prevent_overlap: false,
+            edge_weight_influence: 1.0,
+        }
+    }
+}
+
+// Settings plus the state carried between ticks for speed adaptation
+pub struct ForceAtlas2 {
+    pub settings: ForceAtlas2Settings,
+    previous_forces: Vec<[f32; 2]>,
+    speed: f32,
+    speed_efficiency: f32,
+}
+
+impl Default for ForceAtlas2 {
+    fn default() -> Self {
+        Self {
+            settings: ForceAtlas2Settings::default(),
+            previous_forces: Vec::new(),
+            speed: 1.0,
+            speed_efficiency: 1.0,
+        }
+    }
+}
+
+impl ForceAtlas2 {
+    // Forget the adaptive speeds, e.g. after the nodes were moved from outside
+    pub fn reset(&mut self) {
+        self.previous_forces.clear();
+        self.speed = 1.0;
+        self.speed_efficiency = 1.0;
+    }
+
+    // Runs one iteration; `alpha` scales the displacements so the layout settles
+    // with the same cooling schedule as the spring-electric model
+    pub fn step(&mut self, nodes: &mut [NodeData], adjacency: &Adjacency, edge_weights: &[f32], alpha: f32) {
+        let node_count = nodes.len().min(adjacency.node_count());
+        let nodes = &mut nodes[..node_count];
+        if node_count == 0 {
+            return;
+        }
+        if self.previous_forces.len() != node_count {
+            self.reset();
+            self.previous_forces = vec![[0.0; 2]; node_count];
+        }
+
+        let masses: Vec<f32> = (0..node_count).map(|i| adjacency.degree(i) as f32 + 1.0).collect();
+        let mut forces = vec![[0.0f32; 2]; node_count];
+        self.add_repulsion(nodes, &masses, &mut forces);
+        self.add_gravity(nodes, &masses, &mut forces);
+        self.add_attraction(nodes, adjacency, edge_weights, &mut forces);
+
+        // Swinging (erratic movement) and traction (useful movement) of every node
+        let (mut total_swing, mut total_traction) = (0.0f32, 0.0f32);
+        let mut swings = vec![0.0f32; node_count];
+        for i in 0..node_count {
+            let [fx, fy] = forces[i];
+            let [px, py] = self.previous_forces[i];
+            swings[i] = masses[i] * ((fx - px).powi(2) + (fy - py).powi(2)).sqrt();
+            total_swing += swings[i];
+            total_traction += masses[i] * 0.5 * ((fx + px).powi(2) + (fy + py).powi(2)).sqrt();
+        }
+        self.adjust_speed(node_count, total_swing, total_traction);
+
+        for (i, node) in nodes.iter_mut().enumerate() {
+            self.previous_forces[i] = forces[i];
+            node.vx = 0.0;
+            node.vy = 0.0;
+            node.fx = 0.0;
+            node.fy = 0.0;
+            if node.flags & NODE_FLAG_PINNED != 0 {
+                continue;
+            }
+
+            let [fx, fy] = forces[i];
+            let mut factor = self.speed / (1.0 + (self.speed * swings[i]).sqrt());
+            if self.settings.prevent_overlap {
+                let force = (fx * fx + fy * fy).sqrt();
+                factor = (0.1 * factor).min(10.0 / force.max(1e-6));
+            }
+
+            // Report the displacement as velocity so kinetic energy stays meaningful
+            node.vx = fx * factor * alpha;
+            node.vy = fy * factor * alpha;
+            node.x += node.vx;
+            node.y += node.vy;
+        }
+    }
+
+    fn add_repulsion(&self, nodes: &[NodeData], masses: &[f32], forces: &mut [[f32; 2]]) {
+        let tree = QuadTree::build_weighted(nodes, Some(masses), 4 * nodes.len());
+        let scaling = self.settings.scaling;
+
+        for (index, node) in nodes.iter().enumerate() {
+            let mut i = 0;
+            while i < tree.nodes.len() {
+                let cell = &tree.nodes[i];
+                let (dx, dy) = (node.x - cell.center_x, node.y - cell.center_y);
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                let magnitude = if cell.is_leaf != 0 && cell.body != QUAD_NO_BODY {
+                    i = cell.next as usize;
+                    let body = cell.body as usize;
+                    if body == index {
+                        continue;
+                    }
+                    self.pair_repulsion(distance, masses[index] * masses[body], node.size + nodes[body].size)
+                } else if cell.is_leaf != 0 || cell.width < THETA * distance {
+                    i = cell.next as usize;
+                    if distance > 0.0 {
+                        scaling * masses[index] * cell.charge / distance
+                    } else {
+                        0.0
+                    }
+                } else {
+                    i += 1;
+                    continue;
+                };
+
+                if distance > 0.0 {
+                    forces[index][0] += dx / distance * magnitude;
+                    forces[index][1] += dy / distance * magnitude;
+                }
+            }
+        }
+    }
+
+    // Repulsion magnitude between two single nodes
+    fn pair_repulsion(&self, distance: f32, mass_product: f32, radii: f32) -> f32 {
+        let scaling = self.settings.scaling;
+        if !self.settings.prevent_overlap {
+            return if distance > 0.0 { scaling * mass_product / distance } else { 0.0 };
+        }
+
+        let gap = distance - radii;
+        if gap > 0.0 {
+            scaling * mass_product / gap
+        } else if gap < 0.0 {
+            OVERLAP_REPULSION_FACTOR * scaling * mass_product
+        } else {
+            0.0
+        }
+    }
+
+    fn add_gravity(&self, nodes: &[NodeData], masses: &[f32], forces: &mut [[f32; 2]]) {
+        let gravity = self.settings.gravity;
+        if gravity <= 0.0 {
+            return;
+        }
+
+        for (i, node) in nodes.iter().enumerate() {
+            let distance = (node.x * node.x + node.y * node.y).sqrt();
+            if distance <= 0.0 {
+                continue;
+            }
+            // Constant pull, or one proportional to the distance (strong gravity)
+            let magnitude = if self.settings.strong_gravity { gravity * masses[i] } else { gravity * masses[i] / distance };
+            forces[i][0] -= node.x * magnitude;
+            forces[i][1] -= node.y * magnitude;
+        }
+    }
+
+    fn add_attraction(&self, nodes: &[NodeData], adjacency: &Adjacency, edge_weights: &[f32], forces: &mut [[f32; 2]]) {
+        let influence = self.settings.edge_weight_influence;
+
+        for (index, node) in nodes.iter().enumerate() {
+            for (&other, &edge_id) in adjacency.neighbors(index).iter().zip(adjacency.incident_edges(index)) {
+                let Some(other) = nodes.get(other as usize) else {
+                    continue;
+                };
+
+                let (dx, dy) = (other.x - node.x, other.y - node.y);
+                let mut distance = (dx * dx + dy * dy).sqrt();
+                let direction = distance;
+                if self.settings.prevent_overlap {
+                    distance -= node.size + other.size;
+                }
+                if distance <= 0.0 || direction <= 0.0 {
+                    continue;
+                }
+
+                let weight = edge_weights.get(edge_id as usize).copied().unwrap_or(1.0);
+                let weight = if influence == 0.0 { 1.0 } else { weight.max(0.0).powf(influence) };
+                let magnitude = if self.settings.linlog { weight * (1.0 + distance).ln() } else { weight * distance };
+                forces[index][0] += dx / direction * magnitude;
+                forces[index][1] += dy / direction * magnitude;
+            }
+        }
+    }
+
+    // Gephi's global speed adaptation: tolerate more swinging on large graphs and
+    // speed up while the layout moves coherently
+    fn adjust_speed(&mut self, node_count: usize, total_swing: f32, total_traction: f32) {
+        let n = node_count as f32;
+        let estimated_optimal = 0.05 * n.sqrt();
+        let min_tolerance = estimated_optimal.sqrt();
+        let mut jitter_tolerance = (estimated_optimal * total_traction / (n * n)).min(10.0).max(min_tolerance);
+
+        let min_efficiency = 0.05;
+        if total_traction > 0.0 && total_swing / total_traction > 2.0 {
+            if self.speed_efficiency > min_efficiency {
+                self.speed_efficiency *= 0.5;
+            }
+            jitter_tolerance = jitter_tolerance.max(1.0);
+        }
+
+        let target_speed = if total_swing > 0.0 {
+            jitter_tolerance * self.speed_efficiency * total_traction / total_swing
+        } else {
+            self.speed
+        };
+
+        if total_swing > jitter_tolerance * total_traction {
+            if self.speed_efficiency > min_efficiency {
+                self.speed_efficiency *= 0.7;
+            }
+        } else if self.speed < 1000.0 {
+            self.speed_efficiency *= 1.3;
+        }
+
+        self.speed += (target_speed - self.speed).min(MAX_SPEED_RISE * self.speed);
+    }
+}
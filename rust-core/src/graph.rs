@@ -0,0 +1,71 @@
+// Graph topology built from indexed edges (source/target node indices)
+
+// Compressed per-node incidence lists: for node `i`, entries
+// `offsets[i]..offsets[i + 1]` of `neighbors`/`edge_ids` describe its edges.
+// Every edge appears twice, once from each endpoint.
+pub struct Adjacency {
+    offsets: Vec<u32>,
+    neighbors: Vec<u32>,
+    edge_ids: Vec<u32>,
+}
+
+impl Adjacency {
+    pub fn new(node_count: usize, edges: &[[u32; 2]]) -> Self {
+        let mut degrees = vec![0u32; node_count];
+        for &[source, target] in edges {
+            if (source as usize) < node_count && (target as usize) < node_count {
+                degrees[source as usize] += 1;
+                degrees[target as usize] += 1;
+            }
+        }
+
+        let mut offsets = Vec::with_capacity(node_count + 1);
+        let mut total = 0u32;
+        offsets.push(0);
+        for degree in &degrees {
+            total += degree;
+            offsets.push(total);
+        }
+
+        let mut cursor: Vec<u32> = offsets[..node_count].to_vec();
+        let mut neighbors = vec![0u32; total as usize];
+        let mut edge_ids = vec![0u32; total as usize];
+        for (edge_id, &[source, target]) in edges.iter().enumerate() {
+            if (source as usize) >= node_count || (target as usize) >= node_count {
+                continue;
+            }
+            for (from, to) in [(source, target), (target, source)] {
+                let slot = cursor[from as usize] as usize;
+                neighbors[slot] = to;
+                edge_ids[slot] = edge_id as u32;
+                cursor[from as usize] += 1;
+            }
+        }
+
+        Self {
+            offsets,
+            neighbors,
+            edge_ids,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn neighbors(&self, node: usize) -> &[u32] {
+        &self.neighbors[self.range(node)]
+    }
+
+    pub fn incident_edges(&self, node: usize) -> &[u32] {
+        &self.edge_ids[self.range(node)]
+    }
+
+    pub fn degree(&self, node: usize) -> usize {
+        self.range(node).len()
+    }
+
+    fn range(&self, node: usize) -> std::ops::Range<usize> {
+        self.offsets[node] as usize..self.offsets[node + 1] as usize
+    }
+}
@@ -0,0 +1,73 @@
+// Node group membership (communities, clusters) used to pull members together
+
+use crate::NodeData;
+
+pub const NO_GROUP: u32 = u32::MAX;
+
+// User group ids remapped to dense slots 0..count, NO_GROUP for ungrouped nodes
+pub struct NodeGroups {
+    ids: Vec<i32>,
+    slots: Vec<u32>,
+    count: usize,
+}
+
+impl NodeGroups {
+    // Negative ids mean "no group"
+    pub fn new(ids: &[i32]) -> Self {
+        let mut sorted: Vec<i32> = ids.iter().copied().filter(|&id| id >= 0).collect();
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        let slots = ids
+            .iter()
+            .map(|&id| match sorted.binary_search(&id) {
+                Ok(slot) if id >= 0 => slot as u32,
+                _ => NO_GROUP,
+            })
+            .collect();
+
+        Self {
+            ids: ids.to_vec(),
+            slots,
+            count: sorted.len(),
+        }
+    }
+
+    pub fn ids(&self) -> &[i32] {
+        &self.ids
+    }
+
+    pub fn slots(&self) -> &[u32] {
+        &self.slots
+    }
+
+    // Keeps existing memberships by index, new nodes are ungrouped
+    pub fn resized(&self, node_count: usize) -> Self {
+        let mut ids = self.ids.clone();
+        ids.resize(node_count, -1);
+        Self::new(&ids)
+    }
+
+    // Mean position of each group's members, indexed by slot
+    pub fn centroids(&self, nodes: &[NodeData]) -> Vec<[f32; 2]> {
+        let mut sums = vec![[0.0f64; 3]; self.count];
+        for (node, &slot) in nodes.iter().zip(&self.slots) {
+            if slot != NO_GROUP && node.x.is_finite() && node.y.is_finite() {
+                let sum = &mut sums[slot as usize];
+                sum[0] += node.x as f64;
+                sum[1] += node.y as f64;
+                sum[2] += 1.0;
+            }
+        }
+
+        sums.iter()
+            .map(|&[x, y, count]| {
+                if count > 0.0 {
+                    [(x / count) as f32, (y / count) as f32]
+                } else {
+                    [0.0, 0.0]
+                }
+            })
+            .collect()
+    }
+}
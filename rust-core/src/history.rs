@@ -0,0 +1,156 @@
+// Bounded undo/redo stacks of whole-state snapshots. What a snapshot holds and
+// when one is taken is up to the owner; nothing is recorded automatically.
+
+use std::collections::VecDeque;
+
+pub struct History<T> {
+    undo: VecDeque<T>, // oldest first
+    redo: Vec<T>,      // most recently undone last
+    limit: usize,
+}
+
+impl<T> History<T> {
+    pub fn new(limit: usize) -> Self {
+        Self { undo: VecDeque::new(), redo: Vec::new(), limit }
+    }
+
+    // Records the state to return to on the next undo; drops the redo states
+    pub fn push(&mut self, snapshot: T) {
+        self.redo.clear();
+        self.undo.push_back(snapshot);
+        self.trim();
+    }
+
+    // Swaps `current` for the latest undo state, keeping it for redo
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current);
+        self.trim();
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // The states next to the current one, for sharing unchanged data with them
+    pub fn neighbors(&self) -> impl Iterator<Item = &T> {
+        self.undo.back().into_iter().chain(self.redo.last())
+    }
+
+    pub fn undo_count(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_count(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.trim();
+        // Redo states furthest from the current one go first
+        let excess = self.redo.len().saturating_sub(limit);
+        self.redo.drain(..excess);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    // Forgets the oldest undo states beyond the limit
+    fn trim(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_forgets_the_oldest_states_past_the_limit() {
+        let mut history = History::new(3);
+        for state in 0..5 {
+            history.push(state);
+        }
+        assert_eq!(history.undo_count(), 3);
+        assert_eq!(history.undo(5), Some(4));
+        assert_eq!(history.undo(4), Some(3));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), None);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn push_clears_redo() {
+        let mut history = History::new(10);
+        history.push(0);
+        history.push(1);
+        assert_eq!(history.undo(2), Some(1));
+        assert!(history.can_redo());
+        history.push(1);
+        assert!(!history.can_redo());
+        assert_eq!(history.redo(7), None);
+        assert_eq!(history.undo_count(), 2);
+    }
+
+    #[test]
+    fn undo_and_redo_retrace_each_other() {
+        let mut history = History::new(10);
+        for state in 0..3 {
+            history.push(state);
+        }
+        // At 3: undo to 2 and 1, redo back to 2 and 3, and undo again
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!((history.undo_count(), history.redo_count()), (1, 2));
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), None);
+        assert_eq!((history.undo_count(), history.redo_count()), (3, 0));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.neighbors().copied().collect::<Vec<_>>(), [1, 3]);
+    }
+
+    #[test]
+    fn lowering_the_limit_drops_the_furthest_states() {
+        let mut history = History::new(10);
+        for state in 0..6 {
+            history.push(state);
+        }
+        // At 6, with 3 undos: undo 0 1 2, redo 6 5 4 (4 next)
+        for current in (4..7).rev() {
+            history.undo(current);
+        }
+        history.set_limit(2);
+        assert_eq!((history.undo_count(), history.redo_count()), (2, 2));
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        // The redo states nearest to the current one survive; 6 is gone
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), Some(4));
+        assert_eq!(history.redo(4), Some(5));
+        assert_eq!(history.redo(5), None);
+
+        // Redoing trims the undo states to the limit too
+        assert_eq!(history.undo_count(), 2);
+        history.clear();
+        assert!(!history.can_undo() && !history.can_redo());
+    }
+}
@@ -0,0 +1,31 @@
+// Placement of text along edges. The renderer draws no text itself, so this
+// only works out where an app should draw each label, in screen space.
+
+// Average advance of a character in ems, for estimating label widths without
+// measuring text
+pub const CHAR_WIDTH_EM: f32 = 0.6;
+// Padding of the background pill on each side of the text, in ems
+pub const PILL_PADDING_EM: f32 = 0.4;
+
+// Width of `label` with its pill at `font_px`
+pub fn label_width(label: &str, font_px: f32) -> f32 {
+    (label.chars().count() as f32 * CHAR_WIDTH_EM + 2.0 * PILL_PADDING_EM) * font_px
+}
+
+// Center and rotation (radians, clockwise as y points down) of a label of
+// `width` on the edge from `start` to `end`, both in screen pixels. The angle
+// stays within a quarter turn of horizontal so the text never reads upside
+// down. None when the edge is shorter than the label.
+pub fn edge_label_placement(start: [f32; 2], end: [f32; 2], width: f32) -> Option<([f32; 2], f32)> {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    if (dx * dx + dy * dy).sqrt() < width {
+        return None;
+    }
+    let mut angle = dy.atan2(dx);
+    if angle > std::f32::consts::FRAC_PI_2 {
+        angle -= std::f32::consts::PI;
+    } else if angle < -std::f32::consts::FRAC_PI_2 {
+        angle += std::f32::consts::PI;
+    }
+    Some(([(start[0] + end[0]) * 0.5, (start[1] + end[1]) * 0.5], angle))
+}
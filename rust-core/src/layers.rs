@@ -0,0 +1,99 @@
+// Layers group nodes and edges so they can be shown, faded and stacked
+// together, e.g. two related networks over the same nodes. Layer 0 always
+// exists and holds everything not assigned elsewhere. Items of a higher layer
+// in the order are drawn over those of lower ones, edges still under nodes.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layer {
+    pub visible: bool,
+    pub opacity: f32, // alpha multiplier, 0-1
+}
+
+impl Default for Layer {
+    fn default() -> Self {
+        Self { visible: true, opacity: 1.0 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layers {
+    layers: Vec<Layer>,
+    order: Vec<u32>, // layer ids, bottom first
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self { layers: vec![Layer::default()], order: vec![0] }
+    }
+}
+
+impl Layers {
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    // Adds a layer on top of the others
+    pub fn create(&mut self) -> u32 {
+        let id = self.layers.len() as u32;
+        self.layers.push(Layer::default());
+        self.order.push(id);
+        id
+    }
+
+    // Unknown layers read as the default
+    pub fn get(&self, layer: u32) -> Layer {
+        self.layers.get(layer as usize).copied().unwrap_or_default()
+    }
+
+    pub fn get_mut(&mut self, layer: u32) -> Option<&mut Layer> {
+        self.layers.get_mut(layer as usize)
+    }
+
+    // Whether any layer is faded, so drawing has to scale alphas
+    pub fn is_faded(&self) -> bool {
+        self.layers.iter().any(|layer| layer.opacity < 1.0)
+    }
+
+    pub fn order(&self) -> &[u32] {
+        &self.order
+    }
+
+    // `order` must list every layer once, bottom first
+    pub fn set_order(&mut self, order: &[u32]) -> Result<(), String> {
+        if order.len() != self.layers.len() {
+            return Err(format!("Layer order must list all {} layers, got {}", self.layers.len(), order.len()));
+        }
+        let mut seen = vec![false; self.layers.len()];
+        for &layer in order {
+            match seen.get_mut(layer as usize) {
+                Some(seen) if !*seen => *seen = true,
+                Some(_) => return Err(format!("Layer {} is listed twice", layer)),
+                None => return Err(format!("Layer {} does not exist", layer)),
+            }
+        }
+        self.order = order.to_vec();
+        Ok(())
+    }
+
+    // Each layer's position in the order, indexed by layer id
+    pub fn ranks(&self) -> Vec<u32> {
+        let mut ranks = vec![0; self.layers.len()];
+        for (rank, &layer) in self.order.iter().enumerate() {
+            ranks[layer as usize] = rank as u32;
+        }
+        ranks
+    }
+
+    // Item indices sorted by the rank of their layer, keeping index order
+    // within a layer
+    pub fn draw_order(&self, layer_of: impl Fn(usize) -> u32, count: usize) -> Vec<u32> {
+        let ranks = self.ranks();
+        let mut order: Vec<u32> = (0..count as u32).collect();
+        order.sort_by_key(|&i| ranks.get(layer_of(i as usize) as usize).copied().unwrap_or(0));
+        order
+    }
+}
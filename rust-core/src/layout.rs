@@ -0,0 +1,652 @@
+// Non-physics node placement: initial seeding for the force simulation and
+// deterministic layouts
+
+use std::f32::consts::PI;
+
+use crate::graph::Adjacency;
+
+// Small seeded PRNG (SplitMix64) so seeded layouts are reproducible across sessions
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeedStrategy {
+    Random,      // uniform in a disc whose area grows with the node count
+    Circle,      // evenly spaced on a circle
+    Grid,        // row-major square grid
+    Phyllotaxis, // sunflower spiral, evenly spread with no overlaps
+}
+
+impl SeedStrategy {
+    pub fn parse(strategy: &str) -> Option<Self> {
+        match strategy {
+            "random" => Some(Self::Random),
+            "circle" => Some(Self::Circle),
+            "grid" => Some(Self::Grid),
+            "phyllotaxis" => Some(Self::Phyllotaxis),
+            _ => None,
+        }
+    }
+}
+
+// `count` positions centered on the origin with neighbors roughly `spacing` apart
+pub fn seed_positions(strategy: SeedStrategy, count: usize, seed: u64, spacing: f32) -> Vec<[f32; 2]> {
+    let mut rng = Rng::new(seed);
+    let n = count as f32;
+
+    (0..count)
+        .map(|i| {
+            let i = i as f32;
+            match strategy {
+                SeedStrategy::Random => {
+                    // sqrt keeps the density uniform over the disc
+                    let radius = spacing * n.sqrt() * 0.5 * rng.next_f32().sqrt();
+                    let angle = 2.0 * PI * rng.next_f32();
+                    [radius * angle.cos(), radius * angle.sin()]
+                }
+                SeedStrategy::Circle => {
+                    let radius = spacing * n / (2.0 * PI);
+                    let angle = 2.0 * PI * i / n;
+                    [radius * angle.cos(), radius * angle.sin()]
+                }
+                SeedStrategy::Grid => {
+                    let columns = n.sqrt().ceil().max(1.0);
+                    let rows = (n / columns).ceil();
+                    let (column, row) = (i % columns, (i / columns).floor());
+                    [(column - (columns - 1.0) * 0.5) * spacing, (row - (rows - 1.0) * 0.5) * spacing]
+                }
+                SeedStrategy::Phyllotaxis => {
+                    let radius = spacing * 0.5 * (i + 0.5).sqrt();
+                    let angle = i * PI * (3.0 - 5.0f32.sqrt()); // golden angle
+                    [radius * angle.cos(), radius * angle.sin()]
+                }
+            }
+        })
+        .collect()
+}
+
+// `count` positions evenly spaced on an arc of a circle centered on the origin.
+// A full turn leaves a gap after the last node; partial arcs use both ends.
+// Node indices in `order`, followed by the unlisted ones in index order
+pub fn layout_order(node_count: usize, order: &[u32]) -> Result<Vec<usize>, String> {
+    let mut listed = vec![false; node_count];
+    for &index in order {
+        match listed.get_mut(index as usize) {
+            None => return Err(format!("Node index {} out of range ({} nodes)", index, node_count)),
+            Some(seen) if *seen => return Err(format!("Node index {} listed twice", index)),
+            Some(seen) => *seen = true,
+        }
+    }
+
+    let mut indices: Vec<usize> = order.iter().map(|&index| index as usize).collect();
+    indices.extend((0..node_count).filter(|&index| !listed[index]));
+    Ok(indices)
+}
+
+pub fn circular_positions(count: usize, radius: f32, start_angle: f32, end_angle: f32) -> Vec<[f32; 2]> {
+    let sweep = end_angle - start_angle;
+    let full_turn = sweep.abs() >= 2.0 * PI - 1e-4;
+    let divisions = if full_turn { count } else { count.saturating_sub(1) }.max(1) as f32;
+
+    (0..count)
+        .map(|i| {
+            let angle = start_angle + sweep * i as f32 / divisions;
+            [radius * angle.cos(), radius * angle.sin()]
+        })
+        .collect()
+}
+
+// Row-major grid centered on the origin with `columns` per row (0 picks a square
+// grid). A partial last row is centered under the full ones.
+pub fn grid_positions(count: usize, columns: usize, cell_width: f32, cell_height: f32) -> Vec<[f32; 2]> {
+    let columns = if columns == 0 { (count as f32).sqrt().ceil() as usize } else { columns }.max(1);
+    let rows = count.div_ceil(columns);
+
+    (0..count)
+        .map(|i| {
+            let (row, column) = (i / columns, i % columns);
+            let row_length = if row + 1 == rows { count - row * columns } else { columns };
+            [
+                (column as f32 - (row_length as f32 - 1.0) * 0.5) * cell_width,
+                (row as f32 - (rows as f32 - 1.0) * 0.5) * cell_height,
+            ]
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayerDirection {
+    TopDown,
+    BottomUp,
+    LeftRight,
+    RightLeft,
+}
+
+impl LayerDirection {
+    pub fn parse(direction: &str) -> Option<Self> {
+        match direction {
+            "top-down" => Some(Self::TopDown),
+            "bottom-up" => Some(Self::BottomUp),
+            "left-right" => Some(Self::LeftRight),
+            "right-left" => Some(Self::RightLeft),
+            _ => None,
+        }
+    }
+
+    // Turns a top-down position from layered_positions into this direction
+    pub fn orient(self, [x, y]: [f32; 2]) -> [f32; 2] {
+        match self {
+            Self::TopDown => [x, y],
+            Self::BottomUp => [x, -y],
+            Self::LeftRight => [y, x],
+            Self::RightLeft => [-y, x],
+        }
+    }
+}
+
+// Barycenter sweeps (down then up) used to reduce crossings between layers
+const CROSSING_REDUCTION_PASSES: usize = 4;
+
+// Sugiyama-style layering of a directed graph: cycles are broken by reversing
+// DFS back edges, nodes are assigned longest-path layers, and each layer is
+// ordered by the barycenter of its neighbors. Returns the positions (layers
+// along y, centered on the origin) and the number of reversed edges.
+pub fn layered_positions(node_count: usize, edges: &[[u32; 2]], layer_gap: f32, node_gap: f32) -> (Vec<[f32; 2]>, usize) {
+    let mut successors = vec![Vec::new(); node_count];
+    for &[source, target] in edges {
+        if source != target && (source as usize) < node_count && (target as usize) < node_count {
+            successors[source as usize].push(target as usize);
+        }
+    }
+
+    let reversed = break_cycles(&mut successors);
+    let layers = longest_path_layers(&successors);
+
+    let mut predecessors = vec![Vec::new(); node_count];
+    for (source, targets) in successors.iter().enumerate() {
+        for &target in targets {
+            predecessors[target].push(source);
+        }
+    }
+
+    // Nodes of each layer in order, initially by index
+    let layer_count = layers.iter().max().map_or(0, |&max| max + 1);
+    let mut rows: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for (node, &layer) in layers.iter().enumerate() {
+        rows[layer].push(node);
+    }
+
+    let mut order = vec![0usize; node_count];
+    for row in &rows {
+        for (position, &node) in row.iter().enumerate() {
+            order[node] = position;
+        }
+    }
+
+    for _ in 0..CROSSING_REDUCTION_PASSES {
+        for row in rows.iter_mut().skip(1) {
+            sort_by_barycenter(row, &predecessors, &mut order);
+        }
+        for row in rows.iter_mut().rev().skip(1) {
+            sort_by_barycenter(row, &successors, &mut order);
+        }
+    }
+
+    let mut positions = vec![[0.0f32; 2]; node_count];
+    for (layer, row) in rows.iter().enumerate() {
+        for (position, &node) in row.iter().enumerate() {
+            positions[node] = [
+                (position as f32 - (row.len() as f32 - 1.0) * 0.5) * node_gap,
+                (layer as f32 - (layer_count as f32 - 1.0) * 0.5) * layer_gap,
+            ];
+        }
+    }
+    (positions, reversed)
+}
+
+// Reverses the edges that close a cycle during an iterative DFS
+fn break_cycles(successors: &mut [Vec<usize>]) -> usize {
+    const UNVISITED: u8 = 0;
+    const ON_STACK: u8 = 1;
+    const DONE: u8 = 2;
+
+    let node_count = successors.len();
+    let mut state = vec![UNVISITED; node_count];
+    let mut back_edges = Vec::new();
+
+    for root in 0..node_count {
+        if state[root] != UNVISITED {
+            continue;
+        }
+        // (node, index of the next successor to visit)
+        let mut stack = vec![(root, 0usize)];
+        state[root] = ON_STACK;
+        while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+            if let Some(&target) = successors[node].get(*next) {
+                *next += 1;
+                match state[target] {
+                    UNVISITED => {
+                        state[target] = ON_STACK;
+                        stack.push((target, 0));
+                    }
+                    ON_STACK => back_edges.push((node, target)),
+                    _ => {}
+                }
+            } else {
+                state[node] = DONE;
+                stack.pop();
+            }
+        }
+    }
+
+    for &(source, target) in &back_edges {
+        if let Some(slot) = successors[source].iter().position(|&node| node == target) {
+            successors[source].swap_remove(slot);
+        }
+        successors[target].push(source);
+    }
+    back_edges.len()
+}
+
+// Layer of each node in an acyclic graph: sources at 0, every edge going down
+// at least one layer
+fn longest_path_layers(successors: &[Vec<usize>]) -> Vec<usize> {
+    let node_count = successors.len();
+    let mut in_degree = vec![0usize; node_count];
+    for targets in successors {
+        for &target in targets {
+            in_degree[target] += 1;
+        }
+    }
+
+    let mut layers = vec![0usize; node_count];
+    let mut queue: std::collections::VecDeque<usize> = (0..node_count).filter(|&node| in_degree[node] == 0).collect();
+    while let Some(node) = queue.pop_front() {
+        for &target in &successors[node] {
+            layers[target] = layers[target].max(layers[node] + 1);
+            in_degree[target] -= 1;
+            if in_degree[target] == 0 {
+                queue.push_back(target);
+            }
+        }
+    }
+    layers
+}
+
+// Orders a layer by the mean position of each node's neighbors in the adjacent
+// layer; nodes without neighbors keep their current position
+fn sort_by_barycenter(row: &mut [usize], neighbors: &[Vec<usize>], order: &mut [usize]) {
+    let mut keyed: Vec<(f32, usize, usize)> = row
+        .iter()
+        .map(|&node| {
+            let adjacent = &neighbors[node];
+            let barycenter = if adjacent.is_empty() {
+                order[node] as f32
+            } else {
+                adjacent.iter().map(|&other| order[other] as f32).sum::<f32>() / adjacent.len() as f32
+            };
+            (barycenter, order[node], node)
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+    for (position, (slot, &(_, _, node))) in row.iter_mut().zip(&keyed).enumerate() {
+        *slot = node;
+        order[node] = position;
+    }
+}
+
+// Radial tree around `root` over the BFS tree of the graph: depth d sits on the
+// ring of radius d * ring_spacing, and each subtree gets an angular sector in
+// proportion to its size. Nodes unreachable from the root are None.
+// radial_positions for the reachable nodes, plus with `place_unreachable` an
+// outer ring for the rest; nodes left out keep their position
+pub fn radial_layout(adjacency: &Adjacency, root: usize, ring_spacing: f32, place_unreachable: bool) -> Vec<(usize, [f32; 2])> {
+    let positions = radial_positions(adjacency, root, ring_spacing);
+
+    let unreachable: Vec<usize> = (0..positions.len()).filter(|&i| positions[i].is_none()).collect();
+    let mut placed: Vec<(usize, [f32; 2])> = positions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, position)| position.map(|position| (i, position)))
+        .collect();
+    if place_unreachable && !unreachable.is_empty() {
+        let outer = placed.iter().map(|(_, [x, y])| (x * x + y * y).sqrt()).fold(0.0f32, f32::max) + ring_spacing;
+        let ring = circular_positions(unreachable.len(), outer, 0.0, 2.0 * std::f32::consts::PI);
+        placed.extend(unreachable.into_iter().zip(ring));
+    }
+    placed
+}
+
+pub fn radial_positions(adjacency: &Adjacency, root: usize, ring_spacing: f32) -> Vec<Option<[f32; 2]>> {
+    let node_count = adjacency.node_count();
+    let mut positions = vec![None; node_count];
+    if root >= node_count {
+        return positions;
+    }
+
+    // BFS visit order and tree parents
+    let mut parent = vec![usize::MAX; node_count];
+    let mut depth = vec![0usize; node_count];
+    let mut visited = vec![false; node_count];
+    let mut bfs_order = vec![root];
+    visited[root] = true;
+    let mut head = 0;
+    while let Some(&node) = bfs_order.get(head) {
+        head += 1;
+        for &neighbor in adjacency.neighbors(node) {
+            let neighbor = neighbor as usize;
+            if !std::mem::replace(&mut visited[neighbor], true) {
+                parent[neighbor] = node;
+                depth[neighbor] = depth[node] + 1;
+                bfs_order.push(neighbor);
+            }
+        }
+    }
+
+    // Subtree sizes, accumulated leaves first
+    let mut subtree_size = vec![1usize; node_count];
+    for &node in bfs_order.iter().skip(1).rev() {
+        subtree_size[parent[node]] += subtree_size[node];
+    }
+
+    // Sector [start, end) of each node; children split their parent's sector in
+    // BFS order, which is also the order they were discovered in
+    let mut sector = vec![[0.0f32, 0.0f32]; node_count];
+    let mut next_start = vec![0.0f32; node_count];
+    sector[root] = [0.0, 2.0 * PI];
+    for &node in &bfs_order {
+        if node != root {
+            let up = parent[node];
+            let share = (sector[up][1] - sector[up][0]) * subtree_size[node] as f32 / (subtree_size[up] - 1) as f32;
+            let start = sector[up][0] + next_start[up];
+            next_start[up] += share;
+            sector[node] = [start, start + share];
+        }
+
+        let [start, end] = sector[node];
+        let radius = depth[node] as f32 * ring_spacing;
+        let angle = (start + end) * 0.5;
+        positions[node] = Some([radius * angle.cos(), radius * angle.sin()]);
+    }
+    positions
+}
+
+// Concentric rings by value: the value range is split into `levels` equal bins,
+// the highest innermost. Within a ring nodes are ordered by value descending,
+// then by index, and spread evenly; rings grow past `ring_spacing` apart when
+// their nodes (of the given radii) wouldn't fit side by side.
+pub fn concentric_positions(values: &[f32], radii: &[f32], levels: usize, ring_spacing: f32) -> Vec<[f32; 2]> {
+    let levels = levels.max(1);
+    let (min, max) = values.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| (min.min(value), max.max(value)));
+    let range = max - min;
+
+    let mut rings: Vec<Vec<usize>> = vec![Vec::new(); levels];
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[b].total_cmp(&values[a]).then(a.cmp(&b)));
+    for node in order {
+        // Level 0 holds the highest values
+        let level = if range > 0.0 { ((max - values[node]) / range * levels as f32) as usize } else { 0 };
+        rings[level.min(levels - 1)].push(node);
+    }
+
+    let mut positions = vec![[0.0f32; 2]; values.len()];
+    let (mut radius, mut previous_node_radius) = (0.0f32, 0.0f32);
+    let mut first = true;
+    for ring in rings.iter().filter(|ring| !ring.is_empty()) {
+        let node_radius = ring.iter().map(|&node| radii.get(node).copied().unwrap_or(0.0)).fold(0.0f32, f32::max);
+        // Circumference needed for the nodes to sit one diameter plus the spacing apart
+        let fitting = ring.len() as f32 * (2.0 * node_radius + ring_spacing) / (2.0 * PI);
+        radius = if first && ring.len() == 1 {
+            0.0
+        } else if first {
+            fitting.max(ring_spacing)
+        } else {
+            fitting.max(radius + ring_spacing.max(previous_node_radius + node_radius))
+        };
+        first = false;
+        previous_node_radius = node_radius;
+
+        for (&node, position) in ring.iter().zip(circular_positions(ring.len(), radius, 0.0, 2.0 * PI)) {
+            positions[node] = position;
+        }
+    }
+    positions
+}
+
+// Two columns `column_gap` apart, partition 0 on the left and 1 on the right,
+// each centered vertically with `node_gap` between nodes. With
+// `reduce_crossings`, the columns are ordered by barycenter against each other
+// using the edges between them; otherwise nodes keep index order.
+pub fn bipartite_positions(partition: &[u32], edges: &[[u32; 2]], column_gap: f32, node_gap: f32, reduce_crossings: bool) -> Vec<[f32; 2]> {
+    let node_count = partition.len();
+    let mut columns: [Vec<usize>; 2] = [Vec::new(), Vec::new()];
+    for (node, &side) in partition.iter().enumerate() {
+        columns[side.min(1) as usize].push(node);
+    }
+
+    if reduce_crossings {
+        let mut neighbors = vec![Vec::new(); node_count];
+        for &[source, target] in edges {
+            let (source, target) = (source as usize, target as usize);
+            if source < node_count && target < node_count && partition[source] != partition[target] {
+                neighbors[source].push(target);
+                neighbors[target].push(source);
+            }
+        }
+
+        let mut order = vec![0usize; node_count];
+        for column in &columns {
+            for (position, &node) in column.iter().enumerate() {
+                order[node] = position;
+            }
+        }
+        for _ in 0..CROSSING_REDUCTION_PASSES {
+            for column in columns.iter_mut() {
+                sort_by_barycenter(column, &neighbors, &mut order);
+            }
+        }
+    }
+
+    let mut positions = vec![[0.0f32; 2]; node_count];
+    for (side, column) in columns.iter().enumerate() {
+        let x = (side as f32 - 0.5) * column_gap;
+        for (position, &node) in column.iter().enumerate() {
+            positions[node] = [x, (position as f32 - (column.len() as f32 - 1.0) * 0.5) * node_gap];
+        }
+    }
+    positions
+}
+
+// Rounds a position to the nearest point of a grid `spacing` apart with a point
+// at the origin
+pub fn snapped_position([x, y]: [f32; 2], spacing: f32) -> [f32; 2] {
+    [(x / spacing).round() * spacing, (y / spacing).round() * spacing]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    CenterX,
+    CenterY,
+}
+
+impl Alignment {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "left" => Some(Self::Left),
+            "right" => Some(Self::Right),
+            "top" => Some(Self::Top),
+            "bottom" => Some(Self::Bottom),
+            "center_x" => Some(Self::CenterX),
+            "center_y" => Some(Self::CenterY),
+            _ => None,
+        }
+    }
+}
+
+// Lines up nodes (center and radius) on a side or a middle line of their
+// bounding box; sides align the node edges, middles the centers. Only the
+// coordinate across the line changes.
+pub fn aligned_positions(nodes: &[([f32; 2], f32)], alignment: Alignment) -> Vec<[f32; 2]> {
+    let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+    for &([x, y], radius) in nodes {
+        min = [min[0].min(x - radius), min[1].min(y - radius)];
+        max = [max[0].max(x + radius), max[1].max(y + radius)];
+    }
+    let center = [(min[0] + max[0]) * 0.5, (min[1] + max[1]) * 0.5];
+
+    nodes
+        .iter()
+        .map(|&([x, y], radius)| match alignment {
+            Alignment::Left => [min[0] + radius, y],
+            Alignment::Right => [max[0] - radius, y],
+            Alignment::Top => [x, min[1] + radius],
+            Alignment::Bottom => [x, max[1] - radius],
+            Alignment::CenterX => [center[0], y],
+            Alignment::CenterY => [x, center[1]],
+        })
+        .collect()
+}
+
+// Spaces the centers evenly along `axis` (0 for x, 1 for y) between the two
+// outermost ones, keeping their order along it; the other coordinate is kept
+pub fn distributed_positions(positions: &[[f32; 2]], axis: usize) -> Vec<[f32; 2]> {
+    let mut distributed = positions.to_vec();
+    if positions.len() < 3 {
+        return distributed;
+    }
+    let mut order: Vec<usize> = (0..positions.len()).collect();
+    order.sort_by(|&a, &b| positions[a][axis].total_cmp(&positions[b][axis]));
+
+    let first = positions[order[0]][axis];
+    let step = (positions[order[order.len() - 1]][axis] - first) / (order.len() - 1) as f32;
+    for (rank, &node) in order.iter().enumerate() {
+        distributed[node][axis] = first + step * rank as f32;
+    }
+    distributed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: [f32; 2], b: [f32; 2]) -> bool {
+        (a[0] - b[0]).abs() < 1e-3 && (a[1] - b[1]).abs() < 1e-3
+    }
+
+    #[test]
+    fn seeding_is_reproducible_per_seed() {
+        for strategy in [SeedStrategy::Random, SeedStrategy::Circle, SeedStrategy::Grid, SeedStrategy::Phyllotaxis] {
+            let positions = seed_positions(strategy, 50, 7, 10.0);
+            assert_eq!(positions.len(), 50);
+            assert_eq!(positions, seed_positions(strategy, 50, 7, 10.0));
+            assert!(positions.iter().flatten().all(|value| value.is_finite()));
+        }
+        assert_ne!(seed_positions(SeedStrategy::Random, 50, 7, 10.0), seed_positions(SeedStrategy::Random, 50, 8, 10.0));
+        assert_eq!(SeedStrategy::parse("phyllotaxis"), Some(SeedStrategy::Phyllotaxis));
+        assert_eq!(SeedStrategy::parse("spiral"), None);
+    }
+
+    #[test]
+    fn layout_order_lists_the_given_nodes_first() {
+        assert_eq!(layout_order(4, &[2, 0]).unwrap(), vec![2, 0, 1, 3]);
+        assert!(layout_order(4, &[2, 2]).is_err());
+        assert!(layout_order(4, &[4]).is_err());
+    }
+
+    #[test]
+    fn full_circles_leave_a_gap_and_arcs_use_both_ends() {
+        let circle = circular_positions(4, 10.0, 0.0, 2.0 * PI);
+        assert!(close(circle[0], [10.0, 0.0]) && close(circle[1], [0.0, 10.0]) && close(circle[3], [0.0, -10.0]));
+        let arc = circular_positions(3, 10.0, 0.0, PI);
+        assert!(close(arc[0], [10.0, 0.0]) && close(arc[1], [0.0, 10.0]) && close(arc[2], [-10.0, 0.0]));
+    }
+
+    #[test]
+    fn grid_is_centered_with_the_last_row_centered_under_the_others() {
+        let positions = grid_positions(5, 3, 10.0, 20.0);
+        assert_eq!(positions, vec![[-10.0, -10.0], [0.0, -10.0], [10.0, -10.0], [-5.0, 10.0], [5.0, 10.0]]);
+        // Zero columns picks a square grid
+        assert_eq!(grid_positions(9, 0, 1.0, 1.0)[2], [1.0, -1.0]);
+    }
+
+    #[test]
+    fn layered_layout_breaks_cycles_and_puts_edges_downwards() {
+        // 0 -> 1 -> 2 -> 0 is a cycle, 1 -> 3 hangs off it
+        let edges = [[0, 1], [1, 2], [2, 0], [1, 3]];
+        let (positions, reversed) = layered_positions(4, &edges, 100.0, 50.0);
+        assert_eq!(reversed, 1);
+        assert!(positions[0][1] < positions[1][1]);
+        assert!(positions[1][1] < positions[2][1] && positions[1][1] < positions[3][1]);
+        // Layers are centered on the origin
+        assert_eq!(positions[0][1], -positions[2][1]);
+        assert_eq!(LayerDirection::parse("left-right").map(|direction| direction.orient([1.0, 2.0])), Some([2.0, 1.0]));
+    }
+
+    #[test]
+    fn radial_layout_rings_by_depth() {
+        let adjacency = Adjacency::new(5, &[[0, 1], [0, 2], [1, 3]]);
+        let positions = radial_positions(&adjacency, 0, 10.0);
+        let radius = |i: usize| positions[i].map(|[x, y]| x.hypot(y));
+        assert_eq!(radius(0), Some(0.0));
+        assert!((radius(1).unwrap() - 10.0).abs() < 1e-3 && (radius(2).unwrap() - 10.0).abs() < 1e-3);
+        assert!((radius(3).unwrap() - 20.0).abs() < 1e-3);
+        assert_eq!(positions[4], None);
+
+        let placed = radial_layout(&adjacency, 0, 10.0, true);
+        assert_eq!(placed.len(), 5);
+        assert_eq!(radial_layout(&adjacency, 0, 10.0, false).len(), 4);
+    }
+
+    #[test]
+    fn concentric_layout_puts_the_highest_values_innermost() {
+        let positions = concentric_positions(&[5.0, 1.0, 3.0, 1.0], &[1.0; 4], 3, 10.0);
+        let radius = |i: usize| positions[i][0].hypot(positions[i][1]);
+        assert_eq!(radius(0), 0.0);
+        assert!(radius(2) > radius(0) && radius(1) > radius(2));
+        assert!((radius(1) - radius(3)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn bipartite_layout_reduces_crossings() {
+        // Left 0, 1 and right 2, 3, wired crosswise
+        let partition = [0, 0, 1, 1];
+        let edges = [[0, 3], [1, 2]];
+        let plain = bipartite_positions(&partition, &edges, 100.0, 10.0, false);
+        assert_eq!(plain, vec![[-50.0, -5.0], [-50.0, 5.0], [50.0, -5.0], [50.0, 5.0]]);
+        let sorted = bipartite_positions(&partition, &edges, 100.0, 10.0, true);
+        assert_eq!(sorted[0][1] < sorted[1][1], sorted[3][1] < sorted[2][1]);
+    }
+
+    #[test]
+    fn alignment_and_distribution() {
+        let nodes = [([0.0, 0.0], 1.0), ([10.0, 5.0], 2.0), ([4.0, -3.0], 1.0)];
+        assert_eq!(aligned_positions(&nodes, Alignment::Left), vec![[0.0, 0.0], [1.0, 5.0], [0.0, -3.0]]);
+        assert_eq!(aligned_positions(&nodes, Alignment::CenterY)[1], [10.0, 1.5]);
+        let positions = distributed_positions(&[[0.0, 0.0], [9.0, 1.0], [1.0, 2.0], [6.0, 3.0]], 0);
+        assert_eq!(positions, vec![[0.0, 0.0], [9.0, 1.0], [3.0, 2.0], [6.0, 3.0]]);
+        assert_eq!(snapped_position([14.0, -6.0], 10.0), [10.0, -10.0]);
+    }
+}
@@ -1,10 +1,56 @@
-use wasm_bindgen::prelude::*;
-use web_sys::{console, HtmlCanvasElement};
-
+// The graph model, layouts, physics and algorithms build without any web or
+// GPU dependency; the default "renderer" feature adds the wgpu renderer and
+// the wasm-bindgen API (FastGraphRenderer) on top.
+
+#[cfg(feature = "renderer")]
+#[macro_use]
+mod logging;
+pub mod algorithms;
+pub mod animation;
+#[cfg(feature = "renderer")]
+mod bindings;
+pub mod bundling;
+pub mod clustering;
+pub mod color;
+#[cfg(feature = "renderer")]
+mod events;
+pub mod forceatlas2;
+pub mod graph;
+pub mod groups;
+pub mod history;
+pub mod labels;
+pub mod layers;
+pub mod layout;
+mod model;
+pub mod morton;
+pub mod palettes;
+pub mod physics;
+pub mod picking;
+pub mod quadtree;
+#[cfg(feature = "renderer")]
 mod renderer;
-use renderer::{Renderer, MAX_EDGES, MAX_NODES};
+pub mod scale;
+pub mod search;
+mod simd;
+pub mod theme;
+pub mod timeline;
+pub mod view_state;
+
+pub use graph::Adjacency;
+pub use model::Graph;
+// The renderer on its own, for native use (see examples/native_viewer.rs)
+#[cfg(feature = "renderer")]
+pub use renderer::{ArrowStyle, Camera, Capabilities, DeviceInfo, GradientStyle, IdleMotion, NodeStyle, Projection, PulseState, Renderer};
+#[cfg(feature = "renderer")]
+pub use bindings::FastGraphRenderer;
 
 // Struct to represent a node for WebGPU rendering with physics
+//
+// Positions, sizes, edge endpoints, world bounds and physics distances all share
+// one world space: arbitrary float units with x to the right and y down, which
+// the camera maps to canvas pixels as (world - camera position) * zoom on both
+// axes. At zoom 1 with the camera at the origin, one unit is one canvas pixel
+// from the top-left corner. Only the vertex shaders project to NDC.
 #[repr(C)]
 #[derive(Clone, Debug, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct NodeData {
@@ -20,10 +66,34 @@ pub struct NodeData {
     pub a: f32,
     pub size: f32,
     pub mass: f32, // for physics calculations
+    pub flags: u32, // NODE_FLAG_* bits
 }
 
-// Struct to represent an edge for WebGPU rendering
-#[derive(Clone, Debug)]
+// Pinned nodes keep their position but still push and pull on others
+pub const NODE_FLAG_PINNED: u32 = 1;
+pub const NODE_FLAG_HIDDEN: u32 = 2;
+// Hidden by filter_by_core; kept apart from NODE_FLAG_HIDDEN so filters don't
+// touch nodes the app hid itself
+pub const NODE_FLAG_BELOW_CORE: u32 = 4;
+// Hidden by isolate_nodes
+pub const NODE_FLAG_OUTSIDE_FOCUS: u32 = 8;
+// Hidden by set_time_window
+pub const NODE_FLAG_OUTSIDE_TIME: u32 = 16;
+// In a layer hidden by set_layer_visible
+pub const NODE_FLAG_LAYER_HIDDEN: u32 = 32;
+// A node with any of these isn't drawn, nor are its edges
+const NODE_HIDDEN_FLAGS: u32 = NODE_FLAG_HIDDEN | NODE_FLAG_BELOW_CORE | NODE_FLAG_OUTSIDE_FOCUS | NODE_FLAG_OUTSIDE_TIME | NODE_FLAG_LAYER_HIDDEN;
+// Left out of the simulation by set_physics_enabled_for
+pub const NODE_FLAG_NO_PHYSICS: u32 = 64;
+// A node with any of these neither moves nor exerts forces, and keeps its velocity
+const NODE_PHYSICS_EXCLUDED_FLAGS: u32 = NODE_HIDDEN_FLAGS | NODE_FLAG_NO_PHYSICS;
+// The style class set by set_node_classes lives in the upper half of `flags`
+pub const NODE_CLASS_SHIFT: u32 = 16;
+
+// Struct to represent an edge for WebGPU rendering; the field order is the
+// edge instance layout, so edges upload without conversion
+#[repr(C)]
+#[derive(Clone, Debug, Copy, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct EdgeData {
     pub x1: f32,
     pub y1: f32,
@@ -36,391 +106,181 @@ pub struct EdgeData {
     pub width: f32,
 }
 
-// A macro to provide `println!(..)`-style syntax for `console.log` logging.
-macro_rules! log {
-    ( $( $t:tt )* ) => {
-        console::log_1(&format!( $( $t )* ).into());
-    }
-}
-
-#[wasm_bindgen]
-pub struct FastGraphRenderer {
-    renderer: Renderer,
-    color1: [f32; 4],
-    color2: [f32; 4],
-    nodes: Vec<NodeData>,
-    edges: Vec<EdgeData>,
-    camera_position: [f32; 2],
-    camera_zoom: f32,
-    is_initialized: bool,
-    is_rendering: bool,
+// How the gravity force scales with a node's distance from the gravity center
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GravityMode {
+    Linear,   // proportional to distance, like a spring anchored at the center
+    Constant, // same magnitude everywhere, pointing at the center
 }
 
-#[wasm_bindgen]
-impl FastGraphRenderer {
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> Self {
-        console_error_panic_hook::set_once();
-        log!("Initializing FastGraph renderer...");
-
-        Self {
-            renderer: Renderer::new(),
-            color1: [1.0, 0.0, 0.0, 1.0], // Default red
-            color2: [0.0, 0.0, 1.0, 1.0], // Default blue
-            nodes: Vec::new(),
-            edges: Vec::new(),
-            camera_position: [0.0, 0.0],
-            camera_zoom: 1.0,
-            is_initialized: false,
-            is_rendering: false,
+impl GravityMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "linear" => Some(Self::Linear),
+            "constant" => Some(Self::Constant),
+            _ => None,
         }
     }
+}
 
-    #[wasm_bindgen]
-    pub async fn init(&mut self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
-        if self.is_initialized {
-            log!("Renderer already initialized");
-            return Ok(());
-        }
-
-        log!("Initializing WebGPU renderer for canvas");
-
-        // Simple retry logic for WebGPU initialization
-        let mut attempts = 0;
-        let max_attempts = 2;
-
-        while attempts < max_attempts {
-            match self.renderer.init(canvas).await {
-                Ok(_) => {
-                    self.is_initialized = true;
-                    log!("WebGPU renderer initialized successfully");
-                    return Ok(());
-                }
-                Err(e) => {
-                    attempts += 1;
-                    log!("WebGPU initialization attempt {} failed: {:?}", attempts, e);
-
-                    if attempts >= max_attempts {
-                        return Err(JsValue::from_str(&format!(
-                            "Failed to initialize WebGPU: {:?}",
-                            e
-                        )));
-                    }
-                }
-            }
-        }
-
-        Err(JsValue::from_str("Failed to initialize WebGPU"))
-    }
-
-    #[wasm_bindgen]
-    pub fn render(&mut self, time: f64) {
-        if !self.is_initialized {
-            return;
-        }
-
-        if self.is_rendering {
-            return; // Skip frame if already rendering
-        }
-
-        self.is_rendering = true;
-
-        // Perform render with error handling
-        match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-            self.renderer.render(
-                time,
-                &self.color1,
-                &self.color2,
-                &self.nodes,
-                &self.edges,
-                &self.camera_position,
-                self.camera_zoom,
-            );
-        })) {
-            Ok(_) => {}
-            Err(_) => {
-                log!("Render operation failed, skipping frame");
-            }
-        }
-
-        self.is_rendering = false;
-    }
+// How nodes are kept inside a bounds constraint
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BoundsMode {
+    Clamp,  // positions are hard-clamped to the rectangle
+    Bounce, // velocity is reflected at the walls, losing some energy
+    Force,  // soft spring pushing nodes back inside
+}
 
-    #[wasm_bindgen]
-    pub fn resize(&mut self, width: u32, height: u32) {
-        if !self.is_initialized || self.is_rendering {
-            return;
+impl BoundsMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "clamp" => Some(Self::Clamp),
+            "bounce" => Some(Self::Bounce),
+            "force" => Some(Self::Force),
+            _ => None,
         }
-
-        self.renderer.resize(width, height);
     }
+}
 
-    #[wasm_bindgen]
-    pub fn set_colors(
-        &mut self,
-        color1_r: f32,
-        color1_g: f32,
-        color1_b: f32,
-        color1_a: f32,
-        color2_r: f32,
-        color2_g: f32,
-        color2_b: f32,
-        color2_a: f32,
-    ) {
-        self.color1 = [color1_r, color1_g, color1_b, color1_a];
-        self.color2 = [color2_r, color2_g, color2_b, color2_a];
-    }
+#[derive(Clone, Copy, Debug)]
+pub struct BoundsConstraint {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub mode: BoundsMode,
+}
 
-    #[wasm_bindgen]
-    pub fn set_color1(&mut self, r: f32, g: f32, b: f32, a: f32) {
-        self.color1 = [r, g, b, a];
-    }
+// Fraction of the speed kept when bouncing off a wall
+pub const BOUNDS_RESTITUTION: f32 = 0.5;
+// Force per unit of distance outside the bounds in "force" mode
+pub const BOUNDS_FORCE_STRENGTH: f32 = 1.0;
+
+// A periodic world, see set_world_wrap: positions wrap into [min, min + size)
+// and forces act across the seams through the nearest image of each node
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorldWrap {
+    pub min: [f32; 2],
+    pub size: [f32; 2],
+}
 
-    #[wasm_bindgen]
-    pub fn set_color2(&mut self, r: f32, g: f32, b: f32, a: f32) {
-        self.color2 = [r, g, b, a];
+impl WorldWrap {
+    pub fn wrap(&self, position: [f32; 2]) -> [f32; 2] {
+        [0, 1].map(|axis| self.min[axis] + (position[axis] - self.min[axis]).rem_euclid(self.size[axis]))
     }
 
-    #[wasm_bindgen]
-    pub fn set_color1_hex(&mut self, hex: &str) {
-        if let Some(color) = parse_hex_color(hex) {
-            self.color1 = color;
-        }
+    // The copy of `to`, shifted by whole world sizes, nearest to `from`
+    pub fn nearest(&self, from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+        [0, 1].map(|axis| {
+            let delta = to[axis] - from[axis];
+            from[axis] + delta - self.size[axis] * (delta / self.size[axis]).round()
+        })
     }
 
-    #[wasm_bindgen]
-    pub fn set_color2_hex(&mut self, hex: &str) {
-        if let Some(color) = parse_hex_color(hex) {
-            self.color2 = color;
-        }
+    // Whether the shortest way from `from` to `to` crosses a seam
+    pub fn crosses_seam(&self, from: [f32; 2], to: [f32; 2]) -> bool {
+        (0..2).any(|axis| (to[axis] - from[axis]).abs() > self.size[axis] * 0.5)
     }
+}
 
-    #[wasm_bindgen]
-    pub fn set_nodes(&mut self, node_data: &[f32]) {
-        self.nodes.clear();
-
-        // Each node has 7 floats: x, y, r, g, b, a, size
-        let stride = 7;
-        let node_count = node_data.len() / stride;
+// How node-node repulsion is computed
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RepulsionMethod {
+    Grid,      // uniform grid, only nearby nodes within repulsion_radius (fast)
+    BarnesHut, // quadtree approximation of all-pairs repulsion
+}
 
-        for i in 0..node_count {
-            let base = i * stride;
-            if base + stride <= node_data.len() {
-                self.nodes.push(NodeData {
-                    x: node_data[base],
-                    y: node_data[base + 1],
-                    vx: 0.0,
-                    vy: 0.0,
-                    fx: 0.0,
-                    fy: 0.0,
-                    r: node_data[base + 2],
-                    g: node_data[base + 3],
-                    b: node_data[base + 4],
-                    a: node_data[base + 5],
-                    size: node_data[base + 6],
-                    mass: 1.0,
-                });
-            }
+impl RepulsionMethod {
+    pub fn parse(method: &str) -> Option<Self> {
+        match method {
+            "grid" => Some(Self::Grid),
+            "barnes-hut" | "barnes_hut" => Some(Self::BarnesHut),
+            _ => None,
         }
-
-        // log!("Updated nodes: {} nodes", self.nodes.len());
     }
+}
 
-    #[wasm_bindgen]
-    pub fn set_edges(&mut self, edge_data: &[f32]) {
-        self.edges.clear();
-
-        // Each edge has 9 floats: x1, y1, x2, y2, r, g, b, a, width
-        let stride = 9;
-        let edge_count = edge_data.len() / stride;
+// Force model driving the simulation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LayoutAlgorithm {
+    SpringElectric, // springs along edges plus repulsion, on the GPU when available
+    ForceAtlas2,    // Gephi's ForceAtlas2, always on the CPU
+}
 
-        for i in 0..edge_count {
-            let base = i * stride;
-            if base + stride <= edge_data.len() {
-                self.edges.push(EdgeData {
-                    x1: edge_data[base],
-                    y1: edge_data[base + 1],
-                    x2: edge_data[base + 2],
-                    y2: edge_data[base + 3],
-                    r: edge_data[base + 4],
-                    g: edge_data[base + 5],
-                    b: edge_data[base + 6],
-                    a: edge_data[base + 7],
-                    width: edge_data[base + 8],
-                });
-            }
+impl LayoutAlgorithm {
+    pub fn parse(algorithm: &str) -> Option<Self> {
+        match algorithm {
+            "spring-electric" | "spring_electric" => Some(Self::SpringElectric),
+            "forceatlas2" => Some(Self::ForceAtlas2),
+            _ => None,
         }
-
-        // log!("Updated edges: {} edges", self.edges.len());
-    }
-
-    #[wasm_bindgen]
-    pub fn set_camera_position(&mut self, x: f32, y: f32) {
-        self.camera_position = [x, y];
-    }
-
-    #[wasm_bindgen]
-    pub fn set_camera_zoom(&mut self, zoom: f32) {
-        self.camera_zoom = zoom.max(0.1).min(10.0); // Clamp zoom between 0.1x and 10x
-    }
-
-    #[wasm_bindgen]
-    pub fn get_camera_position_x(&self) -> f32 {
-        self.camera_position[0]
-    }
-
-    #[wasm_bindgen]
-    pub fn get_camera_position_y(&self) -> f32 {
-        self.camera_position[1]
-    }
-
-    #[wasm_bindgen]
-    pub fn get_camera_zoom(&self) -> f32 {
-        self.camera_zoom
-    }
-
-    #[wasm_bindgen]
-    pub fn reset_camera(&mut self) {
-        self.camera_position = [0.0, 0.0];
-        self.camera_zoom = 1.0;
-    }
-
-    #[wasm_bindgen]
-    pub fn get_max_nodes(&self) -> u32 {
-        MAX_NODES as u32
     }
+}
 
-    #[wasm_bindgen]
-    pub fn get_max_edges(&self) -> u32 {
-        MAX_EDGES as u32
-    }
+// Simulation parameters consumed by the physics passes. The first six are
+// supplied on every `integrate_physics` call, the rest persist between ticks.
+#[derive(Clone, Debug)]
+pub struct PhysicsSettings {
+    pub delta_time: f32,
+    pub damping_factor: f32,
+    pub spring_constant: f32,
+    pub rest_length: f32,
+    pub repulsion_strength: f32,
+    pub repulsion_radius: f32,
+    pub gravity_strength: f32, // 0 disables gravity
+    pub gravity_center: [f32; 2],
+    pub gravity_mode: GravityMode,
+    pub alpha: f32,        // cooling factor multiplying all forces
+    pub alpha_decay: f32,  // fraction of the distance to alpha_target covered per tick
+    pub alpha_min: f32,    // the simulation pauses once alpha falls below this
+    pub alpha_target: f32,
+    pub repulsion_method: RepulsionMethod,
+    pub theta: f32, // Barnes-Hut opening criterion: cell width / distance
+    pub world_bounds: Option<[f32; 4]>, // fixed grid bounds (min_x, min_y, max_x, max_y), None fits the nodes
+    pub group_attraction: f32, // pull towards the group centroid, 0 disables it
+    pub bounds: Option<BoundsConstraint>,
+    pub deterministic: bool, // order-stable forces and lockstep ticks, see set_deterministic
+    pub world_wrap: bool, // periodic world_bounds, see set_world_wrap
+}
 
-    #[wasm_bindgen]
-    pub fn get_current_node_count(&self) -> u32 {
-        self.nodes.len() as u32
+impl PhysicsSettings {
+    pub fn is_active(&self) -> bool {
+        self.alpha >= self.alpha_min
     }
 
-    #[wasm_bindgen]
-    pub fn get_current_edge_count(&self) -> u32 {
-        self.edges.len() as u32
+    // d3-style exponential cooling towards alpha_target
+    pub fn decay_alpha(&mut self) {
+        self.alpha += (self.alpha_target - self.alpha) * self.alpha_decay;
     }
 
-    #[wasm_bindgen]
-    pub fn integrate_physics(
-        &mut self,
-        delta_time: f32,
-        damping_factor: f32,
-        spring_constant: f32,
-        rest_length: f32,
-        repulsion_strength: f32,
-        repulsion_radius: f32,
-    ) -> Result<(), JsValue> {
-        if !self.is_initialized {
-            return Err(JsValue::from_str("Renderer not initialized"));
-        }
-
-        // Convert current nodes to the right format for physics integration
-        let mut physics_nodes: Vec<NodeData> = self
-            .nodes
-            .iter()
-            .map(|node| NodeData {
-                x: node.x,
-                y: node.y,
-                vx: node.vx,
-                vy: node.vy,
-                fx: node.fx,
-                fy: node.fy,
-                r: node.r,
-                g: node.g,
-                b: node.b,
-                a: node.a,
-                size: node.size,
-                mass: node.mass,
-            })
-            .collect();
-
-        // Prepare edge data for physics integration
-        let physics_edges: Vec<EdgeData> = self.edges.clone();
-
-        // Run physics integration on GPU
-        self.renderer.integrate_physics(
-            &mut physics_nodes,
-            &physics_edges,
-            delta_time,
-            damping_factor,
-            spring_constant,
-            rest_length,
-            repulsion_strength,
-            repulsion_radius,
-        )?;
-
-        // Update internal nodes with GPU results
-        for (i, physics_node) in physics_nodes.iter().enumerate() {
-            if i < self.nodes.len() {
-                self.nodes[i].x = physics_node.x;
-                self.nodes[i].y = physics_node.y;
-                self.nodes[i].vx = physics_node.vx;
-                self.nodes[i].vy = physics_node.vy;
-                self.nodes[i].fx = physics_node.fx;
-                self.nodes[i].fy = physics_node.fy;
-            }
-        }
-
-        Ok(())
+    // The periodic world, when wrapping is on and world bounds are set
+    pub fn wrap(&self) -> Option<WorldWrap> {
+        let [min_x, min_y, max_x, max_y] = self.world_bounds.filter(|_| self.world_wrap)?;
+        Some(WorldWrap { min: [min_x, min_y], size: [max_x - min_x, max_y - min_y] })
     }
 }
 
-fn parse_hex_color(hex: &str) -> Option<[f32; 4]> {
-    let hex = hex.trim_start_matches('#');
-
-    match hex.len() {
-        6 => {
-            // RGB format
-            if let (Ok(r), Ok(g), Ok(b)) = (
-                u8::from_str_radix(&hex[0..2], 16),
-                u8::from_str_radix(&hex[2..4], 16),
-                u8::from_str_radix(&hex[4..6], 16),
-            ) {
-                Some([r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0])
-            } else {
-                None
-            }
-        }
-        8 => {
-            // RGBA format
-            if let (Ok(r), Ok(g), Ok(b), Ok(a)) = (
-                u8::from_str_radix(&hex[0..2], 16),
-                u8::from_str_radix(&hex[2..4], 16),
-                u8::from_str_radix(&hex[4..6], 16),
-                u8::from_str_radix(&hex[6..8], 16),
-            ) {
-                Some([
-                    r as f32 / 255.0,
-                    g as f32 / 255.0,
-                    b as f32 / 255.0,
-                    a as f32 / 255.0,
-                ])
-            } else {
-                None
-            }
-        }
-        3 => {
-            // Short RGB format
-            if let (Ok(r), Ok(g), Ok(b)) = (
-                u8::from_str_radix(&hex[0..1], 16),
-                u8::from_str_radix(&hex[1..2], 16),
-                u8::from_str_radix(&hex[2..3], 16),
-            ) {
-                Some([
-                    (r * 17) as f32 / 255.0, // Convert single digit to double
-                    (g * 17) as f32 / 255.0,
-                    (b * 17) as f32 / 255.0,
-                    1.0,
-                ])
-            } else {
-                None
-            }
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        Self {
+            delta_time: 0.016,
+            damping_factor: 0.9,
+            spring_constant: 0.1,
+            rest_length: 50.0,
+            repulsion_strength: 0.05,
+            repulsion_radius: 0.5,
+            gravity_strength: 0.0,
+            gravity_center: [0.0, 0.0],
+            gravity_mode: GravityMode::Linear,
+            alpha: 1.0,
+            alpha_decay: 1.0 - 0.001f32.powf(1.0 / 300.0), // ~300 ticks to cool down
+            alpha_min: 0.001,
+            alpha_target: 0.0,
+            repulsion_method: RepulsionMethod::Grid,
+            theta: 0.9,
+            world_bounds: None,
+            group_attraction: 0.0,
+            bounds: None,
+            deterministic: false,
+            world_wrap: false,
         }
-        _ => None,
     }
 }
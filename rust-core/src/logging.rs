@@ -0,0 +1,79 @@
+// Console logging with a global level, shared by every renderer instance. The
+// macros check the level before formatting, so disabled messages cost nothing.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+#[cfg(target_arch = "wasm32")]
+use web_sys::console;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off = 0,
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+}
+
+impl LogLevel {
+    pub fn parse(level: &str) -> Option<Self> {
+        match level {
+            "off" => Some(Self::Off),
+            "error" => Some(Self::Error),
+            "warn" => Some(Self::Warn),
+            "info" => Some(Self::Info),
+            "debug" => Some(Self::Debug),
+            _ => None,
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+pub fn set_level(level: LogLevel) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: LogLevel) -> bool {
+    level != LogLevel::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(level: LogLevel, message: &str) {
+    let message = message.into();
+    match level {
+        LogLevel::Error => console::error_1(&message),
+        LogLevel::Warn => console::warn_1(&message),
+        _ => console::log_1(&message),
+    }
+}
+
+// Native builds (tests, the native viewer) log to stderr
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(level: LogLevel, message: &str) {
+    eprintln!("[{:?}] {}", level, message);
+}
+
+macro_rules! log_at {
+    ( $level:expr, $( $t:tt )* ) => {
+        if $crate::logging::enabled($level) {
+            $crate::logging::write($level, &format!( $( $t )* ));
+        }
+    }
+}
+
+// `println!(..)`-style logging at info level
+macro_rules! log {
+    ( $( $t:tt )* ) => { log_at!($crate::logging::LogLevel::Info, $( $t )*) }
+}
+
+macro_rules! log_debug {
+    ( $( $t:tt )* ) => { log_at!($crate::logging::LogLevel::Debug, $( $t )*) }
+}
+
+macro_rules! log_warn {
+    ( $( $t:tt )* ) => { log_at!($crate::logging::LogLevel::Warn, $( $t )*) }
+}
+
+macro_rules! log_error {
+    ( $( $t:tt )* ) => { log_at!($crate::logging::LogLevel::Error, $( $t )*) }
+}
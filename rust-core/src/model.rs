@@ -0,0 +1,350 @@
+// A graph with positions and a CPU simulation but no renderer, for computing
+// layouts outside the browser (e.g. in a service) and shipping only positions.
+// The methods mirror the FastGraphRenderer ones of the same name, without
+// animation; the edges are the indexed edges there.
+
+use crate::algorithms;
+use crate::forceatlas2::ForceAtlas2;
+use crate::graph::Adjacency;
+use crate::groups::NodeGroups;
+use crate::layout::{self, LayerDirection};
+use crate::physics::{self, PhysicsInputs};
+use crate::{LayoutAlgorithm, NodeData, PhysicsSettings, NODE_HIDDEN_FLAGS};
+
+pub struct Graph {
+    nodes: Vec<NodeData>,
+    edges: Vec<[u32; 2]>,
+    edge_lengths: Vec<f32>, // per-edge rest lengths, <= 0 uses the global one
+    edge_weights: Vec<f32>, // per-edge spring multipliers, empty means all 1
+    groups: Option<NodeGroups>,
+    node_damping: Vec<f32>, // per-node damping factors, 0 or empty uses the global one
+    adjacency: Option<Adjacency>, // built from `edges` on first use
+    pub physics: PhysicsSettings,
+    pub layout_algorithm: LayoutAlgorithm,
+    pub forceatlas2: ForceAtlas2,
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            edge_lengths: Vec::new(),
+            edge_weights: Vec::new(),
+            groups: None,
+            node_damping: Vec::new(),
+            adjacency: None,
+            physics: PhysicsSettings::default(),
+            layout_algorithm: LayoutAlgorithm::SpringElectric,
+            forceatlas2: ForceAtlas2::default(),
+        }
+    }
+}
+
+impl Graph {
+    pub fn new(nodes: Vec<NodeData>, edges: Vec<[u32; 2]>) -> Result<Self, String> {
+        let mut graph = Self { nodes, ..Self::default() };
+        graph.set_edges(edges)?;
+        Ok(graph)
+    }
+
+    pub fn nodes(&self) -> &[NodeData] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut [NodeData] {
+        &mut self.nodes
+    }
+
+    pub fn edges(&self) -> &[[u32; 2]] {
+        &self.edges
+    }
+
+    pub fn positions(&self) -> Vec<[f32; 2]> {
+        self.nodes.iter().map(|node| [node.x, node.y]).collect()
+    }
+
+    // Replaces the edges, dropping per-edge lengths and weights
+    pub fn set_edges(&mut self, edges: Vec<[u32; 2]>) -> Result<(), String> {
+        self.check_node_indices(edges.iter().flatten())?;
+        self.edges = edges;
+        self.edge_lengths.clear();
+        self.edge_weights.clear();
+        self.adjacency = None;
+        Ok(())
+    }
+
+    pub fn set_edge_lengths(&mut self, lengths: &[f32]) -> Result<(), String> {
+        self.check_edge_values("edge lengths", lengths)?;
+        self.edge_lengths = lengths.to_vec();
+        Ok(())
+    }
+
+    pub fn set_edge_weights(&mut self, weights: &[f32]) -> Result<(), String> {
+        self.check_edge_values("edge weights", weights)?;
+        self.edge_weights = weights.iter().map(|&weight| if weight.is_finite() { weight.max(0.0) } else { 1.0 }).collect();
+        Ok(())
+    }
+
+    // Negative ids leave a node ungrouped
+    pub fn set_node_groups(&mut self, groups: &[i32]) -> Result<(), String> {
+        if groups.len() != self.nodes.len() {
+            return Err(format!("Expected {} node groups, got {}", self.nodes.len(), groups.len()));
+        }
+        self.groups = Some(NodeGroups::new(groups));
+        Ok(())
+    }
+
+    pub fn set_node_damping(&mut self, values: &[f32]) -> Result<(), String> {
+        if values.len() != self.nodes.len() {
+            return Err(format!("Expected {} node damping values, got {}", self.nodes.len(), values.len()));
+        }
+        self.node_damping = values.iter().map(|&value| if value > 0.0 { value.min(1.0) } else { 0.0 }).collect();
+        Ok(())
+    }
+
+    pub fn apply_circular_layout(&mut self, radius: f32, order: &[u32]) -> Result<(), String> {
+        self.apply_arc_layout(radius, 0.0, 2.0 * std::f32::consts::PI, order)
+    }
+
+    pub fn apply_arc_layout(&mut self, radius: f32, start_angle: f32, end_angle: f32, order: &[u32]) -> Result<(), String> {
+        let order = layout::layout_order(self.nodes.len(), order)?;
+        let positions = layout::circular_positions(order.len(), radius, start_angle, end_angle);
+        self.place_nodes(order.into_iter().zip(positions));
+        Ok(())
+    }
+
+    pub fn apply_grid_layout(&mut self, columns: u32, cell_width: f32, cell_height: f32, skip_hidden: bool) {
+        let indices: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| !(skip_hidden && self.nodes[i].flags & NODE_HIDDEN_FLAGS != 0))
+            .collect();
+        let positions = layout::grid_positions(indices.len(), columns as usize, cell_width, cell_height);
+        self.place_nodes(indices.into_iter().zip(positions));
+    }
+
+    // Returns how many edges were reversed to break cycles
+    pub fn apply_layered_layout(&mut self, direction: LayerDirection, layer_gap: f32, node_gap: f32) -> u32 {
+        let (positions, reversed) = layout::layered_positions(self.nodes.len(), &self.edges, layer_gap, node_gap);
+        let positions = positions.into_iter().map(|position| direction.orient(position));
+        self.place_nodes(positions.enumerate());
+        reversed as u32
+    }
+
+    pub fn apply_radial_layout(&mut self, root_index: u32, ring_spacing: f32, place_unreachable: bool) -> Result<(), String> {
+        self.check_node_indices([root_index].iter())?;
+        let placed = layout::radial_layout(self.adjacency(), root_index as usize, ring_spacing, place_unreachable);
+        self.place_nodes(placed);
+        Ok(())
+    }
+
+    pub fn apply_concentric_layout(&mut self, values: &[f32], levels: u32, ring_spacing: f32) -> Result<(), String> {
+        if values.len() != self.nodes.len() {
+            return Err(format!("Expected {} values, got {}", self.nodes.len(), values.len()));
+        }
+        if let Some(index) = values.iter().position(|value| !value.is_finite()) {
+            return Err(format!("Value for node {} is not finite", index));
+        }
+
+        let radii: Vec<f32> = self.nodes.iter().map(|node| node.size).collect();
+        let positions = layout::concentric_positions(values, &radii, levels as usize, ring_spacing);
+        self.place_nodes(positions.into_iter().enumerate());
+        Ok(())
+    }
+
+    pub fn apply_bipartite_layout(&mut self, partition: &[u32], column_gap: f32, node_gap: f32, reduce_crossings: bool) -> Result<(), String> {
+        if partition.len() != self.nodes.len() {
+            return Err(format!("Expected {} partition values, got {}", self.nodes.len(), partition.len()));
+        }
+        if let Some(index) = partition.iter().position(|&side| side > 1) {
+            return Err(format!("Partition of node {} must be 0 or 1, got {}", index, partition[index]));
+        }
+
+        let positions = layout::bipartite_positions(partition, &self.edges, column_gap, node_gap, reduce_crossings);
+        self.place_nodes(positions.into_iter().enumerate());
+        Ok(())
+    }
+
+    // Runs up to `ticks` CPU ticks, cooling alpha as the renderer does, and
+    // returns how many ran before the simulation settled
+    pub fn step_simulation(&mut self, ticks: u32) -> u32 {
+        let mut ran = 0;
+        while ran < ticks && self.physics.is_active() {
+            let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edges));
+            if self.layout_algorithm == LayoutAlgorithm::ForceAtlas2 {
+                self.forceatlas2.step(&mut self.nodes, adjacency, &self.edge_weights, self.physics.alpha);
+            } else {
+                let inputs = PhysicsInputs {
+                    adjacency: Some(adjacency),
+                    edge_lengths: &self.edge_lengths,
+                    edge_weights: &self.edge_weights,
+                    groups: self.groups.as_ref(),
+                    damping: &self.node_damping,
+                    excluded: &[],
+                    frozen: &[],
+                };
+                physics::step(&mut self.nodes, &inputs, &self.physics);
+            }
+            self.physics.decay_alpha();
+            ran += 1;
+        }
+        ran
+    }
+
+    // Restarts a settled simulation at the given alpha
+    pub fn reheat(&mut self, alpha: f32) {
+        self.physics.alpha = alpha;
+        self.forceatlas2.reset();
+    }
+
+    pub fn compute_components(&self) -> Vec<u32> {
+        algorithms::connected_components(self.nodes.len(), &self.edges).0
+    }
+
+    pub fn detect_communities(&self, resolution: f32, weighted: bool, seed: u64) -> Vec<u32> {
+        let weights = weighted.then_some(self.edge_weights.as_slice());
+        algorithms::louvain(self.nodes.len(), &self.edges, weights, resolution, seed)
+    }
+
+    pub fn shortest_path(&mut self, source: u32, target: u32, weighted: bool) -> Vec<u32> {
+        let costs = weighted.then_some(self.edge_weights.as_slice());
+        let adjacency = self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edges));
+        algorithms::shortest_path(adjacency, source as usize, target as usize, costs)
+    }
+
+    pub fn compute_pagerank(&self, damping: f32, iterations: u32) -> Vec<f32> {
+        algorithms::pagerank(self.nodes.len(), &self.edges, damping, iterations)
+    }
+
+    pub fn compute_core_numbers(&mut self) -> Vec<u32> {
+        algorithms::core_numbers(self.adjacency())
+    }
+
+    fn adjacency(&mut self) -> &Adjacency {
+        self.adjacency.get_or_insert_with(|| Adjacency::new(self.nodes.len(), &self.edges))
+    }
+
+    fn check_node_indices<'a>(&self, mut indices: impl Iterator<Item = &'a u32>) -> Result<(), String> {
+        match indices.find(|&&index| index as usize >= self.nodes.len()) {
+            Some(index) => Err(format!("Node index {} out of range ({} nodes)", index, self.nodes.len())),
+            None => Ok(()),
+        }
+    }
+
+    fn check_edge_values(&self, what: &str, values: &[f32]) -> Result<(), String> {
+        if values.len() != self.edges.len() {
+            return Err(format!("Expected {} {}, got {}", self.edges.len(), what, values.len()));
+        }
+        Ok(())
+    }
+
+    // Moves nodes to new positions at rest, like FastGraphRenderer::place_nodes
+    fn place_nodes(&mut self, positions: impl IntoIterator<Item = (usize, [f32; 2])>) {
+        for (index, [x, y]) in positions {
+            if let Some(node) = self.nodes.get_mut(index) {
+                node.x = x;
+                node.y = y;
+                node.vx = 0.0;
+                node.vy = 0.0;
+                node.fx = 0.0;
+                node.fy = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(x: f32, y: f32) -> NodeData {
+        NodeData { x, y, a: 1.0, size: 5.0, mass: 1.0, ..bytemuck::Zeroable::zeroed() }
+    }
+
+    // A ring of `count` nodes with a chord across, scattered so the simulation
+    // has something to do
+    fn ring(count: usize) -> Graph {
+        let nodes = (0..count).map(|i| node((i * 37 % 101) as f32 * 3.0, (i * 53 % 97) as f32 * 3.0)).collect();
+        let mut edges: Vec<[u32; 2]> = (0..count as u32).map(|i| [i, (i + 1) % count as u32]).collect();
+        edges.push([0, count as u32 / 2]);
+        Graph::new(nodes, edges).unwrap()
+    }
+
+    #[test]
+    fn new_rejects_edges_to_missing_nodes() {
+        assert!(Graph::new(vec![node(0.0, 0.0); 3], vec![[0, 2]]).is_ok());
+        let error = Graph::new(vec![node(0.0, 0.0); 3], vec![[0, 1], [1, 3]]).err().unwrap();
+        assert_eq!(error, "Node index 3 out of range (3 nodes)");
+    }
+
+    #[test]
+    fn per_edge_and_per_node_values_must_match_the_counts() {
+        let mut graph = ring(4);
+        assert!(graph.set_edge_lengths(&[10.0; 5]).is_ok());
+        assert!(graph.set_edge_lengths(&[10.0; 4]).is_err());
+        assert!(graph.set_edge_weights(&[1.0; 6]).is_err());
+        assert!(graph.set_node_groups(&[0, 0, 1]).is_err());
+        assert!(graph.set_node_damping(&[0.5; 4]).is_ok());
+        // Replacing the edges drops the per-edge values
+        graph.set_edges(vec![[0, 1]]).unwrap();
+        assert!(graph.set_edge_lengths(&[10.0]).is_ok());
+    }
+
+    #[test]
+    fn circular_layout_puts_nodes_evenly_on_the_circle_in_order() {
+        let mut graph = ring(4);
+        graph.nodes_mut()[3].vx = 5.0;
+        graph.apply_circular_layout(100.0, &[3, 1]).unwrap();
+
+        // Listed nodes first, then the rest by index, counterclockwise from +x
+        let expected = [(3, [100.0, 0.0]), (1, [0.0, 100.0]), (0, [-100.0, 0.0]), (2, [0.0, -100.0])];
+        for (index, [x, y]) in expected {
+            let node = graph.nodes()[index];
+            assert!((node.x - x).abs() < 1e-3 && (node.y - y).abs() < 1e-3, "node {index} at ({}, {})", node.x, node.y);
+        }
+        assert_eq!(graph.nodes()[3].vx, 0.0);
+    }
+
+    #[test]
+    fn circular_layout_rejects_bad_orders() {
+        let mut graph = ring(4);
+        assert!(graph.apply_circular_layout(100.0, &[1, 1]).is_err());
+        assert!(graph.apply_circular_layout(100.0, &[4]).is_err());
+    }
+
+    #[test]
+    fn step_simulation_converges_and_then_stops() {
+        let mut graph = ring(40);
+        graph.physics.delta_time = 1.0;
+        graph.physics.spring_constant = 0.05;
+        let mean_edge_length = |graph: &Graph| {
+            let lengths = graph.edges().iter().map(|&[a, b]| {
+                let (a, b) = (graph.nodes()[a as usize], graph.nodes()[b as usize]);
+                (a.x - b.x).hypot(a.y - b.y)
+            });
+            lengths.sum::<f32>() / graph.edges().len() as f32
+        };
+        let before = mean_edge_length(&graph);
+
+        let ran = graph.step_simulation(10_000);
+        assert!(ran > 0 && ran < 10_000, "ran {ran} ticks");
+        assert!(!graph.physics.is_active());
+        assert!(graph.positions().iter().flatten().all(|value| value.is_finite()));
+        assert_eq!(graph.step_simulation(10), 0);
+        // The scattered ring's springs pulled its neighbors together
+        let after = mean_edge_length(&graph);
+        assert!(after < before * 0.8, "mean edge length {before} -> {after}");
+
+        graph.reheat(1.0);
+        assert_eq!(graph.step_simulation(5), 5);
+    }
+
+    #[test]
+    fn algorithms_run_on_the_model() {
+        let mut graph = ring(6);
+        assert_eq!(graph.compute_components(), vec![0; 6]);
+        assert_eq!(graph.shortest_path(0, 3, false), vec![0, 3]);
+        assert_eq!(graph.compute_core_numbers(), vec![2; 6]);
+        let ranks = graph.compute_pagerank(0.85, 50);
+        assert!((ranks.iter().sum::<f32>() - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,40 @@
+// Morton (Z-order) codes: points are quantized to a grid over their bounds and
+// the bits of the two cell coordinates interleaved, so sorting by code keeps
+// points in nearby cells next to each other.
+
+// Cells per axis, as a power of two; two axes fill a u32
+const CELL_BITS: u32 = 16;
+
+// The code of each point, non-finite points getting u32::MAX so they sort last
+pub fn codes(points: &[[f32; 2]]) -> Vec<u32> {
+    let finite = |point: &&[f32; 2]| point[0].is_finite() && point[1].is_finite();
+    let (mut min, mut max) = ([f32::INFINITY; 2], [f32::NEG_INFINITY; 2]);
+    for point in points.iter().filter(finite) {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(point[axis]);
+            max[axis] = max[axis].max(point[axis]);
+        }
+    }
+    let cells = ((1u32 << CELL_BITS) - 1) as f32;
+    let scale = [0, 1].map(|axis| if max[axis] > min[axis] { cells / (max[axis] - min[axis]) } else { 0.0 });
+
+    points
+        .iter()
+        .map(|point| {
+            if !finite(&point) {
+                return u32::MAX;
+            }
+            let [x, y] = [0, 1].map(|axis| ((point[axis] - min[axis]) * scale[axis]).clamp(0.0, cells) as u32);
+            spread(x) | spread(y) << 1
+        })
+        .collect()
+}
+
+// Moves the low 16 bits of `value` to the even bits
+fn spread(value: u32) -> u32 {
+    let mut value = value & 0xffff;
+    value = (value | value << 8) & 0x00ff_00ff;
+    value = (value | value << 4) & 0x0f0f_0f0f;
+    value = (value | value << 2) & 0x3333_3333;
+    (value | value << 1) & 0x5555_5555
+}
@@ -0,0 +1,69 @@
+// Built-in color palettes: categorical sets for classes (components,
+// communities, groups) and continuous colormaps for scalar values
+
+use crate::color::rgb24;
+
+const TABLEAU10: [u32; 10] = [
+    0x4e79a7, 0xf28e2b, 0xe15759, 0x76b7b2, 0x59a14f, 0xedc948, 0xb07aa1, 0xff9da7, 0x9c755f, 0xbab0ac,
+];
+// ColorBrewer Set3 and Pastel1
+const SET3: [u32; 12] = [
+    0x8dd3c7, 0xffffb3, 0xbebada, 0xfb8072, 0x80b1d3, 0xfdb462, 0xb3de69, 0xfccde5, 0xd9d9d9, 0xbc80bd, 0xccebc5, 0xffed6f,
+];
+const PASTEL: [u32; 9] = [0xfbb4ae, 0xb3cde3, 0xccebc5, 0xdecbe4, 0xfed9a6, 0xffffcc, 0xe5d8bd, 0xfddaec, 0xf2f2f2];
+
+// Colormaps as evenly spaced stops, interpolated linearly in between
+const VIRIDIS: [u32; 11] = [
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c, 0x22a884, 0x44bf70, 0x7ad151, 0xbddf26, 0xfde725,
+];
+const PLASMA: [u32; 11] = [
+    0x0d0887, 0x41049d, 0x6a00a8, 0x8f0da4, 0xb12a90, 0xcc4778, 0xe16462, 0xf2844b, 0xfca636, 0xfcce25, 0xf0f921,
+];
+const INFERNO: [u32; 11] = [
+    0x000004, 0x160b39, 0x420a68, 0x6a176e, 0x932667, 0xbc3754, 0xdd513a, 0xf37819, 0xfca50a, 0xf6d746, 0xfcffa4,
+];
+const COOLWARM: [u32; 9] = [0x3b4cc0, 0x6282ea, 0x8db0fe, 0xb8d0f9, 0xdddddd, 0xf5c4ad, 0xf49a7b, 0xde604d, 0xb40426];
+
+pub fn categorical(name: &str) -> Option<&'static [u32]> {
+    match name {
+        "tableau10" => Some(&TABLEAU10),
+        "set3" => Some(&SET3),
+        "pastel" => Some(&PASTEL),
+        _ => None,
+    }
+}
+
+// A continuous colormap by name; the "_r" suffix reverses it
+pub struct Colormap {
+    stops: &'static [u32],
+    reversed: bool,
+}
+
+impl Colormap {
+    pub fn parse(name: &str) -> Option<Self> {
+        let (base, reversed) = match name.strip_suffix("_r") {
+            Some(base) => (base, true),
+            None => (name, false),
+        };
+        let stops: &'static [u32] = match base {
+            "viridis" => &VIRIDIS,
+            "plasma" => &PLASMA,
+            "inferno" => &INFERNO,
+            "coolwarm" => &COOLWARM,
+            _ => return None,
+        };
+        Some(Self { stops, reversed })
+    }
+
+    // Color at `t` in [0, 1] (clamped)
+    pub fn sample(&self, t: f32) -> [f32; 4] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let t = if self.reversed { 1.0 - t } else { t };
+
+        let position = t * (self.stops.len() - 1) as f32;
+        let index = (position.floor() as usize).min(self.stops.len() - 2);
+        let fraction = position - index as f32;
+        let (low, high) = (rgb24(self.stops[index]), rgb24(self.stops[index + 1]));
+        std::array::from_fn(|channel| low[channel] + (high[channel] - low[channel]) * fraction)
+    }
+}
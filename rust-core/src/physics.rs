@@ -0,0 +1,682 @@
+// CPU implementation of the force simulation, used when the device has no usable
+// compute shaders (WebGL2, headless without WebGPU). Mirrors PHYSICS_SHADER.
+//
+// The grid repulsion and the springs, which dominate a tick, process four
+// neighbors at a time (see simd.rs); step_scalar keeps the one-at-a-time
+// versions as a baseline for examples/physics_bench.rs.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::Adjacency;
+use crate::groups::{NodeGroups, NO_GROUP};
+use crate::quadtree::{QuadTree, QUAD_NO_BODY};
+use crate::simd::{F32x4, LANES};
+use crate::{BoundsMode, GravityMode, NodeData, PhysicsSettings, RepulsionMethod, WorldWrap, BOUNDS_FORCE_STRENGTH, BOUNDS_RESTITUTION, NODE_FLAG_PINNED, NODE_PHYSICS_EXCLUDED_FLAGS};
+
+// Graph data the forces depend on besides the nodes themselves
+pub struct PhysicsInputs<'a> {
+    pub adjacency: Option<&'a Adjacency>,
+    pub edge_lengths: &'a [f32],
+    pub edge_weights: &'a [f32],
+    pub groups: Option<&'a NodeGroups>,
+    pub damping: &'a [f32], // per-node damping factors, missing or non-positive means the global one
+    pub excluded: &'a [bool], // nodes left out besides those their flags exclude, e.g. by the visibility filter
+    pub frozen: &'a [bool], // participants that exert forces but hold still, e.g. outside a local reheat
+}
+
+// Whether each node takes part in the simulation: neither excluded by its flags
+// (hidden, or NODE_FLAG_NO_PHYSICS) nor by `excluded`. Nodes that don't are
+// left exactly as they are, velocities included.
+pub fn participants(nodes: &[NodeData], excluded: &[bool]) -> Vec<bool> {
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| node.flags & NODE_PHYSICS_EXCLUDED_FLAGS == 0 && !excluded.get(index).copied().unwrap_or(false))
+        .collect()
+}
+
+// Whether forces on each node matter: pinned and frozen participants exert
+// them but don't move
+fn movers(nodes: &[NodeData], active: &[bool], frozen: &[bool]) -> Vec<bool> {
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| active[index] && node.flags & NODE_FLAG_PINNED == 0 && !frozen.get(index).copied().unwrap_or(false))
+        .collect()
+}
+
+// Runs one tick: accumulates all forces, then integrates velocities and positions
+pub fn step(nodes: &mut [NodeData], inputs: &PhysicsInputs, settings: &PhysicsSettings) {
+    run_step(nodes, inputs, settings, true);
+}
+
+// The same tick without the four-lane passes
+pub fn step_scalar(nodes: &mut [NodeData], inputs: &PhysicsInputs, settings: &PhysicsSettings) {
+    run_step(nodes, inputs, settings, false);
+}
+
+fn run_step(nodes: &mut [NodeData], inputs: &PhysicsInputs, settings: &PhysicsSettings, vectorized: bool) {
+    let mut forces = vec![[0.0f32; 2]; nodes.len()];
+    let active = participants(nodes, inputs.excluded);
+    let moving = movers(nodes, &active, inputs.frozen);
+
+    match settings.repulsion_method {
+        RepulsionMethod::Grid if vectorized => add_grid_repulsion_lanes(nodes, &active, &moving, settings, &mut forces),
+        RepulsionMethod::Grid => add_grid_repulsion(nodes, &active, &moving, settings, &mut forces),
+        RepulsionMethod::BarnesHut => add_barnes_hut_repulsion(nodes, &active, &moving, settings, &mut forces),
+    }
+    match inputs.adjacency {
+        Some(adjacency) if vectorized => add_springs_lanes(nodes, &active, &moving, adjacency, inputs, settings, &mut forces),
+        Some(adjacency) => add_springs(nodes, &active, &moving, adjacency, inputs, settings, &mut forces),
+        None => {}
+    }
+
+    let centroids = match inputs.groups {
+        Some(groups) if settings.group_attraction > 0.0 => Some((groups, groups.centroids(nodes))),
+        _ => None,
+    };
+
+    // Integration stays scalar in both paths: it branches per node (pins, groups,
+    // bounds, wrapping) and NodeData is interleaved in its GPU layout, so lanes
+    // would be gathered and scattered field by field. It's about 2% of a tick.
+    for (index, node) in nodes.iter_mut().enumerate() {
+        // Frozen nodes are left as they are, like excluded ones
+        if !active[index] || inputs.frozen.get(index).copied().unwrap_or(false) {
+            continue;
+        }
+        // Pinned nodes hold still but keep exerting forces on the others
+        if node.flags & NODE_FLAG_PINNED != 0 {
+            node.vx = 0.0;
+            node.vy = 0.0;
+            node.fx = 0.0;
+            node.fy = 0.0;
+            continue;
+        }
+
+        let mut force = forces[index];
+        let gravity = gravity_force(node, settings);
+        force[0] += gravity[0];
+        force[1] += gravity[1];
+
+        if let Some((groups, centroids)) = &centroids {
+            let slot = groups.slots().get(index).copied().unwrap_or(NO_GROUP);
+            if let Some(centroid) = centroids.get(slot as usize) {
+                force[0] += (centroid[0] - node.x) * settings.group_attraction;
+                force[1] += (centroid[1] - node.y) * settings.group_attraction;
+            }
+        }
+
+        if let Some(bounds) = settings.bounds.filter(|bounds| bounds.mode == BoundsMode::Force) {
+            force[0] += (node.x.clamp(bounds.min[0], bounds.max[0]) - node.x) * BOUNDS_FORCE_STRENGTH;
+            force[1] += (node.y.clamp(bounds.min[1], bounds.max[1]) - node.y) * BOUNDS_FORCE_STRENGTH;
+        }
+
+        // v += f / m * alpha * dt, then damping and x += v * dt
+        let inverse_mass = 1.0 / node.mass.max(0.01);
+        let damping = inputs.damping.get(index).copied().filter(|&damping| damping > 0.0).unwrap_or(settings.damping_factor);
+        node.vx = (node.vx + force[0] * inverse_mass * settings.alpha * settings.delta_time) * damping;
+        node.vy = (node.vy + force[1] * inverse_mass * settings.alpha * settings.delta_time) * damping;
+        node.x += node.vx * settings.delta_time;
+        node.y += node.vy * settings.delta_time;
+        apply_bounds(node, settings);
+        if let Some(wrap) = settings.wrap() {
+            [node.x, node.y] = wrap.wrap([node.x, node.y]);
+        }
+
+        node.fx = 0.0;
+        node.fy = 0.0;
+    }
+}
+
+// The nodes a local reheat simulates, the others frozen as obstacles: seeded
+// with the perturbed nodes and their surroundings, it takes in the neighbors of
+// members moving faster than a threshold and sheds members that have slowed
+// below it with no fast member beside them. Seeds stay in until it's cleared.
+pub struct ActiveSet {
+    members: Vec<u32>,
+    frozen: Vec<bool>, // the complement of `members`, as PhysicsInputs::frozen
+    seeds: Vec<bool>,
+}
+
+impl ActiveSet {
+    pub fn new(node_count: usize) -> Self {
+        Self { members: Vec::new(), frozen: vec![true; node_count], seeds: vec![false; node_count] }
+    }
+
+    // Indices past the node count are ignored
+    pub fn seed(&mut self, nodes: &[u32]) {
+        for &node in nodes {
+            let node = node as usize;
+            if node < self.frozen.len() {
+                self.seeds[node] = true;
+                if self.frozen[node] {
+                    self.frozen[node] = false;
+                    self.members.push(node as u32);
+                }
+            }
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.frozen.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn frozen(&self) -> &[bool] {
+        &self.frozen
+    }
+
+    // Grows and shrinks the set by the speeds the last tick left, at a cost
+    // proportional to the members' degrees rather than the node count
+    pub fn update(&mut self, nodes: &[NodeData], adjacency: &Adjacency, speed_threshold: f32) {
+        let fast = |index: u32| nodes.get(index as usize).is_some_and(|node| node.vx.hypot(node.vy) > speed_threshold);
+        let neighbors = |index: u32| if (index as usize) < adjacency.node_count() { adjacency.neighbors(index as usize) } else { &[] };
+
+        let mut grown = Vec::new();
+        for &member in self.members.iter().filter(|&&member| fast(member)) {
+            for &neighbor in neighbors(member) {
+                if self.frozen.get(neighbor as usize).copied().unwrap_or(false) {
+                    self.frozen[neighbor as usize] = false;
+                    grown.push(neighbor);
+                }
+            }
+        }
+
+        let (frozen, seeds) = (&mut self.frozen, &self.seeds);
+        self.members.retain(|&member| {
+            let kept = seeds[member as usize] || fast(member) || neighbors(member).iter().any(|&neighbor| !frozen.get(neighbor as usize).copied().unwrap_or(true) && fast(neighbor));
+            frozen[member as usize] = !kept;
+            kept
+        });
+        self.members.extend(grown);
+    }
+}
+
+// The hash grid of the grid repulsion. Cells are one radius wide, or a little
+// wider on a wrapping world so a whole number of them spans it; an axis with
+// fewer than three is a single cell, so no neighbor is visited twice.
+struct Cells {
+    origin: [f32; 2],
+    width: [f32; 2],
+    counts: Option<[i32; 2]>, // cells per axis on a wrapping world
+}
+
+impl Cells {
+    fn new(radius: f32, wrap: Option<WorldWrap>) -> Self {
+        match wrap {
+            Some(wrap) => {
+                let counts = wrap.size.map(|size| match (size / radius).floor() as i32 {
+                    count if count >= 3 => count,
+                    _ => 1,
+                });
+                Self { origin: wrap.min, width: [wrap.size[0] / counts[0] as f32, wrap.size[1] / counts[1] as f32], counts: Some(counts) }
+            }
+            None => Self { origin: [0.0; 2], width: [radius; 2], counts: None },
+        }
+    }
+
+    fn of(&self, node: &NodeData) -> (i32, i32) {
+        let position = [node.x, node.y];
+        let cell = [0, 1].map(|axis| {
+            let cell = ((position[axis] - self.origin[axis]) / self.width[axis]).floor() as i32;
+            self.counts.map_or(cell, |counts| cell.rem_euclid(counts[axis]))
+        });
+        (cell[0], cell[1])
+    }
+
+    // The cell and its neighbors, across the seams on a wrapping world
+    fn around(&self, (x, y): (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let reach = |axis: usize| self.counts.map_or(1, |counts| if counts[axis] == 1 { 0 } else { 1 });
+        let (reach_x, reach_y) = (reach(0), reach(1));
+        (-reach_y..=reach_y).flat_map(move |dy| {
+            (-reach_x..=reach_x).map(move |dx| match self.counts {
+                Some(counts) => ((x + dx).rem_euclid(counts[0]), (y + dy).rem_euclid(counts[1])),
+                None => (x + dx, y + dy),
+            })
+        })
+    }
+}
+
+// The cells the moving nodes search, when they are few enough (say, during a
+// local reheat) that filling only those beats filling the whole grid
+fn searched_cells(nodes: &[NodeData], active: &[bool], moving: &[bool], grid: &Cells) -> Option<HashSet<(i32, i32)>> {
+    let movers = moving.iter().filter(|&&moving| moving).count();
+    if movers * 4 > active.iter().filter(|&&active| active).count() {
+        return None;
+    }
+    let cells = nodes
+        .iter()
+        .enumerate()
+        .filter(|&(index, node)| moving[index] && node.x.is_finite() && node.y.is_finite())
+        .flat_map(|(_, node)| grid.around(grid.of(node)))
+        .collect();
+    Some(cells)
+}
+
+// The copy of `to` the forces on a node at `from` act through
+fn image(wrap: Option<WorldWrap>, from: [f32; 2], to: [f32; 2]) -> [f32; 2] {
+    wrap.map_or(to, |wrap| wrap.nearest(from, to))
+}
+
+// Pairs closer than the repulsion radius, found through a hash grid with cells
+// about one radius wide
+fn add_grid_repulsion(nodes: &[NodeData], active: &[bool], moving: &[bool], settings: &PhysicsSettings, forces: &mut [[f32; 2]]) {
+    let radius = settings.repulsion_radius;
+    if radius <= 0.0 {
+        return;
+    }
+
+    let wrap = settings.wrap();
+    let grid = Cells::new(radius, wrap);
+    let searched = searched_cells(nodes, active, moving, &grid);
+    let mut cells: HashMap<(i32, i32), Vec<u32>> = HashMap::new();
+    for (index, node) in nodes.iter().enumerate() {
+        if active[index] && node.x.is_finite() && node.y.is_finite() && searched.as_ref().is_none_or(|cells| cells.contains(&grid.of(node))) {
+            cells.entry(grid.of(node)).or_default().push(index as u32);
+        }
+    }
+
+    for (index, node) in nodes.iter().enumerate() {
+        if !(moving[index] && node.x.is_finite() && node.y.is_finite()) {
+            continue;
+        }
+
+        for cell in grid.around(grid.of(node)) {
+            let Some(members) = cells.get(&cell) else {
+                continue;
+            };
+            for &other in members {
+                let other = &nodes[other as usize];
+                let [other_x, other_y] = image(wrap, [node.x, node.y], [other.x, other.y]);
+                let distance = ((node.x - other_x).powi(2) + (node.y - other_y).powi(2)).sqrt();
+                if distance <= radius {
+                    let force = repulsion([node.x, node.y], [other_x, other_y], 1.0, settings.repulsion_strength);
+                    forces[index][0] += force[0];
+                    forces[index][1] += force[1];
+                }
+            }
+        }
+    }
+}
+
+// add_grid_repulsion with each cell's positions stored contiguously, sorted by
+// cell, so four candidates load at once
+fn add_grid_repulsion_lanes(nodes: &[NodeData], active: &[bool], moving: &[bool], settings: &PhysicsSettings, forces: &mut [[f32; 2]]) {
+    let radius = settings.repulsion_radius;
+    if radius <= 0.0 {
+        return;
+    }
+
+    let wrap = settings.wrap();
+    let grid = Cells::new(radius, wrap);
+    let searched = searched_cells(nodes, active, moving, &grid);
+    let mut sorted: Vec<((i32, i32), u32)> = nodes
+        .iter()
+        .enumerate()
+        .filter(|&(index, node)| active[index] && node.x.is_finite() && node.y.is_finite())
+        .map(|(index, node)| (grid.of(node), index as u32))
+        .filter(|(cell, _)| searched.as_ref().is_none_or(|cells| cells.contains(cell)))
+        .collect();
+    sorted.sort_unstable();
+
+    // Wrapped into the world, so offsets between them are less than its size
+    let position = |node: &NodeData| wrap.map_or([node.x, node.y], |wrap| wrap.wrap([node.x, node.y]));
+    let xs: Vec<f32> = sorted.iter().map(|&(_, index)| position(&nodes[index as usize])[0]).collect();
+    let ys: Vec<f32> = sorted.iter().map(|&(_, index)| position(&nodes[index as usize])[1]).collect();
+    let mut cells: HashMap<(i32, i32), (usize, usize)> = HashMap::new();
+    for (position, &(cell, _)) in sorted.iter().enumerate() {
+        cells.entry(cell).or_insert((position, position)).1 = position + 1;
+    }
+
+    let zero = F32x4::splat(0.0);
+    let min_distance = F32x4::splat(0.001);
+    let radius_lanes = F32x4::splat(radius);
+    let strength = F32x4::splat(settings.repulsion_strength);
+    for (index, node) in nodes.iter().enumerate() {
+        if !(moving[index] && node.x.is_finite() && node.y.is_finite()) {
+            continue;
+        }
+
+        let [node_x, node_y] = position(node);
+        let (x, y) = (F32x4::splat(node_x), F32x4::splat(node_y));
+        let (mut force_x, mut force_y) = (zero, zero);
+        for cell in grid.around(grid.of(node)) {
+            let Some(&(start, end)) = cells.get(&cell) else {
+                continue;
+            };
+            for chunk in (start..end).step_by(LANES) {
+                let chunk = chunk..(chunk + LANES).min(end);
+                // Padding lanes sit on the node itself, which exerts no force
+                let mut delta_x = x - F32x4::load(&xs[chunk.clone()], node_x);
+                let mut delta_y = y - F32x4::load(&ys[chunk], node_y);
+                if let Some(wrap) = wrap {
+                    delta_x = nearest_offset(delta_x, wrap.size[0]);
+                    delta_y = nearest_offset(delta_y, wrap.size[1]);
+                }
+                let distance = (delta_x * delta_x + delta_y * delta_y).sqrt();
+                let in_range = distance.le(radius_lanes).and(min_distance.le(distance));
+
+                // repulsion() with a charge of 1
+                let clamped = distance.max(F32x4::splat(0.01));
+                let magnitude = strength / (clamped * clamped);
+                force_x = force_x + in_range.select(delta_x / distance * magnitude, zero);
+                force_y = force_y + in_range.select(delta_y / distance * magnitude, zero);
+            }
+        }
+        forces[index][0] += force_x.sum();
+        forces[index][1] += force_y.sum();
+    }
+}
+
+// Offsets between positions less than a world `size` apart, shifted to those
+// of the nearest images
+fn nearest_offset(delta: F32x4, size: f32) -> F32x4 {
+    let delta = F32x4::splat(size * 0.5).le(delta).select(delta - F32x4::splat(size), delta);
+    delta.le(F32x4::splat(-size * 0.5)).select(delta + F32x4::splat(size), delta)
+}
+
+// On a wrapping world each cell acts through the image of its center nearest
+// the node, which is approximate for cells that straddle half the world
+fn add_barnes_hut_repulsion(nodes: &[NodeData], active: &[bool], moving: &[bool], settings: &PhysicsSettings, forces: &mut [[f32; 2]]) {
+    let tree = QuadTree::build_among(nodes, active, 4 * nodes.len().max(1));
+    let wrap = settings.wrap();
+
+    for (index, node) in nodes.iter().enumerate() {
+        if !moving[index] {
+            continue;
+        }
+        let position = [node.x, node.y];
+        let mut i = 0;
+        while i < tree.nodes.len() {
+            let cell = &tree.nodes[i];
+            let center = image(wrap, position, [cell.center_x, cell.center_y]);
+            let distance = ((node.x - center[0]).powi(2) + (node.y - center[1]).powi(2)).sqrt();
+
+            let force = if cell.is_leaf != 0 {
+                i = cell.next as usize;
+                if cell.body == QUAD_NO_BODY {
+                    repulsion(position, center, cell.charge, settings.repulsion_strength)
+                } else if cell.body as usize != index {
+                    let other = &nodes[cell.body as usize];
+                    repulsion(position, image(wrap, position, [other.x, other.y]), 1.0, settings.repulsion_strength)
+                } else {
+                    [0.0, 0.0]
+                }
+            } else if cell.width < settings.theta * distance {
+                i = cell.next as usize;
+                repulsion(position, center, cell.charge, settings.repulsion_strength)
+            } else {
+                i += 1;
+                [0.0, 0.0]
+            };
+
+            forces[index][0] += force[0];
+            forces[index][1] += force[1];
+        }
+    }
+}
+
+fn add_springs(nodes: &[NodeData], active: &[bool], moving: &[bool], adjacency: &Adjacency, inputs: &PhysicsInputs, settings: &PhysicsSettings, forces: &mut [[f32; 2]]) {
+    let wrap = settings.wrap();
+    for (index, node) in nodes.iter().enumerate().take(adjacency.node_count()) {
+        if !moving[index] {
+            continue;
+        }
+        for (&other, &edge_id) in adjacency.neighbors(index).iter().zip(adjacency.incident_edges(index)) {
+            let Some(other) = nodes.get(other as usize).filter(|_| active[other as usize]) else {
+                continue;
+            };
+
+            let [other_x, other_y] = image(wrap, [node.x, node.y], [other.x, other.y]);
+            let (dx, dy) = (other_x - node.x, other_y - node.y);
+            let distance = (dx * dx + dy * dy).sqrt();
+            if distance < 0.001 {
+                continue;
+            }
+
+            let magnitude = settings.spring_constant * edge_strength(inputs, edge_id) * (distance - edge_rest_length(inputs, settings, edge_id));
+            forces[index][0] += dx / distance * magnitude;
+            forces[index][1] += dy / distance * magnitude;
+        }
+    }
+}
+
+// add_springs over four of a node's neighbors at a time
+fn add_springs_lanes(nodes: &[NodeData], active: &[bool], moving: &[bool], adjacency: &Adjacency, inputs: &PhysicsInputs, settings: &PhysicsSettings, forces: &mut [[f32; 2]]) {
+    let zero = F32x4::splat(0.0);
+    let min_distance = F32x4::splat(0.001);
+    let spring_constant = F32x4::splat(settings.spring_constant);
+    let wrap = settings.wrap();
+    for (index, node) in nodes.iter().enumerate().take(adjacency.node_count()) {
+        if !moving[index] {
+            continue;
+        }
+        let (mut force_x, mut force_y) = (zero, zero);
+        for (others, edge_ids) in adjacency.neighbors(index).chunks(LANES).zip(adjacency.incident_edges(index).chunks(LANES)) {
+            // Padding lanes and missing or inactive neighbors sit on the node itself and are skipped
+            let (mut other_x, mut other_y) = ([node.x; LANES], [node.y; LANES]);
+            let (mut rest_length, mut strength) = ([0.0; LANES], [0.0; LANES]);
+            for (lane, (&other, &edge_id)) in others.iter().zip(edge_ids).enumerate() {
+                if let Some(other) = nodes.get(other as usize).filter(|_| active[other as usize]) {
+                    [other_x[lane], other_y[lane]] = image(wrap, [node.x, node.y], [other.x, other.y]);
+                }
+                rest_length[lane] = edge_rest_length(inputs, settings, edge_id);
+                strength[lane] = edge_strength(inputs, edge_id);
+            }
+
+            let delta_x = F32x4::from_array(other_x) - F32x4::splat(node.x);
+            let delta_y = F32x4::from_array(other_y) - F32x4::splat(node.y);
+            let distance = (delta_x * delta_x + delta_y * delta_y).sqrt();
+            let stretched = min_distance.le(distance);
+            let magnitude = spring_constant * F32x4::from_array(strength) * (distance - F32x4::from_array(rest_length));
+            force_x = force_x + stretched.select(delta_x / distance * magnitude, zero);
+            force_y = force_y + stretched.select(delta_y / distance * magnitude, zero);
+        }
+        forces[index][0] += force_x.sum();
+        forces[index][1] += force_y.sum();
+    }
+}
+
+// Non-positive per-edge rest lengths defer to the global one
+fn edge_rest_length(inputs: &PhysicsInputs, settings: &PhysicsSettings, edge_id: u32) -> f32 {
+    inputs.edge_lengths.get(edge_id as usize).copied().filter(|&length| length > 0.0).unwrap_or(settings.rest_length)
+}
+
+fn edge_strength(inputs: &PhysicsInputs, edge_id: u32) -> f32 {
+    inputs.edge_weights.get(edge_id as usize).copied().unwrap_or(1.0)
+}
+
+// Inverse-square push of `charge` bodies at `source` on a node at `position`
+fn repulsion(position: [f32; 2], source: [f32; 2], charge: f32, strength: f32) -> [f32; 2] {
+    let (dx, dy) = (position[0] - source[0], position[1] - source[1]);
+    let distance = (dx * dx + dy * dy).sqrt();
+    if distance < 0.001 {
+        return [0.0, 0.0];
+    }
+
+    let min_distance = distance.max(0.01);
+    let magnitude = strength * charge / (min_distance * min_distance);
+    [dx / distance * magnitude, dy / distance * magnitude]
+}
+
+fn gravity_force(node: &NodeData, settings: &PhysicsSettings) -> [f32; 2] {
+    if settings.gravity_strength <= 0.0 {
+        return [0.0, 0.0];
+    }
+
+    let to_center = [settings.gravity_center[0] - node.x, settings.gravity_center[1] - node.y];
+    match settings.gravity_mode {
+        GravityMode::Linear => [to_center[0] * settings.gravity_strength, to_center[1] * settings.gravity_strength],
+        GravityMode::Constant => {
+            let distance = (to_center[0] * to_center[0] + to_center[1] * to_center[1]).sqrt();
+            if distance < 0.001 {
+                return [0.0, 0.0];
+            }
+            [to_center[0] / distance * settings.gravity_strength, to_center[1] / distance * settings.gravity_strength]
+        }
+    }
+}
+
+// Hard walls for the clamp and bounce modes, applied after the position update
+fn apply_bounds(node: &mut NodeData, settings: &PhysicsSettings) {
+    let Some(bounds) = settings.bounds else {
+        return;
+    };
+    if bounds.mode == BoundsMode::Force {
+        return;
+    }
+
+    let bounce = bounds.mode == BoundsMode::Bounce;
+    if node.x < bounds.min[0] || node.x > bounds.max[0] {
+        node.x = node.x.clamp(bounds.min[0], bounds.max[0]);
+        node.vx = if bounce { -node.vx * BOUNDS_RESTITUTION } else { 0.0 };
+    }
+    if node.y < bounds.min[1] || node.y > bounds.max[1] {
+        node.y = node.y.clamp(bounds.min[1], bounds.max[1]);
+        node.vy = if bounce { -node.vy * BOUNDS_RESTITUTION } else { 0.0 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NODE_FLAG_NO_PHYSICS;
+
+    fn node(x: f32, y: f32) -> NodeData {
+        NodeData { x, y, a: 1.0, size: 5.0, mass: 1.0, ..bytemuck::Zeroable::zeroed() }
+    }
+
+    fn inputs<'a>(adjacency: Option<&'a Adjacency>, excluded: &'a [bool], frozen: &'a [bool]) -> PhysicsInputs<'a> {
+        PhysicsInputs { adjacency, edge_lengths: &[], edge_weights: &[], groups: None, damping: &[], excluded, frozen }
+    }
+
+    fn distance(a: &NodeData, b: &NodeData) -> f32 {
+        (a.x - b.x).hypot(a.y - b.y)
+    }
+
+    fn settings() -> PhysicsSettings {
+        PhysicsSettings { delta_time: 1.0, repulsion_radius: 50.0, repulsion_strength: 50.0, ..PhysicsSettings::default() }
+    }
+
+    #[test]
+    fn springs_pull_stretched_edges_together() {
+        let mut nodes = vec![node(0.0, 0.0), node(200.0, 0.0)];
+        let adjacency = Adjacency::new(2, &[[0, 1]]);
+        let before = distance(&nodes[0], &nodes[1]);
+        step(&mut nodes, &inputs(Some(&adjacency), &[], &[]), &settings());
+        assert!(distance(&nodes[0], &nodes[1]) < before);
+        // Equal and opposite
+        assert!((nodes[0].x + nodes[1].x - 200.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn repulsion_pushes_close_nodes_apart_by_either_method() {
+        for method in [RepulsionMethod::Grid, RepulsionMethod::BarnesHut] {
+            let mut nodes = vec![node(0.0, 0.0), node(5.0, 0.0)];
+            step(&mut nodes, &inputs(None, &[], &[]), &PhysicsSettings { repulsion_method: method, ..settings() });
+            assert!(distance(&nodes[0], &nodes[1]) > 5.0, "{method:?}");
+        }
+    }
+
+    #[test]
+    fn pinned_nodes_hold_still_but_still_push() {
+        let mut nodes = vec![node(0.0, 0.0), node(5.0, 0.0)];
+        nodes[0].flags = NODE_FLAG_PINNED;
+        nodes[0].vx = 3.0;
+        step(&mut nodes, &inputs(None, &[], &[]), &settings());
+        assert_eq!((nodes[0].x, nodes[0].y, nodes[0].vx), (0.0, 0.0, 0.0));
+        assert!(nodes[1].x > 5.0);
+    }
+
+    #[test]
+    fn excluded_nodes_neither_move_nor_push() {
+        for (flags, excluded) in [(NODE_FLAG_NO_PHYSICS, vec![]), (0, vec![true, false])] {
+            let mut nodes = vec![node(0.0, 0.0), node(5.0, 0.0)];
+            nodes[0].flags = flags;
+            nodes[0].vx = 3.0;
+            step(&mut nodes, &inputs(None, &excluded, &[]), &settings());
+            assert_eq!((nodes[0].x, nodes[0].vx), (0.0, 3.0));
+            assert_eq!(nodes[1].x, 5.0);
+        }
+    }
+
+    // The CPU simulation has no parallel or unordered steps, so it needs no
+    // deterministic mode; this keeps it that way
+    #[test]
+    fn cpu_ticks_are_reproducible() {
+        let run = || {
+            let mut rng = crate::layout::Rng::new(7);
+            let mut nodes: Vec<NodeData> = (0..300).map(|_| node(rng.next_f32() * 1000.0, rng.next_f32() * 1000.0)).collect();
+            let edges: Vec<[u32; 2]> = (0..600).map(|_| [(rng.next_u64() % 300) as u32, (rng.next_u64() % 300) as u32]).collect();
+            let adjacency = Adjacency::new(nodes.len(), &edges);
+            for _ in 0..200 {
+                step(&mut nodes, &inputs(Some(&adjacency), &[], &[]), &settings());
+            }
+            nodes.iter().map(|node| [node.x.to_bits(), node.y.to_bits()]).collect::<Vec<_>>()
+        };
+        let first = run();
+        assert!(first.iter().all(|&[x, y]| f32::from_bits(x).is_finite() && f32::from_bits(y).is_finite()));
+        assert_eq!(first, run());
+    }
+
+    #[test]
+    fn vectorized_and_scalar_ticks_agree() {
+        let mut rng = crate::layout::Rng::new(3);
+        let nodes: Vec<NodeData> = (0..500).map(|_| node(rng.next_f32() * 600.0, rng.next_f32() * 600.0)).collect();
+        let edges: Vec<[u32; 2]> = (0..1000).map(|_| [(rng.next_u64() % 500) as u32, (rng.next_u64() % 500) as u32]).collect();
+        let adjacency = Adjacency::new(nodes.len(), &edges);
+        let (mut vectorized, mut scalar) = (nodes.clone(), nodes.clone());
+        // One tick only: summation order differs, and the simulation amplifies that
+        step(&mut vectorized, &inputs(Some(&adjacency), &[], &[]), &settings());
+        step_scalar(&mut scalar, &inputs(Some(&adjacency), &[], &[]), &settings());
+        assert!(vectorized.iter().zip(&nodes).any(|(a, b)| distance(a, b) > 0.1));
+        for (a, b) in vectorized.iter().zip(&scalar) {
+            assert!((a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3, "{:?} vs {:?}", (a.x, a.y), (b.x, b.y));
+        }
+    }
+
+    #[test]
+    fn group_attraction_pulls_mixed_groups_into_separate_clusters() {
+        // Three groups of 30, scattered over the same square
+        let mut rng = crate::layout::Rng::new(5);
+        let start: Vec<NodeData> = (0..90).map(|_| node(rng.next_f32() * 300.0, rng.next_f32() * 300.0)).collect();
+        let ids: Vec<i32> = (0..90).map(|i| i % 3).collect();
+        let groups = NodeGroups::new(&ids);
+
+        // Mean distance of the members from their centroid, and the smallest
+        // distance between two centroids
+        let measure = |nodes: &[NodeData]| {
+            let centroids = groups.centroids(nodes);
+            let apart = |a: [f32; 2], b: [f32; 2]| (a[0] - b[0]).hypot(a[1] - b[1]);
+            let spread = nodes.iter().zip(groups.slots()).map(|(node, &slot)| apart([node.x, node.y], centroids[slot as usize])).sum::<f32>() / nodes.len() as f32;
+            let gap = [(0, 1), (0, 2), (1, 2)].map(|(a, b)| apart(centroids[a], centroids[b])).into_iter().fold(f32::INFINITY, f32::min);
+            (spread, gap)
+        };
+        let simulate = |group_attraction: f32| {
+            let mut nodes = start.clone();
+            let inputs = PhysicsInputs { groups: Some(&groups), ..inputs(None, &[], &[]) };
+            let settings = PhysicsSettings { group_attraction, ..settings() };
+            for _ in 0..300 {
+                step(&mut nodes, &inputs, &settings);
+            }
+            measure(&nodes)
+        };
+
+        let (spread, gap) = measure(&start);
+        assert!(gap < spread, "the groups start mixed");
+        // Each group contracts, and the clusters end up farther apart than they're wide
+        let (pulled_spread, pulled_gap) = simulate(0.05);
+        assert!(pulled_spread < spread * 0.5, "{pulled_spread} from {spread}");
+        assert!(pulled_gap > pulled_spread * 2.0 && pulled_gap > gap * 2.0, "{pulled_gap} from {gap}");
+        // Without the force, repulsion alone keeps them mixed
+        let (free_spread, free_gap) = simulate(0.0);
+        assert!(free_spread > spread * 0.9 && free_gap < free_spread, "{free_spread}, {free_gap}");
+    }
+}
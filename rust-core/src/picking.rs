@@ -0,0 +1,113 @@
+// Hit testing in world coordinates. Later nodes are drawn over earlier ones,
+// so the last node under the point wins; edges are under every node.
+
+use crate::{EdgeData, NodeData};
+
+// The topmost of `nodes`, listed bottom first, whose disc, grown by `slop`,
+// contains `point`
+pub fn node_at<'a>(nodes: impl DoubleEndedIterator<Item = (usize, &'a NodeData)>, point: [f32; 2], slop: f32) -> Option<usize> {
+    nodes.rev().find_map(|(index, node)| {
+        let (dx, dy) = (point[0] - node.x, point[1] - node.y);
+        let reach = node.size + slop;
+        (dx * dx + dy * dy <= reach * reach).then_some(index)
+    })
+}
+
+// The edge nearest to `point` among those it's within `reach` of, the larger
+// of half their width plus `slop` and `min_reach`. `edges` are listed bottom
+// first, and of equally near edges the last (drawn on top) wins.
+pub fn edge_at<'a>(edges: impl Iterator<Item = (usize, &'a EdgeData)>, point: [f32; 2], slop: f32, min_reach: f32) -> Option<usize> {
+    edges
+        .enumerate()
+        .filter_map(|(position, (index, edge))| {
+            let distance = segment_distance(point, [edge.x1, edge.y1], [edge.x2, edge.y2]);
+            (distance <= (edge.width * 0.5 + slop).max(min_reach)).then_some((index, distance, position))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(b.2.cmp(&a.2)))
+        .map(|(index, _, _)| index)
+}
+
+fn segment_distance(point: [f32; 2], start: [f32; 2], end: [f32; 2]) -> f32 {
+    let (dx, dy) = (end[0] - start[0], end[1] - start[1]);
+    let length_squared = dx * dx + dy * dy;
+    let t = if length_squared > 0.0 {
+        (((point[0] - start[0]) * dx + (point[1] - start[1]) * dy) / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (px, py) = (start[0] + dx * t - point[0], start[1] + dy * t - point[1]);
+    (px * px + py * py).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(start: [f32; 2], end: [f32; 2], width: f32) -> EdgeData {
+        EdgeData { x1: start[0], y1: start[1], x2: end[0], y2: end[1], r: 1.0, g: 1.0, b: 1.0, a: 1.0, width }
+    }
+
+    fn pick(edges: &[EdgeData], point: [f32; 2], slop: f32, min_reach: f32) -> Option<usize> {
+        edge_at(edges.iter().enumerate(), point, slop, min_reach)
+    }
+
+    #[test]
+    fn the_nearer_of_close_edges_wins() {
+        // Horizontal edges 4 apart, both within reach of points between them
+        let edges = [edge([0.0, 0.0], [100.0, 0.0], 1.0), edge([0.0, 4.0], [100.0, 4.0], 1.0)];
+        assert_eq!(pick(&edges, [50.0, 1.5], 3.0, 0.0), Some(0));
+        assert_eq!(pick(&edges, [50.0, 2.5], 3.0, 0.0), Some(1));
+        // Equally near, the one drawn on top
+        assert_eq!(pick(&edges, [50.0, 2.0], 3.0, 0.0), Some(1));
+        // Past the end of a segment, distance is to its endpoint
+        assert_eq!(pick(&edges, [102.0, 1.0], 3.0, 0.0), Some(0));
+    }
+
+    #[test]
+    fn overlapping_edges_go_to_the_nearer() {
+        // Crossing at (50, 0), so near the crossing both are within reach
+        let crossing = [edge([0.0, -10.0], [100.0, 10.0], 1.0), edge([0.0, 10.0], [100.0, -10.0], 1.0)];
+        assert_eq!(pick(&crossing, [50.0, 0.0], 3.0, 0.0), Some(1));
+        for (point, nearer) in [([40.0, -1.5], 0), ([60.0, 1.5], 0), ([40.0, 1.5], 1), ([60.0, -1.5], 1)] {
+            assert_eq!(pick(&crossing, point, 3.0, 0.0), Some(nearer), "{point:?}");
+            let flipped = [crossing[1], crossing[0]];
+            assert_eq!(pick(&flipped, point, 3.0, 0.0), Some(1 - nearer), "{point:?}");
+        }
+        // Nearly coincident hairlines half a pixel apart, both inside min_reach
+        let parallel = [edge([0.0, 0.0], [100.0, 0.0], 0.1), edge([0.0, 0.5], [100.0, 0.5], 0.1)];
+        assert_eq!(pick(&parallel, [50.0, 0.2], 0.0, 5.0), Some(0));
+        assert_eq!(pick(&parallel, [50.0, 0.3], 0.0, 5.0), Some(1));
+        assert_eq!(pick(&[parallel[1], parallel[0]], [50.0, 0.2], 0.0, 5.0), Some(1));
+    }
+
+    #[test]
+    fn hairline_edges_are_reachable_from_min_reach() {
+        let edges = [edge([0.0, 0.0], [100.0, 0.0], 0.1)];
+        assert_eq!(pick(&edges, [50.0, 4.0], 1.0, 0.0), None);
+        assert_eq!(pick(&edges, [50.0, 4.0], 1.0, 5.0), Some(0));
+        // A wide edge reaches farther than the floor
+        let wide = [edge([0.0, 0.0], [100.0, 0.0], 20.0)];
+        assert_eq!(pick(&wide, [50.0, 9.0], 0.0, 5.0), Some(0));
+    }
+
+    #[test]
+    fn points_beyond_the_slop_miss() {
+        let edges = [edge([0.0, 0.0], [100.0, 0.0], 2.0), edge([10.0, 10.0], [10.0, 10.0], 2.0)];
+        // Half the width plus the slop is 4
+        assert_eq!(pick(&edges, [50.0, 3.9], 3.0, 0.0), Some(0));
+        assert_eq!(pick(&edges, [50.0, -4.1], 3.0, 0.0), None);
+        assert_eq!(pick(&edges, [-4.1, 0.0], 3.0, 0.0), None);
+        // Zero-length edges are points
+        assert_eq!(pick(&edges, [13.0, 10.0], 3.0, 0.0), Some(1));
+        assert_eq!(pick(&[], [0.0, 0.0], 3.0, 5.0), None);
+    }
+
+    #[test]
+    fn the_topmost_node_wins() {
+        let node = |x: f32| NodeData { x, size: 5.0, ..bytemuck::Zeroable::zeroed() };
+        let nodes = [node(0.0), node(4.0)];
+        assert_eq!(node_at(nodes.iter().enumerate(), [2.0, 0.0], 0.0), Some(1));
+        assert_eq!(node_at(nodes.iter().enumerate(), [-5.5, 0.0], 0.0), None);
+        assert_eq!(node_at(nodes.iter().enumerate(), [-5.5, 0.0], 1.0), Some(0));
+    }
+}
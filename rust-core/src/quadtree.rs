@@ -0,0 +1,155 @@
+// Barnes-Hut quadtree, flattened in depth-first order for the GPU repulsion pass.
+//
+// Children of an internal cell immediately follow it and `next` points past the
+// cell's whole subtree, so the shader can walk the tree without a stack: open a
+// cell by moving to `index + 1`, or skip it by jumping to `next`.
+
+use crate::NodeData;
+
+pub const QUAD_NO_BODY: u32 = u32::MAX;
+
+// Subdividing further than this only happens for (nearly) coincident nodes
+const MAX_DEPTH: u32 = 24;
+
+// Must match the QuadNode struct in PHYSICS_SHADER
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct QuadNode {
+    pub center_x: f32, // center of mass of the bodies in the cell
+    pub center_y: f32,
+    pub charge: f32, // number of bodies in the cell, or their total charge
+    pub width: f32,  // side length of the cell
+    pub next: u32,   // first node after this cell's subtree
+    pub body: u32,   // node index for leaves holding one body, QUAD_NO_BODY otherwise
+    pub is_leaf: u32,
+    pub _padding: u32,
+}
+
+pub struct QuadTree {
+    pub nodes: Vec<QuadNode>,
+}
+
+impl QuadTree {
+    // Builds a tree over the finite node positions using at most `capacity` cells.
+    // Once capacity runs out, remaining cells become multi-body leaves that are
+    // approximated by their center of mass.
+    pub fn build(bodies: &[NodeData], capacity: usize) -> Self {
+        Self::build_filtered(bodies, None, capacity, |_| true)
+    }
+
+    // Like `build`, over only the bodies whose `members` entry is true
+    pub fn build_among(bodies: &[NodeData], members: &[bool], capacity: usize) -> Self {
+        Self::build_filtered(bodies, None, capacity, |i| members.get(i).copied().unwrap_or(false))
+    }
+
+    // Like `build`, but with a charge per body (indexed like `bodies`): cells sum
+    // the charges and their center is the charge-weighted mean position
+    pub fn build_weighted(bodies: &[NodeData], charges: Option<&[f32]>, capacity: usize) -> Self {
+        Self::build_filtered(bodies, charges, capacity, |_| true)
+    }
+
+    fn build_filtered(bodies: &[NodeData], charges: Option<&[f32]>, capacity: usize, include: impl Fn(usize) -> bool) -> Self {
+        let mut indices: Vec<u32> = (0..bodies.len() as u32)
+            .filter(|&i| {
+                let body = &bodies[i as usize];
+                body.x.is_finite() && body.y.is_finite() && include(i as usize)
+            })
+            .collect();
+
+        let mut tree = Self {
+            nodes: Vec::with_capacity(capacity.min(indices.len() * 2 + 1)),
+        };
+        if indices.is_empty() || capacity == 0 {
+            return tree;
+        }
+
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for &i in &indices {
+            let body = &bodies[i as usize];
+            min_x = min_x.min(body.x);
+            min_y = min_y.min(body.y);
+            max_x = max_x.max(body.x);
+            max_y = max_y.max(body.y);
+        }
+        let width = (max_x - min_x).max(max_y - min_y).max(1e-3);
+
+        tree.insert(&Bodies { nodes: bodies, charges }, &mut indices, [min_x, min_y], width, 0, capacity);
+        tree
+    }
+
+    fn insert(&mut self, bodies: &Bodies, indices: &mut [u32], origin: [f32; 2], width: f32, depth: u32, capacity: usize) {
+        let (mut sum_x, mut sum_y, mut charge) = (0.0f64, 0.0f64, 0.0f64);
+        for &i in indices.iter() {
+            let weight = bodies.charge(i) as f64;
+            sum_x += bodies.nodes[i as usize].x as f64 * weight;
+            sum_y += bodies.nodes[i as usize].y as f64 * weight;
+            charge += weight;
+        }
+        let count = charge.max(f64::MIN_POSITIVE);
+
+        let index = self.nodes.len();
+        // Subdivide only while there's room for a full set of children
+        let is_leaf = indices.len() == 1 || depth >= MAX_DEPTH || index + 5 > capacity;
+        self.nodes.push(QuadNode {
+            center_x: (sum_x / count) as f32,
+            center_y: (sum_y / count) as f32,
+            charge: charge as f32,
+            width,
+            next: 0,
+            body: if indices.len() == 1 { indices[0] } else { QUAD_NO_BODY },
+            is_leaf: is_leaf as u32,
+            _padding: 0,
+        });
+
+        if !is_leaf {
+            let half = width * 0.5;
+            let mid = [origin[0] + half, origin[1] + half];
+
+            // Partition into quadrants in place: left/right first, then bottom/top
+            let split_x = partition(indices, |i| bodies.nodes[i as usize].x < mid[0]);
+            let (left, right) = indices.split_at_mut(split_x);
+            let split_left = partition(left, |i| bodies.nodes[i as usize].y < mid[1]);
+            let split_right = partition(right, |i| bodies.nodes[i as usize].y < mid[1]);
+            let (bottom_left, top_left) = left.split_at_mut(split_left);
+            let (bottom_right, top_right) = right.split_at_mut(split_right);
+
+            let quadrants = [
+                (bottom_left, origin),
+                (top_left, [origin[0], mid[1]]),
+                (bottom_right, [mid[0], origin[1]]),
+                (top_right, mid),
+            ];
+            for (quadrant, quadrant_origin) in quadrants {
+                if !quadrant.is_empty() {
+                    self.insert(bodies, quadrant, quadrant_origin, half, depth + 1, capacity);
+                }
+            }
+        }
+
+        self.nodes[index].next = self.nodes.len() as u32;
+    }
+}
+
+struct Bodies<'a> {
+    nodes: &'a [NodeData],
+    charges: Option<&'a [f32]>,
+}
+
+impl Bodies<'_> {
+    fn charge(&self, index: u32) -> f32 {
+        self.charges.map_or(1.0, |charges| charges[index as usize])
+    }
+}
+
+// Moves the indices matching `predicate` to the front and returns how many matched
+fn partition(indices: &mut [u32], predicate: impl Fn(u32) -> bool) -> usize {
+    let mut split = 0;
+    for i in 0..indices.len() {
+        if predicate(indices[i]) {
+            indices.swap(i, split);
+            split += 1;
+        }
+    }
+    split
+}
@@ -1,43 +1,24 @@
-use wasm_bindgen::prelude::*;
-use web_sys::{HtmlCanvasElement, console};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::JsCast;
+#[cfg(target_arch = "wasm32")]
+use web_sys::HtmlCanvasElement;
 use wgpu::*;
-use crate::{NodeData, EdgeData};
+use crate::{NodeData, EdgeData, BoundsMode, GravityMode, PhysicsSettings, RepulsionMethod, BOUNDS_FORCE_STRENGTH, BOUNDS_RESTITUTION, NODE_CLASS_SHIFT, NODE_FLAG_PINNED};
+use crate::graph::Adjacency;
+use crate::quadtree::{QuadNode, QuadTree};
 
-// A macro to provide `println!(..)`-style syntax for `console.log` logging.
-macro_rules! log {
-    ( $( $t:tt )* ) => {
-        console::log_1(&format!( $( $t )* ).into());
-    }
-}
+// The physics buffer's node struct, prepended to every shader that reads it
+const NODE_DATA_WGSL: &str = include_str!("shaders/node_data.wgsl");
+const _: () = assert!(crate::NODE_HIDDEN_FLAGS == 62 && crate::NODE_FLAG_PINNED == 1, "update node_data.wgsl");
 
-const PHYSICS_SHADER: &str = r#"
-struct NodeData {
-    x: f32,
-    y: f32,
-    vx: f32,
-    vy: f32,
-    fx: f32,
-    fy: f32,
-    r: f32,
-    g: f32,
-    b: f32,
-    a: f32,
-    size: f32,
-    mass: f32,
-}
-
-struct EdgeData {
-    x1: f32,
-    y1: f32,
-    x2: f32,
-    y2: f32,
-    r: f32,
-    g: f32,
-    b: f32,
-    a: f32,
-    width: f32,
+fn with_node_data(source: &str) -> String {
+    format!("{}\n{}", NODE_DATA_WGSL, source)
 }
 
+// NodeData comes from NODE_DATA_WGSL
+const PHYSICS_SHADER: &str = r#"
 struct PhysicsParams {
     delta_time: f32,
     damping_factor: f32,
@@ -47,25 +28,90 @@ struct PhysicsParams {
     repulsion_radius: f32,
     node_count: u32,
     edge_count: u32,
+    gravity_strength: f32,
+    gravity_center_x: f32,
+    gravity_center_y: f32,
+    gravity_mode: u32, // 0 = linear, 1 = constant
+    alpha: f32,
+    theta: f32,
+    quad_node_count: u32,
+    group_attraction: f32,
+    world_min_x: f32,
+    world_min_y: f32,
+    world_size_x: f32,
+    world_size_y: f32,
+    bounds_min_x: f32,
+    bounds_min_y: f32,
+    bounds_max_x: f32,
+    bounds_max_y: f32,
+    bounds_mode: u32, // 0 = none, 1 = clamp, 2 = bounce, 3 = force
+    bounds_restitution: f32,
+    bounds_strength: f32,
+    world_wrap: u32, // 1 = positions wrap around the world rectangle
+    active_count: u32, // nodes taking part in the simulation
+    moving_count: u32, // of those, the ones that aren't pinned
+    _padding0: u32,
+    _padding1: u32,
 }
 
 struct GridCell {
     node_indices: array<u32, 32>,  // Max 32 nodes per cell
-    node_count: atomic<u32>,
+    node_count: atomic<u32>,       // Keeps counting past 32, extra nodes spill to the overflow list
+}
+
+struct OverflowEntry {
+    cell: u32,
+    node: u32,
+}
+
+struct SpatialGrid {
+    cells: array<GridCell, 1024>, // 32x32 grid
+    overflow_count: atomic<u32>,
+    overflow: array<OverflowEntry>,
 }
 
 @group(0) @binding(0) var<storage, read_write> nodes: array<NodeData>;
-@group(0) @binding(1) var<storage, read> edges: array<EdgeData>;
+// Per-node incidence lists: node_count + 1 offsets, followed by one
+// (neighbor index, rest length bits, strength bits) triple per incident edge
+@group(0) @binding(1) var<storage, read> adjacency: array<u32>;
 @group(0) @binding(2) var<uniform> params: PhysicsParams;
-@group(0) @binding(3) var<storage, read_write> grid: array<GridCell, 1024>; // 32x32 grid
+@group(0) @binding(3) var<storage, read_write> grid: SpatialGrid;
+
+// Barnes-Hut quadtree in depth-first order: a cell's children follow it directly
+// and `next` skips past its subtree
+struct QuadNode {
+    center_x: f32,
+    center_y: f32,
+    charge: f32,
+    width: f32,
+    next: u32,
+    body: u32,
+    is_leaf: u32,
+    _padding: u32,
+}
+
+@group(0) @binding(4) var<storage, read> quadtree: array<QuadNode>;
+// MAX_NODES per-node group slots (0xffffffff = ungrouped), followed by one
+// (x, y) centroid pair per group as f32 bits, then MAX_NODES per-node damping
+// factors as f32 bits (0 = params.damping_factor), then MAX_NODES per-node
+// participation words (1 = takes part in the simulation, 2 = frozen: exerts
+// forces but holds still) and the indices of the active_count participants
+// and the moving_count neither pinned nor frozen among them
+@group(0) @binding(5) var<storage, read> groups: array<u32>;
+
+const MAX_NODES: u32 = 100000u;
+const DAMPING_OFFSET: u32 = MAX_NODES * 3u;
+const PARTICIPATION_OFFSET: u32 = MAX_NODES * 4u;
+const ACTIVE_OFFSET: u32 = MAX_NODES * 5u;
+const MOVING_OFFSET: u32 = MAX_NODES * 6u;
+const PARTICIPANT_FROZEN: u32 = 2u;
 
 const GRID_SIZE: u32 = 32u;
-const WORLD_MIN: f32 = -1.0;
-const WORLD_MAX: f32 = 1.0;
-const WORLD_SIZE: f32 = 2.0; // WORLD_MAX - WORLD_MIN
 
 fn get_grid_cell(pos: vec2<f32>) -> vec2<u32> {
-    let normalized_pos = (pos - vec2<f32>(WORLD_MIN)) / WORLD_SIZE;
+    let world_min = vec2<f32>(params.world_min_x, params.world_min_y);
+    let world_size = vec2<f32>(params.world_size_x, params.world_size_y);
+    let normalized_pos = (pos - world_min) / world_size;
     let clamped_pos = clamp(normalized_pos, vec2<f32>(0.0), vec2<f32>(0.999));
     return vec2<u32>(u32(clamped_pos.x * f32(GRID_SIZE)), u32(clamped_pos.y * f32(GRID_SIZE)));
 }
@@ -74,9 +120,30 @@ fn get_grid_index(grid_pos: vec2<u32>) -> u32 {
     return grid_pos.y * GRID_SIZE + grid_pos.x;
 }
 
+// Offset from a to the nearest image of b, across the seams when the world wraps
+fn world_offset(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    let delta = b - a;
+    if (params.world_wrap == 0u) {
+        return delta;
+    }
+    let world_size = vec2<f32>(params.world_size_x, params.world_size_y);
+    return delta - world_size * round(delta / world_size);
+}
+
+// Cells apart along one axis, the short way round when the world wraps
+fn cell_distance(a: i32, b: i32) -> i32 {
+    let d = abs(a - b);
+    if (params.world_wrap == 0u) {
+        return d;
+    }
+    return min(d, i32(GRID_SIZE) - d);
+}
+
+// Push node_a away from node_b, for pairs closer than the repulsion radius
 fn calculate_repulsion_force(node_a: NodeData, node_b: NodeData) -> vec2<f32> {
-    let dx = node_b.x - node_a.x;
-    let dy = node_b.y - node_a.y;
+    let delta = world_offset(vec2<f32>(node_a.x, node_a.y), vec2<f32>(node_b.x, node_b.y));
+    let dx = delta.x;
+    let dy = delta.y;
     let dist = sqrt(dx * dx + dy * dy);
     
     if (dist > params.repulsion_radius || dist < 0.001) {
@@ -91,6 +158,99 @@ fn calculate_repulsion_force(node_a: NodeData, node_b: NodeData) -> vec2<f32> {
     return vec2<f32>(nx * force_magnitude, ny * force_magnitude);
 }
 
+// Inverse-square repulsion from `charge` bodies at `source`, without a radius cutoff
+fn calculate_far_repulsion(pos: vec2<f32>, source: vec2<f32>, charge: f32) -> vec2<f32> {
+    let delta = -world_offset(pos, source);
+    let dist = length(delta);
+    
+    if (dist < 0.001) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    
+    let min_dist = max(dist, 0.01);
+    return delta / dist * (params.repulsion_strength * charge / (min_dist * min_dist));
+}
+
+// Hooke spring pulling node_a towards node_b: F = k * strength * (distance - rest_length)
+fn calculate_spring_force(node_a: NodeData, node_b: NodeData, rest_length: f32, strength: f32) -> vec2<f32> {
+    let delta = world_offset(vec2<f32>(node_a.x, node_a.y), vec2<f32>(node_b.x, node_b.y));
+    let dx = delta.x;
+    let dy = delta.y;
+    let dist = sqrt(dx * dx + dy * dy);
+    
+    if (dist < 0.001) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    
+    let force_magnitude = params.spring_constant * strength * (dist - rest_length);
+    let nx = dx / dist;
+    let ny = dy / dist;
+    
+    return vec2<f32>(nx * force_magnitude, ny * force_magnitude);
+}
+
+// Pull towards the gravity center, either proportional to distance or constant
+fn calculate_gravity_force(node: NodeData) -> vec2<f32> {
+    if (params.gravity_strength <= 0.0) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    
+    let to_center = vec2<f32>(params.gravity_center_x - node.x, params.gravity_center_y - node.y);
+    if (params.gravity_mode == 0u) {
+        return to_center * params.gravity_strength;
+    }
+    
+    let dist = length(to_center);
+    if (dist < 0.001) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    return to_center / dist * params.gravity_strength;
+}
+
+// Spring with zero rest length pulling a node towards its group's centroid
+fn calculate_group_force(index: u32, node: NodeData) -> vec2<f32> {
+    if (params.group_attraction <= 0.0) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    
+    let group = groups[index];
+    if (group == 0xffffffffu) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    
+    let centroid_offset = MAX_NODES + group * 2u;
+    let centroid = vec2<f32>(bitcast<f32>(groups[centroid_offset]), bitcast<f32>(groups[centroid_offset + 1u]));
+    return (centroid - vec2<f32>(node.x, node.y)) * params.group_attraction;
+}
+
+// Soft spring pushing nodes outside the bounds back inside
+fn calculate_bounds_force(node: NodeData) -> vec2<f32> {
+    if (params.bounds_mode != 3u) {
+        return vec2<f32>(0.0, 0.0);
+    }
+    
+    let pos = vec2<f32>(node.x, node.y);
+    let inside = clamp(pos, vec2<f32>(params.bounds_min_x, params.bounds_min_y), vec2<f32>(params.bounds_max_x, params.bounds_max_y));
+    return (inside - pos) * params.bounds_strength;
+}
+
+// Hard walls for the clamp and bounce modes, applied after the position update
+fn apply_bounds(node: ptr<function, NodeData>) {
+    if (params.bounds_mode != 1u && params.bounds_mode != 2u) {
+        return;
+    }
+    
+    let bounce = params.bounds_mode == 2u;
+    if ((*node).x < params.bounds_min_x || (*node).x > params.bounds_max_x) {
+        (*node).x = clamp((*node).x, params.bounds_min_x, params.bounds_max_x);
+        (*node).vx = select(0.0, -(*node).vx * params.bounds_restitution, bounce);
+    }
+    if ((*node).y < params.bounds_min_y || (*node).y > params.bounds_max_y) {
+        (*node).y = clamp((*node).y, params.bounds_min_y, params.bounds_max_y);
+        (*node).vy = select(0.0, -(*node).vy * params.bounds_restitution, bounce);
+    }
+}
+
 // Pass 1: Clear grid and assign nodes to cells
 @compute @workgroup_size(64)
 fn clear_grid(@builtin(global_invocation_id) global_id: vec3<u32>) {
@@ -100,48 +260,59 @@ fn clear_grid(@builtin(global_invocation_id) global_id: vec3<u32>) {
         return;
     }
     
-    atomicStore(&grid[grid_index].node_count, 0u);
+    if (grid_index == 0u) {
+        atomicStore(&grid.overflow_count, 0u);
+    }
+    atomicStore(&grid.cells[grid_index].node_count, 0u);
 }
 
 @compute @workgroup_size(64) 
 fn assign_to_grid(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let node_index = global_id.x;
-    
-    if (node_index >= params.node_count) {
+    if (global_id.x >= params.active_count) {
         return;
     }
+    let node_index = groups[ACTIVE_OFFSET + global_id.x];
     
     let node = nodes[node_index];
     let grid_pos = get_grid_cell(vec2<f32>(node.x, node.y));
     let grid_index = get_grid_index(grid_pos);
     
     // Atomically add node to grid cell
-    let cell_node_count = atomicAdd(&grid[grid_index].node_count, 1u);
+    let cell_node_count = atomicAdd(&grid.cells[grid_index].node_count, 1u);
     
-    // Only add if cell isn't full
     if (cell_node_count < 32u) {
-        grid[grid_index].node_indices[cell_node_count] = node_index;
+        grid.cells[grid_index].node_indices[cell_node_count] = node_index;
+    } else {
+        // Cell is full, so dense clusters still repel through the overflow list
+        let overflow_index = atomicAdd(&grid.overflow_count, 1u);
+        if (overflow_index < arrayLength(&grid.overflow)) {
+            grid.overflow[overflow_index] = OverflowEntry(grid_index, node_index);
+        }
     }
 }
 
 // Pass 2: Calculate repulsion using grid
 @compute @workgroup_size(64)
 fn calculate_repulsion(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let node_index = global_id.x;
-    
-    if (node_index >= params.node_count) {
+    if (global_id.x >= params.moving_count) {
         return;
     }
+    let node_index = groups[MOVING_OFFSET + global_id.x];
     
     let node = nodes[node_index];
     let grid_pos = get_grid_cell(vec2<f32>(node.x, node.y));
     var total_force = vec2<f32>(0.0, 0.0);
+    var has_overflow = false;
     
     // Check current cell and 8 neighboring cells (3x3 area)
     for (var dy = -1; dy <= 1; dy++) {
         for (var dx = -1; dx <= 1; dx++) {
-            let check_x = i32(grid_pos.x) + dx;
-            let check_y = i32(grid_pos.y) + dy;
+            var check_x = i32(grid_pos.x) + dx;
+            var check_y = i32(grid_pos.y) + dy;
+            if (params.world_wrap != 0u) {
+                check_x = (check_x + i32(GRID_SIZE)) % i32(GRID_SIZE);
+                check_y = (check_y + i32(GRID_SIZE)) % i32(GRID_SIZE);
+            }
             
             // Bounds check
             if (check_x >= 0 && check_x < i32(GRID_SIZE) && 
@@ -150,47 +321,176 @@ fn calculate_repulsion(@builtin(global_invocation_id) global_id: vec3<u32>) {
                 let check_grid_pos = vec2<u32>(u32(check_x), u32(check_y));
                 let check_grid_index = get_grid_index(check_grid_pos);
                 
-                // Check all nodes in this cell
-                for (var i = 0u; i < atomicLoad(&grid[check_grid_index].node_count) && i < 32u; i++) {
-                    let other_node_index = grid[check_grid_index].node_indices[i];
+                let cell_node_count = atomicLoad(&grid.cells[check_grid_index].node_count);
+                has_overflow = has_overflow || cell_node_count > 32u;
+                
+                // Check all nodes stored in this cell
+                for (var i = 0u; i < min(cell_node_count, 32u); i++) {
+                    let other_node_index = grid.cells[check_grid_index].node_indices[i];
                     
                     if (other_node_index != node_index) {
                         let other_node = nodes[other_node_index];
                         let repulsion_force = calculate_repulsion_force(node, other_node);
-                        total_force -= repulsion_force;
+                        total_force += repulsion_force;
                     }
                 }
             }
         }
     }
     
+    // Brute-force the spilled nodes of any neighboring cell that overflowed
+    if (has_overflow) {
+        let overflow_count = min(atomicLoad(&grid.overflow_count), arrayLength(&grid.overflow));
+        for (var i = 0u; i < overflow_count; i++) {
+            let entry = grid.overflow[i];
+            let cell_x = i32(entry.cell % GRID_SIZE);
+            let cell_y = i32(entry.cell / GRID_SIZE);
+            
+            if (cell_distance(cell_x, i32(grid_pos.x)) <= 1 && cell_distance(cell_y, i32(grid_pos.y)) <= 1 && entry.node != node_index) {
+                let other_node = nodes[entry.node];
+                total_force += calculate_repulsion_force(node, other_node);
+            }
+        }
+    }
+    
     // Update node with calculated repulsion forces
     nodes[node_index].fx += total_force.x;
     nodes[node_index].fy += total_force.y;
 }
 
-// Pass 3: Physics integration
+// Pass 2 (alternative): Barnes-Hut repulsion, treating cells that look smaller
+// than theta from the node as a single body at their center of mass
 @compute @workgroup_size(64)
-fn integrate_physics(@builtin(global_invocation_id) global_id: vec3<u32>) {
-    let index = global_id.x;
+fn calculate_repulsion_barnes_hut(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= params.moving_count) {
+        return;
+    }
+    let node_index = groups[MOVING_OFFSET + global_id.x];
+    
+    let node = nodes[node_index];
+    let pos = vec2<f32>(node.x, node.y);
+    var total_force = vec2<f32>(0.0, 0.0);
+    var i = 0u;
+    
+    while (i < params.quad_node_count) {
+        let cell = quadtree[i];
+        let center = vec2<f32>(cell.center_x, cell.center_y);
+        
+        if (cell.is_leaf != 0u) {
+            if (cell.body == 0xffffffffu) {
+                total_force += calculate_far_repulsion(pos, center, cell.charge);
+            } else if (cell.body != node_index) {
+                // Single bodies use their exact current position
+                let other_node = nodes[cell.body];
+                total_force += calculate_far_repulsion(pos, vec2<f32>(other_node.x, other_node.y), 1.0);
+            }
+            i = cell.next;
+        } else if (cell.width < params.theta * length(world_offset(pos, center))) {
+            total_force += calculate_far_repulsion(pos, center, cell.charge);
+            i = cell.next;
+        } else {
+            i = i + 1u;
+        }
+    }
+    
+    nodes[node_index].fx += total_force.x;
+    nodes[node_index].fy += total_force.y;
+}
+
+// Pass 3: Spring forces along edges, gathered over each node's incident edges
+@compute @workgroup_size(64)
+fn calculate_springs(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= params.moving_count) {
+        return;
+    }
+    let node_index = groups[MOVING_OFFSET + global_id.x];
+    
+    let node = nodes[node_index];
+    let entries_start = params.node_count + 1u;
+    let first_entry = adjacency[node_index];
+    let last_entry = adjacency[node_index + 1u];
+    var total_force = vec2<f32>(0.0, 0.0);
+    
+    for (var i = first_entry; i < last_entry; i++) {
+        let entry = entries_start + i * 3u;
+        let other_index = adjacency[entry];
+        if (groups[PARTICIPATION_OFFSET + other_index] == 0u) {
+            continue;
+        }
+        let other_node = nodes[other_index];
+        
+        // Non-positive per-edge rest lengths defer to the global one
+        var rest_length = bitcast<f32>(adjacency[entry + 1u]);
+        if (rest_length <= 0.0) {
+            rest_length = params.rest_length;
+        }
+        
+        let strength = bitcast<f32>(adjacency[entry + 2u]);
+        total_force += calculate_spring_force(node, other_node, rest_length, strength);
+    }
     
-    if (index >= params.node_count) {
+    nodes[node_index].fx += total_force.x;
+    nodes[node_index].fy += total_force.y;
+}
+
+// Pass 4: Physics integration, over every participant; the others and
+// frozen participants keep their velocities
+@compute @workgroup_size(64)
+fn integrate_physics(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if (global_id.x >= params.active_count) {
+        return;
+    }
+    let index = groups[ACTIVE_OFFSET + global_id.x];
+    if (groups[PARTICIPATION_OFFSET + index] == PARTICIPANT_FROZEN) {
         return;
     }
     
     var node = nodes[index];
     
-    // Integrate velocity: v += f * dt
-    node.vx += node.fx * params.delta_time;
-    node.vy += node.fy * params.delta_time;
+    // Pinned nodes hold still but keep exerting forces on the others
+    if ((node.flags & NODE_FLAG_PINNED) != 0u) {
+        nodes[index].vx = 0.0;
+        nodes[index].vy = 0.0;
+        nodes[index].fx = 0.0;
+        nodes[index].fy = 0.0;
+        return;
+    }
+    
+    let gravity = calculate_gravity_force(node);
+    node.fx += gravity.x;
+    node.fy += gravity.y;
+    
+    let group_force = calculate_group_force(index, node);
+    node.fx += group_force.x;
+    node.fy += group_force.y;
+    
+    let bounds_force = calculate_bounds_force(node);
+    node.fx += bounds_force.x;
+    node.fy += bounds_force.y;
     
-    // Apply damping: v *= damping
-    node.vx *= params.damping_factor;
-    node.vy *= params.damping_factor;
+    // Integrate velocity: v += f / m * alpha * dt
+    let inverse_mass = 1.0 / max(node.mass, 0.01);
+    node.vx += node.fx * inverse_mass * params.alpha * params.delta_time;
+    node.vy += node.fy * inverse_mass * params.alpha * params.delta_time;
+    
+    // Apply damping: v *= damping, the node's own factor if it has one
+    let node_damping = bitcast<f32>(groups[DAMPING_OFFSET + index]);
+    let damping = select(params.damping_factor, node_damping, node_damping > 0.0);
+    node.vx *= damping;
+    node.vy *= damping;
     
     // Integrate position: x += v * dt
     node.x += node.vx * params.delta_time;
     node.y += node.vy * params.delta_time;
+    apply_bounds(&node);
+    if (params.world_wrap != 0u) {
+        let world_min = vec2<f32>(params.world_min_x, params.world_min_y);
+        let world_size = vec2<f32>(params.world_size_x, params.world_size_y);
+        let offset = vec2<f32>(node.x, node.y) - world_min;
+        let wrapped = world_min + offset - world_size * floor(offset / world_size);
+        node.x = wrapped.x;
+        node.y = wrapped.y;
+    }
     
     // Reset force accumulators
     node.fx = 0.0;
@@ -203,190 +503,1248 @@ fn integrate_physics(@builtin(global_invocation_id) global_id: vec3<u32>) {
 // Buffer size limits - can handle large graphs
 pub const MAX_NODES: usize = 100_000;
 pub const MAX_EDGES: usize = 200_000;
-const FLOATS_PER_NODE: usize = 12;  // x, y, vx, vy, fx, fy, r, g, b, a, size, mass
+// Largest surface `resize` accepts, per side
+pub const MAX_SURFACE_SIZE: u32 = 2048;
+const FLOATS_PER_NODE: usize = 13;  // x, y, vx, vy, fx, fy, r, g, b, a, size, mass, flags
 const FLOATS_PER_EDGE: usize = 9;  // x1, y1, x2, y2, r, g, b, a, width
+// Nodes and edges are uploaded as is, both to the physics buffer and as render
+// instances, so their layouts must not drift from the shaders
+const _: () = assert!(std::mem::size_of::<NodeData>() == FLOATS_PER_NODE * std::mem::size_of::<f32>());
+const _: () = assert!(std::mem::size_of::<EdgeData>() == FLOATS_PER_EDGE * std::mem::size_of::<f32>());
 
-pub struct Renderer {
-    device: Option<Device>,
-    queue: Option<Queue>,
-    surface: Option<Surface<'static>>,
-    config: Option<SurfaceConfiguration>,
-    gradient_pipeline: Option<RenderPipeline>,
-    node_pipeline: Option<RenderPipeline>,
-    edge_pipeline: Option<RenderPipeline>,
-    clear_grid_pipeline: Option<ComputePipeline>,
-    assign_grid_pipeline: Option<ComputePipeline>,
-    repulsion_pipeline: Option<ComputePipeline>,
-    integration_pipeline: Option<ComputePipeline>,
-    canvas: Option<HtmlCanvasElement>,
-    uniform_buffer: Option<Buffer>,
-    uniform_bind_group: Option<BindGroup>,
-    physics_params_buffer: Option<Buffer>,
-    compute_bind_group: Option<BindGroup>,
-    node_vertex_buffer: Option<Buffer>,
-    node_instance_buffer: Option<Buffer>,
-    edge_vertex_buffer: Option<Buffer>,
-    edge_instance_buffer: Option<Buffer>,
-    node_physics_buffer: Option<Buffer>,
-    edge_physics_buffer: Option<Buffer>,
-    grid_buffer: Option<Buffer>,
+// Per-node pulse, a second instance buffer next to the node instances so pulses
+// animate on the GPU from the time uniform. Times are render times in seconds;
+// all zeros (or an end in the past) is no pulse. It also carries the node's idle
+// motion phase. Must match the pulse attributes in nodes.wgsl.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PulseState {
+    pub start: f32,
+    pub period: f32, // seconds per pulse
+    pub end: f32,
+    pub scale: f32, // size multiplier at the peak
+    pub glow: f32,  // halo width at the peak, as a fraction of the radius
+    pub phase: f32, // idle motion offset in radians, see set_idle_motion
 }
 
+// An edge's endpoints as the visibility filter and the arrowheads see them, one
+// per edge instance. Edges without indexed endpoints are never filtered.
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-struct Uniforms {
-    time: f32,
-    _padding1: f32,
-    resolution: [f32; 2],
-    color1: [f32; 4],
-    color2: [f32; 4],
-    camera_position: [f32; 2],
-    camera_zoom: f32,
-    _padding2: f32,
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct EdgeFilter {
+    pub source_flags: u32,
+    pub target_flags: u32,
+    pub has_endpoints: u32, // 1 or 0
+    pub arrow_style: u32,   // ArrowStyle::code() + 1 to override the global style, else 0
+    pub arrow_size: f32,    // canvas pixels to override the global size, else 0
+    pub target_radius: f32, // the arrow tip stops this far (world units) short of the end
 }
 
-impl Renderer {
-    pub fn new() -> Self {
-        Self {
-            device: None,
-            queue: None,
-            surface: None,
-            config: None,
-            gradient_pipeline: None,
-            node_pipeline: None,
-            edge_pipeline: None,
-            clear_grid_pipeline: None,
-            assign_grid_pipeline: None,
-            repulsion_pipeline: None,
-            integration_pipeline: None,
-            canvas: None,
-            uniform_buffer: None,
-            uniform_bind_group: None,
-            physics_params_buffer: None,
-            compute_bind_group: None,
-            node_vertex_buffer: None,
-            node_instance_buffer: None,
-            edge_vertex_buffer: None,
-            edge_instance_buffer: None,
-            node_physics_buffer: None,
-            edge_physics_buffer: None,
-            grid_buffer: None,
+// Arrowheads drawn at the target end of edges
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ArrowStyle {
+    #[default]
+    None,
+    Triangle,
+    Open, // a chevron
+    Circle,
+}
+
+impl ArrowStyle {
+    pub fn parse(style: &str) -> Option<Self> {
+        match style {
+            "none" => Some(Self::None),
+            "triangle" => Some(Self::Triangle),
+            "open" => Some(Self::Open),
+            "circle" => Some(Self::Circle),
+            _ => None,
         }
     }
 
-    pub async fn init(&mut self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
-        let width = canvas.width();
-        let height = canvas.height();
-        
-        // Validate canvas dimensions
-        if width == 0 || height == 0 {
-            return Err(JsValue::from_str("Canvas has invalid dimensions"));
-        }
-        
-        // Validate WebGPU texture size limits
-        const MAX_TEXTURE_SIZE: u32 = 2048;
-        if width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
-            return Err(JsValue::from_str(&format!(
-                "Canvas dimensions exceed WebGPU limits: {}x{} (max: {}x{})",
-                width, height, MAX_TEXTURE_SIZE, MAX_TEXTURE_SIZE
-            )));
+    // Must match ARROW_* in edges.wgsl
+    pub fn code(self) -> u32 {
+        match self {
+            Self::None => 0,
+            Self::Triangle => 1,
+            Self::Open => 2,
+            Self::Circle => 3,
         }
+    }
+}
 
-        // Create WGPU instance - allow WebGPU with WebGL fallback
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::BROWSER_WEBGPU | Backends::GL,
-            flags: Default::default(),
-            ..Default::default()
-        });
+// Where a frame looks: canvas pixel = (world - position) * zoom
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    pub position: [f32; 2],
+    pub zoom: f32,
+}
 
-        // Create surface directly from canvas element
-        let surface = instance
-            .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
-            .map_err(|e| JsValue::from_str(&format!("Failed to create surface: {:?}", e)))?;
+impl Camera {
+    // Canvas pixels of a world point, origin top left and y down
+    pub fn world_to_pixel(&self, world: [f32; 2]) -> [f32; 2] {
+        [0, 1].map(|axis| (world[axis] - self.position[axis]) * self.zoom)
+    }
+}
 
-        // Try to get adapter without surface compatibility first (better for compute shaders)
-        let adapter = match instance
-            .request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-        {
-            Ok(adapter) => {
-                log!("Got adapter without surface compatibility - checking compute capabilities");
-                // Check if this adapter has good compute shader support
-                if adapter.limits().max_storage_buffers_per_shader_stage >= 3 {
-                    log!("Adapter has excellent compute shader support ({} storage buffers per stage)", adapter.limits().max_storage_buffers_per_shader_stage);
-                    adapter
-                } else {
-                    log!("Adapter has limited compute support, trying surface-compatible adapter");
-                    instance
-                        .request_adapter(&RequestAdapterOptions {
-                            power_preference: PowerPreference::default(),
-                            compatible_surface: Some(&surface),
-                            force_fallback_adapter: false,
-                        })
-                        .await
-                        .map_err(|e| JsValue::from_str(&format!("Failed to request surface-compatible adapter: {:?}", e)))?
-                }
-            }
-            Err(_) => {
-                log!("No adapter available without surface compatibility, using surface-compatible adapter");
-                instance
-                    .request_adapter(&RequestAdapterOptions {
-                        power_preference: PowerPreference::default(),
-                        compatible_surface: Some(&surface),
-                        force_fallback_adapter: false,
-                    })
-                    .await
-                    .map_err(|e| JsValue::from_str(&format!("Failed to request adapter: {:?}", e)))?
-            }
-        };
+// A camera on a canvas of `resolution` pixels. The uniforms are filled in from
+// it, and world_to_ndc is what the vertex shaders compute from them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub camera: Camera,
+    pub resolution: [f32; 2],
+}
 
-        // Try to get device with better limits first (for compute shaders), fall back to WebGL2 limits
-        let (device, queue) = match adapter
-            .request_device(
-                &DeviceDescriptor {
-                    label: None,
-                    required_features: Features::empty(),
-                    required_limits: Limits::downlevel_defaults(),
-                    memory_hints: Default::default(),
-                    trace: Default::default(),
-                },
-            )
-            .await
-        {
-            Ok(device_queue) => {
-                log!("Successfully created device with downlevel_defaults limits");
-                device_queue
-            }
-            Err(_) => {
-                log!("Failed with downlevel_defaults, falling back to webgl2_defaults");
-                adapter
-                    .request_device(
-                        &DeviceDescriptor {
-                            label: None,
-                            required_features: Features::empty(),
-                            required_limits: Limits::downlevel_webgl2_defaults(),
-                            memory_hints: Default::default(),
-                            trace: Default::default(),
-                        },
-                    )
-                    .await
-                    .map_err(|e| JsValue::from_str(&format!("Failed to create device with fallback limits: {:?}", e)))?
-            }
-        };
-        
-        // Check actual device capabilities
-        let device_limits = device.limits();
-        log!("Device limits - storage buffers per stage: {}", device_limits.max_storage_buffers_per_shader_stage);
-        log!("Device limits - compute workgroup storage: {}", device_limits.max_compute_workgroup_storage_size);
-        log!("Device limits - compute invocations per workgroup: {}", device_limits.max_compute_invocations_per_workgroup);
+impl Projection {
+    pub fn new(camera: Camera, (width, height): (u32, u32)) -> Self {
+        Self { camera, resolution: [width as f32, height as f32] }
+    }
 
-        // Configure surface
-        let config = SurfaceConfiguration {
-            usage: TextureUsages::RENDER_ATTACHMENT,
+    pub fn world_to_pixel(&self, world: [f32; 2]) -> [f32; 2] {
+        self.camera.world_to_pixel(world)
+    }
+
+    // Canvas pixels to NDC, as pixel_to_ndc in edges.wgsl
+    pub fn pixel_to_ndc(&self, pixel: [f32; 2]) -> [f32; 2] {
+        [pixel[0] / self.resolution[0] * 2.0 - 1.0, 1.0 - pixel[1] / self.resolution[1] * 2.0]
+    }
+
+    pub fn world_to_ndc(&self, world: [f32; 2]) -> [f32; 2] {
+        self.pixel_to_ndc(self.world_to_pixel(world))
+    }
+}
+
+// How node and edge instances reach the vertex shaders
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum InstanceFormat {
+    Full,    // NodeData and EdgeData as is
+    Compact, // CompactNode and CompactEdge
+    Storage, // positions read from the physics buffer, see set_render_from_physics
+}
+
+// What the last frame uploaded, so the minimap can draw it again
+#[derive(Clone, Copy, Debug)]
+struct DrawnInstances {
+    format: InstanceFormat,
+    nodes: u32,
+    edges: u32,
+}
+
+// A second, smaller surface showing the whole graph, see render_minimap
+struct Minimap {
+    surface: Surface<'static>,
+    config: SurfaceConfiguration,
+    uniform_buffer: Buffer,
+    uniform_bind_group: BindGroup,
+    viewport_buffer: Buffer, // four edges outlining the main view, then their zeroed user flags
+}
+
+// Instances drawn over the frame, see set_overlay. Uses the full instance
+// format, with zeroed pulses and user flags after the instances.
+struct Overlay {
+    uniform_buffer: Buffer, // the frame's uniforms, less the edge stride and visibility filter
+    uniform_bind_group: BindGroup,
+    edge_buffer: Buffer,
+    node_buffer: Buffer,
+    edges: u32,
+    nodes: u32,
+}
+
+pub const MAX_OVERLAY_EDGES: usize = 256;
+pub const MAX_OVERLAY_NODES: usize = 16;
+
+// Edges drawn a batch per frame into an offscreen texture while nothing
+// changes, then composited under the nodes, see set_progressive_rendering
+struct Progressive {
+    budget_ms: f32,
+    batch: u32, // edge instances per frame, adapted to the budget
+    last_frame: Option<(f64, bool)>, // time of the previous frame and whether it drew edges
+    composite_pipeline: RenderPipeline,
+    texture_layout: BindGroupLayout,
+    target: Option<AccumulationTarget>,
+    key: Option<AccumulationKey>, // what the previous frame's edges were drawn with
+    edges: Vec<EdgeData>,         // the previous frame's edges, to spot changes
+    accumulated: u32,             // leading edge instances already in the texture
+}
+
+struct AccumulationTarget {
+    size: (u32, u32),
+    _texture: Texture,
+    view: TextureView,
+    bind_group: BindGroup,
+}
+
+// Everything besides the edges themselves that changes the accumulated pixels
+#[derive(Clone, Copy, PartialEq)]
+struct AccumulationKey {
+    camera_position: [f32; 2],
+    camera_zoom: f32,
+    size: (u32, u32),
+    opacity: f32,
+    visibility_filter: [u32; 2],
+    format: InstanceFormat,
+    filter_generation: u64,
+}
+
+// What the edges of a frame go through
+enum EdgePass {
+    Direct { stride: u32 },
+    Accumulate { instances: std::ops::Range<u32> },
+    Composite,
+}
+
+const MIN_PROGRESSIVE_BATCH: u32 = 1_000;
+const INITIAL_PROGRESSIVE_BATCH: u32 = 20_000;
+
+impl Progressive {
+    // Picks the frame's edge pass, after adapting the batch to how long the
+    // previous frame took
+    fn plan(&mut self, time: f64, key: AccumulationKey, edges: &[EdgeData]) -> EdgePass {
+        if let Some((last_time, drew_edges)) = self.last_frame {
+            let interval_ms = ((time - last_time) * 1000.0) as f32;
+            // Longer gaps are the page sleeping rather than slow frames
+            if drew_edges && interval_ms > 0.0 && interval_ms < 1000.0 {
+                self.batch = if interval_ms > self.budget_ms {
+                    (self.batch / 2).max(MIN_PROGRESSIVE_BATCH)
+                } else {
+                    self.batch.saturating_add(self.batch / 4).min(MAX_EDGES as u32)
+                };
+            }
+        }
+
+        let count = edges.len() as u32;
+        let unchanged = self.key == Some(key) && bytemuck::cast_slice::<_, u8>(&self.edges) == bytemuck::cast_slice::<_, u8>(edges);
+        let pass = if count == 0 {
+            self.key = None;
+            EdgePass::Direct { stride: 1 }
+        } else if !unchanged {
+            self.key = Some(key);
+            self.edges.clear();
+            self.edges.extend_from_slice(edges);
+            self.accumulated = 0;
+            EdgePass::Direct { stride: count.div_ceil(self.batch).max(1) }
+        } else if self.accumulated < count {
+            let start = self.accumulated;
+            self.accumulated = count.min(start.saturating_add(self.batch));
+            EdgePass::Accumulate { instances: start..self.accumulated }
+        } else {
+            EdgePass::Composite
+        };
+        self.last_frame = Some((time, !matches!(pass, EdgePass::Composite)));
+        pass
+    }
+
+    // (Re)creates the texture when the surface size changed
+    fn ensure_target(&mut self, device: &Device, config: &SurfaceConfiguration) {
+        let size = (config.width, config.height);
+        if self.target.as_ref().is_some_and(|target| target.size == size) {
+            return;
+        }
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Edge Accumulation Texture"),
+            size: Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Edge Accumulation Bind Group"),
+            layout: &self.texture_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&view),
+            }],
+        });
+        self.target = Some(AccumulationTarget { size, _texture: texture, view, bind_group });
+    }
+}
+
+// Bundled edge polylines, see set_edge_bundles
+struct EdgeBundles {
+    bind_group: BindGroup, // the control points and the segment count
+    segments: u32,
+    edges: u32,
+}
+
+// An image drawn over a world rect under the edges, see set_background_image
+struct BackgroundImage {
+    pipeline: RenderPipeline,
+    texture: Texture,
+    rect_buffer: Buffer,
+    bind_group: BindGroup,
+    size: (u32, u32),
+}
+
+impl BackgroundImage {
+    // Builds the pipeline and a texture of `size` texels with a full mip chain
+    fn new(device: &Device, surface_format: TextureFormat, size: (u32, u32), uniform_bind_group_layout: &BindGroupLayout) -> Self {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Background Image Shader"),
+            source: ShaderSource::Wgsl(include_str!("shaders/background.wgsl").into()),
+        });
+        let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Background Image Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Background Image Pipeline Layout"),
+            bind_group_layouts: &[uniform_bind_group_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Background Image Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format: surface_format,
+                    blend: Some(BlendState::ALPHA_BLENDING), // transparent texels show the clear color
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        // Texels come out of the shader as they went in, so they're stored
+        // sRGB-encoded exactly when the surface is
+        let format = if surface_format.is_srgb() { TextureFormat::Rgba8UnormSrgb } else { TextureFormat::Rgba8Unorm };
+        let texture = device.create_texture(&TextureDescriptor {
+            label: Some("Background Image Texture"),
+            size: Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: mip_level_count(size),
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("Background Image Sampler"),
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+        let rect_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Background Image Rect Buffer"),
+            size: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Background Image Bind Group"),
+            layout: &texture_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: rect_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        Self { pipeline, texture, rect_buffer, bind_group, size }
+    }
+
+    // Writes `rgba` to the base level and box-filtered halvings of it to the others
+    fn upload(&self, queue: &Queue, rgba: &[u8], rect: [f32; 4]) {
+        let mut level = rgba.to_vec();
+        let mut size = self.size;
+        for mip_level in 0..self.texture.mip_level_count() {
+            if mip_level > 0 {
+                (level, size) = downsample(&level, size);
+            }
+            queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &self.texture,
+                    mip_level,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                &level,
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.0 * 4),
+                    rows_per_image: Some(size.1),
+                },
+                Extent3d {
+                    width: size.0,
+                    height: size.1,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        queue.write_buffer(&self.rect_buffer, 0, bytemuck::cast_slice(&rect));
+    }
+}
+
+// Levels down to 1x1, halving the larger side each time
+fn mip_level_count(size: (u32, u32)) -> u32 {
+    32 - size.0.max(size.1).max(1).leading_zeros()
+}
+
+// Averages 2x2 blocks of an RGBA8 image, clamping at odd edges
+fn downsample(rgba: &[u8], size: (u32, u32)) -> (Vec<u8>, (u32, u32)) {
+    let (width, height) = (size.0 as usize, size.1 as usize);
+    let half = ((width / 2).max(1), (height / 2).max(1));
+    let mut out = vec![0; half.0 * half.1 * 4];
+    for y in 0..half.1 {
+        for x in 0..half.0 {
+            let rows = [(y * 2).min(height - 1), (y * 2 + 1).min(height - 1)];
+            let columns = [(x * 2).min(width - 1), (x * 2 + 1).min(width - 1)];
+            for channel in 0..4 {
+                let sum: u32 = rows
+                    .iter()
+                    .flat_map(|&row| columns.iter().map(move |&column| rgba[(row * width + column) * 4 + channel] as u32))
+                    .sum();
+                out[(y * half.0 + x) * 4 + channel] = ((sum + 2) / 4) as u8;
+            }
+        }
+    }
+    (out, (half.0 as u32, half.1 as u32))
+}
+
+// Instance formats for set_compact_instances: colors as Unorm8x4 and node sizes
+// as f16, packed on upload. Must match CompactVertexInput in nodes.wgsl.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CompactNode {
+    x: f32,
+    y: f32,
+    color: u32,      // r in the lowest byte
+    size_class: u32, // f16 size in the low half, style class in the high half
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct CompactEdge {
+    start: [f32; 2],
+    end: [f32; 2],
+    color: u32,
+    width: f32,
+}
+
+impl CompactNode {
+    fn new(node: &NodeData) -> Self {
+        Self {
+            x: node.x,
+            y: node.y,
+            color: pack_unorm8([node.r, node.g, node.b, node.a]),
+            size_class: f16_bits(node.size) as u32 | (node.flags & !((1 << NODE_CLASS_SHIFT) - 1)),
+        }
+    }
+}
+
+impl CompactEdge {
+    fn new(edge: &EdgeData) -> Self {
+        Self {
+            start: [edge.x1, edge.y1],
+            end: [edge.x2, edge.y2],
+            color: pack_unorm8([edge.r, edge.g, edge.b, edge.a]),
+            width: edge.width,
+        }
+    }
+}
+
+fn pack_unorm8(color: [f32; 4]) -> u32 {
+    color
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| ((channel.clamp(0.0, 1.0) * 255.0).round() as u32) << (8 * i))
+        .fold(0, |packed, channel| packed | channel)
+}
+
+// IEEE half precision, rounded to nearest. Sizes are small and positive, so
+// values too small for a normal half become 0 and too large ones infinity.
+fn f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = (bits >> 16) & 0x8000;
+    if value.is_nan() {
+        return (sign | 0x7e00) as u16;
+    }
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    if exponent >= 31 {
+        return (sign | 0x7c00) as u16;
+    }
+    if exponent <= 0 {
+        return sign as u16;
+    }
+    let mantissa = bits & 0x7f_ffff;
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity
+    let half = (exponent as u32) << 10 | mantissa >> 13;
+    (sign | (half + ((mantissa >> 12) & 1))) as u16
+}
+const ADJACENCY_WORDS_PER_ENTRY: usize = 3;  // neighbor index, rest length, strength
+const ADJACENCY_BUFFER_WORDS: usize = MAX_NODES + 1 + 2 * MAX_EDGES * ADJACENCY_WORDS_PER_ENTRY;
+const MAX_QUAD_NODES: usize = 4 * MAX_NODES;
+// Storage buffers bound by PHYSICS_SHADER (nodes, adjacency, grid, quadtree, groups)
+const PHYSICS_STORAGE_BUFFERS: u32 = 5;
+// Group slots for every node, then a centroid pair for up to MAX_NODES groups,
+// then a damping factor and a participation word for every node, and the two
+// participant index lists, see upload_participants
+const GROUP_BUFFER_WORDS: usize = 7 * MAX_NODES;
+const DAMPING_OFFSET_WORDS: usize = 3 * MAX_NODES;
+const PARTICIPATION_OFFSET_WORDS: usize = 4 * MAX_NODES;
+const ACTIVE_OFFSET_WORDS: usize = 5 * MAX_NODES;
+const MOVING_OFFSET_WORDS: usize = 6 * MAX_NODES;
+// Participation word of a participant that exerts forces but holds still
+const PARTICIPANT_FROZEN: u32 = 2;
+
+// States of the physics position readback, shared with the map_async callback
+const READBACK_IDLE: u8 = 0;
+const READBACK_PENDING: u8 = 1;
+const READBACK_READY: u8 = 2;
+
+pub struct Renderer {
+    device: Option<Device>,
+    queue: Option<Queue>,
+    surface: Option<Surface<'static>>,
+    config: Option<SurfaceConfiguration>,
+    gradient_pipeline: Option<RenderPipeline>,
+    node_pipeline: Option<RenderPipeline>,
+    edge_pipeline: Option<RenderPipeline>,
+    compact_node_pipeline: Option<RenderPipeline>,
+    compact_edge_pipeline: Option<RenderPipeline>,
+    storage_node_pipeline: Option<RenderPipeline>,
+    storage_edge_pipeline: Option<RenderPipeline>,
+    clear_grid_pipeline: Option<ComputePipeline>,
+    assign_grid_pipeline: Option<ComputePipeline>,
+    repulsion_pipeline: Option<ComputePipeline>,
+    barnes_hut_pipeline: Option<ComputePipeline>,
+    spring_pipeline: Option<ComputePipeline>,
+    integration_pipeline: Option<ComputePipeline>,
+    #[cfg(target_arch = "wasm32")]
+    canvas: Option<HtmlCanvasElement>,
+    uniform_buffer: Option<Buffer>,
+    uniform_bind_group: Option<BindGroup>,
+    physics_params_buffer: Option<Buffer>,
+    compute_bind_group: Option<BindGroup>,
+    node_vertex_buffer: Option<Buffer>,
+    node_instance_buffer: Option<Buffer>,
+    pulse_buffer: Option<Buffer>,
+    pulse_count: usize, // entries written by the last upload_pulses
+    node_filter_buffer: Option<Buffer>, // user flags per node instance, see upload_filter_flags
+    edge_filter_buffer: Option<Buffer>,
+    filter_counts: (usize, usize), // node and edge entries written by the last upload_filter_flags
+    visibility_filter: [u32; 2],   // required and forbidden user flags
+    filter_generation: u64,        // bumped by each upload_filter_flags
+    edge_vertex_buffer: Option<Buffer>,
+    edge_instance_buffer: Option<Buffer>,
+    node_physics_buffer: Option<Buffer>,
+    adjacency_buffer: Option<Buffer>,
+    grid_buffer: Option<Buffer>,
+    quadtree_buffer: Option<Buffer>,
+    quad_node_count: u32,
+    group_buffer: Option<Buffer>,
+    damping_count: usize, // per-node damping factors in group_buffer
+    physics_active: Vec<u32>, // participant indices in group_buffer, see upload_participants
+    physics_moving: Vec<u32>,
+    position_readback_buffer: Option<Buffer>,
+    physics_edge_count: u32,
+    readback_state: Arc<AtomicU8>,
+    readback_node_count: usize,
+    readback_generation: u64,
+    unread_ticks: Option<usize>, // node count of ticks run since the last readback started
+    upload_generation: u64,
+    node_styles: Vec<NodeStyle>,
+    style_buffer: Option<Buffer>,
+    clear_color: [f32; 4],
+    gradient_style: GradientStyle,
+    gradient_speed: f32,          // 0 freezes the gradient
+    gradient_phase: f32,
+    gradient_clock: Option<f64>, // render time the phase was last advanced at
+    idle_motion: (IdleMotion, f32, f32), // mode, amplitude and frequency
+    arrows: (ArrowStyle, f32),           // global arrowhead style and size in canvas pixels
+    edge_antialias: (f32, f32),          // edge smoothing and minimum width, see set_edge_antialias
+    pixel_snap: f32,                     // see Uniforms::pixel_snap
+    global_opacity: f32,
+    capabilities: Option<Capabilities>,
+    device_info: Option<DeviceInfo>,
+    validation_errors: Arc<Mutex<Vec<String>>>, // filled when a frame's error scope resolves
+    compact_instances: bool,
+    compact_nodes: Vec<CompactNode>, // per-frame packing scratch, kept for its capacity
+    compact_edges: Vec<CompactEdge>,
+    instance_bytes: usize, // node and edge instance bytes written by the last frame
+    storage_bind_group: Option<BindGroup>, // the physics buffer, for the storage pipelines
+    edge_index_buffer: Option<Buffer>,
+    edge_index_count: u32,
+    render_from_physics: bool,
+    bundled_edge_pipeline: Option<RenderPipeline>, // when vertex shaders can read storage buffers
+    bundle_bind_group_layout: Option<BindGroupLayout>,
+    edge_bundles: Option<EdgeBundles>,
+    draw_edge_bundles: bool,
+    drawn: DrawnInstances,
+    instance: Option<Instance>, // kept to create the minimap's surface
+    adapter: Option<Adapter>,
+    uniform_bind_group_layout: Option<BindGroupLayout>,
+    minimap: Option<Minimap>,
+    progressive: Option<Progressive>,
+    overlay: Option<Overlay>,
+    background_image: Option<BackgroundImage>,
+}
+
+// Maps the first `bytes` of the staging buffer once the copy submitted before
+// completes, moving `state` from pending to ready
+fn map_readback(buffer: &Buffer, bytes: u64, state: &Arc<AtomicU8>) {
+    state.store(READBACK_PENDING, Ordering::Release);
+    let state = Arc::clone(state);
+    buffer.slice(..bytes).map_async(MapMode::Read, move |result| {
+        let ready = if result.is_ok() { READBACK_READY } else { READBACK_IDLE };
+        state.store(ready, Ordering::Release);
+    });
+}
+
+// Must match the PhysicsParams struct in PHYSICS_SHADER
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct PhysicsParams {
+    delta_time: f32,
+    damping_factor: f32,
+    spring_constant: f32,
+    rest_length: f32,
+    repulsion_strength: f32,
+    repulsion_radius: f32,
+    node_count: u32,
+    edge_count: u32,
+    gravity_strength: f32,
+    gravity_center_x: f32,
+    gravity_center_y: f32,
+    gravity_mode: u32,
+    alpha: f32,
+    theta: f32,
+    quad_node_count: u32,
+    group_attraction: f32,
+    world_min: [f32; 2],
+    world_size: [f32; 2],
+    bounds_min: [f32; 2],
+    bounds_max: [f32; 2],
+    bounds_mode: u32,
+    bounds_restitution: f32,
+    bounds_strength: f32,
+    world_wrap: u32,
+    active_count: u32,
+    moving_count: u32,
+    _padding: [u32; 2],
+}
+
+// Cells per side of the physics spatial grid, must match GRID_SIZE in PHYSICS_SHADER
+const PHYSICS_GRID_SIZE: f32 = 32.0;
+
+const GRID_CELL_CAPACITY: usize = 32;
+
+// Spatial grid contents in the shader's SpatialGrid layout with every cell (and the
+// overflow list) in ascending node order, so results don't depend on the
+// nondeterministic order of the atomic insertion in assign_to_grid, nor on the
+// order of `active`
+fn pack_sorted_grid(nodes: &[NodeData], active: &[u32], world_min: [f32; 2], world_size: [f32; 2]) -> Vec<u32> {
+    let cell_count = (PHYSICS_GRID_SIZE * PHYSICS_GRID_SIZE) as usize;
+    let cell_words = GRID_CELL_CAPACITY + 1;
+    let mut words = vec![0u32; cell_count * cell_words + 1];
+    let mut overflow = Vec::new();
+
+    // Only participants, as in assign_to_grid. They're usually ascending
+    // already, which makes the sort cheap.
+    let mut active = active.to_vec();
+    active.sort_unstable();
+    for index in active {
+        let node = &nodes[index as usize];
+        // Same arithmetic as get_grid_cell in PHYSICS_SHADER
+        let cell_x = (((node.x - world_min[0]) / world_size[0]).clamp(0.0, 0.999) * PHYSICS_GRID_SIZE) as usize;
+        let cell_y = (((node.y - world_min[1]) / world_size[1]).clamp(0.0, 0.999) * PHYSICS_GRID_SIZE) as usize;
+        let cell = cell_y * PHYSICS_GRID_SIZE as usize + cell_x;
+
+        let count_word = cell * cell_words + GRID_CELL_CAPACITY;
+        let count = words[count_word] as usize;
+        if count < GRID_CELL_CAPACITY {
+            words[cell * cell_words + count] = index;
+        } else {
+            overflow.push([cell as u32, index]);
+        }
+        words[count_word] += 1;
+    }
+
+    overflow.sort_unstable();
+    words[cell_count * cell_words] = overflow.len() as u32;
+    words.extend(overflow.iter().flatten());
+    words
+}
+
+// Area covered by the spatial grid as (min, size): either the fixed world bounds or
+// the padded bounding box of the nodes. Cells never get narrower than the repulsion
+// radius, since the repulsion pass only looks at neighboring cells.
+fn grid_world_bounds(nodes: &[NodeData], settings: &PhysicsSettings) -> ([f32; 2], [f32; 2]) {
+    if let Some([min_x, min_y, max_x, max_y]) = settings.world_bounds {
+        return ([min_x, min_y], [max_x - min_x, max_y - min_y]);
+    }
+
+    let mut min = [f32::INFINITY; 2];
+    let mut max = [f32::NEG_INFINITY; 2];
+    for node in nodes.iter().filter(|node| node.x.is_finite() && node.y.is_finite()) {
+        min = [min[0].min(node.x), min[1].min(node.y)];
+        max = [max[0].max(node.x), max[1].max(node.y)];
+    }
+    if min[0] > max[0] {
+        return ([-1.0, -1.0], [2.0, 2.0]);
+    }
+
+    let min_size = (settings.repulsion_radius * PHYSICS_GRID_SIZE).max(1e-3);
+    let mut world_min = [0.0; 2];
+    let mut world_size = [0.0; 2];
+    for axis in 0..2 {
+        let extent = max[axis] - min[axis];
+        let size = (extent * 1.1).max(min_size);
+        world_min[axis] = min[axis] - (size - extent) * 0.5;
+        world_size[axis] = size;
+    }
+    (world_min, world_size)
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    time: f32,
+    opacity: f32, // global alpha multiplier for nodes and edges
+    resolution: [f32; 2],
+    color1: [f32; 4],
+    color2: [f32; 4],
+    camera_position: [f32; 2],
+    camera_zoom: f32,
+    point_size: f32, // nodes as dots of this diameter and edges as hairlines when > 0
+    filter_required: u32, // see set_visibility_filter
+    filter_forbidden: u32,
+    edge_stride: u32, // draws every nth edge only, see set_progressive_rendering; 0 draws all
+    gradient_style: u32, // GRADIENT_LINEAR or GRADIENT_RADIAL
+    gradient: [f32; 4], // linear: direction; radial: center and radius, see GradientStyle
+    gradient_phase: f32, // animation time of the gradient
+    motion_mode: u32, // IdleMotion as MOTION_*
+    motion_amplitude: f32,
+    motion_frequency: f32, // cycles per second
+    arrow_style: u32, // ArrowStyle::code()
+    arrow_size: f32,  // canvas pixels
+    edge_smoothing: f32, // canvas pixels the edge outlines fade over
+    edge_min_width: f32, // canvas pixels; thinner edges are drawn this wide, fainter
+    pixel_snap: f32, // nodes with a smaller radius in canvas pixels snap to the pixel grid; 0 is off
+    _padding: [u32; 3],
+}
+
+const MOTION_NONE: u32 = 0;
+const MOTION_BOB: u32 = 1;
+const MOTION_SHIMMER: u32 = 2;
+
+// Ambient per-node motion, drawn only, each node offset by its phase
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum IdleMotion {
+    #[default]
+    None,
+    Bob,     // up and down by the amplitude in canvas pixels
+    Shimmer, // brightness up and down by the amplitude as a fraction
+}
+
+pub const DEFAULT_EDGE_SMOOTHING: f32 = 1.0;
+pub const DEFAULT_EDGE_MIN_WIDTH: f32 = 1.0;
+// Nodes drawn with a smaller radius (canvas pixels) snap to the pixel grid when
+// pixel snapping is on; larger ones keep moving smoothly
+const PIXEL_SNAP_MAX_RADIUS: f32 = 4.0;
+
+const GRADIENT_LINEAR: u32 = 0;
+const GRADIENT_RADIAL: u32 = 1;
+
+// How the background gradient blends color1 into color2
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientStyle {
+    // Across the canvas at `angle` radians, 0 running left to right and
+    // pi / 2 top to bottom
+    Linear { angle: f32 },
+    // Outward from `center` (fractions of the canvas width and height) until
+    // `radius` (a fraction of the shorter side)
+    Radial { center: [f32; 2], radius: f32 },
+}
+
+impl Default for GradientStyle {
+    fn default() -> Self {
+        GradientStyle::Linear { angle: std::f32::consts::FRAC_PI_4 }
+    }
+}
+
+impl GradientStyle {
+    fn uniforms(&self) -> (u32, [f32; 4]) {
+        match *self {
+            GradientStyle::Linear { angle } => (GRADIENT_LINEAR, [angle.cos(), angle.sin(), 0.0, 0.0]),
+            GradientStyle::Radial { center, radius } => (GRADIENT_RADIAL, [center[0], center[1], radius, 0.0]),
+        }
+    }
+}
+
+// Style classes available to nodes; class 0 always uses the per-node attributes
+pub const MAX_STYLE_CLASSES: usize = 64;
+
+// NodeStyle::fields bits: which parts of the style override the node's own
+pub const STYLE_FIELD_COLOR: u32 = 1;
+pub const STYLE_FIELD_SIZE: u32 = 2;
+pub const STYLE_FIELD_BORDER: u32 = 4;
+pub const STYLE_FIELD_SHAPE: u32 = 8;
+pub const STYLE_FIELD_GLOW: u32 = 16;
+
+// Must match the NodeStyle struct in nodes.wgsl
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct NodeStyle {
+    pub color: [f32; 4],
+    pub border_color: [f32; 4],
+    pub size: f32,         // radius in pixels
+    pub border_width: f32, // in pixels, inside the radius
+    pub glow: f32,         // halo width as a fraction of the radius
+    pub fields: u32,       // STYLE_FIELD_* bits
+    pub shape: u32,        // 0 circle, 1 square, 2 diamond, 3 triangle, 4 squircle
+    pub corner: f32,       // squircle roundness, 0 square to 1 circle
+    pub _padding: [u32; 2],
+}
+
+// What an adapter (or, after init, the negotiated device) offers
+#[derive(Clone, Debug, Default)]
+pub struct Capabilities {
+    pub webgpu: bool,
+    pub backend: Option<&'static str>, // "webgpu" or "gl" in the browser
+    pub compute: bool,                 // enough storage buffers for the physics
+    pub max_texture_size: u32,
+    pub max_buffer_size: u64,
+    pub adapter_name: String,
+    pub gpu_physics: bool, // only set after init, when the physics pipelines exist
+    pub storage_rendering: bool, // only set after init, see Renderer::set_render_from_physics
+}
+
+fn capabilities_of(adapter: &Adapter, limits: &Limits) -> Capabilities {
+    let info = adapter.get_info();
+    let backend = match info.backend {
+        Backend::BrowserWebGpu => "webgpu",
+        Backend::Gl => "gl",
+        other => other.to_str(),
+    };
+    Capabilities {
+        webgpu: info.backend == Backend::BrowserWebGpu,
+        backend: Some(backend),
+        compute: adapter.get_downlevel_capabilities().flags.contains(DownlevelFlags::COMPUTE_SHADERS)
+            && limits.max_storage_buffers_per_shader_stage >= PHYSICS_STORAGE_BUFFERS,
+        max_texture_size: limits.max_texture_dimension_2d,
+        max_buffer_size: limits.max_buffer_size,
+        adapter_name: info.name,
+        gpu_physics: false,
+        storage_rendering: false,
+    }
+}
+
+// What init actually got, for diagnosing slow or broken setups. Unlike
+// Capabilities, the limits are the device's, not the adapter's.
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub adapter_name: String,
+    pub vendor: u32, // PCI vendor id, 0 where the browser hides it
+    pub device: u32,
+    pub device_type: &'static str,
+    pub backend: &'static str,
+    pub driver: String,
+    pub driver_info: String,
+    pub max_storage_buffers_per_shader_stage: u32,
+    pub max_storage_buffer_binding_size: u32,
+    pub max_buffer_size: u64,
+    pub max_texture_dimension_2d: u32,
+    pub gpu_physics: bool,
+    pub surface_format: Option<String>, // None for headless renderers
+}
+
+fn device_info_of(adapter: &Adapter, limits: &Limits, gpu_physics: bool, surface_format: Option<TextureFormat>) -> DeviceInfo {
+    let info = adapter.get_info();
+    let device_type = match info.device_type {
+        DeviceType::IntegratedGpu => "integrated",
+        DeviceType::DiscreteGpu => "discrete",
+        DeviceType::VirtualGpu => "virtual",
+        DeviceType::Cpu => "cpu",
+        DeviceType::Other => "other",
+    };
+    DeviceInfo {
+        adapter_name: info.name,
+        vendor: info.vendor,
+        device: info.device,
+        device_type,
+        backend: capabilities_of(adapter, limits).backend.unwrap_or("unknown"),
+        driver: info.driver,
+        driver_info: info.driver_info,
+        max_storage_buffers_per_shader_stage: limits.max_storage_buffers_per_shader_stage,
+        max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+        max_buffer_size: limits.max_buffer_size,
+        max_texture_dimension_2d: limits.max_texture_dimension_2d,
+        gpu_physics,
+        surface_format: surface_format.map(|format| format!("{:?}", format)),
+    }
+}
+
+// Looks for an adapter without creating a renderer: WebGPU (or a native
+// backend) first, then on the web WebGL through a detached canvas, since GL
+// adapters need a surface there. Reports no backend when nothing is available.
+pub async fn probe_capabilities() -> Capabilities {
+    let instance = Instance::new(&InstanceDescriptor {
+        backends: Backends::BROWSER_WEBGPU | Backends::PRIMARY | Backends::GL,
+        flags: Default::default(),
+        ..Default::default()
+    });
+
+    let options = RequestAdapterOptions {
+        power_preference: PowerPreference::HighPerformance,
+        compatible_surface: None,
+        force_fallback_adapter: false,
+    };
+    if let Ok(adapter) = instance.request_adapter(&options).await {
+        return capabilities_of(&adapter, &adapter.limits());
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    return probe_with_canvas(&instance, options).await;
+    #[cfg(not(target_arch = "wasm32"))]
+    Capabilities::default()
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn probe_with_canvas(instance: &Instance, options: RequestAdapterOptions<'_, '_>) -> Capabilities {
+    let canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("canvas").ok())
+        .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok());
+    let Some(canvas) = canvas else {
+        return Capabilities::default();
+    };
+    let Ok(surface) = instance.create_surface(SurfaceTarget::Canvas(canvas)) else {
+        return Capabilities::default();
+    };
+    match instance
+        .request_adapter(&RequestAdapterOptions {
+            compatible_surface: Some(&surface),
+            ..options
+        })
+        .await
+    {
+        Ok(adapter) => capabilities_of(&adapter, &adapter.limits()),
+        Err(_) => Capabilities::default(),
+    }
+}
+
+// Requests a device with the limits the physics needs, falling back to WebGL2 limits
+async fn request_device(adapter: &Adapter) -> Result<(Device, Queue), String> {
+    // Try to get device with better limits first (for compute shaders), fall back to WebGL2 limits.
+    // downlevel_defaults only guarantees 4 storage buffers, so ask for what the physics needs
+    let compute_limits = Limits {
+        max_storage_buffers_per_shader_stage: adapter
+            .limits()
+            .max_storage_buffers_per_shader_stage
+            .min(PHYSICS_STORAGE_BUFFERS)
+            .max(Limits::downlevel_defaults().max_storage_buffers_per_shader_stage),
+        ..Limits::downlevel_defaults()
+    };
+    let device_queue = match adapter
+        .request_device(
+            &DeviceDescriptor {
+                label: None,
+                required_features: Features::empty(),
+                required_limits: compute_limits,
+                memory_hints: Default::default(),
+                trace: Default::default(),
+            },
+        )
+        .await
+    {
+        Ok(device_queue) => {
+            log_debug!("Successfully created device with downlevel_defaults limits");
+            device_queue
+        }
+        Err(_) => {
+            log!("Failed with downlevel_defaults, falling back to webgl2_defaults");
+            adapter
+                .request_device(
+                    &DeviceDescriptor {
+                        label: None,
+                        required_features: Features::empty(),
+                        required_limits: Limits::downlevel_webgl2_defaults(),
+                        memory_hints: Default::default(),
+                        trace: Default::default(),
+                    },
+                )
+                .await
+                .map_err(|e| format!("Failed to create device with fallback limits: {:?}", e))?
+        }
+    };
+    Ok(device_queue)
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self {
+            device: None,
+            queue: None,
+            surface: None,
+            config: None,
+            gradient_pipeline: None,
+            node_pipeline: None,
+            edge_pipeline: None,
+            compact_node_pipeline: None,
+            compact_edge_pipeline: None,
+            storage_node_pipeline: None,
+            storage_edge_pipeline: None,
+            clear_grid_pipeline: None,
+            assign_grid_pipeline: None,
+            repulsion_pipeline: None,
+            barnes_hut_pipeline: None,
+            spring_pipeline: None,
+            integration_pipeline: None,
+            #[cfg(target_arch = "wasm32")]
+            canvas: None,
+            uniform_buffer: None,
+            uniform_bind_group: None,
+            physics_params_buffer: None,
+            compute_bind_group: None,
+            node_vertex_buffer: None,
+            node_instance_buffer: None,
+            pulse_buffer: None,
+            pulse_count: 0,
+            node_filter_buffer: None,
+            edge_filter_buffer: None,
+            filter_counts: (0, 0),
+            visibility_filter: [0, 0],
+            filter_generation: 0,
+            edge_vertex_buffer: None,
+            edge_instance_buffer: None,
+            node_physics_buffer: None,
+            adjacency_buffer: None,
+            grid_buffer: None,
+            quadtree_buffer: None,
+            quad_node_count: 0,
+            group_buffer: None,
+            damping_count: 0,
+            physics_active: Vec::new(),
+            physics_moving: Vec::new(),
+            position_readback_buffer: None,
+            physics_edge_count: 0,
+            readback_state: Arc::new(AtomicU8::new(READBACK_IDLE)),
+            readback_node_count: 0,
+            readback_generation: 0,
+            unread_ticks: None,
+            upload_generation: 0,
+            node_styles: vec![NodeStyle::default(); MAX_STYLE_CLASSES],
+            style_buffer: None,
+            clear_color: [0.0, 0.0, 0.0, 1.0],
+            gradient_style: GradientStyle::default(),
+            gradient_speed: 1.0,
+            gradient_phase: 0.0,
+            gradient_clock: None,
+            idle_motion: (IdleMotion::None, 0.0, 0.0),
+            arrows: (ArrowStyle::None, 0.0),
+            edge_antialias: (DEFAULT_EDGE_SMOOTHING, DEFAULT_EDGE_MIN_WIDTH),
+            pixel_snap: 0.0,
+            global_opacity: 1.0,
+            capabilities: None,
+            device_info: None,
+            validation_errors: Arc::new(Mutex::new(Vec::new())),
+            compact_instances: false,
+            compact_nodes: Vec::new(),
+            compact_edges: Vec::new(),
+            instance_bytes: 0,
+            storage_bind_group: None,
+            edge_index_buffer: None,
+            edge_index_count: 0,
+            render_from_physics: false,
+            bundled_edge_pipeline: None,
+            bundle_bind_group_layout: None,
+            edge_bundles: None,
+            draw_edge_bundles: false,
+            drawn: DrawnInstances { format: InstanceFormat::Full, nodes: 0, edges: 0 },
+            instance: None,
+            adapter: None,
+            uniform_bind_group_layout: None,
+            minimap: None,
+            progressive: None,
+            overlay: None,
+            background_image: None,
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub async fn init(&mut self, canvas: &HtmlCanvasElement) -> Result<(), String> {
+        // Create WGPU instance - allow WebGPU with WebGL fallback
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: Backends::BROWSER_WEBGPU | Backends::GL,
+            flags: Default::default(),
+            ..Default::default()
+        });
+
+        // Create surface directly from canvas element
+        let surface = instance
+            .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
+            .map_err(|e| format!("Failed to create surface: {:?}", e))?;
+
+        self.init_surface(instance, surface, canvas.width(), canvas.height()).await?;
+        self.canvas = Some(canvas.clone());
+        Ok(())
+    }
+
+    // Renders into a native window (anything wgpu can make a surface from, such
+    // as an Arc<winit::window::Window>) of `width` x `height` pixels
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn init_with_target(&mut self, target: impl Into<SurfaceTarget<'static>>, width: u32, height: u32) -> Result<(), String> {
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: Backends::PRIMARY | Backends::GL,
+            flags: Default::default(),
+            ..Default::default()
+        });
+
+        let surface = instance
+            .create_surface(target)
+            .map_err(|e| format!("Failed to create surface: {:?}", e))?;
+
+        self.init_surface(instance, surface, width, height).await
+    }
+
+    // Picks an adapter and creates the device, pipelines and buffers for `surface`
+    async fn init_surface(&mut self, instance: Instance, surface: Surface<'static>, width: u32, height: u32) -> Result<(), String> {
+        // Validate surface dimensions
+        if width == 0 || height == 0 {
+            return Err("Canvas has invalid dimensions".to_string());
+        }
+        
+        // Validate WebGPU texture size limits
+        const MAX_TEXTURE_SIZE: u32 = 2048;
+        if width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
+            return Err(format!(
+                "Canvas dimensions exceed WebGPU limits: {}x{} (max: {}x{})",
+                width, height, MAX_TEXTURE_SIZE, MAX_TEXTURE_SIZE
+            ));
+        }
+
+        // Try to get adapter without surface compatibility first (better for compute shaders)
+        let adapter = match instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+        {
+            Ok(adapter) => {
+                log_debug!("Got adapter without surface compatibility - checking compute capabilities");
+                // Check if this adapter has good compute shader support
+                if adapter.limits().max_storage_buffers_per_shader_stage >= PHYSICS_STORAGE_BUFFERS {
+                    log!("Adapter has excellent compute shader support ({} storage buffers per stage)", adapter.limits().max_storage_buffers_per_shader_stage);
+                    adapter
+                } else {
+                    log!("Adapter has limited compute support, trying surface-compatible adapter");
+                    instance
+                        .request_adapter(&RequestAdapterOptions {
+                            power_preference: PowerPreference::default(),
+                            compatible_surface: Some(&surface),
+                            force_fallback_adapter: false,
+                        })
+                        .await
+                        .map_err(|e| format!("Failed to request surface-compatible adapter: {:?}", e))?
+                }
+            }
+            Err(_) => {
+                log!("No adapter available without surface compatibility, using surface-compatible adapter");
+                instance
+                    .request_adapter(&RequestAdapterOptions {
+                        power_preference: PowerPreference::default(),
+                        compatible_surface: Some(&surface),
+                        force_fallback_adapter: false,
+                    })
+                    .await
+                    .map_err(|e| format!("Failed to request adapter: {:?}", e))?
+            }
+        };
+
+        let (device, queue) = request_device(&adapter).await?;
+        
+        // Check actual device capabilities
+        let device_limits = device.limits();
+        log_debug!("Device limits - storage buffers per stage: {}", device_limits.max_storage_buffers_per_shader_stage);
+        log_debug!("Device limits - compute workgroup storage: {}", device_limits.max_compute_workgroup_storage_size);
+        log_debug!("Device limits - compute invocations per workgroup: {}", device_limits.max_compute_invocations_per_workgroup);
+
+        // Configure surface
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
             format: surface.get_capabilities(&adapter).formats[0],
             width,
             height,
@@ -405,119 +1763,103 @@ impl Renderer {
             mapped_at_creation: false,
         });
 
+        // Style class table, looked up by the node shader
+        let style_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Node Style Buffer"),
+            size: (MAX_STYLE_CLASSES * std::mem::size_of::<NodeStyle>()) as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        queue.write_buffer(&style_buffer, 0, bytemuck::cast_slice(&self.node_styles));
+
         // Create bind group layout for uniforms
         let uniform_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
             label: Some("Uniform Bind Group Layout"),
-            entries: &[BindGroupLayoutEntry {
-                binding: 0,
-                visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
-                ty: BindingType::Buffer {
-                    ty: BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX | ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
         });
 
         // Create bind group for uniforms
         let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
             label: Some("Uniform Bind Group"),
             layout: &uniform_bind_group_layout,
-            entries: &[BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
-        });
-
-        // Initialize uniform buffer
-        let initial_uniforms = Uniforms {
-            time: 0.0,
-            _padding1: 0.0,
-            resolution: [width as f32, height as f32],
-            color1: [1.0, 0.0, 0.0, 1.0], // Red
-            color2: [0.0, 0.0, 1.0, 1.0], // Blue
-            camera_position: [0.0, 0.0],
-            camera_zoom: 1.0,
-            _padding2: 0.0,
-        };
-        queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[initial_uniforms]));
-
-        // Create render pipelines
-        let gradient_pipeline = self.create_gradient_pipeline(&device, config.format, &uniform_bind_group_layout);
-        let node_pipeline = self.create_node_pipeline(&device, config.format, &uniform_bind_group_layout);
-        let edge_pipeline = self.create_edge_pipeline(&device, config.format, &uniform_bind_group_layout);
-        
-        // Conditionally create compute pipeline for physics (only if device supports storage buffers)
-        let (compute_pipelines, compute_bind_group, node_physics_buffer, edge_physics_buffer, physics_params_buffer, grid_buffer) = {
-            let device_storage_buffers = device.limits().max_storage_buffers_per_shader_stage;
-            log!("Checking compute shader support: device has {} storage buffers per stage, need >= 3", device_storage_buffers);
-            
-            if device_storage_buffers >= 3 {
-                log!("Device supports compute shaders, enabling GPU physics");
-                let ((clear_grid_pipeline, assign_grid_pipeline, repulsion_pipeline, integration_pipeline), compute_bind_group_layout) = self.create_compute_pipeline(&device);
-                
-                // Create physics buffers
-                let node_physics_buffer = device.create_buffer(&BufferDescriptor {
-                    label: Some("Node Physics Buffer"),
-                    size: (MAX_NODES * std::mem::size_of::<NodeData>()) as u64,
-                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
-                    mapped_at_creation: false,
-                });
-                
-                let edge_physics_buffer = device.create_buffer(&BufferDescriptor {
-                    label: Some("Edge Physics Buffer"),
-                    size: (MAX_EDGES * std::mem::size_of::<EdgeData>()) as u64,
-                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                });
-                
-                // Create physics params buffer
-                let physics_params_buffer = device.create_buffer(&BufferDescriptor {
-                    label: Some("Physics Params Buffer"),
-                    size: std::mem::size_of::<[f32; 8]>() as u64, // delta_time, damping_factor, spring_constant, rest_length, repulsion_strength, repulsion_radius, node_count, edge_count
-                    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                });
-                
-                // Create grid buffer for spatial partitioning (32x32 = 1024 cells)
-                let grid_buffer = device.create_buffer(&BufferDescriptor {
-                    label: Some("Spatial Grid Buffer"),
-                    size: (1024 * (32 * 4 + 4)) as u64, // 1024 cells * (32 u32 indices + 1 u32 count) * 4 bytes
-                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                });
-                
-                // Create compute bind group
-                let compute_bind_group = device.create_bind_group(&BindGroupDescriptor {
-                    label: Some("Physics Compute Bind Group"),
-                    layout: &compute_bind_group_layout,
-                    entries: &[
-                        BindGroupEntry {
-                            binding: 0,
-                            resource: node_physics_buffer.as_entire_binding(),
-                        },
-                        BindGroupEntry {
-                            binding: 1,
-                            resource: edge_physics_buffer.as_entire_binding(),
-                        },
-                        BindGroupEntry {
-                            binding: 2,
-                            resource: physics_params_buffer.as_entire_binding(),
-                        },
-                        BindGroupEntry {
-                            binding: 3,
-                            resource: grid_buffer.as_entire_binding(),
-                        },
-                    ],
-                });
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: style_buffer.as_entire_binding(),
+                },
+            ],
+        });
 
-                (Some((clear_grid_pipeline, assign_grid_pipeline, repulsion_pipeline, integration_pipeline)), Some(compute_bind_group), Some(node_physics_buffer), Some(edge_physics_buffer), Some(physics_params_buffer), Some(grid_buffer))
-            } else {
-                log!("Device does not support compute shaders (only {} storage buffers per stage), physics will be CPU-only", device_storage_buffers);
-                (None, None, None, None, None, None)
-            }
+        // Initialize uniform buffer
+        let projection = Projection::new(Camera { position: [0.0, 0.0], zoom: 1.0 }, (width, height));
+        let initial_uniforms = Uniforms {
+            time: 0.0,
+            opacity: 1.0,
+            resolution: projection.resolution,
+            color1: [1.0, 0.0, 0.0, 1.0], // Red
+            color2: [0.0, 0.0, 1.0, 1.0], // Blue
+            camera_position: projection.camera.position,
+            camera_zoom: projection.camera.zoom,
+            point_size: 0.0,
+            filter_required: 0,
+            filter_forbidden: 0,
+            edge_stride: 0,
+            gradient_style: GRADIENT_LINEAR,
+            gradient: [0.0; 4],
+            gradient_phase: 0.0,
+            motion_mode: MOTION_NONE,
+            motion_amplitude: 0.0,
+            motion_frequency: 0.0,
+            arrow_style: 0,
+            arrow_size: 0.0,
+            edge_smoothing: self.edge_antialias.0,
+            edge_min_width: self.edge_antialias.1,
+            pixel_snap: self.pixel_snap,
+            _padding: [0; 3],
         };
+        queue.write_buffer(&uniform_buffer, 0, bytemuck::cast_slice(&[initial_uniforms]));
+
+        // Create render pipelines
+        let gradient_pipeline = self.create_gradient_pipeline(&device, config.format, &uniform_bind_group_layout);
+        let node_pipeline = self.create_node_pipeline(&device, config.format, &[&uniform_bind_group_layout], InstanceFormat::Full);
+        let edge_pipeline = self.create_edge_pipeline(&device, config.format, &[&uniform_bind_group_layout], InstanceFormat::Full, false);
+        let compact_node_pipeline = self.create_node_pipeline(&device, config.format, &[&uniform_bind_group_layout], InstanceFormat::Compact);
+        let compact_edge_pipeline = self.create_edge_pipeline(&device, config.format, &[&uniform_bind_group_layout], InstanceFormat::Compact, false);
+        
+        self.init_compute(&device);
+        self.init_storage_rendering(&adapter, &device, config.format, &uniform_bind_group_layout);
+        self.init_bundled_edges(&adapter, &device, config.format, &uniform_bind_group_layout);
+        self.capabilities = Some(Capabilities {
+            gpu_physics: self.has_gpu_physics(),
+            storage_rendering: self.has_storage_rendering(),
+            ..capabilities_of(&adapter, &device.limits())
+        });
+        self.record_device_info(&adapter, &device, Some(config.format));
         
         // Create node vertex buffer (quad vertices)
         let quad_vertices: &[f32] = &[
@@ -531,7 +1873,7 @@ impl Renderer {
         
         let node_vertex_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Node Vertex Buffer"),
-            size: (quad_vertices.len() * std::mem::size_of::<f32>()) as u64,
+            size: std::mem::size_of_val(quad_vertices) as u64,
             usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -540,7 +1882,29 @@ impl Renderer {
         // Create node instance buffer (will be updated per frame)
         let node_instance_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Node Instance Buffer"),
-            size: (MAX_NODES * FLOATS_PER_NODE * std::mem::size_of::<f32>()) as u64,
+            size: (MAX_NODES * std::mem::size_of::<NodeData>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create pulse buffer (rewritten only when pulses change; zeroed means none)
+        let pulse_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Node Pulse Buffer"),
+            size: (MAX_NODES * std::mem::size_of::<PulseState>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        // Create filter flag buffers (zeroed means every instance passes the default filter)
+        let node_filter_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Node Filter Buffer"),
+            size: (MAX_NODES * std::mem::size_of::<u32>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let edge_filter_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Edge Filter Buffer"),
+            size: (MAX_EDGES * std::mem::size_of::<EdgeFilter>()) as u64,
             usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
@@ -557,49 +1921,636 @@ impl Renderer {
         
         let edge_vertex_buffer = device.create_buffer(&BufferDescriptor {
             label: Some("Edge Vertex Buffer"),
-            size: (edge_quad_vertices.len() * std::mem::size_of::<f32>()) as u64,
+            size: std::mem::size_of_val(edge_quad_vertices) as u64,
             usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
             mapped_at_creation: false,
         });
         queue.write_buffer(&edge_vertex_buffer, 0, bytemuck::cast_slice(edge_quad_vertices));
 
-        // Create edge instance buffer (will be updated per frame)
-        let edge_instance_buffer = device.create_buffer(&BufferDescriptor {
-            label: Some("Edge Instance Buffer"),
-            size: (MAX_EDGES * FLOATS_PER_EDGE * std::mem::size_of::<f32>()) as u64,
-            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        // Create edge instance buffer (will be updated per frame)
+        let edge_instance_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Edge Instance Buffer"),
+            size: (MAX_EDGES * std::mem::size_of::<EdgeData>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        self.device = Some(device);
+        self.queue = Some(queue);
+        self.surface = Some(surface);
+        self.config = Some(config);
+        self.gradient_pipeline = Some(gradient_pipeline);
+        self.node_pipeline = Some(node_pipeline);
+        self.edge_pipeline = Some(edge_pipeline);
+        self.compact_node_pipeline = Some(compact_node_pipeline);
+        self.compact_edge_pipeline = Some(compact_edge_pipeline);
+        self.uniform_buffer = Some(uniform_buffer);
+        self.uniform_bind_group = Some(uniform_bind_group);
+        self.style_buffer = Some(style_buffer);
+        self.node_vertex_buffer = Some(node_vertex_buffer);
+        self.node_instance_buffer = Some(node_instance_buffer);
+        self.pulse_buffer = Some(pulse_buffer);
+        self.pulse_count = 0;
+        self.node_filter_buffer = Some(node_filter_buffer);
+        self.edge_filter_buffer = Some(edge_filter_buffer);
+        self.filter_counts = (0, 0);
+        self.edge_vertex_buffer = Some(edge_vertex_buffer);
+        self.edge_instance_buffer = Some(edge_instance_buffer);
+        self.instance = Some(instance);
+        self.adapter = Some(adapter);
+        self.uniform_bind_group_layout = Some(uniform_bind_group_layout);
+        self.minimap = None;
+        self.edge_bundles = None;
+
+        Ok(())
+    }
+
+    // Color the frame is cleared to before the background gradient is drawn
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.clear_color = color;
+    }
+
+    pub fn set_gradient_style(&mut self, style: GradientStyle) {
+        self.gradient_style = style;
+    }
+
+    // Runs the gradient's animation at `speed` times the render clock; 0 holds
+    // it where it is
+    pub fn set_gradient_speed(&mut self, speed: f32) {
+        self.gradient_speed = speed;
+    }
+
+    // Moves or shades the nodes `frequency` times a second by `amplitude`,
+    // offset per node by the phase in its PulseState. No motion at amplitude 0.
+    pub fn set_idle_motion(&mut self, motion: IdleMotion, amplitude: f32, frequency: f32) {
+        let motion = if amplitude == 0.0 { IdleMotion::None } else { motion };
+        self.idle_motion = (motion, amplitude, frequency);
+    }
+
+    // Arrowheads for edges without their own in EdgeFilter, `size` canvas pixels long
+    pub fn set_arrow_style(&mut self, style: ArrowStyle, size: f32) {
+        self.arrows = (style, size);
+    }
+
+    pub fn arrow_style(&self) -> ArrowStyle {
+        self.arrows.0
+    }
+
+    // Draws small nodes as crisp, uniform dots aligned to the pixel grid
+    pub fn set_pixel_snap(&mut self, enabled: bool) {
+        self.pixel_snap = if enabled { PIXEL_SNAP_MAX_RADIUS } else { 0.0 };
+    }
+
+    // Draws `rgba`, `width` by `height` texels, stretched over `rect` ([min x,
+    // min y, max x, max y] in world units) under the edges and nodes. An image
+    // of the same size reuses the texture.
+    pub fn set_background_image(&mut self, rgba: &[u8], width: u32, height: u32, rect: [f32; 4]) -> Result<(), String> {
+        let (Some(device), Some(queue), Some(config), Some(uniform_bind_group_layout)) =
+            (&self.device, &self.queue, &self.config, &self.uniform_bind_group_layout)
+        else {
+            return Err("Renderer has no surface to draw to".to_string());
+        };
+        let max_size = device.limits().max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(format!("Background image must be 1 to {} texels a side, got {}x{}", max_size, width, height));
+        }
+        if rgba.len() != width as usize * height as usize * 4 {
+            return Err(format!("Background image of {}x{} needs {} RGBA bytes, got {}", width, height, width as usize * height as usize * 4, rgba.len()));
+        }
+        if self.background_image.as_ref().is_none_or(|image| image.size != (width, height)) {
+            self.background_image = Some(BackgroundImage::new(device, config.format, (width, height), uniform_bind_group_layout));
+        }
+        if let Some(image) = &self.background_image {
+            image.upload(queue, rgba, rect);
+        }
+        Ok(())
+    }
+
+    pub fn clear_background_image(&mut self) {
+        self.background_image = None;
+    }
+
+    // Edge outlines fade over `smoothing` canvas pixels, and edges thinner than
+    // `min_width` are drawn that wide with proportionally less alpha
+    pub fn set_edge_antialias(&mut self, smoothing: f32, min_width: f32) {
+        self.edge_antialias = (smoothing, min_width);
+    }
+
+    // Alpha multiplier for nodes, edges and the background image, clamped to 0-1
+    pub fn set_global_opacity(&mut self, opacity: f32) {
+        self.global_opacity = if opacity.is_nan() { 1.0 } else { opacity.clamp(0.0, 1.0) };
+    }
+
+    // Packs node and edge instances to 16 and 24 bytes, trading color depth
+    // (8 bits per channel) and node size precision (f16) for upload bandwidth
+    pub fn set_compact_instances(&mut self, enabled: bool) {
+        self.compact_instances = enabled;
+    }
+
+    // While the camera and the edges hold still, draws the edges in batches
+    // over several frames into an offscreen texture and composites it under
+    // the nodes, which are drawn every frame. The batch grows while frames take
+    // under `budget_ms` and halves when they don't; while anything changes,
+    // every nth edge is drawn directly so that about a batch is. Storage
+    // instances always draw directly.
+    pub fn set_progressive_rendering(&mut self, enabled: bool, budget_ms: f32) -> Result<(), String> {
+        if !enabled {
+            self.progressive = None;
+            return Ok(());
+        }
+        if !(budget_ms.is_finite() && budget_ms > 0.0) {
+            return Err(format!("Invalid progressive rendering budget: {} ms", budget_ms));
+        }
+        if let Some(progressive) = &mut self.progressive {
+            progressive.budget_ms = budget_ms;
+            return Ok(());
+        }
+        let (Some(device), Some(config)) = (&self.device, &self.config) else {
+            return Err("Renderer has no surface to draw to".to_string());
+        };
+        let (composite_pipeline, texture_layout) = Self::create_composite_pipeline(device, config.format);
+        self.progressive = Some(Progressive {
+            budget_ms,
+            batch: INITIAL_PROGRESSIVE_BATCH,
+            last_frame: None,
+            composite_pipeline,
+            texture_layout,
+            target: None,
+            key: None,
+            edges: Vec::new(),
+            accumulated: 0,
+        });
+        Ok(())
+    }
+
+    // Node and edge instance bytes uploaded by the last frame
+    pub fn instance_bytes(&self) -> usize {
+        self.instance_bytes
+    }
+
+    pub fn node_style(&self, class_id: usize) -> Option<&NodeStyle> {
+        self.node_styles.get(class_id)
+    }
+
+    // Stores a style class and uploads it if the renderer is initialized
+    pub fn set_node_style(&mut self, class_id: usize, style: NodeStyle) {
+        if class_id >= MAX_STYLE_CLASSES {
+            return;
+        }
+        self.node_styles[class_id] = style;
+        if let (Some(queue), Some(style_buffer)) = (&self.queue, &self.style_buffer) {
+            let offset = (class_id * std::mem::size_of::<NodeStyle>()) as BufferAddress;
+            queue.write_buffer(style_buffer, offset, bytemuck::bytes_of(&style));
+        }
+    }
+
+    // Sets up a device for the physics only, with no canvas, surface or render
+    // pipelines. Without a usable adapter the renderer stays uninitialized and
+    // callers fall back to the CPU simulation.
+    pub async fn init_headless(&mut self) -> Result<(), String> {
+        // WebGL needs a canvas, so headless GPU physics is WebGPU only on the web
+        let instance = Instance::new(&InstanceDescriptor {
+            backends: Backends::BROWSER_WEBGPU | Backends::PRIMARY,
+            flags: Default::default(),
+            ..Default::default()
+        });
+
+        let adapter = instance
+            .request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::HighPerformance,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .map_err(|e| format!("Failed to request adapter: {:?}", e))?;
+
+        let (device, queue) = request_device(&adapter).await?;
+        self.init_compute(&device);
+        self.capabilities = Some(Capabilities {
+            gpu_physics: self.has_gpu_physics(),
+            ..capabilities_of(&adapter, &device.limits())
+        });
+        self.record_device_info(&adapter, &device, None);
+
+        self.device = Some(device);
+        self.queue = Some(queue);
+
+        Ok(())
+    }
+
+    // What init negotiated; None before init or when it failed
+    pub fn capabilities(&self) -> Option<&Capabilities> {
+        self.capabilities.as_ref()
+    }
+
+    // The adapter, device limits and surface format init ended up with; None
+    // before init or when it failed
+    pub fn device_info(&self) -> Option<&DeviceInfo> {
+        self.device_info.as_ref()
+    }
+
+    fn record_device_info(&mut self, adapter: &Adapter, device: &Device, surface_format: Option<TextureFormat>) {
+        let info = device_info_of(adapter, &device.limits(), self.has_gpu_physics(), surface_format);
+        log!(
+            "Using {} ({}, {}), surface format {}",
+            info.adapter_name,
+            info.backend,
+            info.device_type,
+            info.surface_format.as_deref().unwrap_or("none")
+        );
+        self.device_info = Some(info);
+    }
+
+    // True when the compute pipelines exist and physics runs on the GPU
+    pub fn has_gpu_physics(&self) -> bool {
+        self.integration_pipeline.is_some()
+    }
+
+    // True when nodes and edges can be drawn straight from the physics buffer
+    pub fn has_storage_rendering(&self) -> bool {
+        self.storage_node_pipeline.is_some()
+    }
+
+    // True when edges can be drawn bundled, see set_edge_bundles
+    pub fn has_edge_bundling(&self) -> bool {
+        self.bundled_edge_pipeline.is_some()
+    }
+
+    // Draws nodes from the physics buffer instead of the nodes passed to render,
+    // which then only give the count, and edges through the indices from
+    // upload_edge_indices. Only meaningful while that buffer holds the current
+    // nodes; ignored without storage rendering.
+    pub fn set_render_from_physics(&mut self, enabled: bool) {
+        self.render_from_physics = enabled;
+    }
+
+    // Source and target node of each edge, for edges drawn from the physics buffer
+    pub fn upload_edge_indices(&mut self, edges: &[[u32; 2]]) {
+        let (Some(queue), Some(edge_index_buffer)) = (&self.queue, &self.edge_index_buffer) else {
+            return;
+        };
+        let edges = &edges[..edges.len().min(MAX_EDGES)];
+        queue.write_buffer(edge_index_buffer, 0, bytemuck::cast_slice(edges));
+        self.edge_index_count = edges.len() as u32;
+    }
+
+    // Creates the pipelines that read nodes from the physics buffer, when there is
+    // one and vertex shaders can read storage buffers. wgpu has no per-stage storage
+    // limit, so the vertex stage is covered by the VERTEX_STORAGE downlevel flag
+    // (missing on WebGL) on top of the shared limit.
+    fn init_storage_rendering(&mut self, adapter: &Adapter, device: &Device, format: TextureFormat, uniform_bind_group_layout: &BindGroupLayout) {
+        let Some(node_physics_buffer) = &self.node_physics_buffer else {
+            return;
+        };
+        if !adapter.get_downlevel_capabilities().flags.contains(DownlevelFlags::VERTEX_STORAGE)
+            || device.limits().max_storage_buffers_per_shader_stage < 1
+        {
+            log!("Vertex shaders can't read storage buffers, simulated positions are drawn after readback");
+            return;
+        }
+
+        let storage_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Render Storage Bind Group Layout"),
+            entries: &[
+                // Nodes buffer (read-only)
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let storage_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Render Storage Bind Group"),
+            layout: &storage_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: node_physics_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let edge_index_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Edge Index Buffer"),
+            size: (MAX_EDGES * std::mem::size_of::<[u32; 2]>()) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layouts = [uniform_bind_group_layout, &storage_bind_group_layout];
+        self.storage_node_pipeline = Some(self.create_node_pipeline(device, format, &bind_group_layouts, InstanceFormat::Storage));
+        self.storage_edge_pipeline = Some(self.create_edge_pipeline(device, format, &bind_group_layouts, InstanceFormat::Storage, false));
+        self.storage_bind_group = Some(storage_bind_group);
+        self.edge_index_buffer = Some(edge_index_buffer);
+        self.edge_index_count = 0;
+        log!("Drawing simulated nodes straight from the physics buffer");
+    }
+
+    // Creates the pipeline for bundled edges, which reads their control points
+    // from a storage buffer in the vertex shader like init_storage_rendering
+    fn init_bundled_edges(&mut self, adapter: &Adapter, device: &Device, format: TextureFormat, uniform_bind_group_layout: &BindGroupLayout) {
+        if !adapter.get_downlevel_capabilities().flags.contains(DownlevelFlags::VERTEX_STORAGE)
+            || device.limits().max_storage_buffers_per_shader_stage < 1
+        {
+            return;
+        }
+        let bundle_bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Edge Bundle Bind Group Layout"),
+            entries: &[
+                // Control points (read-only)
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Segments per edge
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::VERTEX,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group_layouts = [uniform_bind_group_layout, &bundle_bind_group_layout];
+        self.bundled_edge_pipeline = Some(self.create_edge_pipeline(device, format, &bind_group_layouts, InstanceFormat::Full, true));
+        self.bundle_bind_group_layout = Some(bundle_bind_group_layout);
+    }
+
+    // Draws edge i as `segments` straight pieces through points
+    // i * (segments + 1) to (i + 1) * (segments + 1) - 1 (world units), ends
+    // included, instead of a straight line. Applies only to frames that
+    // enable set_draw_edge_bundles with exactly as many edges in full instances.
+    pub fn set_edge_bundles(&mut self, points: &[[f32; 2]], segments: u32) -> Result<(), String> {
+        let (Some(device), Some(layout)) = (&self.device, &self.bundle_bind_group_layout) else {
+            return Err("Vertex shaders can't read storage buffers here, so edges can't be drawn bundled".to_string());
+        };
+        let stride = segments as usize + 1;
+        if segments == 0 || points.is_empty() || !points.len().is_multiple_of(stride) {
+            return Err(format!("{} control points don't make edges of {} segments", points.len(), segments));
+        }
+        let edges = points.len() / stride;
+        let bytes = std::mem::size_of_val(points) as u64;
+        if edges > MAX_EDGES || bytes > device.limits().max_storage_buffer_binding_size as u64 {
+            return Err(format!("{} bundled edges of {} segments don't fit in a storage buffer", edges, segments));
+        }
+
+        let points_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Edge Bundle Points Buffer"),
+            size: bytes,
+            usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let segments_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Edge Bundle Segments Buffer"),
+            size: std::mem::size_of::<[u32; 4]>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        if let Some(queue) = &self.queue {
+            queue.write_buffer(&points_buffer, 0, bytemuck::cast_slice(points));
+            queue.write_buffer(&segments_buffer, 0, bytemuck::cast_slice(&[segments, 0, 0, 0]));
+        }
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Edge Bundle Bind Group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 1,
+                    resource: points_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: segments_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        self.edge_bundles = Some(EdgeBundles { bind_group, segments, edges: edges as u32 });
+        Ok(())
+    }
+
+    pub fn clear_edge_bundles(&mut self) {
+        self.edge_bundles = None;
+    }
+
+    // Whether the next frames draw the edge bundles, when its edges are the
+    // bundled ones in the same order
+    pub fn set_draw_edge_bundles(&mut self, enabled: bool) {
+        self.draw_edge_bundles = enabled;
+    }
+
+    // Creates the physics pipelines and buffers if the device supports enough
+    // storage buffers; otherwise leaves them unset
+    fn init_compute(&mut self, device: &Device) {
+        // Conditionally create compute pipeline for physics (only if device supports storage buffers)
+        let (compute_pipelines, compute_bind_group, node_physics_buffer, adjacency_buffer, physics_params_buffer, grid_buffer, quadtree_buffer, group_buffer, position_readback_buffer) = {
+            let device_storage_buffers = device.limits().max_storage_buffers_per_shader_stage;
+            log_debug!("Checking compute shader support: device has {} storage buffers per stage, need >= {}", device_storage_buffers, PHYSICS_STORAGE_BUFFERS);
+            
+            if device_storage_buffers >= PHYSICS_STORAGE_BUFFERS {
+                log!("Device supports compute shaders, enabling GPU physics");
+                let ((clear_grid_pipeline, assign_grid_pipeline, repulsion_pipeline, barnes_hut_pipeline, spring_pipeline, integration_pipeline), compute_bind_group_layout) = self.create_compute_pipeline(device);
+                
+                // Create physics buffers
+                let node_physics_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Node Physics Buffer"),
+                    size: (MAX_NODES * std::mem::size_of::<NodeData>()) as u64,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST | BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                });
+                
+                let adjacency_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Adjacency Buffer"),
+                    size: (ADJACENCY_BUFFER_WORDS * std::mem::size_of::<u32>()) as u64,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                
+                // Create physics params buffer
+                let physics_params_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Physics Params Buffer"),
+                    size: std::mem::size_of::<PhysicsParams>() as u64,
+                    usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                
+                // Create grid buffer for spatial partitioning (32x32 = 1024 cells)
+                let grid_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Spatial Grid Buffer"),
+                    // 1024 cells * (32 u32 indices + 1 u32 count), the overflow count, then
+                    // one (cell, node) overflow entry per node
+                    size: (1024 * (32 * 4 + 4) + 4 + MAX_NODES * 8) as u64,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                
+                // Create quadtree buffer for Barnes-Hut repulsion (rebuilt on the CPU each tick)
+                let quadtree_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Quadtree Buffer"),
+                    size: (MAX_QUAD_NODES * std::mem::size_of::<QuadNode>()) as u64,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                
+                // Create group buffer for cluster attraction (slots plus per-tick centroids),
+                // per-node damping and the participant lists
+                let group_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Node Group Buffer"),
+                    size: (GROUP_BUFFER_WORDS * std::mem::size_of::<u32>()) as u64,
+                    usage: BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                
+                // Create staging buffer for reading simulated positions back to the CPU
+                let position_readback_buffer = device.create_buffer(&BufferDescriptor {
+                    label: Some("Position Readback Buffer"),
+                    size: (MAX_NODES * std::mem::size_of::<NodeData>()) as u64,
+                    usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                
+                // Create compute bind group
+                let compute_bind_group = device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("Physics Compute Bind Group"),
+                    layout: &compute_bind_group_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: node_physics_buffer.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: adjacency_buffer.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 2,
+                            resource: physics_params_buffer.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 3,
+                            resource: grid_buffer.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 4,
+                            resource: quadtree_buffer.as_entire_binding(),
+                        },
+                        BindGroupEntry {
+                            binding: 5,
+                            resource: group_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+
+                (Some((clear_grid_pipeline, assign_grid_pipeline, repulsion_pipeline, barnes_hut_pipeline, spring_pipeline, integration_pipeline)), Some(compute_bind_group), Some(node_physics_buffer), Some(adjacency_buffer), Some(physics_params_buffer), Some(grid_buffer), Some(quadtree_buffer), Some(group_buffer), Some(position_readback_buffer))
+            } else {
+                log!("Device does not support compute shaders (only {} storage buffers per stage), physics will be CPU-only", device_storage_buffers);
+                (None, None, None, None, None, None, None, None, None)
+            }
+        };
 
-        self.device = Some(device);
-        self.queue = Some(queue);
-        self.surface = Some(surface);
-        self.config = Some(config);
-        self.gradient_pipeline = Some(gradient_pipeline);
-        self.node_pipeline = Some(node_pipeline);
-        self.edge_pipeline = Some(edge_pipeline);
-        
-        if let Some((clear_grid, assign_grid, repulsion, integration)) = compute_pipelines {
+        if let Some((clear_grid, assign_grid, repulsion, barnes_hut, spring, integration)) = compute_pipelines {
             self.clear_grid_pipeline = Some(clear_grid);
             self.assign_grid_pipeline = Some(assign_grid);
             self.repulsion_pipeline = Some(repulsion);
+            self.barnes_hut_pipeline = Some(barnes_hut);
+            self.spring_pipeline = Some(spring);
             self.integration_pipeline = Some(integration);
         }
-        self.canvas = Some(canvas.clone());
-        self.uniform_buffer = Some(uniform_buffer);
-        self.uniform_bind_group = Some(uniform_bind_group);
         self.physics_params_buffer = physics_params_buffer;
         self.compute_bind_group = compute_bind_group;
-        self.node_vertex_buffer = Some(node_vertex_buffer);
-        self.node_instance_buffer = Some(node_instance_buffer);
-        self.edge_vertex_buffer = Some(edge_vertex_buffer);
-        self.edge_instance_buffer = Some(edge_instance_buffer);
         self.node_physics_buffer = node_physics_buffer;
-        self.edge_physics_buffer = edge_physics_buffer;
-        
+        self.adjacency_buffer = adjacency_buffer;
         self.grid_buffer = grid_buffer;
+        self.quadtree_buffer = quadtree_buffer;
+        self.group_buffer = group_buffer;
+        self.physics_active.clear();
+        self.physics_moving.clear();
+        self.position_readback_buffer = position_readback_buffer;
+    }
 
-        Ok(())
+    // Draws the progressive accumulation texture over the frame
+    fn create_composite_pipeline(device: &Device, format: TextureFormat) -> (RenderPipeline, BindGroupLayout) {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Composite Shader"),
+            source: ShaderSource::Wgsl(include_str!("shaders/composite.wgsl").into()),
+        });
+
+        let texture_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Accumulation Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Composite Pipeline Layout"),
+            bind_group_layouts: &[&texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Composite Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    // Alpha blending into a transparent texture leaves premultiplied
+                    // colors, so this matches drawing the edges straight to the frame
+                    blend: Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        });
+        (pipeline, texture_layout)
     }
 
     fn create_gradient_pipeline(&self, device: &Device, format: TextureFormat, uniform_bind_group_layout: &BindGroupLayout) -> RenderPipeline {
@@ -628,7 +2579,7 @@ impl Renderer {
                 entry_point: Some("fs_main"),
                 targets: &[Some(ColorTargetState {
                     format,
-                    blend: Some(BlendState::REPLACE),
+                    blend: Some(BlendState::ALPHA_BLENDING), // translucent gradients show the clear color
                     write_mask: ColorWrites::ALL,
                 })],
                 compilation_options: Default::default(),
@@ -653,63 +2604,142 @@ impl Renderer {
         })
     }
 
-    fn create_node_pipeline(&self, device: &Device, format: TextureFormat, uniform_bind_group_layout: &BindGroupLayout) -> RenderPipeline {
+    fn create_node_pipeline(&self, device: &Device, format: TextureFormat, bind_group_layouts: &[&BindGroupLayout], instances: InstanceFormat) -> RenderPipeline {
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: Some("Node Shader"),
-            source: ShaderSource::Wgsl(include_str!("shaders/nodes.wgsl").into()),
+            source: ShaderSource::Wgsl(with_node_data(include_str!("shaders/nodes.wgsl")).into()),
         });
 
         let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Node Pipeline Layout"),
-            bind_group_layouts: &[uniform_bind_group_layout],
+            bind_group_layouts,
             push_constant_ranges: &[],
         });
 
+        // Vertex buffer (quad positions)
+        let quad_layout = VertexBufferLayout {
+            array_stride: 2 * std::mem::size_of::<f32>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                }
+            ],
+        };
+
+        // Instance buffer (NodeData as is; velocities, forces and mass are skipped)
+        let full_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<NodeData>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    offset: std::mem::offset_of!(NodeData, x) as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                // Color
+                VertexAttribute {
+                    offset: std::mem::offset_of!(NodeData, r) as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x4,
+                },
+                // Size
+                VertexAttribute {
+                    offset: std::mem::offset_of!(NodeData, size) as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32,
+                },
+                // Flags, with the style class in the upper bits
+                VertexAttribute {
+                    offset: std::mem::offset_of!(NodeData, flags) as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Uint32,
+                },
+            ],
+        };
+
+        // Instance buffer (CompactNode)
+        let compact_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<CompactNode>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Position
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactNode, x) as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                // Color
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactNode, color) as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Unorm8x4,
+                },
+                // Size and style class
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactNode, size_class) as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Uint32,
+                },
+            ],
+        };
+
+        // Pulse buffer (PulseState per rendered node)
+        let pulse_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<PulseState>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Start, period, end and scale
+                VertexAttribute {
+                    offset: std::mem::offset_of!(PulseState, start) as BufferAddress,
+                    shader_location: 5,
+                    format: VertexFormat::Float32x4,
+                },
+                // Glow
+                VertexAttribute {
+                    offset: std::mem::offset_of!(PulseState, glow) as BufferAddress,
+                    shader_location: 6,
+                    format: VertexFormat::Float32,
+                },
+                // Idle motion phase
+                VertexAttribute {
+                    offset: std::mem::offset_of!(PulseState, phase) as BufferAddress,
+                    shader_location: 8,
+                    format: VertexFormat::Float32,
+                },
+            ],
+        };
+
+        // Filter buffer (user flags per rendered node)
+        let filter_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<u32>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 7,
+                    format: VertexFormat::Uint32,
+                },
+            ],
+        };
+
+        let (label, entry_point, buffers) = match instances {
+            InstanceFormat::Full => ("Node Pipeline", "vs_main", vec![quad_layout, full_layout, pulse_layout, filter_layout]),
+            InstanceFormat::Compact => ("Compact Node Pipeline", "vs_compact", vec![quad_layout, compact_layout, pulse_layout, filter_layout]),
+            // Nodes are fetched from the physics buffer by instance index
+            InstanceFormat::Storage => ("Storage Node Pipeline", "vs_storage", vec![quad_layout, pulse_layout, filter_layout]),
+        };
+
         device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("Node Pipeline"),
+            label: Some(label),
             layout: Some(&render_pipeline_layout),
             vertex: VertexState {
                 module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[
-                    // Vertex buffer (quad positions)
-                    VertexBufferLayout {
-                        array_stride: 2 * std::mem::size_of::<f32>() as BufferAddress,
-                        step_mode: VertexStepMode::Vertex,
-                        attributes: &[
-                            VertexAttribute {
-                                offset: 0,
-                                shader_location: 0,
-                                format: VertexFormat::Float32x2,
-                            }
-                        ],
-                    },
-                    // Instance buffer (node data)
-                    VertexBufferLayout {
-                        array_stride: 7 * std::mem::size_of::<f32>() as BufferAddress,
-                        step_mode: VertexStepMode::Instance,
-                        attributes: &[
-                            // Position
-                            VertexAttribute {
-                                offset: 0,
-                                shader_location: 1,
-                                format: VertexFormat::Float32x2,
-                            },
-                            // Color
-                            VertexAttribute {
-                                offset: 2 * std::mem::size_of::<f32>() as BufferAddress,
-                                shader_location: 2,
-                                format: VertexFormat::Float32x4,
-                            },
-                            // Size
-                            VertexAttribute {
-                                offset: 6 * std::mem::size_of::<f32>() as BufferAddress,
-                                shader_location: 3,
-                                format: VertexFormat::Float32,
-                            },
-                        ],
-                    },
-                ],
+                entry_point: Some(entry_point),
+                buffers: &buffers,
                 compilation_options: Default::default(),
             },
             fragment: Some(FragmentState {
@@ -742,69 +2772,170 @@ impl Renderer {
         })
     }
 
-    fn create_edge_pipeline(&self, device: &Device, format: TextureFormat, uniform_bind_group_layout: &BindGroupLayout) -> RenderPipeline {
+    // `bundled` draws full instances as polylines through the control points
+    // bound in group 1 (see set_edge_bundles)
+    fn create_edge_pipeline(&self, device: &Device, format: TextureFormat, bind_group_layouts: &[&BindGroupLayout], instances: InstanceFormat, bundled: bool) -> RenderPipeline {
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: Some("Edge Shader"),
-            source: ShaderSource::Wgsl(include_str!("shaders/edges.wgsl").into()),
+            source: ShaderSource::Wgsl(with_node_data(include_str!("shaders/edges.wgsl")).into()),
         });
 
         let render_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: Some("Edge Pipeline Layout"),
-            bind_group_layouts: &[uniform_bind_group_layout],
+            bind_group_layouts,
             push_constant_ranges: &[],
         });
 
+        // Vertex buffer (quad positions)
+        let quad_layout = VertexBufferLayout {
+            array_stride: 2 * std::mem::size_of::<f32>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x2,
+                }
+            ],
+        };
+
+        // Instance buffer (edge data)
+        let full_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<EdgeData>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Start position
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                // End position
+                VertexAttribute {
+                    offset: 2 * std::mem::size_of::<f32>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x2,
+                },
+                // Color
+                VertexAttribute {
+                    offset: 4 * std::mem::size_of::<f32>() as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x4,
+                },
+                // Width
+                VertexAttribute {
+                    offset: 8 * std::mem::size_of::<f32>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32,
+                },
+            ],
+        };
+
+        // Instance buffer (CompactEdge); the shader reads both formats the same way
+        let compact_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<CompactEdge>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Start position
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactEdge, start) as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                // End position
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactEdge, end) as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x2,
+                },
+                // Color
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactEdge, color) as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Unorm8x4,
+                },
+                // Width
+                VertexAttribute {
+                    offset: std::mem::offset_of!(CompactEdge, width) as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32,
+                },
+            ],
+        };
+
+        // Storage instances take only color and width from the edge data; the
+        // endpoints come from the physics buffer through the node indices
+        let storage_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<EdgeData>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Color
+                VertexAttribute {
+                    offset: std::mem::offset_of!(EdgeData, r) as BufferAddress,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x4,
+                },
+                // Width
+                VertexAttribute {
+                    offset: std::mem::offset_of!(EdgeData, width) as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32,
+                },
+            ],
+        };
+        let index_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<[u32; 2]>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                // Source and target node indices
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: VertexFormat::Uint32x2,
+                },
+            ],
+        };
+
+        // Filter buffer (EdgeFilter per rendered edge)
+        let filter_layout = VertexBufferLayout {
+            array_stride: std::mem::size_of::<EdgeFilter>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 6,
+                    format: VertexFormat::Uint32x3,
+                },
+                // Arrow size and target radius
+                VertexAttribute {
+                    offset: std::mem::offset_of!(EdgeFilter, arrow_size) as BufferAddress,
+                    shader_location: 7,
+                    format: VertexFormat::Float32x2,
+                },
+                // Arrow style
+                VertexAttribute {
+                    offset: std::mem::offset_of!(EdgeFilter, arrow_style) as BufferAddress,
+                    shader_location: 8,
+                    format: VertexFormat::Uint32,
+                },
+            ],
+        };
+
+        let (label, entry_point, buffers) = match instances {
+            // The corners come from the vertex index, six per segment
+            InstanceFormat::Full if bundled => ("Bundled Edge Pipeline", "vs_bundled", vec![full_layout, filter_layout]),
+            InstanceFormat::Full => ("Edge Pipeline", "vs_main", vec![quad_layout, full_layout, filter_layout]),
+            InstanceFormat::Compact => ("Compact Edge Pipeline", "vs_main", vec![quad_layout, compact_layout, filter_layout]),
+            InstanceFormat::Storage => ("Storage Edge Pipeline", "vs_storage", vec![quad_layout, storage_layout, index_layout, filter_layout]),
+        };
+
         device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: Some("Edge Pipeline"),
+            label: Some(label),
             layout: Some(&render_pipeline_layout),
             vertex: VertexState {
                 module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[
-                    // Vertex buffer (quad positions)
-                    VertexBufferLayout {
-                        array_stride: 2 * std::mem::size_of::<f32>() as BufferAddress,
-                        step_mode: VertexStepMode::Vertex,
-                        attributes: &[
-                            VertexAttribute {
-                                offset: 0,
-                                shader_location: 0,
-                                format: VertexFormat::Float32x2,
-                            }
-                        ],
-                    },
-                    // Instance buffer (edge data)
-                    VertexBufferLayout {
-                        array_stride: 9 * std::mem::size_of::<f32>() as BufferAddress,
-                        step_mode: VertexStepMode::Instance,
-                        attributes: &[
-                            // Start position
-                            VertexAttribute {
-                                offset: 0,
-                                shader_location: 1,
-                                format: VertexFormat::Float32x2,
-                            },
-                            // End position
-                            VertexAttribute {
-                                offset: 2 * std::mem::size_of::<f32>() as BufferAddress,
-                                shader_location: 2,
-                                format: VertexFormat::Float32x2,
-                            },
-                            // Color
-                            VertexAttribute {
-                                offset: 4 * std::mem::size_of::<f32>() as BufferAddress,
-                                shader_location: 3,
-                                format: VertexFormat::Float32x4,
-                            },
-                            // Width
-                            VertexAttribute {
-                                offset: 8 * std::mem::size_of::<f32>() as BufferAddress,
-                                shader_location: 4,
-                                format: VertexFormat::Float32,
-                            },
-                        ],
-                    },
-                ],
+                entry_point: Some(entry_point),
+                buffers: &buffers,
                 compilation_options: Default::default(),
             },
             fragment: Some(FragmentState {
@@ -837,10 +2968,10 @@ impl Renderer {
         })
     }
 
-    fn create_compute_pipeline(&self, device: &Device) -> ((ComputePipeline, ComputePipeline, ComputePipeline, ComputePipeline), BindGroupLayout) {
+    fn create_compute_pipeline(&self, device: &Device) -> ((ComputePipeline, ComputePipeline, ComputePipeline, ComputePipeline, ComputePipeline, ComputePipeline), BindGroupLayout) {
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: Some("Physics Compute Shader"),
-            source: ShaderSource::Wgsl(PHYSICS_SHADER.into()),
+            source: ShaderSource::Wgsl(with_node_data(PHYSICS_SHADER).into()),
         });
 
         let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
@@ -857,7 +2988,7 @@ impl Renderer {
                     },
                     count: None,
                 },
-                // Edges buffer (read-only)
+                // Adjacency buffer (read-only)
                 BindGroupLayoutEntry {
                     binding: 1,
                     visibility: ShaderStages::COMPUTE,
@@ -890,6 +3021,28 @@ impl Renderer {
                     },
                     count: None,
                 },
+                // Quadtree buffer (read-only)
+                BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Node group buffer (read-only)
+                BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -927,56 +3080,279 @@ impl Renderer {
             compilation_options: Default::default(),
         });
 
-        let integration_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
-            label: Some("Integration Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: &shader,
-            entry_point: Some("integrate_physics"),
-            cache: None,
-            compilation_options: Default::default(),
-        });
+        let barnes_hut_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Barnes-Hut Repulsion Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("calculate_repulsion_barnes_hut"),
+            cache: None,
+            compilation_options: Default::default(),
+        });
+
+        let spring_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Spring Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("calculate_springs"),
+            cache: None,
+            compilation_options: Default::default(),
+        });
+
+        let integration_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Integration Pipeline"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: Some("integrate_physics"),
+            cache: None,
+            compilation_options: Default::default(),
+        });
+
+        // Return all pipelines
+        ((clear_grid_pipeline, assign_grid_pipeline, repulsion_pipeline, barnes_hut_pipeline, spring_pipeline, integration_pipeline), bind_group_layout)
+    }
+
+    // Uploads the incidence lists used by the spring pass. Must be called whenever the
+    // node count or the indexed edges change; `rest_lengths` is indexed by edge id.
+    pub fn upload_adjacency(&mut self, adjacency: &Adjacency, rest_lengths: &[f32], strengths: &[f32]) {
+        let (Some(queue), Some(adjacency_buffer)) = (&self.queue, &self.adjacency_buffer) else {
+            return;
+        };
+
+        // Only nodes and edges that fit in the physics buffers take part in the simulation
+        let node_count = adjacency.node_count().min(MAX_NODES);
+        let mut offsets = Vec::with_capacity(node_count + 1);
+        let mut entries = Vec::new();
+        offsets.push(0u32);
+        for node in 0..node_count {
+            for (&neighbor, &edge_id) in adjacency.neighbors(node).iter().zip(adjacency.incident_edges(node)) {
+                if (neighbor as usize) < node_count && (edge_id as usize) < MAX_EDGES {
+                    let rest_length = rest_lengths.get(edge_id as usize).copied().unwrap_or(0.0);
+                    let strength = strengths.get(edge_id as usize).copied().unwrap_or(1.0);
+                    entries.push(neighbor);
+                    entries.push(rest_length.to_bits());
+                    entries.push(strength.to_bits());
+                }
+            }
+            offsets.push((entries.len() / ADJACENCY_WORDS_PER_ENTRY) as u32);
+        }
+
+        // Each edge is listed once from either endpoint
+        self.physics_edge_count = (entries.len() / ADJACENCY_WORDS_PER_ENTRY / 2) as u32;
+        offsets.extend_from_slice(&entries);
+        queue.write_buffer(adjacency_buffer, 0, bytemuck::cast_slice(&offsets));
+    }
+
+    // Uploads a quadtree built from the CPU-side positions for the Barnes-Hut pass
+    pub fn upload_quadtree(&mut self, tree: &QuadTree) {
+        if let (Some(queue), Some(quadtree_buffer)) = (&self.queue, &self.quadtree_buffer) {
+            let count = tree.nodes.len().min(MAX_QUAD_NODES);
+            if count > 0 {
+                queue.write_buffer(quadtree_buffer, 0, bytemuck::cast_slice(&tree.nodes[..count]));
+            }
+            self.quad_node_count = count as u32;
+        }
+    }
+
+    // Pulse of each rendered node, in the order of the nodes passed to render
+    pub fn upload_pulses(&mut self, pulses: &[PulseState]) {
+        if let (Some(queue), Some(pulse_buffer)) = (&self.queue, &self.pulse_buffer) {
+            let count = pulses.len().min(MAX_NODES);
+            if count > 0 {
+                queue.write_buffer(pulse_buffer, 0, bytemuck::cast_slice(&pulses[..count]));
+            }
+            self.pulse_count = count;
+        }
+    }
+
+    // Stops every pulse by zeroing what upload_pulses wrote
+    pub fn clear_pulses(&mut self) {
+        let cleared = vec![PulseState::default(); self.pulse_count];
+        self.upload_pulses(&cleared);
+        self.pulse_count = 0;
+    }
+
+    // User flags for the visibility filter, in the order of the node and edge
+    // instances drawn next
+    pub fn upload_filter_flags(&mut self, nodes: &[u32], edges: &[EdgeFilter]) {
+        if let (Some(queue), Some(node_filter_buffer), Some(edge_filter_buffer)) = (&self.queue, &self.node_filter_buffer, &self.edge_filter_buffer) {
+            let (node_count, edge_count) = (nodes.len().min(MAX_NODES), edges.len().min(MAX_EDGES));
+            if node_count > 0 {
+                queue.write_buffer(node_filter_buffer, 0, bytemuck::cast_slice(&nodes[..node_count]));
+            }
+            if edge_count > 0 {
+                queue.write_buffer(edge_filter_buffer, 0, bytemuck::cast_slice(&edges[..edge_count]));
+            }
+            self.filter_counts = (node_count, edge_count);
+            self.filter_generation += 1;
+        }
+    }
+
+    // Zeroes what upload_filter_flags wrote, so every instance has no user flags
+    pub fn clear_filter_flags(&mut self) {
+        let (node_count, edge_count) = self.filter_counts;
+        self.upload_filter_flags(&vec![0; node_count], &vec![EdgeFilter::default(); edge_count]);
+        self.filter_counts = (0, 0);
+    }
+
+    // Draws only the instances whose user flags include every `required` bit
+    // and no `forbidden` one; takes effect with the next frame's uniforms
+    pub fn set_visibility_filter(&mut self, required: u32, forbidden: u32) {
+        self.visibility_filter = [required, forbidden];
+    }
+
+    // Uploads each node's dense group slot for the cluster attraction force
+    pub fn upload_node_groups(&mut self, slots: &[u32]) {
+        if let (Some(queue), Some(group_buffer)) = (&self.queue, &self.group_buffer) {
+            let count = slots.len().min(MAX_NODES);
+            if count > 0 {
+                queue.write_buffer(group_buffer, 0, bytemuck::cast_slice(&slots[..count]));
+            }
+        }
+    }
+
+    // Uploads the current centroid of each group, indexed by slot
+    pub fn upload_group_centroids(&mut self, centroids: &[[f32; 2]]) {
+        if let (Some(queue), Some(group_buffer)) = (&self.queue, &self.group_buffer) {
+            let count = centroids.len().min(MAX_NODES);
+            if count > 0 {
+                let offset = (MAX_NODES * std::mem::size_of::<u32>()) as u64;
+                queue.write_buffer(group_buffer, offset, bytemuck::cast_slice(&centroids[..count]));
+            }
+        }
+    }
+
+    // Uploads each node's damping factor, 0 for the global one, zeroing what's
+    // left of the previous upload
+    pub fn upload_node_damping(&mut self, damping: &[f32]) {
+        if let (Some(queue), Some(group_buffer)) = (&self.queue, &self.group_buffer) {
+            let count = damping.len().min(MAX_NODES);
+            let mut values = damping[..count].to_vec();
+            values.resize(count.max(self.damping_count), 0.0);
+            if !values.is_empty() {
+                let offset = (DAMPING_OFFSET_WORDS * std::mem::size_of::<u32>()) as u64;
+                queue.write_buffer(group_buffer, offset, bytemuck::cast_slice(&values));
+            }
+            self.damping_count = count;
+        }
+    }
+
+    // Uploads which nodes take part in the simulation as compacted index lists,
+    // so the passes only run over them: every participant (grid assignment and
+    // integration) and the unpinned ones (force passes). Skipped when neither
+    // list changed.
+    fn upload_participants(&mut self, nodes: &[NodeData], excluded: &[bool], frozen: &[bool]) {
+        let (Some(queue), Some(group_buffer)) = (&self.queue, &self.group_buffer) else {
+            return;
+        };
+        let participants = crate::physics::participants(nodes, excluded);
+        let is_frozen = |i: u32| frozen.get(i as usize).copied().unwrap_or(false);
+        let active: Vec<u32> = (0..nodes.len() as u32).filter(|&i| participants[i as usize]).collect();
+        let moving: Vec<u32> = active.iter().copied().filter(|&i| nodes[i as usize].flags & NODE_FLAG_PINNED == 0 && !is_frozen(i)).collect();
+        if active == self.physics_active && moving == self.physics_moving {
+            return;
+        }
 
-        // Return all pipelines
-        ((clear_grid_pipeline, assign_grid_pipeline, repulsion_pipeline, integration_pipeline), bind_group_layout)
+        let words: Vec<u32> = participants
+            .iter()
+            .enumerate()
+            .map(|(i, &participates)| match participates {
+                true if is_frozen(i as u32) => PARTICIPANT_FROZEN,
+                participates => participates as u32,
+            })
+            .collect();
+        let word_bytes = std::mem::size_of::<u32>();
+        for (offset, values) in [(PARTICIPATION_OFFSET_WORDS, &words), (ACTIVE_OFFSET_WORDS, &active), (MOVING_OFFSET_WORDS, &moving)] {
+            if !values.is_empty() {
+                queue.write_buffer(group_buffer, (offset * word_bytes) as u64, bytemuck::cast_slice(values));
+            }
+        }
+        self.physics_active = active;
+        self.physics_moving = moving;
     }
 
-    pub fn integrate_physics(&mut self, nodes: &mut [NodeData], edges: &[EdgeData], delta_time: f32, damping_factor: f32, spring_constant: f32, rest_length: f32, repulsion_strength: f32, repulsion_radius: f32) -> Result<(), JsValue> {
-        if let (Some(device), Some(queue), Some(clear_grid_pipeline), Some(assign_grid_pipeline), Some(repulsion_pipeline), Some(integration_pipeline), Some(physics_params_buffer), Some(compute_bind_group), Some(node_physics_buffer), Some(edge_physics_buffer)) = (
+    // Runs one tick over `nodes`, leaving out hidden ones, those flagged
+    // NODE_FLAG_NO_PHYSICS and those marked in `excluded`; those marked in
+    // `frozen` exert forces but hold still
+    pub fn integrate_physics(&mut self, nodes: &[NodeData], excluded: &[bool], frozen: &[bool], upload_nodes: bool, settings: &PhysicsSettings) -> Result<(), String> {
+        let node_count = nodes.len().min(MAX_NODES);
+        self.upload_participants(&nodes[..node_count], excluded, frozen);
+
+        if let (Some(device), Some(queue), Some(clear_grid_pipeline), Some(assign_grid_pipeline), Some(repulsion_pipeline), Some(barnes_hut_pipeline), Some(spring_pipeline), Some(integration_pipeline), Some(physics_params_buffer), Some(compute_bind_group), Some(node_physics_buffer), Some(position_readback_buffer)) = (
             &self.device,
             &self.queue,
             &self.clear_grid_pipeline,
             &self.assign_grid_pipeline,
             &self.repulsion_pipeline,
+            &self.barnes_hut_pipeline,
+            &self.spring_pipeline,
             &self.integration_pipeline,
             &self.physics_params_buffer,
             &self.compute_bind_group,
             &self.node_physics_buffer,
-            &self.edge_physics_buffer,
+            &self.position_readback_buffer,
         ) {
+            let node_count = nodes.len().min(MAX_NODES);
+            if node_count == 0 {
+                return Ok(());
+            }
+
+            let (world_min, world_size) = grid_world_bounds(&nodes[..node_count], settings);
+
             // Update physics parameters for full integration
-            let physics_params = [
-                delta_time,
-                damping_factor,
-                spring_constant,
-                rest_length,
-                repulsion_strength,
-                repulsion_radius,
-                nodes.len() as f32,
-                edges.len() as f32,
-            ];
-            queue.write_buffer(physics_params_buffer, 0, bytemuck::cast_slice(&physics_params));
+            let physics_params = PhysicsParams {
+                delta_time: settings.delta_time,
+                damping_factor: settings.damping_factor,
+                spring_constant: settings.spring_constant,
+                rest_length: settings.rest_length,
+                repulsion_strength: settings.repulsion_strength,
+                repulsion_radius: settings.repulsion_radius,
+                node_count: node_count as u32,
+                edge_count: self.physics_edge_count,
+                gravity_strength: settings.gravity_strength,
+                gravity_center_x: settings.gravity_center[0],
+                gravity_center_y: settings.gravity_center[1],
+                gravity_mode: match settings.gravity_mode {
+                    GravityMode::Linear => 0,
+                    GravityMode::Constant => 1,
+                },
+                alpha: settings.alpha,
+                theta: settings.theta,
+                quad_node_count: self.quad_node_count,
+                group_attraction: settings.group_attraction,
+                world_min,
+                world_size,
+                bounds_min: settings.bounds.map_or([0.0; 2], |bounds| bounds.min),
+                bounds_max: settings.bounds.map_or([0.0; 2], |bounds| bounds.max),
+                bounds_mode: match settings.bounds.map(|bounds| bounds.mode) {
+                    None => 0,
+                    Some(BoundsMode::Clamp) => 1,
+                    Some(BoundsMode::Bounce) => 2,
+                    Some(BoundsMode::Force) => 3,
+                },
+                bounds_restitution: BOUNDS_RESTITUTION,
+                bounds_strength: BOUNDS_FORCE_STRENGTH,
+                world_wrap: settings.wrap().is_some() as u32,
+                active_count: self.physics_active.len() as u32,
+                moving_count: self.physics_moving.len() as u32,
+                _padding: [0; 2],
+            };
+            queue.write_buffer(physics_params_buffer, 0, bytemuck::cast_slice(&[physics_params]));
             
-            // Copy node data to physics buffer
-            queue.write_buffer(node_physics_buffer, 0, bytemuck::cast_slice(nodes));
+            // The physics buffer is the source of truth for positions between uploads,
+            // so only copy node data when the CPU side has changed
+            if upload_nodes {
+                queue.write_buffer(node_physics_buffer, 0, bytemuck::cast_slice(&nodes[..node_count]));
+                self.upload_generation += 1;
+            }
             
-            // Copy edge data to physics buffer (convert EdgeData to match shader layout)
-            let edge_data: Vec<[f32; 9]> = edges.iter().map(|edge| [
-                edge.x1, edge.y1, edge.x2, edge.y2,
-                edge.r, edge.g, edge.b, edge.a, edge.width
-            ]).collect();
-            queue.write_buffer(edge_physics_buffer, 0, bytemuck::cast_slice(&edge_data));
+            if settings.deterministic && settings.repulsion_method == RepulsionMethod::Grid {
+                if let Some(grid_buffer) = &self.grid_buffer {
+                    let grid = pack_sorted_grid(nodes, &self.physics_active, world_min, world_size);
+                    queue.write_buffer(grid_buffer, 0, bytemuck::cast_slice(&grid));
+                }
+            }
             
-            // Create command encoder for three-pass grid-based physics
+            // Create command encoder for multi-pass grid-based physics
             let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
                 label: Some("Grid Physics Compute Encoder"),
             });
@@ -987,33 +3363,65 @@ impl Renderer {
                     timestamp_writes: None,
                 });
                 
-                // Pass 1: Clear grid
-                compute_pass.set_pipeline(clear_grid_pipeline);
-                compute_pass.set_bind_group(0, compute_bind_group, &[]);
-                compute_pass.dispatch_workgroups(16, 1, 1); // 1024 cells / 64 threads = 16 workgroups
-                
-                // Pass 2: Assign nodes to grid
-                compute_pass.set_pipeline(assign_grid_pipeline);
-                compute_pass.set_bind_group(0, compute_bind_group, &[]);
-                let node_workgroups = (nodes.len() + 63) / 64;
-                compute_pass.dispatch_workgroups(node_workgroups as u32, 1, 1);
+                // Zero workgroups (no participants) is a valid, empty dispatch
+                let active_workgroups = self.physics_active.len().div_ceil(64) as u32;
+                let moving_workgroups = self.physics_moving.len().div_ceil(64) as u32;
+                match settings.repulsion_method {
+                    RepulsionMethod::Grid => {
+                        // Deterministic mode uploads a sorted grid instead
+                        if !settings.deterministic {
+                            // Pass 1: Clear grid
+                            compute_pass.set_pipeline(clear_grid_pipeline);
+                            compute_pass.set_bind_group(0, compute_bind_group, &[]);
+                            compute_pass.dispatch_workgroups(16, 1, 1); // 1024 cells / 64 threads = 16 workgroups
+                            
+                            // Pass 2: Assign nodes to grid
+                            compute_pass.set_pipeline(assign_grid_pipeline);
+                            compute_pass.set_bind_group(0, compute_bind_group, &[]);
+                            compute_pass.dispatch_workgroups(active_workgroups, 1, 1);
+                        }
+                        
+                        // Pass 3: Calculate repulsion using grid
+                        compute_pass.set_pipeline(repulsion_pipeline);
+                        compute_pass.set_bind_group(0, compute_bind_group, &[]);
+                        compute_pass.dispatch_workgroups(moving_workgroups, 1, 1);
+                    }
+                    RepulsionMethod::BarnesHut => {
+                        // Passes 1-3: Traverse the uploaded quadtree
+                        compute_pass.set_pipeline(barnes_hut_pipeline);
+                        compute_pass.set_bind_group(0, compute_bind_group, &[]);
+                        compute_pass.dispatch_workgroups(moving_workgroups, 1, 1);
+                    }
+                }
                 
-                // Pass 3: Calculate repulsion using grid
-                compute_pass.set_pipeline(repulsion_pipeline);
+                // Pass 4: Accumulate spring forces along edges
+                compute_pass.set_pipeline(spring_pipeline);
                 compute_pass.set_bind_group(0, compute_bind_group, &[]);
-                compute_pass.dispatch_workgroups(node_workgroups as u32, 1, 1);
+                compute_pass.dispatch_workgroups(moving_workgroups, 1, 1);
                 
-                // Pass 4: Integrate physics
+                // Pass 5: Integrate physics
                 compute_pass.set_pipeline(integration_pipeline);
                 compute_pass.set_bind_group(0, compute_bind_group, &[]);
-                compute_pass.dispatch_workgroups(node_workgroups as u32, 1, 1);
+                compute_pass.dispatch_workgroups(active_workgroups, 1, 1);
+            }
+            
+            // Copy the simulated nodes into the staging buffer unless a previous readback
+            // is still in flight; in that case the next tick picks up the newer positions
+            let readback_bytes = (node_count * std::mem::size_of::<NodeData>()) as u64;
+            let start_readback = self.readback_state.load(Ordering::Acquire) == READBACK_IDLE;
+            if start_readback {
+                encoder.copy_buffer_to_buffer(node_physics_buffer, 0, position_readback_buffer, 0, readback_bytes);
             }
             
             // Submit all compute work
             queue.submit(std::iter::once(encoder.finish()));
             
-            // Physics integration completed on GPU
-            // Updated node data will be read back during next render pass
+            if start_readback {
+                map_readback(position_readback_buffer, readback_bytes, &self.readback_state);
+                self.readback_node_count = node_count;
+                self.readback_generation = self.upload_generation;
+            }
+            self.unread_ticks = if start_readback { None } else { Some(node_count) };
             
             Ok(())
         } else {
@@ -1023,47 +3431,272 @@ impl Renderer {
         }
     }
 
-    pub fn render(&mut self, time: f64, color1: &[f32; 4], color2: &[f32; 4], nodes: &[NodeData], edges: &[EdgeData], camera_position: &[f32; 2], camera_zoom: f32) {
-        if let (Some(device), Some(queue), Some(surface), Some(gradient_pipeline), Some(node_pipeline), Some(edge_pipeline), Some(uniform_buffer), Some(uniform_bind_group), Some(config), Some(node_vertex_buffer), Some(node_instance_buffer), Some(edge_vertex_buffer), Some(edge_instance_buffer)) = (
+    // Writes a node's flags into the physics buffer without touching its simulated state
+    pub fn write_node_flags(&mut self, index: usize, flags: u32) {
+        if let (Some(queue), Some(node_physics_buffer)) = (&self.queue, &self.node_physics_buffer) {
+            if index < MAX_NODES {
+                let offset = index * std::mem::size_of::<NodeData>() + std::mem::offset_of!(NodeData, flags);
+                queue.write_buffer(node_physics_buffer, offset as u64, bytemuck::bytes_of(&flags));
+            }
+        }
+    }
+
+    // Writes a node's position and velocity into the physics buffer, e.g. while dragging
+    pub fn write_node_position(&mut self, index: usize, node: &NodeData) {
+        if let (Some(queue), Some(node_physics_buffer)) = (&self.queue, &self.node_physics_buffer) {
+            if index < MAX_NODES {
+                let offset = index * std::mem::size_of::<NodeData>();
+                let state = [node.x, node.y, node.vx, node.vy];
+                queue.write_buffer(node_physics_buffer, offset as u64, bytemuck::cast_slice(&state));
+            }
+        }
+    }
+
+    // Writes whole nodes into the physics buffer from index `first` on, e.g. one
+    // chunk of a streamed graph; nodes past MAX_NODES are left out
+    pub fn write_nodes(&mut self, first: usize, nodes: &[NodeData]) {
+        if let (Some(queue), Some(node_physics_buffer)) = (&self.queue, &self.node_physics_buffer) {
+            let count = nodes.len().min(MAX_NODES.saturating_sub(first));
+            if count > 0 {
+                let offset = first * std::mem::size_of::<NodeData>();
+                queue.write_buffer(node_physics_buffer, offset as u64, bytemuck::cast_slice(&nodes[..count]));
+            }
+        }
+    }
+
+    /// Marks any in-flight readback as stale because the CPU-side nodes changed.
+    pub fn invalidate_physics_readback(&mut self) {
+        self.upload_generation += 1;
+        self.unread_ticks = None;
+    }
+
+    /// Starts copying the simulated nodes to the CPU if the physics buffer holds
+    /// ticks no readback has picked up (because one was in flight when they
+    /// ran). Returns true while a readback is pending, this one or an earlier one.
+    pub fn request_physics_readback(&mut self) -> bool {
+        if self.is_readback_pending() {
+            return true;
+        }
+        let (Some(node_count), Some(device), Some(queue), Some(node_physics_buffer), Some(position_readback_buffer)) =
+            (self.unread_ticks, &self.device, &self.queue, &self.node_physics_buffer, &self.position_readback_buffer)
+        else {
+            return false;
+        };
+
+        let readback_bytes = (node_count * std::mem::size_of::<NodeData>()) as u64;
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Position Readback Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(node_physics_buffer, 0, position_readback_buffer, 0, readback_bytes);
+        queue.submit(std::iter::once(encoder.finish()));
+        map_readback(position_readback_buffer, readback_bytes, &self.readback_state);
+        self.readback_node_count = node_count;
+        self.readback_generation = self.upload_generation;
+        self.unread_ticks = None;
+        true
+    }
+
+    /// True while a copy of simulated positions is in flight or waiting to be read.
+    pub fn is_readback_pending(&self) -> bool {
+        self.readback_state.load(Ordering::Acquire) != READBACK_IDLE
+    }
+
+    /// Copies the most recent simulated positions and velocities into `nodes` if a
+    /// readback has completed. Returns true when `nodes` was updated.
+    pub fn read_physics_positions(&mut self, nodes: &mut [NodeData]) -> bool {
+        let (Some(device), Some(position_readback_buffer)) = (&self.device, &self.position_readback_buffer) else {
+            return false;
+        };
+
+        // Drive map_async callbacks on native backends; a no-op on the web
+        let _ = device.poll(PollType::Poll);
+
+        if self.readback_state.load(Ordering::Acquire) != READBACK_READY {
+            return false;
+        }
+
+        // Positions uploaded after the copy was recorded supersede the readback
+        let is_current = self.readback_generation == self.upload_generation;
+        let readback_bytes = (self.readback_node_count * std::mem::size_of::<NodeData>()) as u64;
+        if is_current {
+            let mapped = position_readback_buffer.slice(..readback_bytes).get_mapped_range();
+            let simulated: &[NodeData] = bytemuck::cast_slice(&mapped);
+            for (node, simulated) in nodes.iter_mut().zip(simulated) {
+                node.x = simulated.x;
+                node.y = simulated.y;
+                node.vx = simulated.vx;
+                node.vy = simulated.vy;
+                node.fx = simulated.fx;
+                node.fy = simulated.fy;
+            }
+        }
+
+        position_readback_buffer.unmap();
+        self.readback_state.store(READBACK_IDLE, Ordering::Release);
+        is_current
+    }
+
+    // Draws a frame. Errors are failures the caller should surface; a surface
+    // that was lost or outdated is reconfigured and the frame skipped. Validation
+    // errors arrive asynchronously, see take_validation_errors.
+    pub fn render(&mut self, time: f64, color1: &[f32; 4], color2: &[f32; 4], nodes: &[NodeData], edges: &[EdgeData], camera: Camera) -> Result<(), String> {
+        // The gradient starts in phase with the clock and only advances from there
+        self.gradient_phase = match self.gradient_clock {
+            Some(last) => self.gradient_phase + ((time - last).max(0.0) * self.gradient_speed as f64) as f32,
+            None => (time * self.gradient_speed as f64) as f32,
+        };
+        self.gradient_clock = Some(time);
+        let (gradient_style, gradient) = self.gradient_style.uniforms();
+        if let (Some(device), Some(queue), Some(surface), Some(gradient_pipeline), Some(uniform_buffer), Some(uniform_bind_group), Some(config), Some(node_instance_buffer), Some(edge_instance_buffer)) = (
             &self.device,
             &self.queue,
             &self.surface,
             &self.gradient_pipeline,
-            &self.node_pipeline,
-            &self.edge_pipeline,
             &self.uniform_buffer,
             &self.uniform_bind_group,
             &self.config,
-            &self.node_vertex_buffer,
             &self.node_instance_buffer,
-            &self.edge_vertex_buffer,
             &self.edge_instance_buffer,
         ) {
-            // Validate configuration
+            // Nothing to draw into while the canvas has no size
             if config.width == 0 || config.height == 0 {
-                return;
+                return Ok(());
             }
             
+            let output = match surface.get_current_texture() {
+                Ok(texture) => texture,
+                Err(SurfaceError::Timeout) => return Ok(()),
+                Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                    surface.configure(device, config);
+                    return Ok(());
+                }
+                Err(SurfaceError::OutOfMemory) => return Err("Out of memory acquiring the surface texture".to_string()),
+                Err(e) => return Err(format!("Failed to acquire the surface texture: {}", e)),
+            };
+
+            device.push_error_scope(ErrorFilter::Validation);
+
+            let storage_ready = self.storage_node_pipeline.is_some()
+                && self.storage_edge_pipeline.is_some()
+                && self.storage_bind_group.is_some()
+                && self.edge_index_buffer.is_some();
+            let format = if self.render_from_physics && storage_ready {
+                InstanceFormat::Storage
+            } else if self.compact_instances && self.compact_node_pipeline.is_some() && self.compact_edge_pipeline.is_some() {
+                InstanceFormat::Compact
+            } else {
+                InstanceFormat::Full
+            };
+            let mut drawn = DrawnInstances { format, nodes: 0, edges: 0 };
+            let mut instance_bytes = 0;
+            // Nothing to draw while faded out. Beyond MAX_NODES and MAX_EDGES is
+            // dropped; set_nodes and set_edges already warned.
+            let edge_instance_data = if self.global_opacity > 0.0 { &edges[..edges.len().min(MAX_EDGES)] } else { &[] };
+            let edge_pass = match &mut self.progressive {
+                Some(progressive) if format != InstanceFormat::Storage => {
+                    let key = AccumulationKey {
+                        camera_position: camera.position,
+                        camera_zoom: camera.zoom,
+                        size: (config.width, config.height),
+                        opacity: self.global_opacity,
+                        visibility_filter: self.visibility_filter,
+                        format,
+                        filter_generation: self.filter_generation,
+                    };
+                    progressive.plan(time, key, edge_instance_data)
+                }
+                _ => EdgePass::Direct { stride: 1 },
+            };
+            if let (EdgePass::Accumulate { .. }, Some(progressive)) = (&edge_pass, &mut self.progressive) {
+                progressive.ensure_target(device, config);
+            }
+
+            if self.global_opacity > 0.0 {
+                // Accumulating and compositing frames reuse the edges already uploaded
+                if !edge_instance_data.is_empty() && matches!(edge_pass, EdgePass::Direct { .. }) {
+                    // EdgeData has the instance layout already, so it's uploaded as is.
+                    // From the physics buffer, the edge data only gives colors and widths.
+                    let bytes = if format == InstanceFormat::Compact {
+                        self.compact_edges.clear();
+                        self.compact_edges.extend(edge_instance_data.iter().map(CompactEdge::new));
+                        bytemuck::cast_slice(&self.compact_edges)
+                    } else {
+                        bytemuck::cast_slice(edge_instance_data)
+                    };
+                    queue.write_buffer(edge_instance_buffer, 0, bytes);
+                    instance_bytes += bytes.len();
+                }
+                drawn.edges = edge_instance_data.len() as u32;
+                if format == InstanceFormat::Storage {
+                    drawn.edges = drawn.edges.min(self.edge_index_count);
+                }
+
+                let instance_data = &nodes[..nodes.len().min(MAX_NODES)];
+                // From the physics buffer there's nothing to upload; instance i is node i there
+                if !instance_data.is_empty() && format != InstanceFormat::Storage {
+                    let bytes = if format == InstanceFormat::Compact {
+                        self.compact_nodes.clear();
+                        self.compact_nodes.extend(instance_data.iter().map(CompactNode::new));
+                        bytemuck::cast_slice(&self.compact_nodes)
+                    } else {
+                        bytemuck::cast_slice(instance_data)
+                    };
+                    queue.write_buffer(node_instance_buffer, 0, bytes);
+                    instance_bytes += bytes.len();
+                }
+                drawn.nodes = instance_data.len() as u32;
+            }
+            self.instance_bytes = instance_bytes;
+            self.drawn = drawn;
+
             // Update uniforms with error handling
+            let projection = Projection::new(camera, (config.width, config.height));
             let uniforms = Uniforms {
                 time: time as f32,
-                _padding1: 0.0,
-                resolution: [config.width as f32, config.height as f32],
+                opacity: self.global_opacity,
+                resolution: projection.resolution,
                 color1: *color1,
                 color2: *color2,
-                camera_position: *camera_position,
-                camera_zoom: camera_zoom,
-                _padding2: 0.0,
+                camera_position: projection.camera.position,
+                camera_zoom: projection.camera.zoom,
+                point_size: 0.0,
+                filter_required: self.visibility_filter[0],
+                filter_forbidden: self.visibility_filter[1],
+                edge_stride: match edge_pass {
+                    EdgePass::Direct { stride } => stride,
+                    _ => 1,
+                },
+                gradient_style,
+                gradient,
+                gradient_phase: self.gradient_phase,
+                motion_mode: match self.idle_motion.0 {
+                    IdleMotion::None => MOTION_NONE,
+                    IdleMotion::Bob => MOTION_BOB,
+                    IdleMotion::Shimmer => MOTION_SHIMMER,
+                },
+                motion_amplitude: self.idle_motion.1,
+                motion_frequency: self.idle_motion.2,
+                arrow_style: self.arrows.0.code(),
+                arrow_size: self.arrows.1,
+                edge_smoothing: self.edge_antialias.0,
+                edge_min_width: self.edge_antialias.1,
+                pixel_snap: self.pixel_snap,
+                _padding: [0; 3],
             };
             
             queue.write_buffer(uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+            if let Some(overlay) = &self.overlay {
+                // Previews hold still
+                let uniforms = Uniforms {
+                    filter_required: 0,
+                    filter_forbidden: 0,
+                    edge_stride: 0,
+                    motion_mode: MOTION_NONE,
+                    ..uniforms
+                };
+                queue.write_buffer(&overlay.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+            }
 
-            // Get surface texture with error handling
-            let output = match surface.get_current_texture() {
-                Ok(texture) => texture,
-                Err(_) => return, // Skip frame if surface is not ready
-            };
-            
             let view = output
                 .texture
                 .create_view(&TextureViewDescriptor::default());
@@ -1072,6 +3705,24 @@ impl Renderer {
                 label: Some("Render Encoder"),
             });
 
+            if let (EdgePass::Accumulate { instances }, Some(Progressive { target: Some(target), .. })) = (&edge_pass, &self.progressive) {
+                let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                    label: Some("Edge Accumulation Pass"),
+                    color_attachments: &[Some(RenderPassColorAttachment {
+                        view: &target.view,
+                        resolve_target: None,
+                        ops: Operations {
+                            load: if instances.start == 0 { LoadOp::Clear(Color::TRANSPARENT) } else { LoadOp::Load },
+                            store: StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+                self.draw_edges(&mut render_pass, uniform_bind_group, instances.clone());
+            }
+
             {
                 let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                     label: Some("Render Pass"),
@@ -1080,10 +3731,10 @@ impl Renderer {
                         resolve_target: None,
                         ops: Operations {
                             load: LoadOp::Clear(Color {
-                                r: 0.0,
-                                g: 0.0,
-                                b: 0.0,
-                                a: 1.0,
+                                r: self.clear_color[0] as f64,
+                                g: self.clear_color[1] as f64,
+                                b: self.clear_color[2] as f64,
+                                a: self.clear_color[3] as f64,
                             }),
                             store: StoreOp::Store,
                         },
@@ -1099,103 +3750,177 @@ impl Renderer {
                     render_pass.set_bind_group(0, uniform_bind_group, &[]);
                     render_pass.draw(0..3, 0..1); // Draw a triangle
                 }
-
-                // Render edges first (behind nodes)
-                if !edges.is_empty() {
-                    // Check edge count limit
-                    if edges.len() > MAX_EDGES {
-                        console::log_1(&format!("Warning: {} edges exceeds limit of {}. Only rendering first {} edges.", 
-                                               edges.len(), MAX_EDGES, MAX_EDGES).into());
-                    }
-                    
-                    // Prepare edge instance data
-                    let mut edge_instance_data = Vec::new();
-                    let edges_to_render = edges.iter().take(MAX_EDGES);
-                    for edge in edges_to_render {
-                        edge_instance_data.extend_from_slice(&[
-                            edge.x1, edge.y1,           // start position
-                            edge.x2, edge.y2,           // end position
-                            edge.r, edge.g, edge.b, edge.a,  // color
-                            edge.width,                  // width
-                        ]);
-                    }
-
-                    // Update edge instance buffer
-                    if !edge_instance_data.is_empty() {
-                        queue.write_buffer(
-                            edge_instance_buffer,
-                            0,
-                            bytemuck::cast_slice(&edge_instance_data)
-                        );
-
-                        render_pass.set_pipeline(edge_pipeline);
-                        render_pass.set_bind_group(0, uniform_bind_group, &[]);
-                        render_pass.set_vertex_buffer(0, edge_vertex_buffer.slice(..));
-                        render_pass.set_vertex_buffer(1, edge_instance_buffer.slice(..));
-                        let edge_count = edges.len().min(MAX_EDGES) as u32;
-                        render_pass.draw(0..6, 0..edge_count); // 6 vertices per quad, N instances
+                if let Some(image) = &self.background_image {
+                    render_pass.set_pipeline(&image.pipeline);
+                    render_pass.set_bind_group(0, uniform_bind_group, &[]);
+                    render_pass.set_bind_group(1, &image.bind_group, &[]);
+                    render_pass.draw(0..6, 0..1);
+                }
+                match edge_pass {
+                    EdgePass::Direct { .. } => self.draw_edges(&mut render_pass, uniform_bind_group, 0..drawn.edges),
+                    EdgePass::Accumulate { .. } | EdgePass::Composite => {
+                        if let Some(Progressive { composite_pipeline, target: Some(target), .. }) = &self.progressive {
+                            render_pass.set_pipeline(composite_pipeline);
+                            render_pass.set_bind_group(0, &target.bind_group, &[]);
+                            render_pass.draw(0..3, 0..1);
+                        }
                     }
                 }
+                self.draw_nodes(&mut render_pass, uniform_bind_group);
+                self.draw_overlay(&mut render_pass);
+            }
 
-                // Render nodes if any
-                if !nodes.is_empty() {
-                    // Check node count limit
-                    if nodes.len() > MAX_NODES {
-                        console::log_1(&format!("Warning: {} nodes exceeds limit of {}. Only rendering first {} nodes.", 
-                                               nodes.len(), MAX_NODES, MAX_NODES).into());
-                    }
-                    
-                    // Prepare node instance data
-                    let mut instance_data = Vec::new();
-                    let nodes_to_render = nodes.iter().take(MAX_NODES);
-                    for node in nodes_to_render {
-                        // Convert normalized coordinates (0-1) to pixel coordinates
-                        let pixel_x = node.x;
-                        let pixel_y = node.y;
-                        
-                        // Convert to NDC in the shader, just pass pixel coordinates
-                        let ndc_x = (pixel_x / config.width as f32) * 2.0 - 1.0;
-                        let ndc_y = 1.0 - (pixel_y / config.height as f32) * 2.0;
-                        
-                        instance_data.extend_from_slice(&[
-                            ndc_x, ndc_y,             // position in NDC (calculated here for now)
-                            node.r, node.g, node.b, node.a,  // color
-                            node.size,                // size in pixels
-                        ]);
-                    }
+            queue.submit(std::iter::once(encoder.finish()));
+            output.present();
 
-                    // Update instance buffer
-                    if !instance_data.is_empty() {
-                        queue.write_buffer(
-                            node_instance_buffer,
-                            0,
-                            bytemuck::cast_slice(&instance_data)
-                        );
-
-                        render_pass.set_pipeline(node_pipeline);
-                        render_pass.set_bind_group(0, uniform_bind_group, &[]);
-                        render_pass.set_vertex_buffer(0, node_vertex_buffer.slice(..));
-                        render_pass.set_vertex_buffer(1, node_instance_buffer.slice(..));
-                        let node_count = nodes.len().min(MAX_NODES) as u32;
-                        render_pass.draw(0..6, 0..node_count); // 6 vertices per quad, N instances
-                    }
-                }
+            self.collect_validation_errors(device);
+            Ok(())
+        } else {
+            Err("Renderer has no surface to draw to".to_string())
+        }
+    }
+
+    // Draws the instances the last frame uploaded, edges under nodes
+    fn draw_instances(&self, render_pass: &mut RenderPass<'_>, uniform_bind_group: &BindGroup) {
+        self.draw_edges(render_pass, uniform_bind_group, 0..self.drawn.edges);
+        self.draw_nodes(render_pass, uniform_bind_group);
+    }
+
+    // The pipelines for the last frame's instance format, and the physics
+    // bind group and edge indices when that's the storage format
+    fn pipelines(&self) -> (&Option<RenderPipeline>, &Option<RenderPipeline>, Option<(&BindGroup, &Buffer)>) {
+        let (node_pipeline, edge_pipeline) = match self.drawn.format {
+            InstanceFormat::Full => (&self.node_pipeline, &self.edge_pipeline),
+            InstanceFormat::Compact => (&self.compact_node_pipeline, &self.compact_edge_pipeline),
+            InstanceFormat::Storage => (&self.storage_node_pipeline, &self.storage_edge_pipeline),
+        };
+        let storage = match (&self.storage_bind_group, &self.edge_index_buffer) {
+            (Some(bind_group), Some(edge_index_buffer)) if self.drawn.format == InstanceFormat::Storage => Some((bind_group, edge_index_buffer)),
+            _ => None,
+        };
+        (node_pipeline, edge_pipeline, storage)
+    }
+
+    // Draws `instances` of the edges the last frame uploaded
+    fn draw_edges(&self, render_pass: &mut RenderPass<'_>, uniform_bind_group: &BindGroup, instances: std::ops::Range<u32>) {
+        let (Some(edge_vertex_buffer), Some(edge_instance_buffer), Some(edge_filter_buffer)) =
+            (&self.edge_vertex_buffer, &self.edge_instance_buffer, &self.edge_filter_buffer)
+        else {
+            return;
+        };
+        let (_, edge_pipeline, storage) = self.pipelines();
+        let bundles = self
+            .edge_bundles
+            .as_ref()
+            .filter(|bundles| self.draw_edge_bundles && self.drawn.format == InstanceFormat::Full && bundles.edges == self.drawn.edges);
+        if let (Some(bundles), Some(pipeline), false) = (bundles, &self.bundled_edge_pipeline, instances.is_empty()) {
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, uniform_bind_group, &[]);
+            render_pass.set_bind_group(1, &bundles.bind_group, &[]);
+            render_pass.set_vertex_buffer(0, edge_instance_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, edge_filter_buffer.slice(..));
+            render_pass.draw(0..6 * bundles.segments, instances);
+        } else if let (Some(pipeline), false) = (edge_pipeline, instances.is_empty()) {
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, edge_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, edge_instance_buffer.slice(..));
+            let filter_slot = if let Some((bind_group, edge_index_buffer)) = storage {
+                render_pass.set_bind_group(1, bind_group, &[]);
+                render_pass.set_vertex_buffer(2, edge_index_buffer.slice(..));
+                3
+            } else {
+                2
+            };
+            render_pass.set_vertex_buffer(filter_slot, edge_filter_buffer.slice(..));
+            render_pass.draw(0..6, instances); // 6 vertices per quad
+        }
+    }
+
+    fn draw_nodes(&self, render_pass: &mut RenderPass<'_>, uniform_bind_group: &BindGroup) {
+        let (Some(node_vertex_buffer), Some(node_instance_buffer), Some(node_filter_buffer)) =
+            (&self.node_vertex_buffer, &self.node_instance_buffer, &self.node_filter_buffer)
+        else {
+            return;
+        };
+        let (node_pipeline, _, storage) = self.pipelines();
+        if let (Some(pipeline), true) = (node_pipeline, self.drawn.nodes > 0) {
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, node_vertex_buffer.slice(..));
+            // Storage nodes come from the physics buffer, so the pulses move up a slot
+            let pulse_slot = if let Some((bind_group, _)) = storage {
+                render_pass.set_bind_group(1, bind_group, &[]);
+                1
+            } else {
+                render_pass.set_vertex_buffer(1, node_instance_buffer.slice(..));
+                2
+            };
+            if let Some(pulse_buffer) = &self.pulse_buffer {
+                render_pass.set_vertex_buffer(pulse_slot, pulse_buffer.slice(..));
             }
+            render_pass.set_vertex_buffer(pulse_slot + 1, node_filter_buffer.slice(..));
+            render_pass.draw(0..6, 0..self.drawn.nodes); // 6 vertices per quad, N instances
+        }
+    }
 
-            // Submit commands and present with error handling
-            match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                queue.submit(std::iter::once(encoder.finish()));
-                output.present();
-            })) {
-                Ok(_) => {},
-                Err(_) => {
-                    // Skip this frame if submission fails
-                    return;
-                }
+    fn draw_overlay(&self, render_pass: &mut RenderPass<'_>) {
+        let (Some(overlay), Some(edge_pipeline), Some(node_pipeline), Some(edge_vertex_buffer), Some(node_vertex_buffer)) =
+            (&self.overlay, &self.edge_pipeline, &self.node_pipeline, &self.edge_vertex_buffer, &self.node_vertex_buffer)
+        else {
+            return;
+        };
+        if overlay.edges > 0 {
+            let flags_offset = (MAX_OVERLAY_EDGES * std::mem::size_of::<EdgeData>()) as u64;
+            render_pass.set_pipeline(edge_pipeline);
+            render_pass.set_bind_group(0, &overlay.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, edge_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, overlay.edge_buffer.slice(..flags_offset));
+            render_pass.set_vertex_buffer(2, overlay.edge_buffer.slice(flags_offset..));
+            render_pass.draw(0..6, 0..overlay.edges);
+        }
+        if overlay.nodes > 0 {
+            let pulses_offset = (MAX_OVERLAY_NODES * std::mem::size_of::<NodeData>()) as u64;
+            let flags_offset = pulses_offset + (MAX_OVERLAY_NODES * std::mem::size_of::<PulseState>()) as u64;
+            render_pass.set_pipeline(node_pipeline);
+            render_pass.set_bind_group(0, &overlay.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, node_vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, overlay.node_buffer.slice(..pulses_offset));
+            render_pass.set_vertex_buffer(2, overlay.node_buffer.slice(pulses_offset..flags_offset));
+            render_pass.set_vertex_buffer(3, overlay.node_buffer.slice(flags_offset..));
+            render_pass.draw(0..6, 0..overlay.nodes);
+        }
+    }
+
+    // Pops the frame's error scope into validation_errors once it resolves
+    fn collect_validation_errors(&self, device: &Device) {
+        let scope = device.pop_error_scope();
+        let validation_errors = self.validation_errors.clone();
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(error) = scope.await {
+                validation_errors.lock().unwrap().push(error.to_string());
             }
+        });
+        // Native backends resolve the scope immediately
+        #[cfg(not(target_arch = "wasm32"))]
+        if let std::task::Poll::Ready(Some(error)) =
+            std::future::Future::poll(std::pin::pin!(scope), &mut std::task::Context::from_waker(std::task::Waker::noop()))
+        {
+            validation_errors.lock().unwrap().push(error.to_string());
         }
     }
 
+    // Validation errors from frames since the last call
+    pub fn take_validation_errors(&self) -> Vec<String> {
+        std::mem::take(&mut *self.validation_errors.lock().unwrap())
+    }
+
+    // The surface's current size in pixels; None before init and when headless
+    pub fn surface_size(&self) -> Option<(u32, u32)> {
+        self.config.as_ref().map(|config| (config.width, config.height))
+    }
+
     pub fn resize(&mut self, width: u32, height: u32) {
         if let (Some(surface), Some(device), Some(config)) =
             (&self.surface, &self.device, &mut self.config)
@@ -1206,8 +3931,7 @@ impl Renderer {
             }
             
             // Validate WebGPU texture size limits
-            const MAX_TEXTURE_SIZE: u32 = 2048;
-            if width > MAX_TEXTURE_SIZE || height > MAX_TEXTURE_SIZE {
+            if width > MAX_SURFACE_SIZE || height > MAX_SURFACE_SIZE {
                 return;
             }
             
@@ -1227,4 +3951,335 @@ impl Renderer {
             }
         }
     }
-}
\ No newline at end of file
+
+    // Extra edges and nodes drawn over everything with the frame's camera, such
+    // as previews of an edge or node about to be added. They ignore the
+    // visibility filter and progressive rendering. Beyond MAX_OVERLAY_EDGES and
+    // MAX_OVERLAY_NODES is dropped; nothing of either removes the overlay.
+    pub fn set_overlay(&mut self, edges: &[EdgeData], nodes: &[NodeData]) {
+        if edges.is_empty() && nodes.is_empty() {
+            self.overlay = None;
+            return;
+        }
+        let (Some(device), Some(queue), Some(layout), Some(style_buffer)) = (&self.device, &self.queue, &self.uniform_bind_group_layout, &self.style_buffer) else {
+            return;
+        };
+        let overlay = self.overlay.get_or_insert_with(|| {
+            let uniform_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Overlay Uniform Buffer"),
+                size: std::mem::size_of::<Uniforms>() as u64,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Overlay Uniform Bind Group"),
+                layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: style_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+            let edge_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Overlay Edge Buffer"),
+                size: (MAX_OVERLAY_EDGES * (std::mem::size_of::<EdgeData>() + std::mem::size_of::<EdgeFilter>())) as u64,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let node_buffer = device.create_buffer(&BufferDescriptor {
+                label: Some("Overlay Node Buffer"),
+                size: (MAX_OVERLAY_NODES * (std::mem::size_of::<NodeData>() + std::mem::size_of::<PulseState>() + std::mem::size_of::<u32>())) as u64,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            Overlay { uniform_buffer, uniform_bind_group, edge_buffer, node_buffer, edges: 0, nodes: 0 }
+        });
+        let (edges, nodes) = (&edges[..edges.len().min(MAX_OVERLAY_EDGES)], &nodes[..nodes.len().min(MAX_OVERLAY_NODES)]);
+        if !edges.is_empty() {
+            queue.write_buffer(&overlay.edge_buffer, 0, bytemuck::cast_slice(edges));
+        }
+        if !nodes.is_empty() {
+            queue.write_buffer(&overlay.node_buffer, 0, bytemuck::cast_slice(nodes));
+        }
+        overlay.edges = edges.len() as u32;
+        overlay.nodes = nodes.len() as u32;
+    }
+
+    // Adds a second surface for render_minimap, sharing the device. It must take
+    // the main surface's format, which the pipelines are built for.
+    pub fn attach_minimap(&mut self, target: impl Into<SurfaceTarget<'static>>, width: u32, height: u32) -> Result<(), String> {
+        let (Some(instance), Some(adapter), Some(device), Some(main_config), Some(layout), Some(style_buffer)) =
+            (&self.instance, &self.adapter, &self.device, &self.config, &self.uniform_bind_group_layout, &self.style_buffer)
+        else {
+            return Err("Renderer has no surface to draw to".to_string());
+        };
+        if width == 0 || height == 0 || width > MAX_SURFACE_SIZE || height > MAX_SURFACE_SIZE {
+            return Err(format!("Minimap size {}x{} is outside 1x1 to {}x{}", width, height, MAX_SURFACE_SIZE, MAX_SURFACE_SIZE));
+        }
+
+        let surface = instance
+            .create_surface(target)
+            .map_err(|e| format!("Failed to create minimap surface: {:?}", e))?;
+        if !surface.get_capabilities(adapter).formats.contains(&main_config.format) {
+            return Err(format!("Minimap surface doesn't support the main surface's format {:?}", main_config.format));
+        }
+        let config = SurfaceConfiguration { width, height, ..main_config.clone() };
+        surface.configure(device, &config);
+
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Minimap Uniform Buffer"),
+            size: std::mem::size_of::<Uniforms>() as u64,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Minimap Uniform Bind Group"),
+            layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: style_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let viewport_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Minimap Viewport Buffer"),
+            size: (4 * (std::mem::size_of::<EdgeData>() + std::mem::size_of::<EdgeFilter>())) as u64,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        self.minimap = Some(Minimap { surface, config, uniform_buffer, uniform_bind_group, viewport_buffer });
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn attach_minimap_canvas(&mut self, canvas: &HtmlCanvasElement) -> Result<(), String> {
+        self.attach_minimap(SurfaceTarget::Canvas(canvas.clone()), canvas.width(), canvas.height())
+    }
+
+    // Native windows go through attach_minimap
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn attach_minimap_canvas(&mut self, _canvas: &web_sys::HtmlCanvasElement) -> Result<(), String> {
+        Err("Canvas minimaps need a browser".to_string())
+    }
+
+    pub fn detach_minimap(&mut self) {
+        self.minimap = None;
+    }
+
+    pub fn minimap_size(&self) -> Option<(u32, u32)> {
+        self.minimap.as_ref().map(|minimap| (minimap.config.width, minimap.config.height))
+    }
+
+    // Ignores sizes the surface can't take, like resize
+    pub fn resize_minimap(&mut self, width: u32, height: u32) {
+        if let (Some(minimap), Some(device)) = (&mut self.minimap, &self.device) {
+            if width == 0 || height == 0 || width > MAX_SURFACE_SIZE || height > MAX_SURFACE_SIZE {
+                return;
+            }
+            minimap.config.width = width;
+            minimap.config.height = height;
+            minimap.surface.configure(device, &minimap.config);
+        }
+    }
+
+    // Draws the instances of the last frame into the minimap with its own
+    // camera, nodes as dots `point_size` pixels across and edges as hairlines,
+    // then outlines `viewport` (min x, min y, max x, max y in world pixels).
+    // Call right after render, while the instance buffers hold that frame.
+    pub fn render_minimap(&mut self, camera: Camera, point_size: f32, viewport: [f32; 4], viewport_color: [f32; 4]) -> Result<(), String> {
+        let (Some(minimap), Some(device), Some(queue), Some(edge_pipeline), Some(edge_vertex_buffer)) =
+            (&self.minimap, &self.device, &self.queue, &self.edge_pipeline, &self.edge_vertex_buffer)
+        else {
+            return Err("Renderer has no minimap to draw to".to_string());
+        };
+
+        let projection = Projection::new(camera, (minimap.config.width, minimap.config.height));
+        let uniforms = Uniforms {
+            time: 0.0,
+            opacity: self.global_opacity,
+            resolution: projection.resolution,
+            color1: [0.0; 4],
+            color2: [0.0; 4],
+            camera_position: projection.camera.position,
+            camera_zoom: projection.camera.zoom,
+            point_size,
+            filter_required: self.visibility_filter[0],
+            filter_forbidden: self.visibility_filter[1],
+            edge_stride: 0,
+            gradient_style: GRADIENT_LINEAR,
+            gradient: [0.0; 4],
+            gradient_phase: 0.0,
+            motion_mode: MOTION_NONE,
+            motion_amplitude: 0.0,
+            motion_frequency: 0.0,
+            arrow_style: 0,
+            arrow_size: 0.0,
+            edge_smoothing: self.edge_antialias.0,
+            edge_min_width: self.edge_antialias.1,
+            pixel_snap: self.pixel_snap,
+            _padding: [0; 3],
+        };
+        queue.write_buffer(&minimap.uniform_buffer, 0, bytemuck::cast_slice(&[uniforms]));
+        let [min_x, min_y, max_x, max_y] = viewport;
+        let [r, g, b, a] = viewport_color;
+        let side = |x1, y1, x2, y2| EdgeData { x1, y1, x2, y2, r, g, b, a, width: 1.0 };
+        let outline = [
+            side(min_x, min_y, max_x, min_y),
+            side(max_x, min_y, max_x, max_y),
+            side(max_x, max_y, min_x, max_y),
+            side(min_x, max_y, min_x, min_y),
+        ];
+        queue.write_buffer(&minimap.viewport_buffer, 0, bytemuck::cast_slice(&outline));
+
+        let output = match minimap.surface.get_current_texture() {
+            Ok(texture) => texture,
+            Err(SurfaceError::Timeout) => return Ok(()),
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                minimap.surface.configure(device, &minimap.config);
+                return Ok(());
+            }
+            Err(SurfaceError::OutOfMemory) => return Err("Out of memory acquiring the minimap texture".to_string()),
+            Err(e) => return Err(format!("Failed to acquire the minimap texture: {}", e)),
+        };
+
+        device.push_error_scope(ErrorFilter::Validation);
+        let view = output.texture.create_view(&TextureViewDescriptor::default());
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Minimap Encoder"),
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Minimap Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color {
+                            r: self.clear_color[0] as f64,
+                            g: self.clear_color[1] as f64,
+                            b: self.clear_color[2] as f64,
+                            a: self.clear_color[3] as f64,
+                        }),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            self.draw_instances(&mut render_pass, &minimap.uniform_bind_group);
+
+            render_pass.set_pipeline(edge_pipeline);
+            render_pass.set_bind_group(0, &minimap.uniform_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, edge_vertex_buffer.slice(..));
+            let flags_offset = std::mem::size_of_val(&outline) as u64;
+            render_pass.set_vertex_buffer(1, minimap.viewport_buffer.slice(..flags_offset));
+            render_pass.set_vertex_buffer(2, minimap.viewport_buffer.slice(flags_offset..));
+            render_pass.draw(0..6, 0..outline.len() as u32);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+        output.present();
+        self.collect_validation_errors(device);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(name: &str, source: &str) {
+        let module = naga::front::wgsl::parse_str(source).unwrap_or_else(|error| panic!("{} doesn't parse:\n{}", name, error.emit_to_string(source)));
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::all())
+            .validate(&module)
+            .unwrap_or_else(|error| panic!("{} doesn't validate:\n{}", name, error.emit_to_string(source)));
+    }
+
+    #[test]
+    fn shaders_parse_and_validate() {
+        validate("physics", &with_node_data(PHYSICS_SHADER));
+        validate("nodes.wgsl", &with_node_data(include_str!("shaders/nodes.wgsl")));
+        validate("edges.wgsl", &with_node_data(include_str!("shaders/edges.wgsl")));
+        validate("background.wgsl", include_str!("shaders/background.wgsl"));
+        validate("composite.wgsl", include_str!("shaders/composite.wgsl"));
+        validate("gradient.wgsl", include_str!("shaders/gradient.wgsl"));
+    }
+
+    #[test]
+    fn projection_keeps_circles_round_on_a_wide_canvas() {
+        let projection = Projection::new(Camera { position: [-500.0, -300.0], zoom: 40.0 }, (1920, 1080));
+        let center = projection.world_to_ndc([3.0, 2.0]);
+        for step in 0..16 {
+            let angle = step as f32 / 16.0 * std::f32::consts::TAU;
+            let ndc = projection.world_to_ndc([3.0 + angle.cos(), 2.0 + angle.sin()]);
+            // Back from NDC offsets to pixels, half the resolution per unit
+            let pixels = [0, 1].map(|axis| (ndc[axis] - center[axis]) * projection.resolution[axis] * 0.5);
+            assert!((pixels[0].hypot(pixels[1]) - 40.0).abs() < 1e-2, "{angle}: {pixels:?}");
+        }
+    }
+
+    #[test]
+    fn canvas_corners_are_the_ndc_corners() {
+        let projection = Projection::new(Camera { position: [10.0, 20.0], zoom: 2.0 }, (1920, 1080));
+        assert_eq!(projection.world_to_pixel([10.0, 20.0]), [0.0, 0.0]);
+        assert_eq!(projection.world_to_ndc([10.0, 20.0]), [-1.0, 1.0]);
+        assert_eq!(projection.world_to_ndc([970.0, 560.0]), [1.0, -1.0]);
+        assert_eq!(projection.pixel_to_ndc([960.0, 540.0]), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn sorted_grid_lists_cells_in_node_order() {
+        // 10 world units per cell. Every third node lands in the first cell, 40
+        // in all, so 8 of them overflow; the rest are spread over other cells.
+        let mut rng = crate::layout::Rng::new(9);
+        let nodes: Vec<NodeData> = (0..120)
+            .map(|i| {
+                let (x, y) = if i % 3 == 0 { (rng.next_f32() * 9.0, rng.next_f32() * 9.0) } else { (20.0 + rng.next_f32() * 290.0, 20.0 + rng.next_f32() * 290.0) };
+                NodeData { x, y, ..bytemuck::Zeroable::zeroed() }
+            })
+            .collect();
+        // Node 7 isn't a participant
+        let active: Vec<u32> = (0..120).filter(|&i| i != 7).collect();
+        let pack = |active: &[u32]| pack_sorted_grid(&nodes, active, [0.0, 0.0], [320.0, 320.0]);
+        let sorted = pack(&active);
+
+        let cell_words = GRID_CELL_CAPACITY + 1;
+        let first_cell: Vec<u32> = (0..120).step_by(3).collect();
+        assert_eq!(sorted[..GRID_CELL_CAPACITY], first_cell[..GRID_CELL_CAPACITY]);
+        assert_eq!(sorted[GRID_CELL_CAPACITY], 40);
+        let overflow_start = (PHYSICS_GRID_SIZE * PHYSICS_GRID_SIZE) as usize * cell_words;
+        assert_eq!(sorted[overflow_start], 8);
+        let overflow: Vec<u32> = first_cell[GRID_CELL_CAPACITY..].iter().flat_map(|&index| [0, index]).collect();
+        assert_eq!(sorted[overflow_start + 1..], overflow[..]);
+
+        // Every other cell lists its nodes ascending, and node 7 is nowhere
+        for cell in sorted[..overflow_start].chunks_exact(cell_words) {
+            let count = (cell[GRID_CELL_CAPACITY] as usize).min(GRID_CELL_CAPACITY);
+            assert!(cell[..count].windows(2).all(|pair| pair[0] < pair[1]));
+            assert!(!cell[..count].contains(&7));
+        }
+
+        // The same words whatever order the participants come in
+        for seed in 1..4 {
+            let mut shuffled = active.clone();
+            let mut rng = crate::layout::Rng::new(seed);
+            for i in (1..shuffled.len()).rev() {
+                shuffled.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+            }
+            assert_eq!(pack(&shuffled), sorted);
+        }
+        let reversed: Vec<u32> = active.iter().rev().copied().collect();
+        assert_eq!(pack(&reversed), sorted);
+    }
+}
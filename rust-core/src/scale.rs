@@ -0,0 +1,116 @@
+// Mapping of data values (degrees, metrics) onto visual ranges like node sizes
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueScale {
+    Linear,
+    Sqrt, // proportional area for circles sized by radius
+    Log,  // compresses heavy-tailed values such as degrees
+}
+
+impl ValueScale {
+    pub fn parse(scale: &str) -> Option<Self> {
+        match scale {
+            "linear" => Some(Self::Linear),
+            "sqrt" => Some(Self::Sqrt),
+            "log" => Some(Self::Log),
+            _ => None,
+        }
+    }
+}
+
+// Maps the finite values onto [min_out, max_out], smallest to largest. Scales
+// apply to the offset from the smallest value, so zeros and negatives are fine.
+// Non-finite values map to min_out, and values that are all equal, or differ
+// only by float rounding, to the middle.
+pub fn scale_to_range(values: &[f32], scale: ValueScale, min_out: f32, max_out: f32) -> Vec<f32> {
+    let transform = |offset: f32| match scale {
+        ValueScale::Linear => offset,
+        ValueScale::Sqrt => offset.sqrt(),
+        ValueScale::Log => offset.ln_1p(),
+    };
+
+    let lowest = values.iter().copied().filter(|value| value.is_finite()).fold(f32::INFINITY, f32::min);
+    let highest = values.iter().copied().filter(|value| value.is_finite()).fold(f32::NEG_INFINITY, f32::max);
+    // Rounding noise in the values shouldn't be stretched over the whole range
+    let spread = highest - lowest;
+    let range = if spread > f32::EPSILON * highest.abs().max(lowest.abs()).max(1.0) { transform(spread) } else { 0.0 };
+
+    values
+        .iter()
+        .map(|&value| {
+            if !value.is_finite() {
+                min_out
+            } else if !(range > 0.0 && range.is_finite()) {
+                (min_out + max_out) * 0.5
+            } else {
+                min_out + (max_out - min_out) * transform(value - lowest) / range
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms;
+
+    #[test]
+    fn scales_hit_the_range_endpoints() {
+        let values = [0.0, 3.0, 8.0, 99.0];
+        for scale in [ValueScale::Linear, ValueScale::Sqrt, ValueScale::Log] {
+            let sizes = scale_to_range(&values, scale, 2.0, 20.0);
+            assert_eq!((sizes[0], sizes[3]), (2.0, 20.0), "{scale:?}");
+            assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]), "{scale:?}: {sizes:?}");
+        }
+        // Offsets from the lowest value, so negatives work too
+        let linear = scale_to_range(&[-10.0, 0.0, 10.0], ValueScale::Linear, 0.0, 1.0);
+        assert_eq!(linear, [0.0, 0.5, 1.0]);
+        let sqrt = scale_to_range(&[0.0, 25.0, 100.0], ValueScale::Sqrt, 0.0, 10.0);
+        assert!((sqrt[1] - 5.0).abs() < 1e-5);
+        let log = scale_to_range(&[0.0, 9.0, 99.0], ValueScale::Log, 0.0, 2.0);
+        assert!((log[1] - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn unusable_values_are_not_nan() {
+        let sizes = scale_to_range(&[f32::NAN, 1.0, f32::INFINITY, 3.0], ValueScale::Linear, 2.0, 10.0);
+        assert_eq!(sizes, [2.0, 2.0, 2.0, 10.0]);
+        for values in [vec![4.0; 3], vec![f32::NAN; 3], vec![7.0, f32::NAN, 7.0]] {
+            for scale in [ValueScale::Linear, ValueScale::Sqrt, ValueScale::Log] {
+                let sizes = scale_to_range(&values, scale, 2.0, 10.0);
+                assert!(sizes.iter().all(|size| size.is_finite()), "{values:?} {scale:?}: {sizes:?}");
+                assert!(sizes.iter().zip(&values).all(|(&size, value)| size == if value.is_finite() { 6.0 } else { 2.0 }));
+            }
+        }
+        assert!(scale_to_range(&[], ValueScale::Log, 2.0, 10.0).is_empty());
+        // Differences at rounding level count as equal
+        for values in [vec![1.0, 1.0 + 1e-7], vec![0.25, 0.25 + 2e-8, 0.25 - 2e-8], vec![1e6, 1e6 + 0.06]] {
+            assert!(scale_to_range(&values, ValueScale::Linear, 2.0, 10.0).iter().all(|&size| size == 6.0), "{values:?}");
+        }
+        assert_eq!(scale_to_range(&[1.0, 1.001], ValueScale::Linear, 2.0, 10.0), [2.0, 10.0]);
+    }
+
+    #[test]
+    fn pagerank_sizes_follow_centrality() {
+        let sizes = |node_count, edges: &[[u32; 2]]| {
+            let links: Vec<[u32; 2]> = edges.iter().flat_map(|&[a, b]| [[a, b], [b, a]]).collect();
+            scale_to_range(&algorithms::pagerank(node_count, &links, 0.85, 50), ValueScale::Sqrt, 4.0, 16.0)
+        };
+        let star = sizes(5, &[[0, 1], [0, 2], [0, 3], [0, 4]]);
+        assert_eq!(star[0], 16.0);
+        assert!(star[1..].iter().all(|&size| (size - 4.0).abs() < 1e-3));
+
+        let path = sizes(5, &[[0, 1], [1, 2], [2, 3], [3, 4]]);
+        assert!((path[0] - 4.0).abs() < 1e-3 && (path[4] - 4.0).abs() < 1e-3);
+        assert!(path[0] < path[1] && path[1] > 4.0);
+        assert!(path.iter().all(|&size| size <= 16.0));
+
+        // Equal ranks put every node in the middle, and ranks a rounding error
+        // apart count as equal
+        let complete = sizes(4, &[[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]]);
+        assert!(complete.iter().all(|&size| (size - 10.0).abs() < 1e-3), "{complete:?}");
+        let ranks = algorithms::pagerank(4, &[[0, 1], [1, 2], [2, 3], [3, 0]], 0.85, 50);
+        let nudged: Vec<f32> = ranks.iter().enumerate().map(|(node, &rank)| if node % 2 == 0 { rank } else { f32::from_bits(rank.to_bits() + 1) }).collect();
+        assert_eq!(scale_to_range(&nudged, ValueScale::Sqrt, 4.0, 16.0), [10.0; 4]);
+    }
+}
@@ -0,0 +1,59 @@
+// Case-insensitive lookup of node labels for find_nodes. Labels are lowercased
+// once; exact and prefix queries binary-search a sorted order of them, and
+// substring queries scan the lowercased copies.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchMode {
+    Exact,
+    Prefix,
+    Substring,
+}
+
+impl SearchMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "exact" => Some(Self::Exact),
+            "prefix" => Some(Self::Prefix),
+            "substring" => Some(Self::Substring),
+            _ => None,
+        }
+    }
+}
+
+pub struct LabelIndex {
+    lowered: Vec<String>,
+    sorted: Vec<u32>, // label indices by lowercased label
+}
+
+impl LabelIndex {
+    pub fn new(labels: &[String]) -> Self {
+        let lowered: Vec<String> = labels.iter().map(|label| label.to_lowercase()).collect();
+        let mut sorted: Vec<u32> = (0..lowered.len() as u32).collect();
+        sorted.sort_by(|&a, &b| lowered[a as usize].cmp(&lowered[b as usize]));
+        Self { lowered, sorted }
+    }
+
+    // Indices of the labels matching `query`, ascending; an empty query matches nothing
+    pub fn find(&self, query: &str, mode: SearchMode) -> Vec<u32> {
+        let query = query.to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        if mode == SearchMode::Substring {
+            return (0..).zip(&self.lowered).filter(|(_, label)| label.contains(&query)).map(|(index, _)| index).collect();
+        }
+
+        // Labels equal to or starting with the query sort right after it, together
+        let start = self.sorted.partition_point(|&index| self.lowered[index as usize] < query);
+        let mut found: Vec<u32> = self.sorted[start..]
+            .iter()
+            .copied()
+            .take_while(|&index| {
+                let label = &self.lowered[index as usize];
+                if mode == SearchMode::Exact { *label == query } else { label.starts_with(&query) }
+            })
+            .collect();
+        found.sort_unstable();
+        found
+    }
+}
@@ -0,0 +1,66 @@
+// Draws the background image over the world rect it was given, under the
+// edges and nodes. Projected like them, so it pans and zooms with the camera.
+
+// Must match Uniforms in renderer.rs
+struct Uniforms {
+    time: f32,
+    opacity: f32,
+    resolution: vec2<f32>,
+    color1: vec4<f32>,
+    color2: vec4<f32>,
+    camera_position: vec2<f32>,
+    camera_zoom: f32,
+    point_size: f32,
+    filter_required: u32,
+    filter_forbidden: u32,
+    edge_stride: u32,
+    gradient_style: u32,
+    gradient: vec4<f32>,
+    gradient_phase: f32,
+    motion_mode: u32,
+    motion_amplitude: f32,
+    motion_frequency: f32,
+    arrow_style: u32,
+    arrow_size: f32,
+    edge_smoothing: f32,
+    edge_min_width: f32,
+    pixel_snap: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+@group(1) @binding(0) var image: texture_2d<f32>;
+@group(1) @binding(1) var image_sampler: sampler;
+@group(1) @binding(2) var<uniform> rect: vec4<f32>; // min x, min y, max x, max y in world units
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    // Two triangles covering the rect
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(1.0, 0.0),
+        vec2<f32>(0.0, 1.0),
+        vec2<f32>(1.0, 1.0)
+    );
+    let uv = corners[vertex_index];
+    let world = mix(rect.xy, rect.zw, uv);
+    let pixel = (world - uniforms.camera_position) * uniforms.camera_zoom;
+
+    var output: VertexOutput;
+    output.position = vec4<f32>(pixel.x / uniforms.resolution.x * 2.0 - 1.0, 1.0 - pixel.y / uniforms.resolution.y * 2.0, 0.0, 1.0);
+    output.uv = uv;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = textureSample(image, image_sampler, input.uv);
+    return vec4<f32>(color.rgb, color.a * uniforms.opacity);
+}
@@ -0,0 +1,19 @@
+// Draws the edges progressive rendering accumulated, stored with premultiplied
+// alpha, over the frame. The texture matches the surface size pixel for pixel.
+@group(0) @binding(0) var accumulated: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    // One triangle covering the screen
+    var positions = array<vec2<f32>, 3>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(3.0, -1.0),
+        vec2<f32>(-1.0, 3.0)
+    );
+    return vec4<f32>(positions[vertex_index], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return textureLoad(accumulated, vec2<i32>(position.xy), 0);
+}
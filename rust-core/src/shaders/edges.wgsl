@@ -1,13 +1,27 @@
 // Vertex shader for rendering edges as oriented rectangles
 struct Uniforms {
     time: f32,
-    _padding1: f32,
+    opacity: f32,  // global alpha multiplier
     resolution: vec2<f32>,
     color1: vec4<f32>,
     color2: vec4<f32>,
     camera_position: vec2<f32>,
     camera_zoom: f32,
-    _padding2: f32,
+    point_size: f32,  // > 0 draws edges as hairlines
+    filter_required: u32,  // see set_visibility_filter
+    filter_forbidden: u32,
+    edge_stride: u32,  // draws every nth edge only when > 1
+    gradient_style: u32,
+    gradient: vec4<f32>,
+    gradient_phase: f32,
+    motion_mode: u32,
+    motion_amplitude: f32,
+    motion_frequency: f32,
+    arrow_style: u32,
+    arrow_size: f32,
+    edge_smoothing: f32,  // pixels the outline fades over
+    edge_min_width: f32,  // thinner edges are drawn this wide, fainter
+    pixel_snap: f32,
 }
 
 struct VertexInput {
@@ -16,79 +30,278 @@ struct VertexInput {
     @location(2) instance_end: vec2<f32>,    // End position in pixels
     @location(3) instance_color: vec4<f32>,  // Edge color
     @location(4) instance_width: f32,        // Edge width in pixels
+    @location(6) instance_filter: vec3<u32>, // endpoint user flags, and 1 if the edge has endpoints
+    @location(7) instance_arrow: vec2<f32>,  // arrow size override in pixels (0 for none), target radius
+    @location(8) instance_arrow_style: u32,  // arrow style override + 1, 0 for none
 }
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
-    @location(1) uv: vec2<f32>,  // UV coordinates for anti-aliasing
+    @location(1) @interpolate(flat) fade: f32,  // alpha left to edges below the minimum width
+    @location(2) local: vec2<f32>,  // pixels along the edge from its start and across it
+    @location(3) @interpolate(flat) head: vec4<f32>,  // tip distance, line half width, head length and half width
+    @location(4) @interpolate(flat) arrow: u32,
 }
 
+// Must match ArrowStyle::code in renderer.rs
+const ARROW_NONE: u32 = 0u;
+const ARROW_TRIANGLE: u32 = 1u;
+const ARROW_OPEN: u32 = 2u;
+const ARROW_CIRCLE: u32 = 3u;
+
+// Pixels between an arrow tip and the target node's outline
+const ARROW_GAP: f32 = 1.0;
+
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
 
+// Whether user flags (set_node_flags) pass the visibility filter
+fn passes_filter(flags: u32) -> bool {
+    return (flags & uniforms.filter_required) == uniforms.filter_required && (flags & uniforms.filter_forbidden) == 0u;
+}
+
+// Progressive rendering thins edges out while the view moves
+fn skipped(instance: u32) -> bool {
+    return instance % max(uniforms.edge_stride, 1u) != 0u;
+}
+
+@vertex
+fn vs_main(input: VertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
+    var output = edge_vertex(input);
+    if (skipped(instance)) {
+        output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+    }
+    return output;
+}
+
+// Storage instances: the endpoints are looked up in the physics buffer (NodeData,
+// prepended by the renderer) by node index; color and width still come from the
+// edge instance buffer
+struct StorageVertexInput {
+    @location(0) position: vec2<f32>,
+    @location(3) instance_color: vec4<f32>,
+    @location(4) instance_width: f32,
+    @location(5) instance_nodes: vec2<u32>,  // source and target node indices
+    @location(6) instance_filter: vec3<u32>,
+    @location(7) instance_arrow: vec2<f32>,
+    @location(8) instance_arrow_style: u32,
+}
+
+@group(1) @binding(0) var<storage, read> physics_nodes: array<NodeData>;
+
+@vertex
+fn vs_storage(input: StorageVertexInput, @builtin(instance_index) instance: u32) -> VertexOutput {
+    let start = physics_nodes[input.instance_nodes.x];
+    let end = physics_nodes[input.instance_nodes.y];
+    var full: VertexInput;
+    full.position = input.position;
+    full.instance_start = vec2<f32>(start.x, start.y);
+    full.instance_end = vec2<f32>(end.x, end.y);
+    full.instance_color = input.instance_color;
+    full.instance_width = input.instance_width;
+    full.instance_filter = input.instance_filter;
+    // The target's radius as simulated, rather than as last uploaded
+    full.instance_arrow = vec2<f32>(input.instance_arrow.x, select(0.0, end.size, input.instance_filter.z != 0u));
+    full.instance_arrow_style = input.instance_arrow_style;
+    var output = edge_vertex(full);
+    // Edges of hidden nodes are hidden with them
+    if (((start.flags | end.flags) & NODE_HIDDEN_FLAGS) != 0u || skipped(instance)) {
+        output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+    }
+    return output;
+}
+
+// Bundled edges: each instance is drawn as bundle.x straight segments through
+// its bundle.x + 1 control points (world units, set_edge_bundles), listed edge
+// after edge; color, width and filter still come from the instance buffers
+struct BundledVertexInput {
+    @location(3) instance_color: vec4<f32>,
+    @location(4) instance_width: f32,
+    @location(6) instance_filter: vec3<u32>,
+    @location(7) instance_arrow: vec2<f32>,
+    @location(8) instance_arrow_style: u32,
+}
+
+@group(1) @binding(1) var<storage, read> bundle_points: array<vec2<f32>>;
+@group(1) @binding(2) var<uniform> bundle: vec4<u32>;  // segments per edge
+
 @vertex
-fn vs_main(input: VertexInput) -> VertexOutput {
+fn vs_bundled(input: BundledVertexInput, @builtin(vertex_index) vertex_index: u32, @builtin(instance_index) instance: u32) -> VertexOutput {
+    // The corners of the edge quad vertex buffer, six vertices per segment
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0)
+    );
+    let segment = vertex_index / 6u;
+    let first = instance * (bundle.x + 1u) + segment;
+    var full: VertexInput;
+    full.position = corners[vertex_index % 6u];
+    full.instance_start = bundle_points[first];
+    full.instance_end = bundle_points[first + 1u];
+    full.instance_color = input.instance_color;
+    full.instance_width = input.instance_width;
+    full.instance_filter = input.instance_filter;
+    full.instance_arrow = input.instance_arrow;
+    // Only the last segment ends at the target, so only it has a head
+    full.instance_arrow_style = input.instance_arrow_style;
+    if (segment + 1u < bundle.x) {
+        full.instance_arrow_style = ARROW_NONE + 1u;
+    }
+    var output = edge_vertex(full);
+    if (skipped(instance)) {
+        output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+    }
+    return output;
+}
+
+// Canvas pixels (origin top left, y down) to NDC
+fn pixel_to_ndc(pixel: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(pixel.x / uniforms.resolution.x * 2.0 - 1.0, 1.0 - pixel.y / uniforms.resolution.y * 2.0);
+}
+
+fn edge_vertex(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
     
-    // Apply camera transformations in pixel space
-    let start_world_x = (input.instance_start.x - uniforms.camera_position.x) * uniforms.camera_zoom;
-    let start_world_y = (input.instance_start.y - uniforms.camera_position.y) * uniforms.camera_zoom;
-    let end_world_x = (input.instance_end.x - uniforms.camera_position.x) * uniforms.camera_zoom;
-    let end_world_y = (input.instance_end.y - uniforms.camera_position.y) * uniforms.camera_zoom;
-    
-    // Convert to NDC
-    let start_ndc = vec2<f32>(
-        (start_world_x / uniforms.resolution.x) * 2.0 - 1.0,
-        1.0 - (start_world_y / uniforms.resolution.y) * 2.0
-    );
-    let end_ndc = vec2<f32>(
-        (end_world_x / uniforms.resolution.x) * 2.0 - 1.0,
-        1.0 - (end_world_y / uniforms.resolution.y) * 2.0
-    );
+    // Apply camera transformations in pixel space. The quad is laid out there
+    // too, where both axes share one scale, so widths and right angles survive
+    // a non-square canvas
+    let start_px = (input.instance_start - uniforms.camera_position) * uniforms.camera_zoom;
+    let end_px = (input.instance_end - uniforms.camera_position) * uniforms.camera_zoom;
     
     // Calculate line direction and perpendicular vector
-    let line_vec = end_ndc - start_ndc;
+    let line_vec = end_px - start_px;
     let line_length = length(line_vec);
     
     if (line_length > 0.0) {
         let line_dir = line_vec / line_length;
         let line_perp = vec2<f32>(-line_dir.y, line_dir.x);
         
-        // Convert width to NDC units (maintain aspect ratio and apply zoom)
-        let width_ndc_x = (input.instance_width * uniforms.camera_zoom / uniforms.resolution.x) * 2.0;
-        let width_ndc_y = (input.instance_width * uniforms.camera_zoom / uniforms.resolution.y) * 2.0;
-        let width_ndc = min(width_ndc_x, width_ndc_y);
+        var width = input.instance_width * uniforms.camera_zoom;
+        if (uniforms.point_size > 0.0) {
+            width = 1.5;  // overviews draw hairlines
+        }
+        // Too thin an edge would fall between pixel centers and vanish, so it
+        // is drawn at the minimum width with its coverage moved into alpha
+        var fade = 1.0;
+        if (width < uniforms.edge_min_width) {
+            fade = width / uniforms.edge_min_width;
+            width = uniforms.edge_min_width;
+        }
+        
+        // The arrowhead ends just outside the target node and takes at most
+        // half of the visible edge; overviews draw none
+        var arrow = uniforms.arrow_style;
+        if (input.instance_arrow_style != 0u) {
+            arrow = input.instance_arrow_style - 1u;
+        }
+        var size = uniforms.arrow_size;
+        if (input.instance_arrow.x > 0.0) {
+            size = input.instance_arrow.x;
+        }
+        let tip = max(line_length - input.instance_arrow.y * uniforms.camera_zoom - ARROW_GAP, 0.0);
+        let head_length = min(size, tip * 0.5);
+        if (uniforms.point_size > 0.0 || head_length <= 0.0) {
+            arrow = ARROW_NONE;
+        }
+        
+        // The quad runs to the tip (or the end without a head), wide enough
+        // for the line or head plus room for the smoothing
+        let margin = uniforms.edge_smoothing * 0.5 + 1.0;
+        var span = line_length;
+        var half_extent = width * 0.5 + margin;
+        var head = vec4<f32>(line_length, width * 0.5, 0.0, 0.0);
+        if (arrow != ARROW_NONE) {
+            let head_half_width = max(head_length * 0.5, width);
+            span = tip;
+            half_extent = max(width * 0.5, head_half_width) + margin;
+            head = vec4<f32>(tip, width * 0.5, head_length, head_half_width);
+        }
         
         // Calculate vertex position
         // position.x (-1 to 1) maps along the line direction
         // position.y (-1 to 1) maps across the line width
-        let along_offset = input.position.x * line_length * 0.5;
-        let across_offset = input.position.y * width_ndc * 0.5;
+        let along_offset = (input.position.x * 0.5 + 0.5) * span;
+        let across_offset = input.position.y * half_extent;
         
-        let line_center = (start_ndc + end_ndc) * 0.5;
-        let vertex_pos = line_center + line_dir * along_offset + line_perp * across_offset;
+        let vertex_px = start_px + line_dir * along_offset + line_perp * across_offset;
         
-        output.clip_position = vec4<f32>(vertex_pos, 0.0, 1.0);
-        output.uv = input.position;  // Pass through UV coordinates (-1 to 1)
+        output.clip_position = vec4<f32>(pixel_to_ndc(vertex_px), 0.0, 1.0);
+        output.fade = fade;
+        output.local = vec2<f32>(along_offset, across_offset);
+        output.head = head;
+        output.arrow = arrow;
     } else {
         // Degenerate line (zero length), hide it
         output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
-        output.uv = vec2<f32>(0.0, 0.0);
+        output.fade = 0.0;
+        output.local = vec2<f32>(0.0, 0.0);
+        output.head = vec4<f32>(0.0);
+        output.arrow = ARROW_NONE;
     }
     
+    // Edges of nodes the visibility filter hides are hidden with them
+    let endpoints = input.instance_filter;
+    if (endpoints.z != 0u && !(passes_filter(endpoints.x) && passes_filter(endpoints.y))) {
+        output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+    }
+
     output.color = input.instance_color;
     return output;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    // Calculate distance from center line (along Y axis)
-    let edge_distance = abs(input.uv.y);
+    // Pixels outside the line (and head), negative inside
+    var dist = abs(input.local.y) - input.head.y;
+    if (input.arrow != ARROW_NONE) {
+        dist = arrow_distance(input);
+    }
     
-    // Add anti-aliasing with smooth falloff at edges
-    let edge_softness = 0.1;
-    let alpha = 1.0 - smoothstep(1.0 - edge_softness, 1.0, edge_distance);
+    // Coverage ramps across the outline over edge_smoothing screen pixels
+    let smoothing = max(uniforms.edge_smoothing * fwidth(dist), 1e-4);
+    let alpha = clamp(0.5 - dist / smoothing, 0.0, 1.0) * input.fade;
     
     // Apply alpha to the color
-    return vec4<f32>(input.color.rgb, input.color.a * alpha);
+    return vec4<f32>(input.color.rgb, input.color.a * alpha * uniforms.opacity);
+}
+
+// Signed pixel distance to an edge with an arrowhead, negative inside. The line
+// stops where a filled head begins, and runs on into the point of a chevron.
+fn arrow_distance(input: VertexOutput) -> f32 {
+    let along = input.local.x;
+    let across = abs(input.local.y);
+    let tip = input.head.x;
+    let half_width = input.head.y;
+    let head_length = input.head.z;
+    let head_half_width = input.head.w;
+    let back = tip - head_length;
+    
+    var line_end = back;
+    var head: f32;
+    switch input.arrow {
+        case ARROW_OPEN: {
+            // A stroke from the tip to the back corner on this side
+            line_end = tip;
+            let corner = vec2<f32>(back, head_half_width);
+            let to_corner = corner - vec2<f32>(tip, 0.0);
+            let t = clamp(dot(vec2<f32>(along - tip, across), to_corner) / dot(to_corner, to_corner), 0.0, 1.0);
+            head = length(vec2<f32>(along - tip, across) - to_corner * t) - max(half_width, 1.0);
+        }
+        case ARROW_CIRCLE: {
+            let radius = head_length * 0.5;
+            line_end = back + radius;
+            head = length(vec2<f32>(along - (back + radius), across)) - radius;
+        }
+        case ARROW_TRIANGLE, default: {
+            // Behind the base or outside the slanted side
+            let side = (across * head_length - (tip - along) * head_half_width) / length(vec2<f32>(head_length, head_half_width));
+            head = max(back - along, side);
+        }
+    }
+    let line = max(across - half_width, along - line_end);
+    return min(line, head);
 }
\ No newline at end of file
@@ -12,15 +12,33 @@ fn vs_main(@builtin(vertex_index) vertexIndex: u32) -> @builtin(position) vec4<f
 // Uniforms struct
 struct Uniforms {
     time: f32,
-    _padding1: f32,
+    opacity: f32,  // global multiplier, not applied to the gradient
     resolution: vec2<f32>,
     color1: vec4<f32>,
     color2: vec4<f32>,
     camera_position: vec2<f32>,
     camera_zoom: f32,
-    _padding2: f32,
+    point_size: f32,
+    filter_required: u32,
+    filter_forbidden: u32,
+    edge_stride: u32,
+    gradient_style: u32,
+    gradient: vec4<f32>,
+    gradient_phase: f32,
+    motion_mode: u32,
+    motion_amplitude: f32,
+    motion_frequency: f32,
+    arrow_style: u32,
+    arrow_size: f32,
+    edge_smoothing: f32,
+    edge_min_width: f32,
+    pixel_snap: f32,
 }
 
+// Must match GRADIENT_* in renderer.rs
+const GRADIENT_LINEAR: u32 = 0u;
+const GRADIENT_RADIAL: u32 = 1u;
+
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
 
 // Fragment shader - creates animated gradient
@@ -29,15 +47,28 @@ fn fs_main(@builtin(position) fragCoord: vec4<f32>) -> @location(0) vec4<f32> {
     // Normalize coordinates to [0, 1]
     let uv = fragCoord.xy / uniforms.resolution.xy;
     
-    // Create animated gradient factor
-    // This creates a diagonal gradient that moves over time
-    let gradient_factor = (uv.x + uv.y + sin(uniforms.time * 0.5) * 0.5) * 0.5;
+    var gradient_factor: f32;
+    if (uniforms.gradient_style == GRADIENT_RADIAL) {
+        // Distance from the center in units of the radius, measured in pixels
+        // so the rings stay round
+        let center = uniforms.gradient.xy * uniforms.resolution;
+        let radius = max(uniforms.gradient.z * min(uniforms.resolution.x, uniforms.resolution.y), 1.0);
+        gradient_factor = length(fragCoord.xy - center) / radius;
+    } else {
+        // Position along the direction, scaled so the canvas corners span 0 to 1
+        let direction = uniforms.gradient.xy;
+        let span = max(abs(direction.x) + abs(direction.y), 1e-6);
+        gradient_factor = 0.5 + dot(uv - vec2<f32>(0.5), direction) / span;
+    }
+    
+    // The blend drifts back and forth over time
+    gradient_factor += sin(uniforms.gradient_phase * 0.5) * 0.25;
     
     // Smooth the gradient factor
     let smooth_factor = smoothstep(0.0, 1.0, gradient_factor);
     
     // Mix the two colors based on the gradient factor
-    let final_color = mix(uniforms.color1.rgb, uniforms.color2.rgb, smooth_factor);
+    let final_color = mix(uniforms.color1, uniforms.color2, smooth_factor);
     
-    return vec4<f32>(final_color, 1.0);
-}
\ No newline at end of file
+    return final_color;
+}
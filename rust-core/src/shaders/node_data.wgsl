@@ -0,0 +1,21 @@
+// The physics buffer's node layout, shared by the physics and render shaders.
+// Must match NodeData in lib.rs.
+struct NodeData {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    fx: f32,
+    fy: f32,
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+    size: f32,
+    mass: f32,
+    flags: u32,
+}
+
+// Must match the NODE_FLAG_* constants in lib.rs
+const NODE_FLAG_PINNED: u32 = 1u;
+const NODE_HIDDEN_FLAGS: u32 = 62u;  // hidden, below core, outside focus, outside time, layer hidden
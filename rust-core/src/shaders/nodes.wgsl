@@ -1,20 +1,81 @@
 // Vertex shader for rendering circular nodes
 struct Uniforms {
     time: f32,
-    _padding1: f32,
+    opacity: f32,  // global alpha multiplier
     resolution: vec2<f32>,
     color1: vec4<f32>,
     color2: vec4<f32>,
     camera_position: vec2<f32>,
     camera_zoom: f32,
-    _padding2: f32,
+    point_size: f32,  // > 0 draws nodes as dots this many pixels across
+    filter_required: u32,  // see set_visibility_filter
+    filter_forbidden: u32,
+    edge_stride: u32,
+    gradient_style: u32,
+    gradient: vec4<f32>,
+    gradient_phase: f32,
+    motion_mode: u32,
+    motion_amplitude: f32,
+    motion_frequency: f32,
+    arrow_style: u32,
+    arrow_size: f32,
+    edge_smoothing: f32,
+    edge_min_width: f32,
+    pixel_snap: f32,  // nodes with a smaller radius in pixels snap to the pixel grid
 }
 
+// Must match NodeStyle in renderer.rs
+struct NodeStyle {
+    color: vec4<f32>,
+    border_color: vec4<f32>,
+    size: f32,
+    border_width: f32,
+    glow: f32,
+    fields: u32,
+    shape: u32,
+    corner: f32,
+    // Scalars, as a vec3 would align to 16 bytes and outgrow the Rust struct
+    _padding0: u32,
+    _padding1: u32,
+}
+
+struct StyleTable {
+    styles: array<NodeStyle, 64>,
+}
+
+const STYLE_FIELD_COLOR: u32 = 1u;
+const STYLE_FIELD_SIZE: u32 = 2u;
+const STYLE_FIELD_BORDER: u32 = 4u;
+const STYLE_FIELD_SHAPE: u32 = 8u;
+const STYLE_FIELD_GLOW: u32 = 16u;
+
+// Must match NODE_CLASS_SHIFT in lib.rs
+const NODE_CLASS_SHIFT: u32 = 16u;
+
+// Must match MOTION_* in renderer.rs
+const MOTION_NONE: u32 = 0u;
+const MOTION_BOB: u32 = 1u;
+const MOTION_SHIMMER: u32 = 2u;
+
+const SHAPE_CIRCLE: u32 = 0u;
+const SHAPE_SQUARE: u32 = 1u;
+const SHAPE_DIAMOND: u32 = 2u;
+const SHAPE_TRIANGLE: u32 = 3u;
+const SHAPE_SQUIRCLE: u32 = 4u;
+
+// Superellipse exponents stop here, past which the shape is a square to the eye
+const MAX_SQUIRCLE_EXPONENT: f32 = 32.0;
+
 struct VertexInput {
     @location(0) position: vec2<f32>,  // Quad vertex position (-1 to 1)
-    @location(1) instance_pos: vec2<f32>,  // Node center position in NDC
+    @location(1) instance_pos: vec2<f32>,  // Node center position in pixels
     @location(2) instance_color: vec4<f32>,  // Node color
     @location(3) instance_size: f32,  // Node radius in pixels
+    @location(4) instance_flags: u32,  // NodeData flags, style class in the upper half
+    @location(5) instance_pulse: vec4<f32>,  // PulseState: start, period, end, scale
+    @location(6) instance_pulse_glow: f32,
+    @location(7) instance_filter: u32,  // user flags
+    @location(8) instance_phase: f32,  // idle motion phase in radians
 }
 
 struct VertexOutput {
@@ -22,34 +83,175 @@ struct VertexOutput {
     @location(0) color: vec4<f32>,
     @location(1) uv: vec2<f32>,  // UV coordinates relative to circle center (-1 to 1)
     @location(2) radius: f32,
+    @location(3) @interpolate(flat) border_color: vec4<f32>,
+    @location(4) @interpolate(flat) border_width: f32,  // fraction of the radius
+    @location(5) @interpolate(flat) glow: f32,  // halo width as a fraction of the radius
+    @location(6) @interpolate(flat) shape: u32,
+    @location(7) @interpolate(flat) brightness: f32,  // color multiplier of the shimmer
+    @location(8) @interpolate(flat) corner: f32,  // squircle roundness
 }
 
 @group(0) @binding(0) var<uniform> uniforms: Uniforms;
+@group(0) @binding(1) var<uniform> style_table: StyleTable;
+
+// Whether user flags (set_node_flags) pass the visibility filter
+fn passes_filter(flags: u32) -> bool {
+    return (flags & uniforms.filter_required) == uniforms.filter_required && (flags & uniforms.filter_forbidden) == 0u;
+}
 
 @vertex
 fn vs_main(input: VertexInput) -> VertexOutput {
+    return node_vertex(input);
+}
+
+// Compact instances: the color arrives as Unorm8x4, and one word holds the size
+// as f16 in the low half and the style class in the high half
+struct CompactVertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) instance_pos: vec2<f32>,
+    @location(2) instance_color: vec4<f32>,
+    @location(3) instance_size_class: u32,
+    @location(5) instance_pulse: vec4<f32>,
+    @location(6) instance_pulse_glow: f32,
+    @location(7) instance_filter: u32,
+    @location(8) instance_phase: f32,
+}
+
+@vertex
+fn vs_compact(input: CompactVertexInput) -> VertexOutput {
+    var full: VertexInput;
+    full.position = input.position;
+    full.instance_pos = input.instance_pos;
+    full.instance_color = input.instance_color;
+    full.instance_size = unpack2x16float(input.instance_size_class).x;
+    full.instance_flags = input.instance_size_class & 0xffff0000u;
+    full.instance_pulse = input.instance_pulse;
+    full.instance_pulse_glow = input.instance_pulse_glow;
+    full.instance_filter = input.instance_filter;
+    full.instance_phase = input.instance_phase;
+    return node_vertex(full);
+}
+
+// Storage instances: nodes are read straight from the physics buffer (NodeData,
+// prepended by the renderer), so simulated positions never leave the GPU
+@group(1) @binding(0) var<storage, read> physics_nodes: array<NodeData>;
+
+@vertex
+fn vs_storage(
+    @location(0) position: vec2<f32>,
+    @location(5) pulse: vec4<f32>,
+    @location(6) pulse_glow: f32,
+    @location(7) user_flags: u32,
+    @location(8) phase: f32,
+    @builtin(instance_index) instance: u32,
+) -> VertexOutput {
+    let node = physics_nodes[instance];
+    var full: VertexInput;
+    full.position = position;
+    full.instance_pos = vec2<f32>(node.x, node.y);
+    full.instance_color = vec4<f32>(node.r, node.g, node.b, node.a);
+    full.instance_size = node.size;
+    full.instance_flags = node.flags;
+    full.instance_pulse = pulse;
+    full.instance_pulse_glow = pulse_glow;
+    full.instance_filter = user_flags;
+    full.instance_phase = phase;
+    var output = node_vertex(full);
+    // The instance path filters hidden nodes on the CPU; here they're clipped away
+    if ((node.flags & NODE_HIDDEN_FLAGS) != 0u) {
+        output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+    }
+    return output;
+}
+
+fn node_vertex(input: VertexInput) -> VertexOutput {
     var output: VertexOutput;
+
+    // Class 0 keeps the per-node attributes; other classes override the fields they set
+    let class_id = input.instance_flags >> NODE_CLASS_SHIFT;
+    var color = input.instance_color;
+    var size = input.instance_size;
+    output.border_color = vec4<f32>(0.0);
+    output.border_width = 0.0;
+    output.glow = 0.0;
+    output.shape = SHAPE_CIRCLE;
+    output.corner = 1.0;
+    if (class_id > 0u && class_id < 64u) {
+        let style = style_table.styles[class_id];
+        if ((style.fields & STYLE_FIELD_COLOR) != 0u) {
+            color = style.color;
+        }
+        if ((style.fields & STYLE_FIELD_SIZE) != 0u) {
+            size = style.size;
+        }
+        if ((style.fields & STYLE_FIELD_BORDER) != 0u) {
+            output.border_color = style.border_color;
+            output.border_width = clamp(style.border_width / max(size, 1e-3), 0.0, 1.0);
+        }
+        if ((style.fields & STYLE_FIELD_SHAPE) != 0u) {
+            output.shape = style.shape;
+            output.corner = style.corner;
+        }
+        if ((style.fields & STYLE_FIELD_GLOW) != 0u) {
+            output.glow = max(style.glow, 0.0);
+        }
+    }
+    // Pulses swell the node to `scale` and back once per period, with a halo
+    let pulse = input.instance_pulse;
+    if (pulse.y > 0.0 && uniforms.time >= pulse.x && uniforms.time < pulse.z) {
+        let wave = sin(fract((uniforms.time - pulse.x) / pulse.y) * 3.14159265);
+        size *= 1.0 + (pulse.w - 1.0) * wave;
+        output.glow = max(output.glow, input.instance_pulse_glow * wave);
+    }
+    // Overviews draw every node as a plain dot of the same size on screen
+    if (uniforms.point_size > 0.0) {
+        size = uniforms.point_size * 0.5 / uniforms.camera_zoom;
+        output.border_width = 0.0;
+        output.glow = 0.0;
+    }
+    // The quad grows to make room for the glow around the shape
+    let extent = 1.0 + output.glow;
     
-    // Calculate aspect ratio to maintain circular nodes
-    let aspect_ratio = uniforms.resolution.x / uniforms.resolution.y;
-    
-    // Convert NDC position back to pixel coordinates
-    let pixel_x = (input.instance_pos.x + 1.0) * 0.5 * uniforms.resolution.x;
-    let pixel_y = (1.0 - input.instance_pos.y) * 0.5 * uniforms.resolution.y;
+    let pixel_x = input.instance_pos.x;
+    let pixel_y = input.instance_pos.y;
     
     // Apply camera transformations in pixel space
-    let world_pixel_x = (pixel_x - uniforms.camera_position.x) * uniforms.camera_zoom;
-    let world_pixel_y = (pixel_y - uniforms.camera_position.y) * uniforms.camera_zoom;
+    var world_pixel_x = (pixel_x - uniforms.camera_position.x) * uniforms.camera_zoom;
+    var world_pixel_y = (pixel_y - uniforms.camera_position.y) * uniforms.camera_zoom;
+    
+    // Idle motion only moves or shades what's drawn, never the node itself
+    output.brightness = 1.0;
+    if (uniforms.motion_mode != MOTION_NONE) {
+        let wave = sin(uniforms.time * uniforms.motion_frequency * 6.28318531 + input.instance_phase);
+        if (uniforms.motion_mode == MOTION_BOB) {
+            world_pixel_y += uniforms.motion_amplitude * wave;
+        } else if (uniforms.motion_mode == MOTION_SHIMMER) {
+            output.brightness = max(1.0 + uniforms.motion_amplitude * wave, 0.0);
+        }
+    }
+    
+    // Small nodes snap to the pixel grid: the radius to a half pixel, so the
+    // diameter is whole, and the center to a pixel center (odd diameters) or a
+    // pixel corner (even ones), so every dot covers the same pixels
+    var radius_px = size * uniforms.camera_zoom;
+    if (radius_px < uniforms.pixel_snap) {
+        radius_px = max(round(radius_px * 2.0) * 0.5, 0.5);
+        let odd = fract(radius_px) != 0.0;
+        let offset = select(0.0, 0.5, odd);
+        world_pixel_x = floor(world_pixel_x - offset + 0.5) + offset;
+        world_pixel_y = floor(world_pixel_y - offset + 0.5) + offset;
+    }
     
     // Convert back to NDC
     let world_ndc_x = (world_pixel_x / uniforms.resolution.x) * 2.0 - 1.0;
     let world_ndc_y = 1.0 - (world_pixel_y / uniforms.resolution.y) * 2.0;
     
-    // Convert pixel size to NDC size with zoom correction
-    let ndc_size_x = (input.instance_size * uniforms.camera_zoom / uniforms.resolution.x) * 2.0;
-    let ndc_size_y = (input.instance_size * uniforms.camera_zoom / uniforms.resolution.y) * 2.0;
+    // Convert pixel size to NDC size per axis, so the quad stays square in
+    // pixels on a non-square canvas
+    let ndc_size_x = (radius_px * extent / uniforms.resolution.x) * 2.0;
+    let ndc_size_y = (radius_px * extent / uniforms.resolution.y) * 2.0;
     
-    // Apply aspect ratio correction to vertex offset
+    // Scale the vertex offset to that size
     var vertex_offset = input.position;
     vertex_offset.x *= ndc_size_x;
     vertex_offset.y *= ndc_size_y;
@@ -57,26 +259,78 @@ fn vs_main(input: VertexInput) -> VertexOutput {
     let ndc_pos = vec2<f32>(world_ndc_x, world_ndc_y) + vertex_offset;
     
     output.clip_position = vec4<f32>(ndc_pos.x, ndc_pos.y, 0.0, 1.0);
-    output.color = input.instance_color;
-    output.uv = input.position;  // This will be -1 to 1 for the quad
+    output.color = color;
+    output.uv = input.position * extent;  // -1 to 1 on the shape, beyond it in the glow
     output.radius = 1.0;  // Normalized radius
+    if (!passes_filter(input.instance_filter)) {
+        output.clip_position = vec4<f32>(0.0, 0.0, -1.0, 1.0);
+    }
     
     return output;
 }
 
 @fragment
 fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
-    // Calculate distance from center
-    let dist = length(input.uv);
+    // Distance from center, normalized so the shape's outline is at 1
+    let dist = shape_distance(input.shape, input.corner, input.uv);
     
-    // Anti-aliased circle
-    let edge_softness = 0.05;
+    // Anti-aliased shape; squircles fade over about a pixel, however large
+    let pixel = fwidth(dist);
+    let edge_softness = select(0.05, clamp(pixel, 1e-4, 0.5), input.shape == SHAPE_SQUIRCLE);
     let alpha = 1.0 - smoothstep(input.radius - edge_softness, input.radius, dist);
+
+    // Soft halo outside the shape
+    var glow_alpha = 0.0;
+    if (input.glow > 0.0 && dist > input.radius) {
+        let falloff = 1.0 - (dist - input.radius) / input.glow;
+        glow_alpha = clamp(falloff, 0.0, 1.0) * clamp(falloff, 0.0, 1.0) * 0.6;
+    }
     
-    // Discard pixels outside the circle
-    if (alpha <= 0.0) {
+    // Discard pixels outside the shape and its glow
+    if (alpha <= 0.0 && glow_alpha <= 0.0) {
         discard;
     }
-    
-    return vec4<f32>(input.color.rgb, input.color.a * alpha);
+
+    // Border band just inside the outline
+    var color = input.color;
+    if (input.border_width > 0.0) {
+        let inner = input.radius - input.border_width;
+        let border_mix = smoothstep(inner - edge_softness, inner, dist);
+        color = mix(color, input.border_color, border_mix);
+    }
+
+    if (alpha <= 0.0) {
+        return vec4<f32>(input.color.rgb * input.brightness, input.color.a * glow_alpha * uniforms.opacity);
+    }
+    return vec4<f32>(color.rgb * input.brightness, color.a * max(alpha, glow_alpha) * uniforms.opacity);
+}
+
+fn shape_distance(shape: u32, corner: f32, uv: vec2<f32>) -> f32 {
+    switch shape {
+        case SHAPE_SQUIRCLE: {
+            // Superellipse |x|^n + |y|^n = 1, n = 2 (circle) at corner 1 and
+            // growing toward a square as the corner goes to 0. Factoring out
+            // the larger coordinate keeps the powers from overflowing.
+            let n = min(2.0 / max(corner, 1e-3), MAX_SQUIRCLE_EXPONENT);
+            let a = abs(uv);
+            let m = max(a.x, a.y);
+            if (m <= 0.0) {
+                return 0.0;
+            }
+            return m * pow(pow(a.x / m, n) + pow(a.y / m, n), 1.0 / n);
+        }
+        case SHAPE_SQUARE: {
+            return max(abs(uv.x), abs(uv.y));
+        }
+        case SHAPE_DIAMOND: {
+            return abs(uv.x) + abs(uv.y);
+        }
+        case SHAPE_TRIANGLE: {
+            // Upward-pointing triangle inscribed in the unit circle
+            return max(0.866 * abs(uv.x) + 0.5 * uv.y, -uv.y) / 0.5;
+        }
+        default: {
+            return length(uv);
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,270 @@
+// Four f32 lanes for the CPU physics: simd128 on wasm32 when the target feature
+// is enabled (.cargo/config.toml turns it on), SSE2 on x86_64 (always available
+// there), and a plain array elsewhere so every target still builds.
+
+use std::ops::{Add, Div, Mul, Sub};
+
+pub const LANES: usize = 4;
+
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod imp {
+    use std::arch::wasm32::*;
+
+    pub type Repr = v128;
+
+    pub fn splat(value: f32) -> Repr {
+        f32x4_splat(value)
+    }
+
+    pub fn from_array(values: [f32; 4]) -> Repr {
+        f32x4(values[0], values[1], values[2], values[3])
+    }
+
+    pub fn to_array(lanes: Repr) -> [f32; 4] {
+        [
+            f32x4_extract_lane::<0>(lanes),
+            f32x4_extract_lane::<1>(lanes),
+            f32x4_extract_lane::<2>(lanes),
+            f32x4_extract_lane::<3>(lanes),
+        ]
+    }
+
+    pub fn add(a: Repr, b: Repr) -> Repr {
+        f32x4_add(a, b)
+    }
+
+    pub fn sub(a: Repr, b: Repr) -> Repr {
+        f32x4_sub(a, b)
+    }
+
+    pub fn mul(a: Repr, b: Repr) -> Repr {
+        f32x4_mul(a, b)
+    }
+
+    pub fn div(a: Repr, b: Repr) -> Repr {
+        f32x4_div(a, b)
+    }
+
+    pub fn sqrt(a: Repr) -> Repr {
+        f32x4_sqrt(a)
+    }
+
+    pub fn max(a: Repr, b: Repr) -> Repr {
+        f32x4_max(a, b)
+    }
+
+    pub fn le(a: Repr, b: Repr) -> Repr {
+        f32x4_le(a, b)
+    }
+
+    pub fn and(a: Repr, b: Repr) -> Repr {
+        v128_and(a, b)
+    }
+
+    pub fn select(mask: Repr, yes: Repr, no: Repr) -> Repr {
+        v128_bitselect(yes, no, mask)
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+mod imp {
+    // The intrinsics are only unsafe because they need SSE/SSE2, which the cfg
+    // above guarantees
+    use std::arch::x86_64::*;
+
+    pub type Repr = __m128;
+
+    pub fn splat(value: f32) -> Repr {
+        unsafe { _mm_set1_ps(value) }
+    }
+
+    pub fn from_array(values: [f32; 4]) -> Repr {
+        unsafe { _mm_set_ps(values[3], values[2], values[1], values[0]) }
+    }
+
+    pub fn to_array(lanes: Repr) -> [f32; 4] {
+        let mut values = [0.0; 4];
+        // `values` has room for the four lanes; unaligned stores are allowed
+        unsafe { _mm_storeu_ps(values.as_mut_ptr(), lanes) };
+        values
+    }
+
+    pub fn add(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_add_ps(a, b) }
+    }
+
+    pub fn sub(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_sub_ps(a, b) }
+    }
+
+    pub fn mul(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_mul_ps(a, b) }
+    }
+
+    pub fn div(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_div_ps(a, b) }
+    }
+
+    pub fn sqrt(a: Repr) -> Repr {
+        unsafe { _mm_sqrt_ps(a) }
+    }
+
+    pub fn max(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_max_ps(a, b) }
+    }
+
+    pub fn le(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_cmple_ps(a, b) }
+    }
+
+    pub fn and(a: Repr, b: Repr) -> Repr {
+        unsafe { _mm_and_ps(a, b) }
+    }
+
+    pub fn select(mask: Repr, yes: Repr, no: Repr) -> Repr {
+        unsafe { _mm_or_ps(_mm_and_ps(mask, yes), _mm_andnot_ps(mask, no)) }
+    }
+}
+
+#[cfg(not(any(all(target_arch = "wasm32", target_feature = "simd128"), all(target_arch = "x86_64", target_feature = "sse2"))))]
+mod imp {
+    // Masks are all-ones or all-zero bit patterns, as in the SIMD versions
+    pub type Repr = [f32; 4];
+
+    fn map(a: Repr, b: Repr, f: impl Fn(f32, f32) -> f32) -> Repr {
+        [f(a[0], b[0]), f(a[1], b[1]), f(a[2], b[2]), f(a[3], b[3])]
+    }
+
+    fn mask(condition: bool) -> f32 {
+        f32::from_bits(if condition { u32::MAX } else { 0 })
+    }
+
+    pub fn splat(value: f32) -> Repr {
+        [value; 4]
+    }
+
+    pub fn from_array(values: [f32; 4]) -> Repr {
+        values
+    }
+
+    pub fn to_array(lanes: Repr) -> [f32; 4] {
+        lanes
+    }
+
+    pub fn add(a: Repr, b: Repr) -> Repr {
+        map(a, b, |a, b| a + b)
+    }
+
+    pub fn sub(a: Repr, b: Repr) -> Repr {
+        map(a, b, |a, b| a - b)
+    }
+
+    pub fn mul(a: Repr, b: Repr) -> Repr {
+        map(a, b, |a, b| a * b)
+    }
+
+    pub fn div(a: Repr, b: Repr) -> Repr {
+        map(a, b, |a, b| a / b)
+    }
+
+    pub fn sqrt(a: Repr) -> Repr {
+        a.map(f32::sqrt)
+    }
+
+    pub fn max(a: Repr, b: Repr) -> Repr {
+        map(a, b, f32::max)
+    }
+
+    pub fn le(a: Repr, b: Repr) -> Repr {
+        map(a, b, |a, b| mask(a <= b))
+    }
+
+    pub fn and(a: Repr, b: Repr) -> Repr {
+        map(a, b, |a, b| f32::from_bits(a.to_bits() & b.to_bits()))
+    }
+
+    pub fn select(mask: Repr, yes: Repr, no: Repr) -> Repr {
+        [0, 1, 2, 3].map(|i| if mask[i].to_bits() != 0 { yes[i] } else { no[i] })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct F32x4(imp::Repr);
+
+impl F32x4 {
+    pub fn splat(value: f32) -> Self {
+        Self(imp::splat(value))
+    }
+
+    pub fn from_array(values: [f32; 4]) -> Self {
+        Self(imp::from_array(values))
+    }
+
+    // The first four values; shorter slices are padded with `fill`
+    pub fn load(values: &[f32], fill: f32) -> Self {
+        let mut lanes = [fill; LANES];
+        let count = values.len().min(LANES);
+        lanes[..count].copy_from_slice(&values[..count]);
+        Self::from_array(lanes)
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        imp::to_array(self.0)
+    }
+
+    // Lanes summed in a fixed order, so results don't depend on the backend
+    pub fn sum(self) -> f32 {
+        let [a, b, c, d] = self.to_array();
+        (a + b) + (c + d)
+    }
+
+    pub fn sqrt(self) -> Self {
+        Self(imp::sqrt(self.0))
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Self(imp::max(self.0, other.0))
+    }
+
+    // All-ones lanes where self <= other
+    pub fn le(self, other: Self) -> Self {
+        Self(imp::le(self.0, other.0))
+    }
+
+    pub fn and(self, other: Self) -> Self {
+        Self(imp::and(self.0, other.0))
+    }
+
+    // `yes` in the lanes set in `self`, `no` in the others
+    pub fn select(self, yes: Self, no: Self) -> Self {
+        Self(imp::select(self.0, yes.0, no.0))
+    }
+}
+
+impl Add for F32x4 {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        Self(imp::add(self.0, other.0))
+    }
+}
+
+impl Sub for F32x4 {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        Self(imp::sub(self.0, other.0))
+    }
+}
+
+impl Mul for F32x4 {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        Self(imp::mul(self.0, other.0))
+    }
+}
+
+impl Div for F32x4 {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        Self(imp::div(self.0, other.0))
+    }
+}
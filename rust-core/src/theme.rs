@@ -0,0 +1,51 @@
+// Renderer-level color defaults that switch together, e.g. for dark and light
+// mode. Per-node and per-edge colors are never part of a theme.
+
+use crate::color::rgb24;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub background: [f32; 4],     // clear color, visible through translucent gradients
+    pub gradient_start: [f32; 4], // color1 of the background gradient
+    pub gradient_end: [f32; 4],   // color2
+    pub highlight: [f32; 4],      // highlight_path without an explicit color
+    pub dim_alpha: f32,           // alpha multiplier for dimmed nodes and edges
+    pub grid: [f32; 4],           // for hosts drawing a grid or other chrome
+}
+
+// The look from before themes existed
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: [0.0, 0.0, 0.0, 1.0],
+            gradient_start: [1.0, 0.0, 0.0, 1.0],
+            gradient_end: [0.0, 0.0, 1.0, 1.0],
+            highlight: rgb24(0xffcc00),
+            dim_alpha: 0.15,
+            grid: [1.0, 1.0, 1.0, 0.1],
+        }
+    }
+}
+
+pub fn builtin(name: &str) -> Option<Theme> {
+    match name {
+        "default" => Some(Theme::default()),
+        "dark" => Some(Theme {
+            background: rgb24(0x101218),
+            gradient_start: rgb24(0x15171f),
+            gradient_end: rgb24(0x1f2330),
+            highlight: rgb24(0xffb000),
+            dim_alpha: 0.15,
+            grid: [1.0, 1.0, 1.0, 0.08],
+        }),
+        "light" => Some(Theme {
+            background: rgb24(0xffffff),
+            gradient_start: rgb24(0xf7f8fa),
+            gradient_end: rgb24(0xe6e9ef),
+            highlight: rgb24(0xd6336c),
+            dim_alpha: 0.25,
+            grid: [0.0, 0.0, 0.0, 0.08],
+        }),
+        _ => None,
+    }
+}
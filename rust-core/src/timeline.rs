@@ -0,0 +1,71 @@
+// Timestamped items (nodes or edges) filtered to a time window. Items are
+// sorted by time once, and the window is kept as two cursors into that order,
+// so moving it only touches the items that cross its ends. Items without a
+// finite timestamp are always inside.
+
+pub struct TimeIndex {
+    times: Vec<f32>,
+    order: Vec<u32>, // items with finite times, by ascending time
+    inside: Vec<bool>,
+    window: (usize, usize), // order[window.0..window.1] is inside
+}
+
+impl TimeIndex {
+    // Starts with every item inside
+    pub fn new(times: &[f32]) -> Self {
+        let mut order: Vec<u32> = (0..times.len() as u32).filter(|&i| times[i as usize].is_finite()).collect();
+        order.sort_by(|&a, &b| times[a as usize].total_cmp(&times[b as usize]));
+        Self {
+            times: times.to_vec(),
+            window: (0, order.len()),
+            order,
+            inside: vec![true; times.len()],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.times.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.times.is_empty()
+    }
+
+    pub fn time(&self, item: usize) -> f32 {
+        self.times[item]
+    }
+
+    pub fn is_inside(&self, item: usize) -> bool {
+        self.inside[item]
+    }
+
+    // Moves the window to [start, end], calling `changed(item, inside)` for each
+    // item that enters or leaves it
+    pub fn set_window(&mut self, start: f32, end: f32, mut changed: impl FnMut(usize, bool)) {
+        let new_start = self.order.partition_point(|&i| self.times[i as usize] < start);
+        let new_end = self.order.partition_point(|&i| self.times[i as usize] <= end).max(new_start);
+        let (old_start, old_end) = self.window;
+
+        // Only the ranges between the old and new cursors change sides
+        for position in old_start.min(new_start)..old_start.max(new_start) {
+            self.toggle(position, position >= new_start && position < new_end, &mut changed);
+        }
+        for position in old_end.min(new_end)..old_end.max(new_end) {
+            self.toggle(position, position >= new_start && position < new_end, &mut changed);
+        }
+        self.window = (new_start, new_end);
+    }
+
+    // Puts every item back inside
+    pub fn clear_window(&mut self, changed: impl FnMut(usize, bool)) {
+        self.set_window(f32::NEG_INFINITY, f32::INFINITY, changed);
+    }
+
+    fn toggle(&mut self, position: usize, inside: bool, changed: &mut impl FnMut(usize, bool)) {
+        let item = self.order[position] as usize;
+        if self.inside[item] != inside {
+            self.inside[item] = inside;
+            changed(item, inside);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+// What the viewer is looking at, without the graph itself, packed into a
+// URL-safe string for shareable links. The encoding is a format byte followed
+// by tagged records (tag, varint length, payload); decoders skip tags they
+// don't know, so newer writers can add records without breaking older readers.
+// The result is base64url without padding.
+
+const FORMAT: u8 = 1;
+
+const TAG_CAMERA: u8 = 1; // x, y, zoom as f32
+const TAG_CORE_FILTER: u8 = 2; // k as a varint
+const TAG_FOCUS: u8 = 3; // count, then ascending node indices as varint deltas
+const TAG_TIME_WINDOW: u8 = 4; // start, end as f32
+const TAG_TIME_FADE_MARGIN: u8 = 5; // f32
+const TAG_THEME: u8 = 6; // UTF-8 name
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ViewState {
+    pub camera: Option<([f32; 2], f32)>, // None leaves the camera alone
+    pub core_filter: u32,                // 0 when not filtering
+    pub focus: Option<Vec<u32>>,         // the isolated nodes, ascending
+    pub time_window: Option<[f32; 2]>,
+    pub time_fade_margin: f32,
+    pub theme: Option<String>, // None leaves the theme alone
+}
+
+impl ViewState {
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![FORMAT];
+        if let Some(([x, y], zoom)) = self.camera {
+            record(&mut bytes, TAG_CAMERA, &floats(&[x, y, zoom]));
+        }
+        if self.core_filter > 0 {
+            let mut payload = Vec::new();
+            write_varint(&mut payload, self.core_filter as u64);
+            record(&mut bytes, TAG_CORE_FILTER, &payload);
+        }
+        if let Some(focus) = &self.focus {
+            let mut payload = Vec::new();
+            write_varint(&mut payload, focus.len() as u64);
+            let mut previous = 0;
+            for &index in focus {
+                write_varint(&mut payload, (index - previous) as u64);
+                previous = index;
+            }
+            record(&mut bytes, TAG_FOCUS, &payload);
+        }
+        if let Some([start, end]) = self.time_window {
+            record(&mut bytes, TAG_TIME_WINDOW, &floats(&[start, end]));
+        }
+        if self.time_fade_margin != 0.0 {
+            record(&mut bytes, TAG_TIME_FADE_MARGIN, &floats(&[self.time_fade_margin]));
+        }
+        if let Some(theme) = &self.theme {
+            record(&mut bytes, TAG_THEME, theme.as_bytes());
+        }
+        base64url_encode(&bytes)
+    }
+
+    // Only checks the encoding; whether the values fit the graph is up to the caller
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let bytes = base64url_decode(text).ok_or("View state is not valid base64url")?;
+        let mut reader = Reader { bytes: &bytes, position: 0 };
+        if reader.byte()? != FORMAT {
+            return Err("Unsupported view state format".to_string());
+        }
+
+        let mut state = Self::default();
+        while !reader.is_done() {
+            let tag = reader.byte()?;
+            let length = reader.varint()? as usize;
+            let mut payload = Reader { bytes: reader.take(length)?, position: 0 };
+            match tag {
+                TAG_CAMERA => {
+                    let [x, y, zoom] = [payload.float()?, payload.float()?, payload.float()?];
+                    state.camera = Some(([x, y], zoom));
+                }
+                TAG_CORE_FILTER => state.core_filter = u32::try_from(payload.varint()?).map_err(|_| "Core filter out of range")?,
+                TAG_FOCUS => {
+                    let count = payload.varint()? as usize;
+                    let mut focus = Vec::with_capacity(count.min(length));
+                    let mut index = 0u64;
+                    for _ in 0..count {
+                        index = index.checked_add(payload.varint()?).ok_or("Focused node index out of range")?;
+                        focus.push(u32::try_from(index).map_err(|_| "Focused node index out of range")?);
+                    }
+                    state.focus = Some(focus);
+                }
+                TAG_TIME_WINDOW => state.time_window = Some([payload.float()?, payload.float()?]),
+                TAG_TIME_FADE_MARGIN => state.time_fade_margin = payload.float()?,
+                TAG_THEME => {
+                    let name = std::str::from_utf8(payload.bytes).map_err(|_| "Theme name is not UTF-8")?;
+                    state.theme = Some(name.to_string());
+                }
+                _ => {} // written by a newer version
+            }
+        }
+        Ok(state)
+    }
+}
+
+fn record(bytes: &mut Vec<u8>, tag: u8, payload: &[u8]) {
+    bytes.push(tag);
+    write_varint(bytes, payload.len() as u64);
+    bytes.extend_from_slice(payload);
+}
+
+fn floats(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|value| value.to_le_bytes()).collect()
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_done(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position.checked_add(count).filter(|&end| end <= self.bytes.len()).ok_or("View state is truncated")?;
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn float(&mut self) -> Result<f32, String> {
+        let bytes = self.take(4)?;
+        Ok(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("View state has an overlong number".to_string())
+    }
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            text.push(BASE64URL[(bits >> (18 - 6 * i)) as usize & 63] as char);
+        }
+    }
+    text
+}
+
+fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = text
+        .trim_end_matches('=')
+        .bytes()
+        .map(|c| BASE64URL.iter().position(|&digit| digit == c).map(|digit| digit as u32))
+        .collect::<Option<_>>()?;
+    if digits.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for chunk in digits.chunks(4) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &digit)| bits | digit << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full_state() -> ViewState {
+        ViewState {
+            camera: Some(([-120.5, 3.25], 1.75)),
+            core_filter: 3,
+            focus: Some(vec![0, 4, 5, 300, 70_000]),
+            time_window: Some([10.0, 2000.5]),
+            time_fade_margin: 12.0,
+            theme: Some("dark".to_string()),
+        }
+    }
+
+    // The raw bytes behind an encoded state
+    fn bytes_of(state: &ViewState) -> Vec<u8> {
+        base64url_decode(&state.encode()).unwrap()
+    }
+
+    #[test]
+    fn round_trips_every_field() {
+        let state = full_state();
+        let text = state.encode();
+        assert!(text.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'), "{text}");
+        assert_eq!(ViewState::decode(&text), Ok(state));
+        // Nothing set is just the format byte
+        assert_eq!(ViewState::decode(&ViewState::default().encode()), Ok(ViewState::default()));
+    }
+
+    #[test]
+    fn skips_unknown_tags() {
+        let mut bytes = bytes_of(&full_state());
+        record(&mut bytes, 200, b"from a newer version");
+        record(&mut bytes, 201, &[]);
+        assert_eq!(ViewState::decode(&base64url_encode(&bytes)), Ok(full_state()));
+
+        // Between known records too
+        let mut bytes = vec![FORMAT];
+        record(&mut bytes, 77, &[1, 2, 3]);
+        record(&mut bytes, TAG_CORE_FILTER, &[5]);
+        assert_eq!(ViewState::decode(&base64url_encode(&bytes)), Ok(ViewState { core_filter: 5, ..ViewState::default() }));
+    }
+
+    #[test]
+    fn corrupted_states_are_errors() {
+        let bytes = bytes_of(&full_state());
+        // Cut anywhere inside a record
+        for length in 1..bytes.len() {
+            let text = base64url_encode(&bytes[..length]);
+            if let Ok(state) = ViewState::decode(&text) {
+                // Only cuts on a record boundary decode, to fewer fields
+                assert_ne!(state, full_state(), "cut at {length}");
+            }
+        }
+        assert!(ViewState::decode(&base64url_encode(&bytes[..bytes.len() - 1])).is_err());
+
+        let mut bad_format = bytes.clone();
+        bad_format[0] = 9;
+        let cases = [
+            String::new(),
+            base64url_encode(&bad_format),
+            "not base64!".to_string(),
+            "abc$".to_string(),
+            "A".to_string(),
+            // A varint that never ends, as a record length and as a value
+            base64url_encode(&[FORMAT, TAG_CAMERA, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+            base64url_encode(&[FORMAT, TAG_CORE_FILTER, 11, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]),
+            // A record longer than what's left
+            base64url_encode(&[FORMAT, TAG_THEME, 0xff, 0x7f, b'd']),
+            // Short payloads
+            base64url_encode(&[FORMAT, TAG_CAMERA, 4, 0, 0, 0, 0]),
+            base64url_encode(&[FORMAT, TAG_THEME, 2, 0xc3, 0x28]),
+        ];
+        for text in cases {
+            assert!(ViewState::decode(&text).is_err(), "{text:?}");
+        }
+
+        // Focus deltas that overflow
+        let mut payload = Vec::new();
+        write_varint(&mut payload, 2);
+        write_varint(&mut payload, u64::MAX);
+        write_varint(&mut payload, u64::MAX);
+        let mut bytes = vec![FORMAT];
+        record(&mut bytes, TAG_FOCUS, &payload);
+        assert!(ViewState::decode(&base64url_encode(&bytes)).is_err());
+    }
+}